@@ -2,11 +2,13 @@ use proc_macro2::TokenStream as TokenStream2;
 use proc_macro2::{Ident, Span};
 use quote::{format_ident, quote, ToTokens, TokenStreamExt};
 use std::fmt::Display;
+use std::ops::Range;
 use syn::spanned::Spanned;
 use syn::{
     parse_quote, Attribute, Data, DeriveInput, Error, Expr, Fields, Generics, Index, Lifetime, Type,
 };
 
+use crate::enums::NumEncoding;
 use crate::flags::{FlagTarget, Flags, TypeModifier};
 use crate::lifetime::process_field_lifetimes;
 use crate::parse::{EnderAttribute, Flag, ReprAttribute};
@@ -267,6 +269,8 @@ pub struct Ctxt {
     pub struct_data: Struct,
     /// Lifetime data. Empty unless `target` is `BorrowDecode`
     pub borrow_data: BorrowData,
+    /// Whether the `Async*` implementation is being generated rather than the regular one
+    pub asynchronous: bool,
 }
 
 impl Ctxt {
@@ -315,13 +319,24 @@ impl Ctxt {
                         variant_index.value(discriminant);
                     }
 
+                    // Retrieve the variant flags and apply each in order
+                    let mut flags = Flags::new(FlagTarget::Variant);
+                    let groups = FlagGroup::from_attrs(&variant.attrs)?;
+                    for group in groups {
+                        if group.scope.matches(target) {
+                            for flag in group.flags {
+                                flags.apply(flag, group.scope)?;
+                            }
+                        }
+                    }
+
                     // Register it
                     variants.push(Variant {
                         name: variant.ident.clone(),
                         index: variant_index.clone(),
                         flavor,
                         fields,
-                        flags: Flags::new(FlagTarget::Variant),
+                        flags,
                     });
 
                     // Increment the variant after each iteration
@@ -361,6 +376,7 @@ impl Ctxt {
                 decoder: Lifetime::new("'__data", Span::call_site()),
                 sub_lifetimes: lifetimes,
             },
+            asynchronous: false,
         };
 
         // Then obtain the item level flags and apply them
@@ -373,8 +389,13 @@ impl Ctxt {
             }
         }
 
+        resolve_tags(&ctxt)?;
+        resolve_format(&ctxt)?;
+
         let seek = ctxt.requires_seeking_impl();
         ctxt.flags.force_seek = seek;
+        let bits = ctxt.requires_bit_impl();
+        ctxt.flags.force_bits = bits;
 
         // TODO Detect and fix any potential name clash here
 
@@ -382,7 +403,9 @@ impl Ctxt {
     }
 
     pub fn requires_seeking_impl(&self) -> bool {
-        self.flags.requires_seeking_impl()
+        // Checksum regions wrap the stream, which then only reports its position if it can seek
+        (self.requires_position_impl() && self.has_checksums())
+            || self.flags.requires_seeking_impl()
             || self
                 .struct_data
                 .fields
@@ -397,6 +420,47 @@ impl Ctxt {
                 .any(Flags::requires_seeking_impl)
     }
 
+    pub fn requires_position_impl(&self) -> bool {
+        self.flags.requires_position_impl()
+            || self
+                .struct_data
+                .fields
+                .iter()
+                .map(|x| &x.flags)
+                .any(Flags::requires_position_impl)
+            || self
+                .variants
+                .iter()
+                .flat_map(|x| &x.fields)
+                .map(|x| &x.flags)
+                .any(Flags::requires_position_impl)
+    }
+
+    /// Whether any of the fields is a checksum
+    pub fn has_checksums(&self) -> bool {
+        self.struct_data
+            .fields
+            .iter()
+            .chain(self.variants.iter().flat_map(|x| &x.fields))
+            .any(|x| x.flags.checksum.is_some())
+    }
+
+    pub fn requires_bit_impl(&self) -> bool {
+        self.flags.requires_bit_impl()
+            || self
+                .struct_data
+                .fields
+                .iter()
+                .map(|x| &x.flags)
+                .any(Flags::requires_bit_impl)
+            || self
+                .variants
+                .iter()
+                .flat_map(|x| &x.fields)
+                .map(|x| &x.flags)
+                .any(Flags::requires_bit_impl)
+    }
+
     pub fn requires_borrowing_impl(&self) -> bool {
         self.flags.requires_borrowing_impl()
             || self
@@ -414,6 +478,143 @@ impl Ctxt {
     }
 }
 
+/// Checks that the variant tags are consistent with the item level "tag_type" flag,
+/// and that the catch-all variant, if any, can hold the unrecognized tags.
+fn resolve_tags(ctxt: &Ctxt) -> syn::Result<()> {
+    let mut unknown = ctxt.variants.iter().filter(|x| x.flags.unknown);
+    if let Some(variant) = unknown.next() {
+        if variant.fields.len() != 2 || !variant.flags.tags.is_empty() {
+            return Err(Error::new(
+                variant.name.span(),
+                r#"The "unknown" variant must have exactly two fields, holding the tag and the remaining bytes, and no "tag" flag"#,
+            ));
+        }
+    }
+    if let Some(variant) = unknown.next() {
+        return Err(Error::new(
+            variant.name.span(),
+            r#"Only one variant can be declared "unknown""#,
+        ));
+    }
+
+    if let Some(tag_type) = &ctxt.flags.tag_type {
+        if ctxt.item_type != ItemType::Enum {
+            return Err(Error::new(
+                tag_type.span(),
+                r#""tag_type" flag can only be declared on enums"#,
+            ));
+        }
+
+        if let Some(variant) = ctxt
+            .variants
+            .iter()
+            .find(|x| x.flags.tags.is_empty() && !x.flags.unknown)
+        {
+            return Err(Error::new(
+                variant.name.span(),
+                r#"Every variant must declare a "tag" when the "tag_type" flag is present"#,
+            ));
+        }
+    }
+
+    for variant in ctxt.variants.iter() {
+        if let Some(Expr::Range(range)) = variant.flags.tags.first() {
+            if range.start.is_none() {
+                return Err(Error::new(
+                    range.span(),
+                    "The first tag is written while encoding, so it must be a value or a range with a start",
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the fields of a struct with a message format can be encoded in that format:
+/// each one must have a unique key and no flag that alters the layout of the stream.
+/// Conversely, the key flags are only allowed when the matching format is declared.
+fn resolve_format(ctxt: &Ctxt) -> syn::Result<()> {
+    let fields = ctxt
+        .struct_data
+        .fields
+        .iter()
+        .chain(ctxt.variants.iter().flat_map(|x| &x.fields));
+
+    for field_key in fields.filter_map(|x| x.flags.key.as_ref()) {
+        if ctxt.flags.format != Some(field_key.format) {
+            return Err(Error::new(
+                field_key.key.span(),
+                format!(
+                    r#""{}" flag can only be declared in structs with the "{}" flag"#,
+                    field_key.format.key_flag(),
+                    field_key.format.flag()
+                ),
+            ));
+        }
+    }
+
+    let Some(format) = ctxt.flags.format else {
+        return Ok(());
+    };
+
+    if ctxt.item_type != ItemType::Struct {
+        return Err(Error::new(
+            ctxt.item_name.span(),
+            format!(r#""{}" flag can only be declared on structs"#, format.flag()),
+        ));
+    }
+    if !ctxt.flags.format_compatible(format)
+        || !ctxt.flags.function.is_default()
+        || !ctxt.flags.stream_modifiers.is_empty()
+        || ctxt.flags.ty_mods.is_some()
+        || ctxt.flags.skip
+    {
+        return Err(Error::new(
+            ctxt.item_name.span(),
+            format!(
+                r#"The "{}" flag can only be accompanied by "crate", "validate" and modifier flags"#,
+                format.flag()
+            ),
+        ));
+    }
+
+    let mut keys: Vec<String> = Vec::new();
+    for field in ctxt.struct_data.fields.iter() {
+        if field.flags.skip {
+            continue;
+        }
+        let Some(field_key) = &field.flags.key else {
+            return Err(Error::new(
+                field.name.span(),
+                format!(
+                    r#"Every field must declare a "{}" key when the "{}" flag is present"#,
+                    format.key_flag(),
+                    format.flag()
+                ),
+            ));
+        };
+        if !field.flags.format_compatible(format) {
+            return Err(Error::new(
+                field.name.span(),
+                format!(
+                    r#"Flags that alter the layout of the stream can't be declared in structs with the "{}" flag"#,
+                    format.flag()
+                ),
+            ));
+        }
+
+        // Keys are arbitrary expressions, so only identical ones can be detected here
+        let string = field_key.key.to_token_stream().to_string();
+        if keys.contains(&string) {
+            return Err(Error::new(field_key.key.span(), "Duplicate field key"));
+        }
+        keys.push(string);
+    }
+
+    Ok(())
+}
+
 /// Extracts the Flavor (Struct, Tuple, Unit) of a list of fields as well as the fields themselves
 pub fn extract_fields_and_flavor(
     fields: &Fields,
@@ -477,5 +678,147 @@ pub fn process_field_flags<'a>(
         idx += 1;
     }
 
+    resolve_len_of(&mut fields)?;
+    resolve_checksums(&mut fields)?;
+
     Ok(fields)
 }
+
+/// Links every field with a `len_of` flag to the field whose length it holds,
+/// flattening the size of the latter when the length is measured in elements.
+fn resolve_len_of(fields: &mut [Field]) -> syn::Result<()> {
+    for idx in 0..fields.len() {
+        let Some(len_of) = fields[idx].flags.len_of.clone() else {
+            continue;
+        };
+
+        let Some(target_idx) = fields.iter().position(|x| x.name == len_of.target) else {
+            return Err(Error::new(
+                len_of.target.span(),
+                format!(r#"No field named "{}" was found"#, len_of.target),
+            ));
+        };
+        if fields[idx].flags.skip || fields[idx].flags.is_conditional() {
+            return Err(Error::new(
+                len_of.target.span(),
+                r#""len_of" flag can't be combined with "skip", "if", "since" or "until""#,
+            ));
+        }
+        if target_idx <= idx {
+            return Err(Error::new(
+                len_of.target.span(),
+                r#""len_of" flag must refer to a field declared after it"#,
+            ));
+        }
+
+        if len_of.bytes {
+            // The placeholder written before the target must take as many bytes as the
+            // real length, which only a fixed numerical encoding guarantees
+            let mods = &mut fields[idx].flags.mods;
+            for group in [&mut mods.num, &mut mods.size] {
+                match group.num_encoding {
+                    None => group.num_encoding = Some(NumEncoding::Fixed),
+                    Some(NumEncoding::Fixed) => {}
+                    Some(_) => {
+                        return Err(Error::new(
+                            len_of.target.span(),
+                            r#""len_of bytes" flag requires a fixed numerical encoding"#,
+                        ))
+                    }
+                }
+            }
+        } else {
+            let name = fields[idx].name.clone();
+            let target = &mut fields[target_idx];
+            if target.flags.mods.size_flatten.is_some() {
+                return Err(Error::new(
+                    len_of.target.span(),
+                    r#"The size of this field is already flattened"#,
+                ));
+            }
+            target.flags.mods.size_flatten = Some(parse_quote!(*#name as usize));
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the range of fields covered by every field with a `checksum` flag,
+/// checking that the ranges can be nested into each other.
+fn resolve_checksums(fields: &mut [Field]) -> syn::Result<()> {
+    let find = |fields: &[Field], name: &Ident| {
+        fields.iter().position(|x| x.name == *name).ok_or_else(|| {
+            Error::new(name.span(), format!(r#"No field named "{}" was found"#, name))
+        })
+    };
+
+    for idx in 0..fields.len() {
+        let Some(checksum) = &fields[idx].flags.checksum else {
+            continue;
+        };
+        let span = checksum.algorithm.span();
+
+        // By default, the checksum covers all the fields before it
+        let range = match &checksum.over {
+            Some((from, to, inclusive)) => {
+                let start = find(fields, from)?;
+                let end = find(fields, to)? + *inclusive as usize;
+                if start >= end || end > idx {
+                    return Err(Error::new(
+                        to.span(),
+                        r#""checksum" flag must cover a non-empty range of fields declared before it"#,
+                    ));
+                }
+                start..end
+            }
+            None => 0..idx,
+        };
+        if range.is_empty() {
+            return Err(Error::new(
+                span,
+                r#""checksum" flag must cover a non-empty range of fields declared before it"#,
+            ));
+        }
+        if fields[idx].flags.skip || fields[idx].flags.is_conditional() {
+            return Err(Error::new(
+                span,
+                r#""checksum" flag can't be combined with "skip", "if", "since" or "until""#,
+            ));
+        }
+        if fields[idx].flags.len_of.is_some() {
+            return Err(Error::new(
+                span,
+                r#""checksum" flag can't be combined with "len_of""#,
+            ));
+        }
+
+        fields[idx].flags.checksum.as_mut().unwrap().fields = range;
+    }
+
+    // Ranges must either be disjoint or nested. When nested, the outer range must also
+    // contain the field holding the inner checksum, so that the checksum is visible to it.
+    let checksums: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, x)| x.flags.checksum.as_ref().map(|checksum| (idx, checksum)))
+        .collect();
+    let contains = |outer: &Range<usize>, inner: &Range<usize>, inner_idx: usize| {
+        outer.start <= inner.start && inner.end <= outer.end && outer.contains(&inner_idx)
+    };
+    for (i, (a_idx, a)) in checksums.iter().enumerate() {
+        for (b_idx, b) in checksums.iter().skip(i + 1) {
+            let disjoint = a.fields.end <= b.fields.start || b.fields.end <= a.fields.start;
+            if !disjoint
+                && !contains(&a.fields, &b.fields, *b_idx)
+                && !contains(&b.fields, &a.fields, *a_idx)
+            {
+                return Err(Error::new(
+                    b.algorithm.span(),
+                    r#"The fields covered by this checksum overlap with those covered by another checksum"#,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -28,6 +28,9 @@ pub enum NumEncoding {
     Leb128,
     ProtobufWasteful,
     ProtobufZigzag,
+    BincodeVarint,
+    PostcardVarint,
+    Asn1,
 }
 
 impl Display for NumEncoding {
@@ -37,6 +40,9 @@ impl Display for NumEncoding {
             NumEncoding::Leb128 => "Leb128",
             NumEncoding::ProtobufWasteful => "ProtobufWasteful",
             NumEncoding::ProtobufZigzag => "ProtobufZigzag",
+            NumEncoding::BincodeVarint => "BincodeVarint",
+            NumEncoding::PostcardVarint => "PostcardVarint",
+            NumEncoding::Asn1 => "Asn1",
         }
         .to_owned();
         write!(f, "{}", str)
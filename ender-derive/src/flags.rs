@@ -1,12 +1,16 @@
+use std::ops::Range;
+
 use proc_macro2::Ident;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::ToTokens;
 use syn::spanned::Spanned;
-use syn::{parse_quote, Error, Expr, Lifetime, Path, Type};
+use syn::{parse_quote, Error, Expr, Lifetime, Path, RangeLimits, Type};
 
 use crate::ctxt::Scope;
 use crate::enums::{BitWidth, Endianness, NumEncoding, StrEncoding, StrLen};
-use crate::parse::{Flag, FlattenTarget, Formatting, ModTarget, Modifier, SeekTarget};
+use crate::parse::{
+    Asn1ClassParam, Asn1Param, Flag, FlattenTarget, FormatParam, Formatting, ModTarget, Modifier, SeekTarget,
+};
 use crate::{dollar_crate, ENDER};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -45,6 +49,8 @@ pub enum Function {
     Default,
     Serde(Ident),
     With(Path, Vec<Expr>, Scope),
+    Bits(Expr),
+    SkipValue(Type),
 }
 
 impl Function {
@@ -54,6 +60,68 @@ impl Function {
             _ => false,
         }
     }
+
+    pub fn is_bits(&self) -> bool {
+        matches!(self, Self::Bits(_))
+    }
+
+    pub fn is_skip_value(&self) -> bool {
+        matches!(self, Self::SkipValue(_))
+    }
+}
+
+/// A message format, where each field is encoded as a record identified by a key
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum MessageFormat {
+    /// The protobuf wire format
+    Protobuf,
+    /// Tag-length-value records, with the tag and length encoded according to the settings
+    Tlv,
+}
+
+impl MessageFormat {
+    /// The item flag that selects this format
+    pub fn flag(&self) -> &'static str {
+        match self {
+            MessageFormat::Protobuf => "format",
+            MessageFormat::Tlv => "tlv",
+        }
+    }
+
+    /// The field flag that declares the key of a field in this format
+    pub fn key_flag(&self) -> &'static str {
+        match self {
+            MessageFormat::Protobuf => "field",
+            MessageFormat::Tlv => "id",
+        }
+    }
+}
+
+/// The key that identifies a field in a message format
+#[derive(Clone)]
+pub struct FieldKey {
+    pub format: MessageFormat,
+    pub key: Expr,
+}
+
+/// The class of an ASN.1 tag given to a field
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Asn1Class {
+    Context,
+    Application,
+    Private,
+}
+
+/// The ASN.1 tag given to a field
+#[derive(Clone)]
+pub struct Asn1Tag {
+    pub class: Asn1Class,
+    pub number: Expr,
+    /// The tag wraps the value, instead of replacing its tag
+    pub explicit: bool,
+    /// The field is an `Option`, which is only encoded when present, and
+    /// recognized by its tag while decoding
+    pub optional: bool,
 }
 
 #[derive(Clone)]
@@ -164,6 +232,36 @@ impl ModifierGroup {
 
                 self.num_encoding = Some(NumEncoding::ProtobufZigzag);
             }
+            Modifier::BincodeVarint { kw, .. } => {
+                if self.target.string() {
+                    return Err(Error::new(kw.span(), NOT_STRING));
+                }
+                if self.num_encoding.is_some() {
+                    return Err(Error::new(kw.span(), REPEATED_NUM_ENCODING));
+                }
+
+                self.num_encoding = Some(NumEncoding::BincodeVarint);
+            }
+            Modifier::PostcardVarint { kw, .. } => {
+                if self.target.string() {
+                    return Err(Error::new(kw.span(), NOT_STRING));
+                }
+                if self.num_encoding.is_some() {
+                    return Err(Error::new(kw.span(), REPEATED_NUM_ENCODING));
+                }
+
+                self.num_encoding = Some(NumEncoding::PostcardVarint);
+            }
+            Modifier::Asn1 { kw, .. } => {
+                if self.target.string() {
+                    return Err(Error::new(kw.span(), NOT_STRING));
+                }
+                if self.num_encoding.is_some() {
+                    return Err(Error::new(kw.span(), REPEATED_NUM_ENCODING));
+                }
+
+                self.num_encoding = Some(NumEncoding::Asn1);
+            }
             Modifier::BigEndian { kw, .. } => {
                 if self.endianness.is_some() {
                     return Err(Error::new(kw.span(), REPEATED_ENDIANNESS));
@@ -352,6 +450,7 @@ pub enum StreamModifier {
     Ptr {
         seek: SeekParam,
     },
+    Sized,
 }
 
 impl StreamModifier {
@@ -361,6 +460,34 @@ impl StreamModifier {
             _ => false,
         }
     }
+
+    pub fn is_sized(&self) -> bool {
+        matches!(self, Self::Sized)
+    }
+
+    pub fn is_transform(&self) -> bool {
+        matches!(self, Self::Transform { .. })
+    }
+}
+
+#[derive(Clone)]
+pub struct LenOf {
+    /// Whether the length is measured in bytes rather than in elements
+    pub bytes: bool,
+    /// The name of the field whose length is stored
+    pub target: Ident,
+}
+
+#[derive(Clone)]
+pub struct ChecksumParam {
+    /// The checksum algorithm, either a built-in name or the path to a type
+    pub algorithm: Path,
+    /// The names of the first and last fields the checksum is computed over, and whether
+    /// the last one is included
+    pub over: Option<(Ident, Ident, bool)>,
+    /// The indices of the fields the checksum is computed over, resolved
+    /// once all the fields are known
+    pub fields: Range<usize>,
 }
 
 #[derive(Clone)]
@@ -394,6 +521,12 @@ pub struct Flags {
     /// If present indicates a field should only be encoded or decoded if the given condition
     /// is true. During decoding, if the condition is false, the default value will be used
     pub condition: Option<Expr>,
+    /// If present, the field is only encoded or decoded if the format version in the context
+    /// is greater than or equal to the given one
+    pub since: Option<Expr>,
+    /// If present, the field is only encoded or decoded if the format version in the context
+    /// is less than or equal to the given one
+    pub until: Option<Expr>,
     /// Modifiers to the underlying Write/Read object itself. Indicate something should be
     /// encrypted or compressed before being encoded or decoded.
     pub stream_modifiers: Vec<StreamModifier>,
@@ -410,6 +543,61 @@ pub struct Flags {
     pub pos_tracker: Option<Ident>,
     /// Forces a `Seek*` implementation
     pub force_seek: bool,
+    /// Also generates the `Async*` implementation
+    ///
+    /// Can only be applied to items
+    pub asynchronous: bool,
+    /// Before encoding this field or item, align the bit stream to the next byte boundary
+    pub bit_align: bool,
+    /// Forces a `Bit*` implementation
+    pub force_bits: bool,
+    /// A constant that is written before this field or item, and checked while decoding
+    pub magic: Option<Expr>,
+    /// Before encoding this field or item, pad the stream with the given number of bytes
+    pub pad: Option<Expr>,
+    /// Before encoding this field or item, pad the stream until its position is
+    /// a multiple of the given alignment
+    pub align: Option<Expr>,
+    /// The value of the padding bytes, zero by default
+    pub fill: Option<Expr>,
+    /// While decoding, check that the padding bytes have the expected value
+    pub check_padding: bool,
+    /// This field holds the length of another field, and is computed from it while encoding
+    ///
+    /// Can only be applied to fields
+    pub len_of: Option<LenOf>,
+    /// This field holds a checksum of other fields, which is computed while encoding
+    /// and verified while decoding
+    ///
+    /// Can only be applied to fields
+    pub checksum: Option<ChecksumParam>,
+    /// The values of the tag that identifies this variant. The first one is written while
+    /// encoding, and any of them is accepted while decoding
+    ///
+    /// Can only be applied to variants
+    pub tags: Vec<Expr>,
+    /// The type of the tag that identifies each variant, instead of a numerical index
+    ///
+    /// Can only be applied to items
+    pub tag_type: Option<Type>,
+    /// This variant catches all the unrecognized tags while decoding, storing the tag
+    /// and the remaining bytes
+    ///
+    /// Can only be applied to variants
+    pub unknown: bool,
+    /// The fields of this struct are encoded as the keyed records of the given message format
+    ///
+    /// Can only be applied to items
+    pub format: Option<MessageFormat>,
+    /// The key that identifies this field in the message format of the struct
+    ///
+    /// Can only be applied to fields
+    pub key: Option<FieldKey>,
+    /// The ASN.1 tag of this field, which replaces the tag of its value, or wraps it
+    /// if explicit
+    ///
+    /// Can only be applied to fields
+    pub asn1: Option<Asn1Tag>,
 }
 
 impl Flags {
@@ -424,34 +612,110 @@ impl Flags {
             mods: AllModifiers::new(),
             validate: None,
             condition: None,
+            since: None,
+            until: None,
             stream_modifiers: Vec::new(),
             borrow: None,
             seek: None,
             pos_tracker: None,
             force_seek: false,
+            asynchronous: false,
+            bit_align: false,
+            force_bits: false,
+            magic: None,
+            pad: None,
+            align: None,
+            fill: None,
+            check_padding: false,
+            len_of: None,
+            checksum: None,
+            tags: Vec::new(),
+            tag_type: None,
+            unknown: false,
+            format: None,
+            key: None,
+            asn1: None,
         }
     }
 
     pub fn requires_seeking_impl(&self) -> bool {
         self.force_seek
             || self.seek.is_some()
-            || self.pos_tracker.is_some()
             || self.stream_modifiers.iter().any(StreamModifier::is_ptr)
+            || self.len_of.as_ref().is_some_and(|x| x.bytes)
+    }
+
+    /// Whether the stream position is needed, which streams that can't seek may report too
+    pub fn requires_position_impl(&self) -> bool {
+        self.pos_tracker.is_some() || self.align.is_some()
     }
     
+    pub fn requires_bit_impl(&self) -> bool {
+        self.force_bits || self.bit_align || self.function.is_bits()
+    }
+
     pub fn requires_borrowing_impl(&self) -> bool {
         self.borrow.is_some()
     }
 
+    /// Whether the field is only encoded or decoded under some condition
+    pub fn is_conditional(&self) -> bool {
+        self.condition.is_some() || self.since.is_some() || self.until.is_some()
+    }
+
     pub fn skip_compatible(&self) -> bool {
         self.function.is_default()
             && self.mods.empty()
-            && self.condition.is_none()
+            && !self.is_conditional()
             && self.stream_modifiers.is_empty()
             && self.ty_mods.is_none()
             && self.borrow.is_none()
             && self.seek.is_none()
             && self.pos_tracker.is_none()
+            && !self.bit_align
+            && self.magic.is_none()
+            && self.pad.is_none()
+            && self.align.is_none()
+            && self.len_of.is_none()
+            && self.checksum.is_none()
+            && self.asn1.is_none()
+    }
+
+    /// Whether the field or item can be part of a message format, where the layout
+    /// of the stream is dictated by the format itself.
+    ///
+    /// In the tlv format each value is encoded in its own byte-counted region, so
+    /// the flags that only affect the value itself are allowed.
+    pub fn format_compatible(&self, format: MessageFormat) -> bool {
+        let value_compatible = match format {
+            MessageFormat::Protobuf => {
+                self.function.is_default()
+                    && self.stream_modifiers.is_empty()
+                    && self.mods.bool_flatten.is_none()
+                    && self.mods.variant_flatten.is_none()
+                    && self.mods.size_flatten.is_none()
+            }
+            MessageFormat::Tlv => {
+                !self.function.is_bits() && !self.stream_modifiers.iter().any(StreamModifier::is_ptr)
+            }
+        };
+
+        value_compatible
+            && self.condition.is_none()
+            && self.borrow.is_none()
+            && self.seek.is_none()
+            && self.pos_tracker.is_none()
+            && !self.force_seek
+            && !self.bit_align
+            && !self.force_bits
+            && self.magic.is_none()
+            && self.pad.is_none()
+            && self.align.is_none()
+            && self.fill.is_none()
+            && !self.check_padding
+            && self.len_of.is_none()
+            && self.checksum.is_none()
+            && self.asn1.is_none()
     }
 }
 
@@ -460,8 +724,16 @@ impl Flags {
     pub fn apply(&mut self, flag: Flag, scope: Scope) -> syn::Result<()> {
         const MULTIPLE_FUNCTION_MODS: &str = "Multiple function-modifier flags declared";
         const MULTIPLE_TY_MODS: &str = "Multiple type-modifier flags declared";
+        const MULTIPLE_FORMATS: &str = "Multiple message format flags declared";
+        const MULTIPLE_KEYS: &str = "Multiple field key flags declared";
 
         let span = flag.span();
+        if self.target == FlagTarget::Variant
+            && !matches!(flag, Flag::Tag { .. } | Flag::Unknown { .. })
+        {
+            return Err(Error::new(span, "This flag can't be declared on variants"));
+        }
+
         match flag {
             Flag::Crate { crate_name, .. } => {
                 if self.target != FlagTarget::Item {
@@ -604,6 +876,34 @@ impl Flags {
 
                 self.condition = Some(expr);
             }
+            Flag::Since { version, .. } => {
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""since" flag can only be applied to fields"#,
+                    ));
+                }
+
+                if self.since.is_some() {
+                    return Err(Error::new(span, r#""since" flag declared more than once"#));
+                }
+
+                self.since = Some(version);
+            }
+            Flag::Until { version, .. } => {
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""until" flag can only be applied to fields"#,
+                    ));
+                }
+
+                if self.until.is_some() {
+                    return Err(Error::new(span, r#""until" flag declared more than once"#));
+                }
+
+                self.until = Some(version);
+            }
             Flag::Redir { path, args, .. } => {
                 let args = args
                     .map(|x| x.args.into_iter().collect::<Vec<Expr>>())
@@ -621,6 +921,13 @@ impl Flags {
                     seek: SeekParam { target, seek },
                 })
             }
+            Flag::Sized { .. } => {
+                if self.stream_modifiers.iter().any(StreamModifier::is_sized) {
+                    return Err(Error::new(span, r#""sized" flag declared more than once"#));
+                }
+
+                self.stream_modifiers.push(StreamModifier::Sized)
+            }
             Flag::En { .. } | Flag::De { .. } => {
                 return Err(Error::new(
                     span,
@@ -672,6 +979,327 @@ impl Flags {
 
                 self.force_seek = true;
             }
+            Flag::Async { .. } => {
+                if self.asynchronous {
+                    return Err(Error::new(span, r#""async" flag declared more than once"#));
+                }
+                if self.target != FlagTarget::Item {
+                    return Err(Error::new(
+                        span,
+                        r#""async" flag can only be declared on items"#,
+                    ));
+                }
+
+                self.asynchronous = true;
+            }
+            Flag::Bits { bits, .. } => {
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""bits" flag can only be declared on fields"#,
+                    ));
+                }
+                if !self.function.is_default() {
+                    return Err(Error::new(span, MULTIPLE_FUNCTION_MODS));
+                }
+
+                self.function = Function::Bits(bits);
+            }
+            Flag::SkipValue { ty, .. } => {
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""skip_value" flag can only be declared on fields"#,
+                    ));
+                }
+                if scope != Scope::Decode {
+                    return Err(Error::new(
+                        span,
+                        r#""skip_value" flag can only be declared in the "de" scope"#,
+                    ));
+                }
+                if !self.function.is_default() {
+                    return Err(Error::new(span, MULTIPLE_FUNCTION_MODS));
+                }
+
+                self.function = Function::SkipValue(ty);
+            }
+            Flag::BitAlign { .. } => {
+                if self.bit_align {
+                    return Err(Error::new(
+                        span,
+                        r#""bit_align" flag declared more than once"#,
+                    ));
+                }
+                if self.target == FlagTarget::Variant {
+                    return Err(Error::new(
+                        span,
+                        r#""bit_align" flag can only be declared on fields or items"#,
+                    ));
+                }
+
+                self.bit_align = true;
+            }
+            Flag::Bitwise { .. } => {
+                if self.force_bits {
+                    return Err(Error::new(span, r#""bitwise" flag declared more than once"#));
+                }
+                if self.target != FlagTarget::Item {
+                    return Err(Error::new(
+                        span,
+                        r#""bitwise" flag can only be declared on items"#,
+                    ));
+                }
+
+                self.force_bits = true;
+            }
+            Flag::Magic { magic, .. } => {
+                if self.magic.is_some() {
+                    return Err(Error::new(span, r#""magic" flag declared more than once"#));
+                }
+                if self.target == FlagTarget::Variant {
+                    return Err(Error::new(
+                        span,
+                        r#""magic" flag can only be declared on fields or items"#,
+                    ));
+                }
+
+                self.magic = Some(magic);
+            }
+            Flag::LenOf { bytes, target, .. } => {
+                if self.len_of.is_some() {
+                    return Err(Error::new(span, r#""len_of" flag declared more than once"#));
+                }
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""len_of" flag can only be declared on fields"#,
+                    ));
+                }
+
+                self.len_of = Some(LenOf {
+                    bytes: bytes.is_some(),
+                    target,
+                });
+            }
+            Flag::Pad { pad, .. } => {
+                if self.pad.is_some() {
+                    return Err(Error::new(span, r#""pad" flag declared more than once"#));
+                }
+                if self.target == FlagTarget::Variant {
+                    return Err(Error::new(
+                        span,
+                        r#""pad" flag can only be declared on fields or items"#,
+                    ));
+                }
+
+                self.pad = Some(pad);
+            }
+            Flag::Align { align, .. } => {
+                if self.align.is_some() {
+                    return Err(Error::new(span, r#""align" flag declared more than once"#));
+                }
+                if self.target == FlagTarget::Variant {
+                    return Err(Error::new(
+                        span,
+                        r#""align" flag can only be declared on fields or items"#,
+                    ));
+                }
+
+                self.align = Some(align);
+            }
+            Flag::Fill { fill, .. } => {
+                if self.fill.is_some() {
+                    return Err(Error::new(span, r#""fill" flag declared more than once"#));
+                }
+
+                self.fill = Some(fill);
+            }
+            Flag::CheckPadding { .. } => {
+                if self.check_padding {
+                    return Err(Error::new(
+                        span,
+                        r#""check_padding" flag declared more than once"#,
+                    ));
+                }
+
+                self.check_padding = true;
+            }
+            Flag::Checksum {
+                algorithm, over, ..
+            } => {
+                if self.checksum.is_some() {
+                    return Err(Error::new(
+                        span,
+                        r#""checksum" flag declared more than once"#,
+                    ));
+                }
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""checksum" flag can only be declared on fields"#,
+                    ));
+                }
+
+                self.checksum = Some(ChecksumParam {
+                    algorithm,
+                    over: over.map(|x| {
+                        let inclusive = matches!(x.limits, RangeLimits::Closed(_));
+                        (x.from, x.to, inclusive)
+                    }),
+                    fields: 0..0,
+                });
+            }
+            Flag::Tag { tags, .. } => {
+                if !self.tags.is_empty() {
+                    return Err(Error::new(span, r#""tag" flag declared more than once"#));
+                }
+                if self.target != FlagTarget::Variant {
+                    return Err(Error::new(
+                        span,
+                        r#""tag" flag can only be declared on variants"#,
+                    ));
+                }
+
+                self.tags = tags.into_iter().collect();
+            }
+            Flag::TagType { ty, .. } => {
+                if self.tag_type.is_some() {
+                    return Err(Error::new(
+                        span,
+                        r#""tag_type" flag declared more than once"#,
+                    ));
+                }
+                if self.target != FlagTarget::Item {
+                    return Err(Error::new(
+                        span,
+                        r#""tag_type" flag can only be declared on items"#,
+                    ));
+                }
+
+                self.tag_type = Some(ty);
+            }
+            Flag::Unknown { .. } => {
+                if self.unknown {
+                    return Err(Error::new(
+                        span,
+                        r#""unknown" flag declared more than once"#,
+                    ));
+                }
+                if self.target != FlagTarget::Variant {
+                    return Err(Error::new(
+                        span,
+                        r#""unknown" flag can only be declared on variants"#,
+                    ));
+                }
+
+                self.unknown = true;
+            }
+            Flag::Format { format, .. } => {
+                if self.format.is_some() {
+                    return Err(Error::new(span, MULTIPLE_FORMATS));
+                }
+                if self.target != FlagTarget::Item {
+                    return Err(Error::new(
+                        span,
+                        r#""format" flag can only be declared on items"#,
+                    ));
+                }
+
+                self.format = Some(match format {
+                    FormatParam::Protobuf { .. } => MessageFormat::Protobuf,
+                });
+            }
+            Flag::Field { key, .. } => {
+                if self.key.is_some() {
+                    return Err(Error::new(span, MULTIPLE_KEYS));
+                }
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""field" flag can only be declared on fields"#,
+                    ));
+                }
+
+                self.key = Some(FieldKey {
+                    format: MessageFormat::Protobuf,
+                    key,
+                });
+            }
+            Flag::Tlv { .. } => {
+                if self.format.is_some() {
+                    return Err(Error::new(span, MULTIPLE_FORMATS));
+                }
+                if self.target != FlagTarget::Item {
+                    return Err(Error::new(
+                        span,
+                        r#""tlv" flag can only be declared on items"#,
+                    ));
+                }
+
+                self.format = Some(MessageFormat::Tlv);
+            }
+            Flag::Id { key, .. } => {
+                if self.key.is_some() {
+                    return Err(Error::new(span, MULTIPLE_KEYS));
+                }
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""id" flag can only be declared on fields"#,
+                    ));
+                }
+
+                self.key = Some(FieldKey {
+                    format: MessageFormat::Tlv,
+                    key,
+                });
+            }
+            Flag::Asn1 {
+                class,
+                number,
+                params,
+                ..
+            } => {
+                if self.asn1.is_some() {
+                    return Err(Error::new(
+                        span,
+                        r#""asn1" flag declared more than once"#,
+                    ));
+                }
+                if self.target != FlagTarget::Field {
+                    return Err(Error::new(
+                        span,
+                        r#""asn1" flag can only be declared on fields"#,
+                    ));
+                }
+
+                let class = match class {
+                    Asn1ClassParam::Context { .. } => Asn1Class::Context,
+                    Asn1ClassParam::Application { .. } => Asn1Class::Application,
+                    Asn1ClassParam::Private { .. } => Asn1Class::Private,
+                };
+                let mut tag = Asn1Tag {
+                    class,
+                    number,
+                    explicit: false,
+                    optional: false,
+                };
+                for (_, param) in params {
+                    let (set, name, span) = match param {
+                        Asn1Param::Explicit { kw } => (&mut tag.explicit, "explicit", kw.span),
+                        Asn1Param::Optional { kw } => (&mut tag.optional, "optional", kw.span),
+                    };
+                    if *set {
+                        return Err(Error::new(
+                            span,
+                            format!(r#""asn1" flag: "{}" declared more than once"#, name),
+                        ));
+                    }
+                    *set = true;
+                }
+                self.asn1 = Some(tag);
+            }
         }
 
         if self.skip && !self.skip_compatible() {
@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::Error;
+
+use crate::ctxt::{Ctxt, Field, Target};
+use crate::flags::Asn1Class;
+use crate::generator::ASYNC_ASN1;
+
+impl Field {
+    /// Wraps the encode or decode code of this field according to its ASN.1 tag, if any:
+    /// the tag either replaces the one of the value, or is written around it if explicit,
+    /// and optional fields are only encoded when present, and recognized by their tag
+    /// while decoding
+    pub(super) fn derive_asn1(&self, ctxt: &Ctxt, input: TokenStream2) -> syn::Result<TokenStream2> {
+        let Some(asn1) = &self.flags.asn1 else {
+            return Ok(input);
+        };
+        if ctxt.asynchronous {
+            return Err(Error::new(self.name.span(), ASYNC_ASN1));
+        }
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref field_name = self.name;
+        let ref number = asn1.number;
+        let explicit = asn1.explicit;
+        let optional = asn1.optional;
+
+        let constructor = match asn1.class {
+            Asn1Class::Context => quote!(context),
+            Asn1Class::Application => quote!(application),
+            Asn1Class::Private => quote!(private),
+        };
+        let tag = quote!(
+            #crate_name::asn1::Tag::#constructor(#number).constructed(#explicit)
+        );
+
+        // The value of an optional field is flattened, since its presence is given by the tag
+        let present = if optional {
+            quote!({
+                #encoder.ctxt.bool_flatten = ::core::option::Option::Some(true);
+                #input
+            })
+        } else {
+            input.clone()
+        };
+
+        Ok(match ctxt.target {
+            Target::Encode => {
+                // The encode code is duplicated, because it's first used to measure the size
+                let tagged = if explicit {
+                    quote!(
+                        #crate_name::Encoder::write_asn1_tlv(&mut * #encoder, #tag, |#encoder| { Ok({ #present }) }, |#encoder| { Ok({ #present }) })?
+                    )
+                } else {
+                    quote!({
+                        #encoder.ctxt.asn1_tag = ::core::option::Option::Some(#tag);
+                        #present
+                    })
+                };
+
+                if optional {
+                    quote!(
+                        if ::core::option::Option::is_some(#field_name) {
+                            #tagged
+                        }
+                    )
+                } else {
+                    tagged
+                }
+            }
+            Target::Decode => {
+                let tagged = if explicit {
+                    quote!(
+                        #crate_name::Encoder::read_asn1_tlv(&mut * #encoder, #tag, |#encoder| { Ok({ #present }) })?
+                    )
+                } else {
+                    quote!({
+                        #encoder.ctxt.asn1_tag = ::core::option::Option::Some(#tag);
+                        #present
+                    })
+                };
+
+                if optional {
+                    quote!({
+                        let __present = match #crate_name::Encoder::peek_asn1_tag(&mut * #encoder)? {
+                            ::core::option::Option::Some(__tag) => __tag.matches(&#tag),
+                            ::core::option::Option::None => false,
+                        };
+                        if __present {
+                            #tagged
+                        } else {
+                            #encoder.ctxt.bool_flatten = ::core::option::Option::Some(false);
+                            #input
+                        }
+                    })
+                } else {
+                    tagged
+                }
+            }
+        })
+    }
+}
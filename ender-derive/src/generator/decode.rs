@@ -1,6 +1,10 @@
 use crate::ctxt::{Ctxt, Field, Flavor, ItemType, Struct, Variant};
 use crate::flags::FlagTarget;
-use crate::generator::{ConstCode, RefCode};
+use crate::generator::{
+    byte_len_source, checksum_ident, derive_checksums, ConstCode, LenIdents, RefCode,
+    ASYNC_UNKNOWN,
+};
+use syn::Error;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, TokenStreamExt};
 
@@ -19,12 +23,18 @@ impl Ctxt {
                     item_name.to_string(),
                 )?;
                 let seek = self.flags.derive_seek(self)?;
+                let bit_align = self.flags.derive_bit_align(self)?;
+                let padding = self.flags.derive_padding(self)?;
+                let magic = self.flags.derive_magic(self)?;
                 let pos_tracker = self.flags.derive_pos_tracker(self)?;
 
                 Ok(quote!(
                     #pos_tracker
                     #pre
                     #seek
+                    #bit_align
+                    #padding
+                    #magic
                     let __val: Self = { #modified };
                     #post
                     #crate_name::EncodingResult::Ok(__val)
@@ -33,6 +43,7 @@ impl Ctxt {
             ItemType::Enum => {
                 let ref crate_name = self.flags.crate_name;
                 let ref encoder = self.encoder;
+                let ref encoder_generic = self.encoder_generic;
 
                 // Edge case for 0-variant enums
                 if self.variants.len() == 0 {
@@ -46,33 +57,88 @@ impl Ctxt {
                 let mut variant_code = TokenStream2::new();
 
                 let read_variant = if self.enum_repr.signed() {
-                    quote!(
-                        #crate_name::Encoder::read_ivariant(#encoder)?
-                    )
+                    if self.asynchronous {
+                        quote!(
+                            #crate_name::Encoder::read_ivariant_async(#encoder).await?
+                        )
+                    } else {
+                        quote!(
+                            #crate_name::Encoder::read_ivariant(#encoder)?
+                        )
+                    }
                 } else {
-                    quote!(
-                        #crate_name::Encoder::read_uvariant(#encoder)?
-                    )
+                    if self.asynchronous {
+                        quote!(
+                            #crate_name::Encoder::read_uvariant_async(#encoder).await?
+                        )
+                    } else {
+                        quote!(
+                            #crate_name::Encoder::read_uvariant(#encoder)?
+                        )
+                    }
                 };
 
                 for variant in self.variants.iter() {
                     const_code.append(variant);
-                    variant_code.append_all(variant.derive_decode(self)?);
+                    if !variant.flags.unknown {
+                        variant_code.append_all(variant.derive_decode(self)?);
+                    }
                 }
 
-                let body = quote!(
-                    match #read_variant {
-                        #variant_code
-                        __unknown_variant => #crate_name::EncodingResult::Err(#crate_name::EncodingError::InvalidVariant(#crate_name::Opaque::from(__unknown_variant))),
-                    }?
-                );
+                // Unrecognized tags are either caught by the "unknown" variant or rejected
+                let unknown = match self.variants.iter().find(|x| x.flags.unknown) {
+                    Some(variant) => variant.derive_decode_unknown(self)?,
+                    None if self.flags.tag_type.is_some() => quote!(
+                        #crate_name::EncodingResult::Err(#crate_name::EncodingError::invalid_tag(&__tag))
+                    ),
+                    None => quote!(
+                        #crate_name::EncodingResult::Err(#crate_name::EncodingError::InvalidVariant(#crate_name::Opaque::from(__tag)))
+                    ),
+                };
+
+                let enum_header = self.derive_enum_header();
+                let body = if let Some(tag_type) = &self.flags.tag_type {
+                    let read_tag = if self.asynchronous {
+                        quote!(<#tag_type as #crate_name::AsyncDecode<#encoder_generic>>::decode_async(#encoder).await?)
+                    } else {
+                        quote!(<#tag_type as #crate_name::Decode<_>>::decode(#encoder)?)
+                    };
+
+                    quote!(
+                        let __tag: #tag_type = #read_tag;
+                        match () {
+                            #variant_code
+                            _ => { #unknown },
+                        }?
+                    )
+                } else if self.tagged() {
+                    let enum_repr = self.enum_repr;
+                    // Tags can be arbitrary expressions, so they are matched with guards
+                    quote!(
+                        let __tag: #enum_repr = #read_variant;
+                        match () {
+                            #variant_code
+                            _ => { #unknown },
+                        }?
+                    )
+                } else {
+                    quote!(
+                        match #read_variant {
+                            #variant_code
+                            __tag => { #unknown },
+                        }?
+                    )
+                };
                 let modified = self.flags.derive_stream_modifiers(
                     self,
-                    body,
+                    quote!(#enum_header #body),
                     FlagTarget::Item,
                     item_name.to_string(),
                 )?;
                 let seek = self.flags.derive_seek(self)?;
+                let bit_align = self.flags.derive_bit_align(self)?;
+                let padding = self.flags.derive_padding(self)?;
+                let magic = self.flags.derive_magic(self)?;
                 let pos_tracker = self.flags.derive_pos_tracker(self)?;
 
                 Ok(quote!(
@@ -81,6 +147,9 @@ impl Ctxt {
                     #pos_tracker
                     #pre
                     #seek
+                    #bit_align
+                    #padding
+                    #magic
                     let __val: Self = { #modified };
                     #post
                     #crate_name::EncodingResult::Ok(__val)
@@ -92,7 +161,7 @@ impl Ctxt {
 
 impl Variant {
     /// Generates the match arm for this variant
-    fn decode_match(&self, ctxt: &Ctxt, body: TokenStream2) -> syn::Result<TokenStream2> {
+    pub(super) fn decode_match(&self, ctxt: &Ctxt, body: TokenStream2) -> syn::Result<TokenStream2> {
         let ref index = self.index.ident;
         let ref name = self.name;
         let body = self.flags.derive_stream_modifiers(
@@ -102,9 +171,47 @@ impl Variant {
             name.to_string(),
         )?;
 
-        Ok(quote!(
-            #index => { #body },
-        ))
+        Ok(if ctxt.tagged() {
+            let condition = self.derive_tag_condition();
+            quote!(
+                _ if #condition => { #body },
+            )
+        } else {
+            quote!(
+                #index => { #body },
+            )
+        })
+    }
+
+    /// Generates the decode code for the catch-all variant, which preserves the
+    /// unrecognized tag along with the remaining bytes
+    fn derive_decode_unknown(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        if ctxt.asynchronous {
+            return Err(Error::new_spanned(&self.name, ASYNC_UNKNOWN));
+        }
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref tag = self.fields[0].name;
+        let ref data = self.fields[1].name;
+
+        let tag_value = if ctxt.flags.tag_type.is_some() {
+            quote!(__tag)
+        } else {
+            quote!(::core::convert::From::from(#crate_name::Opaque::from(__tag)))
+        };
+        let aggregate = self.decode_aggregate(ctxt)?;
+
+        self.flags.derive_stream_modifiers(
+            ctxt,
+            quote!(
+                let #tag = #tag_value;
+                let #data = #crate_name::Encoder::read_remaining(#encoder)?;
+                #aggregate
+            ),
+            FlagTarget::Variant,
+            self.name.to_string(),
+        )
     }
 
     /// Generates code for aggregating together the decoded fields of an enum
@@ -135,17 +242,25 @@ impl Variant {
     /// Generates the decode code for this variant, including the match arm
     pub fn derive_decode(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
         let mut ref_code = RefCode::new(ctxt);
-        let mut field_code = TokenStream2::new();
+        let mut field_code = Vec::new();
 
         for field in self.fields.iter() {
-            field_code.append_all(field.derive_decode(ctxt, &mut ref_code)?);
+            let code = field.derive_decode(ctxt, &mut ref_code)?;
+            field_code.push(match byte_len_source(&self.fields, field) {
+                Some(len_field) => len_field.derive_len_check(ctxt, code),
+                None => code,
+            });
         }
 
+        let field_code = derive_checksums(ctxt, &self.fields, field_code)?;
+        let fields_len = ctxt.derive_fields_len(&self.fields);
+
         let aggregate = self.decode_aggregate(ctxt)?;
 
         self.decode_match(
             ctxt,
             quote!(
+                #fields_len
                 #field_code
 
                 #aggregate
@@ -156,7 +271,7 @@ impl Variant {
 
 impl Struct {
     /// Generates code for aggregating together the decoded fields of a struct
-    fn decode_aggregate(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+    pub(super) fn decode_aggregate(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
         let fields = self.fields.iter().map(|x| &x.name);
         let ref crate_name = ctxt.flags.crate_name;
         Ok(match self.flavor {
@@ -181,15 +296,23 @@ impl Struct {
     /// Generates the decode code for this struct
     pub fn derive_decode(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
         let mut ref_code = RefCode::new(ctxt);
-        let mut field_code = TokenStream2::new();
+        let mut field_code = Vec::new();
 
         for field in self.fields.iter() {
-            field_code.append_all(field.derive_decode(ctxt, &mut ref_code)?);
+            let code = field.derive_decode(ctxt, &mut ref_code)?;
+            field_code.push(match byte_len_source(&self.fields, field) {
+                Some(len_field) => len_field.derive_len_check(ctxt, code),
+                None => code,
+            });
         }
 
+        let field_code = derive_checksums(ctxt, &self.fields, field_code)?;
+        let fields_len = ctxt.derive_fields_len(&self.fields);
+
         let aggregate = self.decode_aggregate(ctxt)?;
 
         Ok(quote!(
+            #fields_len
             #field_code
 
             #aggregate ?
@@ -200,48 +323,82 @@ impl Struct {
 impl Field {
     pub fn derive_decode(&self, ctxt: &Ctxt, ref_code: &mut RefCode) -> syn::Result<TokenStream2> {
         let ref field_name = self.name;
-        let ref field_accessor = self.accessor;
         let ref field_ty = self.ty;
-        let ref default = self.flags.default;
 
-        let (pre, post) = self.flags.mods.derive(ctxt)?;
-        let decode = if let Some(converter) = &self.flags.ty_mods {
-            converter.convert_from(
-                self,
-                self.flags
-                    .function
-                    .derive_decode(ctxt, converter.ty(), &self)?,
-            )?
+        let decode = self.derive_decode_block(ctxt, ref_code)?;
+        let pos_tracker = self.flags.derive_pos_tracker(ctxt)?;
+
+        ref_code.append(self);
+        let validate = self.flags.derive_validation(ctxt, Some(&ref_code))?;
+
+        // The stored checksum is compared to the one computed from the fields it covers
+        let checksum = if self.flags.checksum.is_some() {
+            let ref crate_name = ctxt.flags.crate_name;
+            let digest = checksum_ident(self);
+            quote!(
+                {
+                    let __computed = <#field_ty as ::core::convert::From<_>>::from(#digest);
+                    if #field_name != __computed {
+                        return ::core::result::Result::Err(#crate_name::EncodingError::ChecksumMismatch {
+                            computed: #crate_name::Opaque::from(__computed),
+                            stored: #crate_name::Opaque::from(#field_name),
+                        });
+                    }
+                }
+            )
         } else {
-            self.flags.function.derive_decode(ctxt, field_ty, &self)?
+            TokenStream2::new()
         };
-        let modified = self.flags.derive_stream_modifiers(
-            ctxt,
-            decode,
-            FlagTarget::Field,
-            field_accessor.to_string(),
-        )?;
+
+        let decode = quote!(
+            #pos_tracker
+            let #field_name: #field_ty = #decode;
+            #checksum
+            #validate
+        );
+
+        Ok(decode)
+    }
+
+    /// Generates the block evaluating to the decoded value of this field, or to its default
+    /// value if it's skipped or its condition is false
+    pub(super) fn derive_decode_block(
+        &self,
+        ctxt: &Ctxt,
+        ref_code: &RefCode,
+    ) -> syn::Result<TokenStream2> {
+        let ref field_ty = self.ty;
+        let ref default = self.flags.default;
+
+        let (pre, modified, post) = self.derive_decode_value(ctxt)?;
         let seek = self.flags.derive_seek(ctxt)?;
-        let pos_tracker = self.flags.derive_pos_tracker(ctxt)?;
+        let bit_align = self.flags.derive_bit_align(ctxt)?;
+        let padding = self.flags.derive_padding(ctxt)?;
+        let magic = self.flags.derive_magic(ctxt)?;
+        let null = ctxt.derive_null();
 
-        let decode = if self.flags.skip {
+        Ok(if self.flags.skip {
             quote!(
                 {
                     #ref_code
                     #default
                 }
             )
-        } else if let Some(condition) = &self.flags.condition {
+        } else if let Some(condition) = self.flags.derive_condition(ctxt) {
             quote!(
                 {
                     #ref_code
                     if #condition {
                         #pre
                         #seek
+                        #bit_align
+                        #padding
+                        #magic
                         let __val: #field_ty = #modified;
                         #post
                         __val
                     } else {
+                        #null
                         #default
                     }
                 }
@@ -252,22 +409,75 @@ impl Field {
                     #ref_code
                     #pre
                     #seek
+                    #bit_align
+                    #padding
+                    #magic
                     let __val: #field_ty = #modified;
                     #post
                     __val
                 }
             )
+        })
+    }
+
+    /// Generates the code that decodes the value of this field, split into the
+    /// modifiers that come before it, the decoding itself, and the modifiers that come after it
+    pub(super) fn derive_decode_value(
+        &self,
+        ctxt: &Ctxt,
+    ) -> syn::Result<(TokenStream2, TokenStream2, TokenStream2)> {
+        let ref field_accessor = self.accessor;
+        let ref field_ty = self.ty;
+
+        let (pre, post) = self.flags.mods.derive(ctxt)?;
+        // A skipped region evaluates to the default value of the field, which isn't converted
+        let converter = self.flags.ty_mods.as_ref().filter(|_| !self.flags.function.is_skip_value());
+        let decode = if let Some(converter) = converter {
+            converter.convert_from(
+                self,
+                self.flags
+                    .function
+                    .derive_decode(ctxt, converter.ty(), &self)?,
+            )?
+        } else {
+            self.flags.function.derive_decode(ctxt, field_ty, &self)?
         };
+        let modified = self.flags.derive_stream_modifiers(
+            ctxt,
+            decode,
+            FlagTarget::Field,
+            field_accessor.to_string(),
+        )?;
+        let modified = self.derive_asn1(ctxt, modified)?;
 
-        ref_code.append(self);
-        let validate = self.flags.derive_validation(ctxt, Some(&ref_code))?;
+        Ok((pre, modified, post))
+    }
 
-        let decode = quote!(
-            #pos_tracker
-            let #field_name: #field_ty = #decode;
-            #validate
-        );
+    /// Wraps the decode code of `target`, whose length in bytes is stored in
+    /// this field, so that the amount of bytes actually read is checked against this field
+    pub fn derive_len_check(&self, ctxt: &Ctxt, target: TokenStream2) -> TokenStream2 {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref field_name = self.name;
+        let target_name = self.flags.len_of.as_ref().map(|x| &x.target);
+        let LenIdents {
+            ref start,
+            ref stop,
+            ..
+        } = LenIdents::new(self);
+        let position = ctxt.derive_stream_position();
 
-        Ok(decode)
+        quote!(
+            let #start = #position;
+            #target
+            let #stop = #position;
+            if (#stop - #start) as usize != #field_name as usize {
+                return ::core::result::Result::Err(#crate_name::val_error!(
+                    "Expected `{}` to be {} bytes long, but it was {} bytes long",
+                    ::core::stringify!(#target_name),
+                    #field_name,
+                    #stop - #start
+                ));
+            }
+        )
     }
 }
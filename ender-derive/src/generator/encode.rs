@@ -2,8 +2,12 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, ToTokens, TokenStreamExt};
 
 use crate::ctxt::{Ctxt, Field, Flavor, ItemType, Struct, Variant};
-use crate::flags::FlagTarget;
-use crate::generator::{ConstCode, RefCode};
+use crate::flags::{FlagTarget, Function};
+use crate::generator::{
+    byte_len_source, checksum_ident, derive_checksums, ConstCode, LenIdents, RefCode,
+    ASYNC_UNKNOWN,
+};
+use syn::Error;
 
 impl Ctxt {
     pub(super) fn derive_encode(&self) -> syn::Result<TokenStream2> {
@@ -21,12 +25,18 @@ impl Ctxt {
                     item_name.to_string(),
                 )?;
                 let seek = self.flags.derive_seek(self)?;
+                let bit_align = self.flags.derive_bit_align(self)?;
+                let padding = self.flags.derive_padding(self)?;
+                let magic = self.flags.derive_magic(self)?;
                 let pos_tracker = self.flags.derive_pos_tracker(self)?;
 
                 Ok(quote!(
                     #pos_tracker
                     #pre
                     #seek
+                    #bit_align
+                    #padding
+                    #magic
                     { #modified }
                     #post
                     #crate_name::EncodingResult::Ok(())
@@ -61,6 +71,9 @@ impl Ctxt {
                     item_name.to_string(),
                 )?;
                 let seek = self.flags.derive_seek(self)?;
+                let bit_align = self.flags.derive_bit_align(self)?;
+                let padding = self.flags.derive_padding(self)?;
+                let magic = self.flags.derive_magic(self)?;
                 let pos_tracker = self.flags.derive_pos_tracker(self)?;
 
                 Ok(quote!(
@@ -69,6 +82,9 @@ impl Ctxt {
                     #pos_tracker
                     #pre
                     #seek
+                    #bit_align
+                    #padding
+                    #magic
                     { #modified }
                     #post
                     #crate_name::EncodingResult::Ok(())
@@ -110,49 +126,132 @@ impl Variant {
 
     /// Generates the encode code for this variant, including the match arm
     pub fn derive_encode(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        if self.flags.unknown {
+            return self.derive_encode_unknown(ctxt);
+        }
+
         let mut ref_code = RefCode::new(ctxt);
-        let mut field_code = TokenStream2::new();
+        let mut field_code = Vec::new();
 
         for field in self.fields.iter() {
-            field_code.append_all(field.derive_encode(ctxt, &mut ref_code)?);
+            let code = field.derive_encode(ctxt, &mut ref_code)?;
+            field_code.push(match byte_len_source(&self.fields, field) {
+                Some(len_field) => len_field.derive_len_patch(ctxt, code)?,
+                None => code,
+            });
         }
 
+        let field_code = derive_checksums(ctxt, &self.fields, field_code)?;
+
         let ref crate_name = ctxt.flags.crate_name;
         let ref encoder = ctxt.encoder;
         let ref index = self.index.ident;
+        let enum_header = ctxt.derive_enum_header();
+        let fields_len = ctxt.derive_fields_len(&self.fields);
 
-        let write_variant = if ctxt.enum_repr.signed() {
+        let write_variant = if let Some(tag_type) = &ctxt.flags.tag_type {
+            // Every variant is guaranteed to have a tag when a tag type is specified
+            let tag = self.canonical_tag().unwrap();
+            let encode = Function::Default.derive_encode(ctxt, quote!(&__tag), tag_type)?;
             quote!(
-                #crate_name::Encoder::write_ivariant(#encoder, #index)?;
+                let __tag: #tag_type = ::core::convert::Into::into(#tag);
+                #encode;
             )
+        } else if ctxt.enum_repr.signed() {
+            if ctxt.asynchronous {
+                quote!(
+                    #crate_name::Encoder::write_ivariant_async(#encoder, #index).await?;
+                )
+            } else {
+                quote!(
+                    #crate_name::Encoder::write_ivariant(#encoder, #index)?;
+                )
+            }
         } else {
-            quote!(
-                #crate_name::Encoder::write_uvariant(#encoder, #index)?;
-            )
+            if ctxt.asynchronous {
+                quote!(
+                    #crate_name::Encoder::write_uvariant_async(#encoder, #index).await?;
+                )
+            } else {
+                quote!(
+                    #crate_name::Encoder::write_uvariant(#encoder, #index)?;
+                )
+            }
         };
 
         self.encode_match(
             ctxt,
             quote!(
+                #enum_header
                 #write_variant
+                #fields_len
                 #ref_code
                 #field_code
             ),
         )
     }
+
+    /// Generates the encode code for the catch-all variant, which writes back the
+    /// preserved tag and bytes as-is
+    fn derive_encode_unknown(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        if ctxt.asynchronous {
+            return Err(Error::new_spanned(&self.name, ASYNC_UNKNOWN));
+        }
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref tag = self.fields[0].name;
+        let ref data = self.fields[1].name;
+        let enum_header = ctxt.derive_enum_header();
+
+        let write_tag = if let Some(tag_type) = &ctxt.flags.tag_type {
+            let encode = Function::Default.derive_encode(ctxt, quote!(#tag), tag_type)?;
+            quote!(#encode;)
+        } else {
+            let enum_repr = ctxt.enum_repr;
+            let write_variant = if enum_repr.signed() {
+                quote!(write_ivariant)
+            } else {
+                quote!(write_uvariant)
+            };
+            quote!(
+                let __tag: #enum_repr = ::core::convert::TryInto::try_into(
+                    #crate_name::Opaque::from(::core::clone::Clone::clone(#tag))
+                )?;
+                #crate_name::Encoder::#write_variant(#encoder, __tag)?;
+            )
+        };
+
+        self.encode_match(
+            ctxt,
+            quote!(
+                #enum_header
+                #write_tag
+                #crate_name::Encoder::write_bytes(#encoder, #data)?;
+            ),
+        )
+    }
 }
 
 impl Struct {
     /// Generates the encode code for this struct
     pub fn derive_encode(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
         let mut ref_code = RefCode::new(ctxt);
-        let mut field_code = TokenStream2::new();
+        let mut field_code = Vec::new();
 
         for field in self.fields.iter() {
-            field_code.append_all(field.derive_encode(ctxt, &mut ref_code)?);
+            let code = field.derive_encode(ctxt, &mut ref_code)?;
+            field_code.push(match byte_len_source(&self.fields, field) {
+                Some(len_field) => len_field.derive_len_patch(ctxt, code)?,
+                None => code,
+            });
         }
 
+        let field_code = derive_checksums(ctxt, &self.fields, field_code)?;
+        let fields_len = ctxt.derive_fields_len(&self.fields);
+
         Ok(quote!(
+            #fields_len
             #ref_code
             #field_code
         ))
@@ -162,60 +261,189 @@ impl Struct {
 impl Field {
     pub fn derive_encode(&self, ctxt: &Ctxt, ref_code: &mut RefCode) -> syn::Result<TokenStream2> {
         ref_code.append(self);
-        let ref field_name = self.name;
-        let ref field_accessor = self.accessor;
-        let ref field_ty = self.ty;
 
-        let (pre, post) = self.flags.mods.derive(ctxt)?;
         let validate = self.flags.derive_validation(ctxt, Some(&ref_code))?;
         let seek = self.flags.derive_seek(ctxt)?;
+        let bit_align = self.flags.derive_bit_align(ctxt)?;
+        let padding = self.flags.derive_padding(ctxt)?;
+        let magic = self.flags.derive_magic(ctxt)?;
         let pos_tracker = self.flags.derive_pos_tracker(ctxt)?;
-
-        let encode = if let Some(converter) = &self.flags.ty_mods {
-            self.flags.function.derive_encode(
-                ctxt,
-                converter.convert_into(self)?,
-                converter.ty(),
-            )?
-        } else {
-            self.flags
-                .function
-                .derive_encode(ctxt, field_name.to_token_stream(), field_ty)?
-        };
-
-        let modified = self.flags.derive_stream_modifiers(
-            ctxt,
-            encode,
-            FlagTarget::Field,
-            field_accessor.to_string(),
-        )?;
+        let (pre, modified, post) = self.derive_encode_value(ctxt)?;
+        let null = ctxt.derive_null();
 
         let encode = if self.flags.skip {
             quote!(
                 #validate
             )
-        } else if let Some(condition) = &self.flags.condition {
+        } else if let Some(condition) = self.flags.derive_condition(ctxt) {
             quote!(
                 #validate
                 #pos_tracker
                 if #condition {
                     #pre
                     #seek
+                    #bit_align
+                    #padding
+                    #magic
                     #modified;
                     #post
+                } else {
+                    #null
                 }
             )
+        } else if let Some(len_of) = &self.flags.len_of {
+            // The length is computed from the target field and shadows the field's value
+            let ref target = len_of.target;
+            let (len_value, len_pos, len_end) = if len_of.bytes {
+                // The real value is back-patched once the target has been encoded
+                let position = ctxt.derive_stream_position();
+                let LenIdents { ref pos, ref end, .. } = LenIdents::new(self);
+                (
+                    quote!(0usize),
+                    quote!(let #pos = #position;),
+                    quote!(let #end = #position;),
+                )
+            } else {
+                (
+                    quote!((#target).len()),
+                    TokenStream2::new(),
+                    TokenStream2::new(),
+                )
+            };
+            let len = self.derive_len(ctxt, len_value);
+
+            quote!(
+                #len
+                #validate
+                #pos_tracker
+                #pre
+                #seek
+                #bit_align
+                #padding
+                #magic
+                #len_pos
+                #modified;
+                #len_end
+                #post
+            )
         } else {
             quote!(
                 #validate
                 #pos_tracker
                 #pre
                 #seek
+                #bit_align
+                #padding
+                #magic
                 #modified;
                 #post
             )
         };
 
-        Ok(quote!(#encode ; ))
+        // The checksum is computed from the fields it covers and shadows the field's value
+        let checksum = if self.flags.checksum.is_some() {
+            let ref field_name = self.name;
+            let ref field_ty = self.ty;
+            let digest = checksum_ident(self);
+            quote!(
+                let #field_name: &#field_ty = &<#field_ty as ::core::convert::From<_>>::from(#digest);
+            )
+        } else {
+            TokenStream2::new()
+        };
+
+        Ok(quote!(#checksum #encode ; ))
+    }
+
+    /// Generates the code that encodes the value of this field, split into the
+    /// modifiers that come before it, the encoding itself, and the modifiers that come after it
+    pub(super) fn derive_encode_value(
+        &self,
+        ctxt: &Ctxt,
+    ) -> syn::Result<(TokenStream2, TokenStream2, TokenStream2)> {
+        let ref field_name = self.name;
+        let ref field_accessor = self.accessor;
+        let ref field_ty = self.ty;
+
+        let (pre, post) = self.flags.mods.derive(ctxt)?;
+
+        let encode = if let Some(converter) = &self.flags.ty_mods {
+            self.flags.function.derive_encode(
+                ctxt,
+                converter.convert_into(self)?,
+                converter.ty(),
+            )?
+        } else {
+            self.flags
+                .function
+                .derive_encode(ctxt, field_name.to_token_stream(), field_ty)?
+        };
+
+        let modified = self.flags.derive_stream_modifiers(
+            ctxt,
+            encode,
+            FlagTarget::Field,
+            field_accessor.to_string(),
+        )?;
+        let modified = self.derive_asn1(ctxt, modified)?;
+
+        Ok((pre, modified, post))
+    }
+
+    /// Generates the code that shadows the value of this `len_of` field with
+    /// the given length, converted to the type of the field
+    pub(super) fn derive_len(&self, ctxt: &Ctxt, len: TokenStream2) -> TokenStream2 {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref field_name = self.name;
+        let ref field_ty = self.ty;
+        let target = self.flags.len_of.as_ref().map(|x| &x.target);
+
+        quote!(
+            let #field_name: &#field_ty = &<#field_ty as ::core::convert::TryFrom<usize>>::try_from(#len)
+                .map_err(|_| #crate_name::val_error!(
+                    "The length of `{}` doesn't fit in `{}`",
+                    ::core::stringify!(#target),
+                    ::core::stringify!(#field_name)
+                ))?;
+        )
+    }
+
+    /// Wraps the encode code of `target`, whose length in bytes is stored in
+    /// this field, so that this field is back-patched once `target` has been encoded
+    pub fn derive_len_patch(&self, ctxt: &Ctxt, target: TokenStream2) -> syn::Result<TokenStream2> {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref field_name = self.name;
+        let LenIdents {
+            ref pos,
+            ref end,
+            ref start,
+            ref stop,
+        } = LenIdents::new(self);
+        let position = ctxt.derive_stream_position();
+        let seek_pos = ctxt.derive_seek_start(pos);
+        let seek_stop = ctxt.derive_seek_start(stop);
+
+        let len = self.derive_len(ctxt, quote!((#stop - #start) as usize));
+        let (pre, modified, post) = self.derive_encode_value(ctxt)?;
+
+        Ok(quote!(
+            let #start = #position;
+            #target
+            let #stop = #position;
+            #seek_pos
+            {
+                #len
+                #pre
+                #modified;
+                #post
+            }
+            if #position != #end {
+                return ::core::result::Result::Err(#crate_name::val_error!(
+                    "The encoded size of `{}` changed while back-patching it",
+                    ::core::stringify!(#field_name)
+                ));
+            }
+            #seek_stop
+        ))
     }
 }
@@ -0,0 +1,306 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens, TokenStreamExt};
+
+use crate::ctxt::{Ctxt, Field, Target};
+use crate::flags::MessageFormat;
+use crate::generator::RefCode;
+
+impl Ctxt {
+    /// Generates the encode code for a struct with the "format" flag, where each field
+    /// is written as a record identified by its key
+    pub(super) fn derive_encode_format(&self, format: MessageFormat) -> syn::Result<TokenStream2> {
+        let ref crate_name = self.flags.crate_name;
+
+        let (item_pre, item_post) = self.flags.mods.derive(self)?;
+        let (format_pre, format_post) = self.derive_format_settings(format);
+
+        let mut ref_code = RefCode::new(self);
+        let mut field_code = TokenStream2::new();
+        for field in self.struct_data.fields.iter() {
+            field_code.append_all(field.derive_encode_record(self, format, &mut ref_code)?);
+        }
+
+        Ok(quote!(
+            #format_pre
+            #item_pre
+            {
+                #ref_code
+                #field_code
+            }
+            #item_post
+            #format_post
+            #crate_name::EncodingResult::Ok(())
+        ))
+    }
+
+    /// Generates the decode code for a struct with the "format" flag: records are read until
+    /// the end of the message, in any order, and the ones with an unknown key are skipped
+    pub(super) fn derive_decode_format(&self, format: MessageFormat) -> syn::Result<TokenStream2> {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        let (item_pre, item_post) = self.flags.mods.derive(self)?;
+        let (format_pre, format_post) = self.derive_format_settings(format);
+
+        let mut slots = TokenStream2::new();
+        let mut arms = TokenStream2::new();
+        let mut ref_code = RefCode::new(self);
+        let mut field_code = TokenStream2::new();
+
+        for field in self.struct_data.fields.iter() {
+            let ref field_name = field.name;
+            let ref field_ty = field.ty;
+            let ref default = field.flags.default;
+
+            if field.flags.skip {
+                field_code.append_all(quote!(
+                    let #field_name: #field_ty = {
+                        #ref_code
+                        #default
+                    };
+                ));
+            } else {
+                // In protobuf, the slot holds the converted type, which is converted back here
+                let slot = format_ident!("__slot_{}", field_name);
+                let (slot_ty, value) = match (format, &field.flags.ty_mods) {
+                    (MessageFormat::Protobuf, Some(converter)) => (
+                        converter.ty(),
+                        converter.convert_from(field, quote!(__val))?,
+                    ),
+                    _ => (field_ty, quote!(__val)),
+                };
+                slots.append_all(quote!(
+                    let mut #slot: ::core::option::Option<#slot_ty> = ::core::option::Option::None;
+                ));
+
+                arms.append_all(field.derive_decode_record(self, format, &slot)?);
+
+                field_code.append_all(quote!(
+                    let #field_name: #field_ty = match #slot {
+                        ::core::option::Option::Some(__val) => #value,
+                        ::core::option::Option::None => {
+                            #ref_code
+                            #default
+                        }
+                    };
+                ));
+            }
+
+            ref_code.append(field);
+            field_code.append_all(field.flags.derive_validation(self, Some(&ref_code))?);
+        }
+
+        let (read_key, skip) = match format {
+            MessageFormat::Protobuf => (
+                quote!(
+                    ::core::option::Option::Some((__key, __wire_type)) = #crate_name::Encoder::read_proto_key(#encoder)?
+                ),
+                quote!(#crate_name::Encoder::skip_proto_value(#encoder, __wire_type)?;),
+            ),
+            MessageFormat::Tlv => (
+                quote!(
+                    ::core::option::Option::Some(__key) = #crate_name::Encoder::read_tlv_id(#encoder)?
+                ),
+                quote!(#crate_name::Encoder::skip_sized(#encoder)?;),
+            ),
+        };
+        let aggregate = self.struct_data.decode_aggregate(self)?;
+
+        Ok(quote!(
+            #format_pre
+            #item_pre
+            #slots
+            while let #read_key {
+                #arms
+                {
+                    #skip
+                }
+            }
+            #field_code
+            let __val: Self = #aggregate ?;
+            #item_post
+            #format_post
+            #crate_name::EncodingResult::Ok(__val)
+        ))
+    }
+
+    /// Generates the code that switches the settings to the ones mandated by the format,
+    /// and the code that restores them
+    fn derive_format_settings(&self, format: MessageFormat) -> (TokenStream2, TokenStream2) {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        match format {
+            MessageFormat::Protobuf => (
+                quote!(
+                    let __format_num_repr = #encoder.ctxt.settings.num_repr;
+                    #encoder.ctxt.settings.num_repr = #crate_name::protobuf::NUM_REPR;
+                ),
+                quote!(
+                    #encoder.ctxt.settings.num_repr = __format_num_repr;
+                ),
+            ),
+            // The tags and lengths follow the regular settings
+            MessageFormat::Tlv => (TokenStream2::new(), TokenStream2::new()),
+        }
+    }
+
+    /// Generates the impl that allows a struct with the "format" flag to be nested
+    /// in other structs with the same format
+    pub fn derive_format_impl(&self) -> TokenStream2 {
+        let Some(format) = self.flags.format else {
+            return TokenStream2::new();
+        };
+
+        let ref crate_name = self.flags.crate_name;
+        let ref item_name = self.item_name;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        match (format, self.target) {
+            // A nested tlv struct is delimited by the record it is encoded in
+            (MessageFormat::Tlv, _) => TokenStream2::new(),
+            (MessageFormat::Protobuf, Target::Encode) => quote!(
+                #[automatically_derived]
+                #[allow(unused)]
+                #[allow(dead_code)]
+                impl #impl_generics #crate_name::protobuf::ProtoEncode for #item_name #ty_generics #where_clause {
+                    fn wire_type(_ctxt: &#crate_name::Context) -> #crate_name::protobuf::WireType {
+                        #crate_name::protobuf::WireType::Len
+                    }
+
+                    fn encode_value<__W: #crate_name::io::Write>(&self, __encoder: &mut #crate_name::Encoder<__W>) -> #crate_name::EncodingResult<()> {
+                        #crate_name::Encoder::write_proto_message(__encoder, self)
+                    }
+                }
+            ),
+            (MessageFormat::Protobuf, Target::Decode) => quote!(
+                #[automatically_derived]
+                #[allow(unused)]
+                #[allow(dead_code)]
+                impl #impl_generics #crate_name::protobuf::ProtoDecode for #item_name #ty_generics #where_clause {
+                    fn wire_type(_ctxt: &#crate_name::Context) -> #crate_name::protobuf::WireType {
+                        #crate_name::protobuf::WireType::Len
+                    }
+
+                    fn decode_value<__R: #crate_name::io::Read>(__decoder: &mut #crate_name::Encoder<__R>) -> #crate_name::EncodingResult<Self> {
+                        #crate_name::Encoder::read_proto_message(__decoder)
+                    }
+                }
+            ),
+        }
+    }
+}
+
+impl Field {
+    /// Generates the code that writes this field as a record of the given format
+    fn derive_encode_record(
+        &self,
+        ctxt: &Ctxt,
+        format: MessageFormat,
+        ref_code: &mut RefCode,
+    ) -> syn::Result<TokenStream2> {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref field_name = self.name;
+
+        ref_code.append(self);
+        let validate = self.flags.derive_validation(ctxt, Some(&ref_code))?;
+        if self.flags.skip {
+            return Ok(validate);
+        }
+
+        // Every field that isn't skipped is guaranteed to have a key
+        let ref key = self.flags.key.as_ref().unwrap().key;
+        let encode = match format {
+            MessageFormat::Protobuf => {
+                let virtual_ty = self.virtual_ty();
+                let value = match &self.flags.ty_mods {
+                    Some(converter) => converter.convert_into(self)?,
+                    None => field_name.to_token_stream(),
+                };
+                let (pre, post) = self.flags.mods.derive(ctxt)?;
+                quote!(
+                    #pre
+                    <#virtual_ty as #crate_name::protobuf::ProtoEncode>::encode_field(#value, #key, #encoder)?;
+                    #post
+                )
+            }
+            MessageFormat::Tlv => {
+                // The encode code is duplicated, because it's first used to measure the size
+                let (pre, modified, post) = self.derive_encode_value(ctxt)?;
+                quote!(
+                    #crate_name::Encoder::write_uvariant(#encoder, (#key) as u64)?;
+                    #crate_name::Encoder::write_sized(
+                        &mut * #encoder,
+                        |#encoder| { #pre #modified; #post Ok(()) },
+                        |#encoder| { #pre #modified; #post Ok(()) },
+                    )?;
+                )
+            }
+        };
+        let condition = self
+            .flags
+            .derive_condition(ctxt)
+            .unwrap_or_else(|| quote!(true));
+
+        Ok(quote!(
+            #validate
+            if #condition {
+                #encode
+            }
+        ))
+    }
+
+    /// Generates the branch that reads a record of the given format into the slot
+    /// of this field, if its key matches
+    fn derive_decode_record(
+        &self,
+        ctxt: &Ctxt,
+        format: MessageFormat,
+        slot: &impl ToTokens,
+    ) -> syn::Result<TokenStream2> {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref field_ty = self.ty;
+
+        let ref key = self.flags.key.as_ref().unwrap().key;
+        let (key, decode) = match format {
+            MessageFormat::Protobuf => {
+                let virtual_ty = self.virtual_ty();
+                let (pre, post) = self.flags.mods.derive(ctxt)?;
+                (
+                    quote!((#key)),
+                    quote!(
+                        #pre
+                        <#virtual_ty as #crate_name::protobuf::ProtoDecode>::merge_field(&mut #slot, __wire_type, #encoder)?;
+                        #post
+                    ),
+                )
+            }
+            MessageFormat::Tlv => {
+                let (pre, modified, post) = self.derive_decode_value(ctxt)?;
+                (
+                    quote!((#key) as u64),
+                    quote!(
+                        #slot = ::core::option::Option::Some(#crate_name::Encoder::read_sized(&mut * #encoder, |#encoder| {
+                            #pre
+                            let __val: #field_ty = #modified;
+                            #post
+                            Ok(__val)
+                        })?);
+                    ),
+                )
+            }
+        };
+        let condition = match self.flags.derive_condition(ctxt) {
+            Some(condition) => quote!(&& #condition),
+            None => TokenStream2::new(),
+        };
+
+        Ok(quote!(
+            if __key == #key #condition {
+                #decode
+            } else
+        ))
+    }
+}
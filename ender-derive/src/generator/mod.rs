@@ -1,25 +1,286 @@
 use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::Ident;
 use quote::{format_ident, quote, ToTokens, TokenStreamExt};
-use syn::{parse_quote, Expr, Type};
+use syn::{parse_quote, Error, Expr, ExprRange, Type};
 
 use crate::ctxt::{Ctxt, Field, ItemType, Scope, Target, Variant};
 use crate::flags::{
-    AllModifiers, FlagTarget, Flags, Function, ModifierGroup, StreamModifier, TypeModifier,
+    AllModifiers, ChecksumParam, FlagTarget, Flags, Function, ModifierGroup, StreamModifier,
+    TypeModifier,
 };
 use crate::generator::tokenize::CtxtToTokens;
 use crate::parse::Formatting;
 
+mod asn1;
 pub mod decode;
 pub mod encode;
+mod format;
+mod size;
+mod skip;
 mod tokenize;
 
+const ASYNC_SERDE: &str = r#""serde" flag is not supported when deriving async implementations"#;
+const ASYNC_WITH: &str = r#""with" flag is not supported when deriving async implementations"#;
+const ASYNC_REDIR: &str = r#""redir" flag is not supported when deriving async implementations"#;
+const ASYNC_SIZED: &str = r#""sized" flag is not supported when deriving async implementations"#;
+const ASYNC_CHECKSUM: &str = r#""checksum" flag is not supported when deriving async implementations"#;
+const ASYNC_UNKNOWN: &str = r#""unknown" flag is not supported when deriving async implementations"#;
+const ASYNC_FORMAT: &str = r#""format" and "tlv" flags are not supported when deriving async implementations"#;
+const ASYNC_ASN1: &str = r#""asn1" flag is not supported when deriving async implementations"#;
+const ASYNC_SKIP_VALUE: &str = r#""skip_value" flag is not supported when deriving async implementations"#;
+const CHECKSUM_BITS: &str = r#""bits", "bit_align" and "bitwise" flags are not supported on the fields covered by a "checksum" flag"#;
+
 impl Ctxt {
     pub fn derive(&self) -> syn::Result<TokenStream2> {
+        if let Some(format) = self.flags.format {
+            if self.asynchronous {
+                return Err(Error::new_spanned(&self.item_name, ASYNC_FORMAT));
+            }
+
+            return match self.target {
+                Target::Encode => self.derive_encode_format(format),
+                Target::Decode => self.derive_decode_format(format),
+            };
+        }
+
         match self.target {
             Target::Encode => self.derive_encode(),
             Target::Decode => self.derive_decode(),
         }
     }
+
+    /// Generates an expression evaluating to the current stream position
+    pub fn derive_stream_position(&self) -> TokenStream2 {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        if self.asynchronous {
+            quote!(#crate_name::Encoder::stream_position_async(#encoder).await?)
+        } else {
+            quote!(#crate_name::Encoder::stream_position(#encoder)?)
+        }
+    }
+
+    /// Generates a statement seeking to the given offset from the start of the stream
+    pub fn derive_seek_start(&self, offset: &Ident) -> TokenStream2 {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        if self.asynchronous {
+            quote!(#crate_name::Encoder::seek_async(#encoder, #crate_name::io::SeekFrom::Start(#offset)).await?;)
+        } else {
+            quote!(#crate_name::Encoder::seek(#encoder, #crate_name::io::SeekFrom::Start(#offset))?;)
+        }
+    }
+
+    /// Whether the variants of this enum are identified by explicit tags rather than
+    /// by their index alone
+    pub fn tagged(&self) -> bool {
+        self.flags.tag_type.is_some() || self.variants.iter().any(|x| !x.flags.tags.is_empty())
+    }
+
+    /// Generates the statement writing or reading the number of items the given fields are
+    /// encoded as, which groups them together in the self-describing formats
+    pub fn derive_fields_len(&self, fields: &[Field]) -> TokenStream2 {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+        let len = fields.iter().filter(|x| !x.flags.skip).count();
+
+        // The asynchronous API only supports the binary format, where nothing is written
+        match (self.asynchronous, self.target) {
+            (true, _) => TokenStream2::new(),
+            (false, Target::Encode) => quote!(#crate_name::Encoder::write_fields_len(#encoder, #len)?;),
+            (false, Target::Decode) => quote!(#crate_name::Encoder::read_fields_len(#encoder, #len)?;),
+        }
+    }
+
+    /// Generates the statement writing or reading what precedes the variant of an enum
+    pub fn derive_enum_header(&self) -> TokenStream2 {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        match (self.asynchronous, self.target) {
+            (true, _) => TokenStream2::new(),
+            (false, Target::Encode) => quote!(#crate_name::Encoder::write_enum_header(#encoder)?;),
+            (false, Target::Decode) => quote!(#crate_name::Encoder::read_enum_header(#encoder)?;),
+        }
+    }
+
+    /// Generates the statement writing or reading the null that takes the place
+    /// of a field whose condition is false
+    pub fn derive_null(&self) -> TokenStream2 {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        match (self.asynchronous, self.target) {
+            (true, _) => TokenStream2::new(),
+            (false, Target::Encode) => quote!(#crate_name::Encoder::write_null(#encoder)?;),
+            (false, Target::Decode) => quote!(#crate_name::Encoder::read_null(#encoder)?;),
+        }
+    }
+
+    /// Wraps the body of a decoding function so that it counts as one level
+    /// of nesting against the max depth of the context
+    pub fn derive_nested(&self, body: TokenStream2) -> TokenStream2 {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        if self.asynchronous {
+            quote!(
+                #crate_name::Encoder::with_nested_async(&mut * #encoder, async |#encoder| { #body }).await
+            )
+        } else {
+            quote!(
+                #crate_name::Encoder::with_nested(&mut * #encoder, |#encoder| { #body })
+            )
+        }
+    }
+}
+
+impl Variant {
+    /// The tag written while encoding this variant: the first one, or its start if it's a range
+    pub fn canonical_tag(&self) -> Option<&Expr> {
+        self.flags.tags.first().map(|x| match x {
+            Expr::Range(ExprRange {
+                start: Some(start), ..
+            }) => start.as_ref(),
+            x => x,
+        })
+    }
+
+    /// Generates a condition that is true when the decoded `__tag` identifies this variant
+    pub fn derive_tag_condition(&self) -> TokenStream2 {
+        if self.flags.tags.is_empty() {
+            let ref index = self.index.ident;
+            return quote!(__tag == #index);
+        }
+
+        let conditions = self.flags.tags.iter().map(|tag| match tag {
+            Expr::Range(_) => quote!((#tag).contains(&__tag)),
+            _ => quote!(__tag == (#tag)),
+        });
+
+        quote!(#(#conditions)||*)
+    }
+}
+
+/// The name of the variable holding the digest computed for a `checksum` field
+pub fn checksum_ident(field: &Field) -> Ident {
+    format_ident!("__ender_checksum_{}", field.name)
+}
+
+/// Joins together the code of the given fields, grouping the code of the fields
+/// covered by each checksum so that the bytes they encode/decode are fed into it
+pub fn derive_checksums(
+    ctxt: &Ctxt,
+    fields: &[Field],
+    mut codes: Vec<TokenStream2>,
+) -> syn::Result<TokenStream2> {
+    let ref crate_name = ctxt.flags.crate_name;
+    let ref encoder = ctxt.encoder;
+
+    let mut checksums: Vec<&Field> = fields
+        .iter()
+        .filter(|x| x.flags.checksum.is_some())
+        .collect();
+    // Nested checksums must be grouped before the ones containing them
+    checksums.sort_by_key(|x| x.flags.checksum.as_ref().unwrap().fields.len());
+
+    for field in checksums {
+        let checksum = field.flags.checksum.as_ref().unwrap();
+        if ctxt.asynchronous {
+            return Err(Error::new_spanned(&checksum.algorithm, ASYNC_CHECKSUM));
+        }
+
+        let ref range = checksum.fields;
+        if let Some(field) = fields[range.clone()].iter().find(|x| x.flags.requires_bit_impl()) {
+            return Err(Error::new_spanned(&field.name, CHECKSUM_BITS));
+        }
+        let algorithm = checksum.derive_algorithm(ctxt);
+        let digest = checksum_ident(field);
+        let body: TokenStream2 = codes[range.clone()].iter_mut().map(std::mem::take).collect();
+
+        codes[range.start] = match ctxt.target {
+            Target::Encode => quote!(
+                let ((), #digest) = #crate_name::Encoder::with_checksum(
+                    &mut * #encoder,
+                    <#algorithm as ::core::default::Default>::default(),
+                    |#encoder| { #body Ok(()) }
+                )?;
+            ),
+            Target::Decode => {
+                // The decoded fields are moved out of the closure
+                let names: Vec<_> = fields[range.clone()].iter().map(|x| &x.name).collect();
+                quote!(
+                    let ((#(#names,)*), #digest) = #crate_name::Encoder::with_checksum(
+                        &mut * #encoder,
+                        <#algorithm as ::core::default::Default>::default(),
+                        |#encoder| { #body Ok((#(#names,)*)) }
+                    )?;
+                )
+            }
+        };
+    }
+
+    Ok(codes.into_iter().collect())
+}
+
+impl ChecksumParam {
+    /// Generates the checksum type, resolving the names of the built-in algorithms
+    pub fn derive_algorithm(&self, ctxt: &Ctxt) -> TokenStream2 {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref algorithm = self.algorithm;
+
+        let builtin = match algorithm.get_ident().map(|x| x.to_string()).as_deref() {
+            Some("crc8") => Some(quote!(Crc8)),
+            Some("crc16") => Some(quote!(Crc16)),
+            Some("crc32") => Some(quote!(Crc32)),
+            Some("adler32") => Some(quote!(Adler32)),
+            Some("fletcher16") => Some(quote!(Fletcher16)),
+            Some("fletcher32") => Some(quote!(Fletcher32)),
+            _ => None,
+        };
+
+        match builtin {
+            Some(builtin) => quote!(#crate_name::io::#builtin),
+            None => algorithm.to_token_stream(),
+        }
+    }
+}
+
+/// Finds the field holding the length in bytes of `field`, if any
+pub fn byte_len_source<'a>(fields: &'a [Field], field: &Field) -> Option<&'a Field> {
+    fields.iter().find(|x| {
+        x.flags
+            .len_of
+            .as_ref()
+            .is_some_and(|len_of| len_of.bytes && len_of.target == field.name)
+    })
+}
+
+/// The names of the variables used to keep track of the positions involved
+/// in a `len_of bytes` relationship
+pub struct LenIdents {
+    /// Where the length field starts
+    pub pos: Ident,
+    /// Where the length field ends
+    pub end: Ident,
+    /// Where the measured field starts
+    pub start: Ident,
+    /// Where the measured field ends
+    pub stop: Ident,
+}
+
+impl LenIdents {
+    pub fn new(len_field: &Field) -> Self {
+        let ref name = len_field.name;
+        Self {
+            pos: format_ident!("__ender_len_pos_{}", name),
+            end: format_ident!("__ender_len_end_{}", name),
+            start: format_ident!("__ender_len_start_{}", name),
+            stop: format_ident!("__ender_len_stop_{}", name),
+        }
+    }
 }
 
 /// This is an aggregation of statements to allow access to fields inside `ender` flags expressions
@@ -84,7 +345,11 @@ impl<'a> ConstCode<'a> {
     pub fn append(&mut self, variant: &Variant) {
         let enum_repr = self.ctxt.enum_repr;
         let ref ident = variant.index.ident;
-        let ref expr = variant.index.expr;
+        // Without a tag type, a tagged variant's index is replaced by its tag
+        let expr = match variant.canonical_tag() {
+            Some(tag) if self.ctxt.flags.tag_type.is_none() => tag,
+            _ => &variant.index.expr,
+        };
 
         self.code.append_all(quote!(
             const #ident: #enum_repr = { #expr };
@@ -109,17 +374,32 @@ impl Function {
         let ref encoder_generic = ctxt.encoder_generic;
         let ref encoder = ctxt.encoder;
         Ok(match self {
+            Function::Default if ctxt.asynchronous => {
+                quote!(<#ty as #crate_name::AsyncEncode<#encoder_generic>>::encode_async(#input, #encoder).await?)
+            }
             Function::Default => {
-                quote!(<#ty as #crate_name::Encode<#encoder_generic>>::encode(#input, #encoder)?)
+                // The stream type is inferred, because stream modifiers like "sized" can change it
+                quote!(<#ty as #crate_name::Encode<_>>::encode(#input, #encoder)?)
+            }
+            Function::Serde(serde_crate) if ctxt.asynchronous => {
+                return Err(Error::new_spanned(serde_crate, ASYNC_SERDE));
+            }
+            Function::With(path, ..) if ctxt.asynchronous => {
+                return Err(Error::new_spanned(path, ASYNC_WITH));
             }
             Function::Serde(serde_crate) => {
                 quote!(<#ty as #serde_crate::Serialize>::serialize(#input, &mut * #encoder)?)
             }
+            Function::Bits(bits) => {
+                quote!(<#ty as #crate_name::BitField>::encode_bits(#input, #encoder, #bits)?)
+            }
             Function::With(path, args, scope) => match scope {
                 Scope::Encode => quote!(#path(#input, &mut * #encoder, #(#args),* )?),
                 Scope::Decode => unreachable!(),
                 Scope::Both => quote!(#path::encode(#input, &mut * #encoder, #(#args),* )?),
             },
+            // Only available in the "de" scope
+            Function::SkipValue(_) => unreachable!(),
         })
     }
 
@@ -127,23 +407,46 @@ impl Function {
         &self,
         ctxt: &Ctxt,
         ty: &Type,
-        _field: &Field,
+        field: &Field,
     ) -> syn::Result<TokenStream2> {
         let ref crate_name = ctxt.flags.crate_name;
         let ref encoder_generic = ctxt.encoder_generic;
         let ref encoder = ctxt.encoder;
         Ok(match self {
+            Function::Default if ctxt.asynchronous => {
+                quote!(<#ty as #crate_name::AsyncDecode<#encoder_generic>>::decode_async(#encoder).await?)
+            }
             Function::Default => {
-                quote!(<#ty as #crate_name::Decode<#encoder_generic>>::decode(#encoder)?)
+                // The stream type is inferred, because stream modifiers like "sized" can change it
+                quote!(<#ty as #crate_name::Decode<_>>::decode(#encoder)?)
+            }
+            Function::Serde(serde_crate) if ctxt.asynchronous => {
+                return Err(Error::new_spanned(serde_crate, ASYNC_SERDE));
+            }
+            Function::With(path, ..) if ctxt.asynchronous => {
+                return Err(Error::new_spanned(path, ASYNC_WITH));
             }
             Function::Serde(serde_crate) => {
                 quote!(<#ty as #serde_crate::Deserialize>::deserialize(&mut * #encoder)?)
             }
+            Function::Bits(bits) => {
+                quote!(<#ty as #crate_name::BitField>::decode_bits(#encoder, #bits)?)
+            }
             Function::With(path, args, scope) => match scope {
                 Scope::Encode => unreachable!(),
                 Scope::Decode => quote!(#path(&mut * #encoder, #(#args),* )?),
                 Scope::Both => quote!(#path::decode(&mut * #encoder, #(#args),* )?),
             },
+            Function::SkipValue(skip_ty) if ctxt.asynchronous => {
+                return Err(Error::new_spanned(skip_ty, ASYNC_SKIP_VALUE));
+            }
+            Function::SkipValue(skip_ty) => {
+                let ref default = field.flags.default;
+                quote!({
+                    <#skip_ty as #crate_name::Skip<_>>::skip(#encoder)?;
+                    #default
+                })
+            }
         })
     }
 }
@@ -240,12 +543,128 @@ impl Flags {
             let ref crate_name = ctxt.flags.crate_name;
             let ref encoder = ctxt.encoder;
 
-            quote!(#crate_name::Encoder::seek(#encoder, #seek)?;)
+            if ctxt.asynchronous {
+                quote!(#crate_name::Encoder::seek_async(#encoder, #seek).await?;)
+            } else {
+                quote!(#crate_name::Encoder::seek(#encoder, #seek)?;)
+            }
         } else {
             quote!()
         })
     }
 
+    pub fn derive_bit_align(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        Ok(if self.bit_align {
+            let ref crate_name = ctxt.flags.crate_name;
+            let ref encoder = ctxt.encoder;
+
+            match ctxt.target {
+                Target::Encode => quote!(#crate_name::Encoder::write_bit_align(#encoder)?;),
+                Target::Decode => quote!(#crate_name::Encoder::read_bit_align(#encoder)?;),
+            }
+        } else {
+            quote!()
+        })
+    }
+
+    /// Generates the condition under which a field is encoded/decoded, combining the "if",
+    /// "since" and "until" flags, or `None` if the field is unconditional
+    pub fn derive_condition(&self, ctxt: &Ctxt) -> Option<TokenStream2> {
+        let ref encoder = ctxt.encoder;
+        let mut conditions = Vec::new();
+
+        if let Some(condition) = &self.condition {
+            conditions.push(quote!((#condition)));
+        }
+        if let Some(since) = &self.since {
+            conditions.push(quote!(#encoder.ctxt.version >= (#since)));
+        }
+        if let Some(until) = &self.until {
+            conditions.push(quote!(#encoder.ctxt.version <= (#until)));
+        }
+
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(quote!(#(#conditions)&&*))
+        }
+    }
+
+    pub fn derive_magic(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        Ok(if let Some(magic) = &self.magic {
+            let ref crate_name = ctxt.flags.crate_name;
+            let ref encoder = ctxt.encoder;
+
+            match (ctxt.target, ctxt.asynchronous) {
+                (Target::Encode, false) => {
+                    quote!(#crate_name::Encoder::write_magic(#encoder, #magic)?;)
+                }
+                (Target::Encode, true) => {
+                    quote!(#crate_name::Encoder::write_magic_async(#encoder, #magic).await?;)
+                }
+                (Target::Decode, false) => {
+                    quote!(#crate_name::Encoder::read_magic(#encoder, #magic)?;)
+                }
+                (Target::Decode, true) => {
+                    quote!(#crate_name::Encoder::read_magic_async(#encoder, #magic).await?;)
+                }
+            }
+        } else {
+            quote!()
+        })
+    }
+
+    pub fn derive_padding(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        if self.pad.is_none() && self.align.is_none() {
+            if let Some(fill) = &self.fill {
+                return Err(Error::new_spanned(
+                    fill,
+                    r#""fill" flag requires a "pad" or "align" flag"#,
+                ));
+            }
+            return Ok(quote!());
+        }
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let fill = self
+            .fill
+            .as_ref()
+            .map(ToTokens::to_token_stream)
+            .unwrap_or(quote!(0u8));
+        let check = if self.check_padding {
+            quote!(::core::option::Option::Some(#fill))
+        } else {
+            quote!(::core::option::Option::None)
+        };
+
+        let (pad_fn, align_fn, arg) = match ctxt.target {
+            Target::Encode => (quote!(write_padding), quote!(write_align), fill),
+            Target::Decode => (quote!(read_padding), quote!(read_align), check),
+        };
+        let (pad_fn, align_fn, suffix) = if ctxt.asynchronous {
+            (
+                format_ident!("{}_async", pad_fn.to_string()).to_token_stream(),
+                format_ident!("{}_async", align_fn.to_string()).to_token_stream(),
+                quote!(.await),
+            )
+        } else {
+            (pad_fn, align_fn, quote!())
+        };
+
+        let pad = self.pad.as_ref().map(|pad| {
+            quote!(#crate_name::Encoder::#pad_fn(#encoder, #pad, #arg) #suffix ?;)
+        });
+        let align = self.align.as_ref().map(|align| {
+            quote!(#crate_name::Encoder::#align_fn(#encoder, #align, #arg) #suffix ?;)
+        });
+
+        Ok(quote!(
+            #pad
+            #align
+        ))
+    }
+
     pub fn derive_pos_tracker(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
         Ok(if let Some(var) = &self.pos_tracker {
             let ref crate_name = ctxt.flags.crate_name;
@@ -253,7 +672,11 @@ impl Flags {
 
             let var = format_ident!("{}", var);
 
-            quote!(let #var = #crate_name::Encoder::stream_position(#encoder)?;)
+            if ctxt.asynchronous {
+                quote!(let #var = #crate_name::Encoder::stream_position_async(#encoder).await?;)
+            } else {
+                quote!(let #var = #crate_name::Encoder::stream_position(#encoder)?;)
+            }
         } else {
             quote!()
         })
@@ -462,9 +885,16 @@ impl Flags {
                 FlagTarget::Field => quote!(with_field),
             };
 
-            let input = quote!(
-                #crate_name::Encoder::#method_name(&mut * #encoder, |#encoder| { Ok({ #input }) }, #string)?
-            );
+            let input = if ctxt.asynchronous {
+                let method_name = format_ident!("{}_async", method_name.to_string());
+                quote!(
+                    #crate_name::Encoder::#method_name(&mut * #encoder, async |#encoder| { Ok({ #input }) }, #string).await?
+                )
+            } else {
+                quote!(
+                    #crate_name::Encoder::#method_name(&mut * #encoder, |#encoder| { Ok({ #input }) }, #string)?
+                )
+            };
             Ok(input)
         }
 
@@ -483,6 +913,9 @@ impl StreamModifier {
         let ref crate_name = ctxt.flags.crate_name;
 
         Ok(match self {
+            StreamModifier::Transform { path, .. } if ctxt.asynchronous => {
+                return Err(Error::new_spanned(path, ASYNC_REDIR));
+            }
             StreamModifier::Transform { path, args, scope } => {
                 match ctxt.target {
                     Target::Encode => {
@@ -521,12 +954,30 @@ impl StreamModifier {
                     }
                 }
             }
+            StreamModifier::Ptr { seek } if ctxt.asynchronous => {
+                let seek = seek.ctxt_tokens(ctxt);
+                quote!(
+                    #crate_name::Encoder::with_seek_async(&mut * #encoder, async |#encoder| { Ok({ #input }) }, #seek ).await?
+                )
+            }
             StreamModifier::Ptr { seek } => {
                 let seek = seek.ctxt_tokens(ctxt);
                 quote!(
                     #crate_name::Encoder::with_seek(&mut * #encoder, |#encoder| { Ok({ #input }) }, #seek )?
                 )
             }
+            StreamModifier::Sized if ctxt.asynchronous => {
+                return Err(Error::new_spanned(&ctxt.item_name, ASYNC_SIZED));
+            }
+            StreamModifier::Sized => match ctxt.target {
+                // The encode code is duplicated, because it's first used to measure the size
+                Target::Encode => quote!(
+                    #crate_name::Encoder::write_sized(&mut * #encoder, |#encoder| { Ok({ #input }) }, |#encoder| { Ok({ #input }) })?
+                ),
+                Target::Decode => quote!(
+                    #crate_name::Encoder::read_sized(&mut * #encoder, |#encoder| { Ok({ #input }) })?
+                ),
+            },
         })
     }
 }
@@ -0,0 +1,376 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens, TokenStreamExt};
+use syn::Error;
+
+use crate::ctxt::{Ctxt, Field, Flavor, ItemType, Struct, Variant};
+use crate::flags::{Flags, StreamModifier};
+use crate::generator::{ConstCode, RefCode};
+
+const SIZE_SEEK: &str = r#""seek", "ptr", "pos_tracker" and "align" flags are not supported when deriving EncodedSize"#;
+const SIZE_BITS: &str = r#""bits", "bit_align" and "bitwise" flags are not supported when deriving EncodedSize"#;
+const SIZE_CHECKSUM: &str = r#""checksum" flag is not supported when deriving EncodedSize"#;
+const SIZE_FORMAT: &str = r#""format" and "tlv" flags are not supported when deriving EncodedSize"#;
+const SIZE_ASN1: &str = r#""asn1" flag is not supported when deriving EncodedSize"#;
+
+/// Rejects the flags whose effect on the encoded size can't be computed, because it depends
+/// on the position in the stream or on the data itself
+fn check_size_flags(flags: &Flags, span: impl ToTokens) -> syn::Result<()> {
+    if flags.seek.is_some()
+        || flags.pos_tracker.is_some()
+        || flags.align.is_some()
+        || flags.stream_modifiers.iter().any(StreamModifier::is_ptr)
+    {
+        return Err(Error::new_spanned(span, SIZE_SEEK));
+    }
+    if let Some(checksum) = &flags.checksum {
+        return Err(Error::new_spanned(&checksum.algorithm, SIZE_CHECKSUM));
+    }
+    if flags.asn1.is_some() {
+        return Err(Error::new_spanned(span, SIZE_ASN1));
+    }
+    Ok(())
+}
+
+/// Wraps the size of a value with the sizes added by its "sized" flag, if any
+fn derive_sized(ctxt: &Ctxt, flags: &Flags, size: TokenStream2) -> TokenStream2 {
+    let ref crate_name = ctxt.flags.crate_name;
+    let ref encoder = ctxt.encoder;
+
+    if flags.stream_modifiers.iter().any(StreamModifier::is_sized) {
+        // The size flatten variable belongs to the value, not to the size of the region
+        quote!({
+            let __inner: usize = #size;
+            #encoder.ctxt.size_flatten = ::core::option::Option::None;
+            #crate_name::Context::usize_size(&mut #encoder.ctxt, __inner)? + __inner
+        })
+    } else {
+        size
+    }
+}
+
+/// Generates the statements adding the size of the padding and magic number to `__size`
+fn derive_padding_size(flags: &Flags) -> TokenStream2 {
+    let pad = flags.pad.as_ref().map(|pad| quote!(__size += (#pad);));
+    let magic = flags
+        .magic
+        .as_ref()
+        .map(|magic| quote!(__size += <[u8]>::len(#magic);));
+
+    quote!(
+        #pad
+        #magic
+    )
+}
+
+impl Ctxt {
+    pub fn derive_encoded_size(&self) -> syn::Result<TokenStream2> {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+
+        if self.flags.format.is_some() {
+            return Err(Error::new_spanned(&self.item_name, SIZE_FORMAT));
+        }
+        if self.requires_bit_impl() {
+            return Err(Error::new_spanned(&self.item_name, SIZE_BITS));
+        }
+        check_size_flags(&self.flags, &self.item_name)?;
+
+        // A "redir" flag on the item or on a variant transforms the whole encoded data,
+        // so it's measured by encoding it
+        if self.flags.stream_modifiers.iter().any(StreamModifier::is_transform)
+            || self
+                .variants
+                .iter()
+                .flat_map(|x| &x.flags.stream_modifiers)
+                .any(StreamModifier::is_transform)
+        {
+            let body = self.derive_encode()?;
+            return Ok(quote!(
+                let mut __sizer = #crate_name::Encoder::new(
+                    #crate_name::io::SizeTrack::new(#crate_name::io::Zero),
+                    #encoder.ctxt
+                );
+                let mut __encode = || -> #crate_name::EncodingResult<()> {
+                    let #encoder = &mut __sizer;
+                    #body
+                };
+                __encode()?;
+                #crate_name::EncodingResult::Ok(__sizer.finish().0.size_written())
+            ));
+        }
+
+        let (const_code, value) = match self.item_type {
+            ItemType::Struct => (TokenStream2::new(), self.struct_data.derive_size(self)?),
+            ItemType::Enum => {
+                // Edge case for 0-variant enums
+                if self.variants.len() == 0 {
+                    return Ok(quote!(
+                        #crate_name::EncodingResult::Ok(0)
+                    ));
+                }
+
+                let mut const_code = ConstCode::new(self);
+                let mut variant_code = TokenStream2::new();
+
+                for variant in self.variants.iter() {
+                    const_code.append(variant);
+                    variant_code.append_all(variant.derive_size(self)?);
+                }
+
+                (
+                    const_code.to_token_stream(),
+                    quote!({
+                        let mut __size: usize = 0;
+                        match self {
+                            #variant_code
+                        }
+                        __size
+                    }),
+                )
+            }
+        };
+
+        let (pre, post) = self.flags.mods.derive(self)?;
+        let padding = derive_padding_size(&self.flags);
+        let value = derive_sized(self, &self.flags, value);
+
+        Ok(quote!(
+            #const_code
+
+            let mut __size: usize = 0;
+            #pre
+            #padding
+            __size += #value;
+            #post
+            #crate_name::EncodingResult::Ok(__size)
+        ))
+    }
+}
+
+impl Variant {
+    /// Generates the match arm adding the size of this variant to `__size`
+    pub fn derive_size(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        check_size_flags(&self.flags, &self.name)?;
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref index = self.index.ident;
+
+        let body = if self.flags.unknown {
+            let ref tag = self.fields[0].name;
+            let ref data = self.fields[1].name;
+
+            let tag_size = if let Some(tag_type) = &ctxt.flags.tag_type {
+                quote!(
+                    __size += <#tag_type as #crate_name::EncodedSize>::encoded_size(#tag, &#encoder.ctxt)?;
+                )
+            } else {
+                let enum_repr = ctxt.enum_repr;
+                let variant_size = if enum_repr.signed() {
+                    quote!(ivariant_size)
+                } else {
+                    quote!(uvariant_size)
+                };
+                quote!(
+                    let __tag: #enum_repr = ::core::convert::TryInto::try_into(
+                        #crate_name::Opaque::from(::core::clone::Clone::clone(#tag))
+                    )?;
+                    __size += #crate_name::Context::#variant_size(&mut #encoder.ctxt, __tag)?;
+                )
+            };
+
+            quote!(
+                __size += #crate_name::Context::enum_header_size(&#encoder.ctxt)?;
+                #tag_size
+                __size += <[u8]>::len(#data);
+            )
+        } else {
+            let mut ref_code = RefCode::new(ctxt);
+            let mut field_code = TokenStream2::new();
+
+            for field in self.fields.iter() {
+                field_code.append_all(field.derive_size(ctxt, &mut ref_code, &self.fields)?);
+            }
+
+            let variant_size = if let Some(tag_type) = &ctxt.flags.tag_type {
+                // Every variant is guaranteed to have a tag when a tag type is specified
+                let tag = self.canonical_tag().unwrap();
+                quote!(
+                    let __tag: #tag_type = ::core::convert::Into::into(#tag);
+                    __size += <#tag_type as #crate_name::EncodedSize>::encoded_size(&__tag, &#encoder.ctxt)?;
+                )
+            } else if ctxt.enum_repr.signed() {
+                quote!(
+                    __size += #crate_name::Context::ivariant_size(&mut #encoder.ctxt, #index)?;
+                )
+            } else {
+                quote!(
+                    __size += #crate_name::Context::uvariant_size(&mut #encoder.ctxt, #index)?;
+                )
+            };
+
+            let len = self.fields.iter().filter(|x| !x.flags.skip).count();
+            quote!(
+                __size += #crate_name::Context::enum_header_size(&#encoder.ctxt)?;
+                #variant_size
+                __size += #crate_name::Context::fields_len_size(&#encoder.ctxt, #len)?;
+                #ref_code
+                #field_code
+            )
+        };
+
+        let size = derive_sized(
+            ctxt,
+            &self.flags,
+            quote!({
+                let mut __size: usize = 0;
+                #body
+                __size
+            }),
+        );
+
+        let ref name = self.name;
+        let fields = self.fields.iter().map(|x| &x.name);
+        Ok(match self.flavor {
+            Flavor::Unit => quote!(
+                Self::#name => { __size += #size; },
+            ),
+            Flavor::Tuple => quote!(
+                Self::#name ( #(#fields),* ) => { __size += #size; },
+            ),
+            Flavor::Struct => quote!(
+                Self::#name { #(#fields),* } => { __size += #size; },
+            ),
+        })
+    }
+}
+
+impl Struct {
+    /// Generates an expression evaluating to the size of the fields of this struct
+    pub fn derive_size(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        let mut ref_code = RefCode::new(ctxt);
+        let mut field_code = TokenStream2::new();
+
+        for field in self.fields.iter() {
+            field_code.append_all(field.derive_size(ctxt, &mut ref_code, &self.fields)?);
+        }
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let len = self.fields.iter().filter(|x| !x.flags.skip).count();
+
+        Ok(quote!({
+            let mut __size: usize = 0;
+            __size += #crate_name::Context::fields_len_size(&#encoder.ctxt, #len)?;
+            #ref_code
+            #field_code
+            __size
+        }))
+    }
+}
+
+impl Field {
+    /// Generates the statements adding the size of this field to `__size`
+    pub fn derive_size(
+        &self,
+        ctxt: &Ctxt,
+        ref_code: &mut RefCode,
+        fields: &[Field],
+    ) -> syn::Result<TokenStream2> {
+        ref_code.append(self);
+
+        let validate = self.flags.derive_validation(ctxt, Some(&ref_code))?;
+        if self.flags.skip {
+            return Ok(validate);
+        }
+
+        // The length is computed from the target field and shadows the field's value
+        let len = if let Some(len_of) = &self.flags.len_of {
+            let ref target = len_of.target;
+            let len_value = if len_of.bytes {
+                // The field exists, or the flag would have been rejected while parsing
+                let target = fields.iter().find(|x| &x.name == target).unwrap();
+                target.derive_size_expr(ctxt)?
+            } else {
+                quote!((#target).len())
+            };
+            self.derive_len(ctxt, len_value)
+        } else {
+            TokenStream2::new()
+        };
+
+        let size = self.derive_size_expr(ctxt)?;
+
+        Ok(quote!(
+            #len
+            #validate
+            __size += #size;
+        ))
+    }
+
+    /// Generates an expression evaluating to the size of this field, including its padding
+    /// and magic number, or `0` if its condition is false
+    fn derive_size_expr(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        check_size_flags(&self.flags, &self.name)?;
+
+        let (pre, post) = self.flags.mods.derive(ctxt)?;
+        let padding = derive_padding_size(&self.flags);
+        let value = self.derive_size_value(ctxt)?;
+
+        let size = quote!({
+            let mut __size: usize = 0;
+            #pre
+            #padding
+            __size += #value;
+            #post
+            __size
+        });
+
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+
+        Ok(match self.flags.derive_condition(ctxt) {
+            Some(condition) => quote!(
+                if #condition { #size } else { #crate_name::Context::null_size(&#encoder.ctxt) }
+            ),
+            None => size,
+        })
+    }
+
+    /// Generates an expression evaluating to the size of the value of this field
+    fn derive_size_value(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+
+        // The output of a "with" function or of a "redir" transformation is unknown,
+        // so the value is measured by encoding it
+        if !self.flags.function.is_default()
+            || self.flags.stream_modifiers.iter().any(StreamModifier::is_transform)
+        {
+            let (_, modified, _) = self.derive_encode_value(ctxt)?;
+            return Ok(quote!({
+                let mut __sizer = #crate_name::Encoder::new(
+                    #crate_name::io::SizeTrack::new(#crate_name::io::Zero),
+                    #encoder.ctxt
+                );
+                {
+                    let #encoder = &mut __sizer;
+                    #modified;
+                }
+                __sizer.finish().0.size_written()
+            }));
+        }
+
+        let ref field_name = self.name;
+        let ref field_ty = self.ty;
+        let size = if let Some(converter) = &self.flags.ty_mods {
+            let input = converter.convert_into(self)?;
+            let ty = converter.ty();
+            quote!(<#ty as #crate_name::EncodedSize>::encoded_size(#input, &#encoder.ctxt)?)
+        } else {
+            quote!(<#field_ty as #crate_name::EncodedSize>::encoded_size(#field_name, &#encoder.ctxt)?)
+        };
+
+        Ok(derive_sized(ctxt, &self.flags, size))
+    }
+}
@@ -0,0 +1,287 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2, TokenTree};
+use quote::{quote, ToTokens, TokenStreamExt};
+use syn::Error;
+
+use crate::ctxt::{Ctxt, Field, ItemType, Struct, Variant};
+use crate::flags::{FlagTarget, Function};
+use crate::generator::{ConstCode, RefCode};
+
+const SKIP_FORMAT: &str = r#""format" and "tlv" flags are not supported when deriving Skip"#;
+
+/// Whether the given identifier appears anywhere in the tokens
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(x) => &x == ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Generates the skip code of a list of fields.
+///
+/// A field is only decoded when the flags of the following fields may refer to it,
+/// otherwise it is skipped
+fn derive_fields_skip(ctxt: &Ctxt, fields: &[Field]) -> syn::Result<TokenStream2> {
+    let mut ref_code = RefCode::new(ctxt);
+    let mut field_code = TokenStream2::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let mut bind = false;
+        for next in fields[i + 1..].iter() {
+            let code = next.derive_decode_block(ctxt, &RefCode::new(ctxt))?;
+            if mentions(code, &field.name) {
+                bind = true;
+                break;
+            }
+        }
+
+        field_code.append_all(field.derive_skip(ctxt, &ref_code, bind)?);
+        if bind {
+            ref_code.append(field);
+        }
+    }
+
+    Ok(field_code)
+}
+
+impl Ctxt {
+    pub fn derive_skip(&self) -> syn::Result<TokenStream2> {
+        let ref crate_name = self.flags.crate_name;
+        let ref encoder = self.encoder;
+        let ref item_name = self.item_name;
+
+        if self.flags.format.is_some() {
+            return Err(Error::new_spanned(item_name, SKIP_FORMAT));
+        }
+
+        let (const_code, body) = match self.item_type {
+            ItemType::Struct => (TokenStream2::new(), self.struct_data.derive_skip(self)?),
+            ItemType::Enum => {
+                // Edge case for 0-variant enums
+                if self.variants.len() == 0 {
+                    return Ok(quote!(
+                        #crate_name::EncodingResult::Err(#crate_name::EncodingError::InvalidVariant(#crate_name::Opaque::from(0usize)))
+                    ));
+                }
+
+                let mut const_code = ConstCode::new(self);
+                let mut variant_code = TokenStream2::new();
+
+                let read_variant = if self.enum_repr.signed() {
+                    quote!(#crate_name::Encoder::read_ivariant(#encoder)?)
+                } else {
+                    quote!(#crate_name::Encoder::read_uvariant(#encoder)?)
+                };
+
+                for variant in self.variants.iter() {
+                    const_code.append(variant);
+                    if !variant.flags.unknown {
+                        variant_code.append_all(variant.derive_skip(self)?);
+                    }
+                }
+
+                // The data of an unrecognized tag is whatever remains of the stream
+                let unknown = match self.variants.iter().find(|x| x.flags.unknown) {
+                    Some(variant) => variant.flags.derive_stream_modifiers(
+                        self,
+                        quote!(#crate_name::Encoder::skip_remaining(#encoder)),
+                        FlagTarget::Variant,
+                        variant.name.to_string(),
+                    )?,
+                    None if self.flags.tag_type.is_some() => quote!(
+                        #crate_name::EncodingResult::Err(#crate_name::EncodingError::invalid_tag(&__tag))
+                    ),
+                    None => quote!(
+                        #crate_name::EncodingResult::Err(#crate_name::EncodingError::InvalidVariant(#crate_name::Opaque::from(__tag)))
+                    ),
+                };
+
+                let enum_header = self.derive_enum_header();
+                let body = if let Some(tag_type) = &self.flags.tag_type {
+                    quote!(
+                        let __tag: #tag_type = <#tag_type as #crate_name::Decode<_>>::decode(#encoder)?;
+                        match () {
+                            #variant_code
+                            _ => { #unknown },
+                        }?
+                    )
+                } else if self.tagged() {
+                    let enum_repr = self.enum_repr;
+                    quote!(
+                        let __tag: #enum_repr = #read_variant;
+                        match () {
+                            #variant_code
+                            _ => { #unknown },
+                        }?
+                    )
+                } else {
+                    quote!(
+                        match #read_variant {
+                            #variant_code
+                            __tag => { #unknown },
+                        }?
+                    )
+                };
+
+                (const_code.to_token_stream(), quote!(#enum_header #body))
+            }
+        };
+
+        let (pre, post) = self.flags.mods.derive(self)?;
+        let modified = self.flags.derive_stream_modifiers(
+            self,
+            body,
+            FlagTarget::Item,
+            item_name.to_string(),
+        )?;
+        let seek = self.flags.derive_seek(self)?;
+        let bit_align = self.flags.derive_bit_align(self)?;
+        let padding = self.flags.derive_padding(self)?;
+        let magic = self.flags.derive_magic(self)?;
+        let pos_tracker = self.flags.derive_pos_tracker(self)?;
+
+        Ok(quote!(
+            #const_code
+
+            #pos_tracker
+            #pre
+            #seek
+            #bit_align
+            #padding
+            #magic
+            { #modified };
+            #post
+            #crate_name::EncodingResult::Ok(())
+        ))
+    }
+}
+
+impl Variant {
+    /// Generates the match arm skipping this variant
+    pub fn derive_skip(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        let ref crate_name = ctxt.flags.crate_name;
+        let field_code = derive_fields_skip(ctxt, &self.fields)?;
+        let fields_len = ctxt.derive_fields_len(&self.fields);
+
+        self.decode_match(
+            ctxt,
+            quote!(
+                #fields_len
+                #field_code
+
+                #crate_name::EncodingResult::Ok(())
+            ),
+        )
+    }
+}
+
+impl Struct {
+    /// Generates the skip code for this struct
+    pub fn derive_skip(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        let field_code = derive_fields_skip(ctxt, &self.fields)?;
+        let fields_len = ctxt.derive_fields_len(&self.fields);
+
+        Ok(quote!(
+            #fields_len
+            #field_code
+        ))
+    }
+}
+
+impl Field {
+    /// Generates the skip code for this field.
+    ///
+    /// If `bind` is true, the field is decoded into a variable of the same name,
+    /// without being validated
+    fn derive_skip(&self, ctxt: &Ctxt, ref_code: &RefCode, bind: bool) -> syn::Result<TokenStream2> {
+        let ref field_name = self.name;
+        let ref field_ty = self.ty;
+        let pos_tracker = self.flags.derive_pos_tracker(ctxt)?;
+
+        if bind {
+            let decode = self.derive_decode_block(ctxt, ref_code)?;
+            return Ok(quote!(
+                #pos_tracker
+                let #field_name: #field_ty = #decode;
+            ));
+        }
+        if self.flags.skip {
+            return Ok(TokenStream2::new());
+        }
+
+        let (pre, post) = self.flags.mods.derive(ctxt)?;
+        let skip = self.derive_skip_value(ctxt)?;
+        let seek = self.flags.derive_seek(ctxt)?;
+        let bit_align = self.flags.derive_bit_align(ctxt)?;
+        let padding = self.flags.derive_padding(ctxt)?;
+        let magic = self.flags.derive_magic(ctxt)?;
+        let null = ctxt.derive_null();
+
+        let skip = quote!(
+            #pre
+            #seek
+            #bit_align
+            #padding
+            #magic
+            #skip
+            #post
+        );
+
+        Ok(match self.flags.derive_condition(ctxt) {
+            Some(condition) => quote!(
+                #pos_tracker
+                {
+                    #ref_code
+                    if #condition {
+                        #skip
+                    } else {
+                        #null
+                    }
+                }
+            ),
+            None => quote!(
+                #pos_tracker
+                {
+                    #ref_code
+                    #skip
+                }
+            ),
+        })
+    }
+
+    /// Generates the statement skipping the value of this field.
+    ///
+    /// The values that are only known to the "with", "serde", "bits" and "asn1" flags
+    /// are decoded and dropped instead
+    fn derive_skip_value(&self, ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+        let ref crate_name = ctxt.flags.crate_name;
+        let ref encoder = ctxt.encoder;
+        let ref field_ty = self.ty;
+
+        let skip_ty = match &self.flags.function {
+            Function::SkipValue(ty) => Some(ty),
+            Function::Default if self.flags.asn1.is_none() => Some(match &self.flags.ty_mods {
+                Some(converter) => converter.ty(),
+                None => field_ty,
+            }),
+            _ => None,
+        };
+
+        let Some(skip_ty) = skip_ty else {
+            let (_, modified, _) = self.derive_decode_value(ctxt)?;
+            return Ok(quote!(
+                let _: #field_ty = #modified;
+            ));
+        };
+
+        let skip = self.flags.derive_stream_modifiers(
+            ctxt,
+            quote!(<#skip_ty as #crate_name::Skip<_>>::skip(#encoder)?),
+            FlagTarget::Field,
+            self.accessor.to_string(),
+        )?;
+        Ok(quote!(
+            #skip;
+        ))
+    }
+}
@@ -1,8 +1,8 @@
 use proc_macro::TokenStream as TokenStream1;
 
-use proc_macro2::{Ident, Span};
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use quote::quote;
-use syn::{parse_macro_input, parse_quote, DeriveInput, GenericParam};
+use syn::{parse_macro_input, parse_quote, DeriveInput, Error, GenericParam};
 
 use crate::ctxt::{Ctxt, Target};
 
@@ -15,6 +15,8 @@ mod parse;
 
 static ENDER: &str = "ender";
 
+const ASYNC_BITS: &str = r#""bits", "bit_align" and "bitwise" flags are not supported when deriving async implementations"#;
+
 /// Emulates the $crate available in regular macros
 fn dollar_crate(name: &str) -> Ident {
     let crate_name = std::env::var("CARGO_PKG_NAME").expect("Can't obtain current crate name");
@@ -31,18 +33,54 @@ fn dollar_crate(name: &str) -> Ident {
 #[proc_macro_derive(Encode, attributes(ender))]
 pub fn encode(input: TokenStream1) -> TokenStream1 {
     let input = parse_macro_input!(input as DeriveInput);
-    let ctxt = match Ctxt::parse_from(&input, Target::Encode) {
+    let mut ctxt = match Ctxt::parse_from(&input, Target::Encode) {
         Ok(ctxt) => ctxt,
         Err(err) => return TokenStream1::from(err.to_compile_error()),
     };
 
+    let mut output = match encode_impl(&ctxt) {
+        Ok(output) => output,
+        Err(err) => return TokenStream1::from(err.to_compile_error()),
+    };
+
+    // Optionally also generate the async impl
+    if ctxt.flags.asynchronous {
+        ctxt.asynchronous = true;
+        match encode_impl(&ctxt) {
+            Ok(async_output) => output.extend(async_output),
+            Err(err) => return TokenStream1::from(err.to_compile_error()),
+        }
+    }
+
+    output.into()
+}
+
+/// Generates either the `Encode` or the `AsyncEncode` impl, depending on the context
+fn encode_impl(ctxt: &Ctxt) -> syn::Result<TokenStream2> {
     let ref encoder_generic = ctxt.encoder_generic;
     let ref crate_name = ctxt.flags.crate_name;
-    let type_param = if ctxt.requires_seeking_impl() {
-        parse_quote!(#encoder_generic: #crate_name::io::Write + #crate_name::io::Seek)
+    if ctxt.asynchronous && ctxt.requires_bit_impl() {
+        return Err(Error::new_spanned(&ctxt.item_name, ASYNC_BITS));
+    }
+
+    let mut bounds = Vec::new();
+    if ctxt.asynchronous {
+        bounds.push(quote!(#crate_name::io::AsyncWrite));
+    } else if ctxt.requires_bit_impl() {
+        bounds.push(quote!(#crate_name::io::BitWrite));
     } else {
-        parse_quote!(#encoder_generic: #crate_name::io::Write)
-    };
+        bounds.push(quote!(#crate_name::io::Write));
+    }
+    if ctxt.requires_seeking_impl() || ctxt.requires_position_impl() {
+        if ctxt.asynchronous {
+            bounds.push(quote!(#crate_name::io::AsyncSeek));
+        } else if ctxt.requires_seeking_impl() {
+            bounds.push(quote!(#crate_name::io::Seek));
+        } else {
+            bounds.push(quote!(#crate_name::io::Position));
+        }
+    }
+    let type_param = parse_quote!(#encoder_generic: #(#bounds)+*);
 
     // Inject the decoder's generic parameter in the `impl` generics
     let mut generics = ctxt.generics.clone();
@@ -55,48 +93,141 @@ pub fn encode(input: TokenStream1) -> TokenStream1 {
     let ref item_name = ctxt.item_name;
     let ref encoder = ctxt.encoder;
 
-    let body = match ctxt.derive() {
+    let body = ctxt.derive()?;
+    let format_impl = ctxt.derive_format_impl();
+
+    Ok(if ctxt.asynchronous {
+        quote!(
+            #[automatically_derived]
+            #[allow(unused)]
+            #[allow(dead_code)]
+            impl #impl_generics #crate_name::AsyncEncode<#encoder_generic> for #item_name #ty_generics #where_clause {
+                async fn encode_async(&self, #encoder: &mut #crate_name::Encoder<'_, #encoder_generic>) -> #crate_name::EncodingResult<()> {
+                    #body
+                }
+            }
+        )
+    } else {
+        quote!(
+            #[automatically_derived]
+            #[allow(unused)]
+            #[allow(dead_code)]
+            impl #impl_generics #crate_name::Encode<#encoder_generic> for #item_name #ty_generics #where_clause {
+                fn encode(&self, #encoder: &mut #crate_name::Encoder<#encoder_generic>) -> #crate_name::EncodingResult<()> {
+                    #body
+                }
+            }
+            #format_impl
+        )
+    })
+}
+
+#[proc_macro_derive(EncodedSize, attributes(ender))]
+pub fn encoded_size(input: TokenStream1) -> TokenStream1 {
+    let input = parse_macro_input!(input as DeriveInput);
+    // The size is computed according to the flags used while encoding
+    let ctxt = match Ctxt::parse_from(&input, Target::Encode) {
         Ok(ctxt) => ctxt,
         Err(err) => return TokenStream1::from(err.to_compile_error()),
     };
 
-    quote!(
+    match encoded_size_impl(&ctxt) {
+        Ok(output) => output.into(),
+        Err(err) => TokenStream1::from(err.to_compile_error()),
+    }
+}
+
+/// Generates the `EncodedSize` impl
+fn encoded_size_impl(ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+    let ref crate_name = ctxt.flags.crate_name;
+
+    let (impl_generics, ty_generics, where_clause) = ctxt.generics.split_for_impl();
+    let ref item_name = ctxt.item_name;
+    let ref encoder = ctxt.encoder;
+
+    let body = ctxt.derive_encoded_size()?;
+
+    Ok(quote!(
         #[automatically_derived]
         #[allow(unused)]
         #[allow(dead_code)]
-        impl #impl_generics #crate_name::Encode<#encoder_generic> for #item_name #ty_generics #where_clause {
-            fn encode(&self, #encoder: &mut #crate_name::Encoder<#encoder_generic>) -> #crate_name::EncodingResult<()> {
+        impl #impl_generics #crate_name::EncodedSize for #item_name #ty_generics #where_clause {
+            fn encoded_size(&self, __ctxt: &#crate_name::Context) -> #crate_name::EncodingResult<usize> {
+                // The context is held by an encoder that never writes, so that the
+                // flags can modify it just like while encoding
+                let mut __holder = #crate_name::Encoder::new(#crate_name::io::Zero, *__ctxt);
+                let #encoder = &mut __holder;
                 #body
             }
         }
-    ).into()
+    ))
 }
 
 #[proc_macro_derive(Decode, attributes(ender))]
 pub fn decode(input: TokenStream1) -> TokenStream1 {
     let input = parse_macro_input!(input as DeriveInput);
-    let ctxt = match Ctxt::parse_from(&input, Target::Decode) {
+    let mut ctxt = match Ctxt::parse_from(&input, Target::Decode) {
         Ok(ctxt) => ctxt,
         Err(err) => return TokenStream1::from(err.to_compile_error()),
     };
 
+    let mut output = match decode_impl(&ctxt) {
+        Ok(output) => output,
+        Err(err) => return TokenStream1::from(err.to_compile_error()),
+    };
+
+    // Optionally also generate the async impl
+    if ctxt.flags.asynchronous {
+        ctxt.asynchronous = true;
+        match decode_impl(&ctxt) {
+            Ok(async_output) => output.extend(async_output),
+            Err(err) => return TokenStream1::from(err.to_compile_error()),
+        }
+    }
+
+    output.into()
+}
+
+/// Generates either the `Decode` or the `AsyncDecode` impl, depending on the context
+fn decode_impl(ctxt: &Ctxt) -> syn::Result<TokenStream2> {
     let ref encoder_generic = ctxt.encoder_generic;
     let ref crate_name = ctxt.flags.crate_name;
     let ref decoder_lif = ctxt.borrow_data.decoder;
-    
-    let type_param = if ctxt.requires_borrowing_impl() {
-        if ctxt.requires_seeking_impl() {
-            parse_quote!(#encoder_generic: #crate_name::io::BorrowRead<#decoder_lif> + #crate_name::io::Seek)
-        } else {
-            parse_quote!(#encoder_generic: #crate_name::io::BorrowRead<#decoder_lif>)
-        }
+
+    if ctxt.asynchronous && ctxt.requires_borrowing_impl() {
+        return Err(Error::new_spanned(
+            &ctxt.item_name,
+            r#""borrow" flag is not supported when deriving async implementations"#,
+        ));
+    }
+    if ctxt.asynchronous && ctxt.requires_bit_impl() {
+        return Err(Error::new_spanned(&ctxt.item_name, ASYNC_BITS));
+    }
+
+    let mut bounds = Vec::new();
+    if ctxt.asynchronous {
+        bounds.push(quote!(#crate_name::io::AsyncRead));
     } else {
-        if ctxt.requires_seeking_impl() {
-            parse_quote!(#encoder_generic: #crate_name::io::Read + #crate_name::io::Seek)
+        if ctxt.requires_borrowing_impl() {
+            bounds.push(quote!(#crate_name::io::BorrowRead<#decoder_lif>));
+        }
+        if ctxt.requires_bit_impl() {
+            bounds.push(quote!(#crate_name::io::BitRead));
+        }
+        if bounds.is_empty() {
+            bounds.push(quote!(#crate_name::io::Read));
+        }
+    }
+    if ctxt.requires_seeking_impl() || ctxt.requires_position_impl() {
+        if ctxt.asynchronous {
+            bounds.push(quote!(#crate_name::io::AsyncSeek));
+        } else if ctxt.requires_seeking_impl() {
+            bounds.push(quote!(#crate_name::io::Seek));
         } else {
-            parse_quote!(#encoder_generic: #crate_name::io::Read)
+            bounds.push(quote!(#crate_name::io::Position));
         }
-    };
+    }
+    let type_param = parse_quote!(#encoder_generic: #(#bounds)+*);
 
     let lif = if ctxt.borrow_data.sub_lifetimes.is_empty() {
         parse_quote!(
@@ -123,19 +254,109 @@ pub fn decode(input: TokenStream1) -> TokenStream1 {
     let ref item_name = ctxt.item_name;
     let ref encoder = ctxt.encoder;
 
-    let body = match ctxt.derive() {
+    let body = ctxt.derive_nested(ctxt.derive()?);
+    let format_impl = ctxt.derive_format_impl();
+
+    Ok(if ctxt.asynchronous {
+        quote!(
+            #[automatically_derived]
+            #[allow(unused)]
+            #[allow(dead_code)]
+            impl #impl_generics #crate_name::AsyncDecode<#encoder_generic> for #item_name #ty_generics #where_clause {
+                async fn decode_async(#encoder: &mut #crate_name::Encoder<'_, #encoder_generic>) -> #crate_name::EncodingResult<Self> {
+                    #body
+                }
+            }
+        )
+    } else {
+        quote!(
+            #[automatically_derived]
+            #[allow(unused)]
+            #[allow(dead_code)]
+            impl #impl_generics #crate_name::Decode<#encoder_generic> for #item_name #ty_generics #where_clause {
+                fn decode(#encoder: &mut #crate_name::Encoder<#encoder_generic>) -> #crate_name::EncodingResult<Self> {
+                    #body
+                }
+            }
+            #format_impl
+        )
+    })
+}
+
+#[proc_macro_derive(Skip, attributes(ender))]
+pub fn skip(input: TokenStream1) -> TokenStream1 {
+    let input = parse_macro_input!(input as DeriveInput);
+    // The data is skipped according to the flags used while decoding
+    let ctxt = match Ctxt::parse_from(&input, Target::Decode) {
         Ok(ctxt) => ctxt,
         Err(err) => return TokenStream1::from(err.to_compile_error()),
     };
 
-    quote!(
+    match skip_impl(&ctxt) {
+        Ok(output) => output.into(),
+        Err(err) => TokenStream1::from(err.to_compile_error()),
+    }
+}
+
+/// Generates the `Skip` impl
+fn skip_impl(ctxt: &Ctxt) -> syn::Result<TokenStream2> {
+    let ref encoder_generic = ctxt.encoder_generic;
+    let ref crate_name = ctxt.flags.crate_name;
+    let ref decoder_lif = ctxt.borrow_data.decoder;
+
+    // Some fields may still have to be decoded, so the bounds are the same as `Decode`'s
+    let mut bounds = Vec::new();
+    if ctxt.requires_borrowing_impl() {
+        bounds.push(quote!(#crate_name::io::BorrowRead<#decoder_lif>));
+    }
+    if ctxt.requires_bit_impl() {
+        bounds.push(quote!(#crate_name::io::BitRead));
+    }
+    if bounds.is_empty() {
+        bounds.push(quote!(#crate_name::io::Read));
+    }
+    if ctxt.requires_seeking_impl() {
+        bounds.push(quote!(#crate_name::io::Seek));
+    } else if ctxt.requires_position_impl() {
+        bounds.push(quote!(#crate_name::io::Position));
+    }
+    let type_param = parse_quote!(#encoder_generic: #(#bounds)+*);
+
+    let lif = if ctxt.borrow_data.sub_lifetimes.is_empty() {
+        parse_quote!(
+            #decoder_lif
+        )
+    } else {
+        let sub_lifs = ctxt.borrow_data.sub_lifetimes.iter();
+        parse_quote!(
+            #decoder_lif: #(#sub_lifs)+*
+        )
+    };
+
+    // Inject the decoder's generic parameter and lifetime in the `impl` generics
+    let mut generics = ctxt.generics.clone();
+    generics.params.push(GenericParam::Type(type_param));
+    if ctxt.requires_borrowing_impl() {
+        generics.params.insert(0, GenericParam::Lifetime(lif));
+    }
+
+    // Impl generics use injected generics
+    let (impl_generics, _, _) = generics.split_for_impl();
+    // Ty and where clause use the original generics
+    let (_, ty_generics, where_clause) = ctxt.generics.split_for_impl();
+    let ref item_name = ctxt.item_name;
+    let ref encoder = ctxt.encoder;
+
+    let body = ctxt.derive_nested(ctxt.derive_skip()?);
+
+    Ok(quote!(
         #[automatically_derived]
         #[allow(unused)]
         #[allow(dead_code)]
-        impl #impl_generics #crate_name::Decode<#encoder_generic> for #item_name #ty_generics #where_clause {
-            fn decode(#encoder: &mut #crate_name::Encoder<#encoder_generic>) -> #crate_name::EncodingResult<Self> {
+        impl #impl_generics #crate_name::Skip<#encoder_generic> for #item_name #ty_generics #where_clause {
+            fn skip(#encoder: &mut #crate_name::Encoder<#encoder_generic>) -> #crate_name::EncodingResult<()> {
                 #body
             }
         }
-    ).into()
+    ))
 }
\ No newline at end of file
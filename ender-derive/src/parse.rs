@@ -4,14 +4,14 @@ use proc_macro2::{Ident, Span};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::token::Paren;
-use syn::{parenthesized, Error, Expr, Lifetime, LitStr, Path, Token, Type};
+use syn::{parenthesized, Error, Expr, Lifetime, LitStr, Path, RangeLimits, Token, Type};
 
 use crate::ctxt::Scope;
 use crate::enums::BitWidth;
 
 const FLAGS_USAGE: &str = r#"Unknown Flag. Please refer to the documentation of the macro for a list of valid flags and their usage."#;
 
-const MODIFIER_USAGE: &str = r#"Unknown modifier. Modifier can be str-encoding (utf8, utf16, utf32), bit-width (bit8, bit16, bit32, bit64, bit128), endianness (big_endian, little_endian), num-encoding (fixed, leb128, protobuf_wasteful, protobuf_zz), max-size (max = $expr)"#;
+const MODIFIER_USAGE: &str = r#"Unknown modifier. Modifier can be str-encoding (utf8, utf16, utf32), bit-width (bit8, bit16, bit32, bit64, bit128), endianness (big_endian, little_endian), num-encoding (fixed, leb128, protobuf_wasteful, protobuf_zz, bincode_varint, postcard_varint, asn1), max-size (max = $expr)"#;
 
 pub mod kw {
     use syn::custom_keyword;
@@ -26,10 +26,33 @@ pub mod kw {
     custom_keyword!(serde);
     custom_keyword!(skip);
     custom_keyword!(with);
+    custom_keyword!(skip_value);
     custom_keyword!(flatten);
     custom_keyword!(validate);
     custom_keyword!(borrow);
     custom_keyword!(goto);
+    custom_keyword!(magic);
+    custom_keyword!(len_of);
+    custom_keyword!(bytes);
+    custom_keyword!(checksum);
+    custom_keyword!(over);
+    custom_keyword!(tag);
+    custom_keyword!(tag_type);
+    custom_keyword!(unknown);
+    custom_keyword!(since);
+    custom_keyword!(until);
+    custom_keyword!(format);
+    custom_keyword!(field);
+    custom_keyword!(tlv);
+    custom_keyword!(id);
+    custom_keyword!(explicit);
+    custom_keyword!(optional);
+
+    /* Keywords related to padding */
+    custom_keyword!(align);
+    custom_keyword!(pad);
+    custom_keyword!(fill);
+    custom_keyword!(check_padding);
 
     /* Keywords related to `Seek` specifically */
     custom_keyword!(start);
@@ -38,13 +61,27 @@ pub mod kw {
     custom_keyword!(pos_tracker);
     custom_keyword!(seeking);
 
+    /* Keywords related to bit fields */
+    custom_keyword!(bits);
+    custom_keyword!(bit_align);
+    custom_keyword!(bitwise);
+
     /* Stream modifiers */
     custom_keyword!(redir);
     custom_keyword!(ptr);
+    custom_keyword!(sized);
 
     /* Flatten targets */
     custom_keyword!(bool);
 
+    /* Message formats */
+    custom_keyword!(protobuf);
+
+    /* ASN.1 tag classes */
+    custom_keyword!(context);
+    custom_keyword!(application);
+    custom_keyword!(private);
+
     /* Keywords used for modifiers TARGETS */
     custom_keyword!(num);
     custom_keyword!(size);
@@ -64,6 +101,9 @@ pub mod kw {
     custom_keyword!(leb128);
     custom_keyword!(protobuf_wasteful);
     custom_keyword!(protobuf_zz);
+    custom_keyword!(bincode_varint);
+    custom_keyword!(postcard_varint);
+    custom_keyword!(asn1);
     // Endianness
     custom_keyword!(big_endian);
     custom_keyword!(little_endian);
@@ -103,6 +143,32 @@ pub enum FlattenTarget {
     Size { kw: kw::size },
 }
 
+/// The message formats the fields of a struct can be encoded in, identified by a key
+#[derive(Clone)]
+#[allow(dead_code)]
+pub enum FormatParam {
+    Protobuf { kw: kw::protobuf },
+}
+
+/// The classes of the ASN.1 tags that can be given to a field
+#[derive(Clone)]
+#[allow(dead_code)]
+pub enum Asn1ClassParam {
+    Context { kw: kw::context },
+    Application { kw: kw::application },
+    Private { kw: kw::private },
+}
+
+/// The options of the ASN.1 tag given to a field
+#[derive(Clone)]
+#[allow(dead_code)]
+pub enum Asn1Param {
+    /// The tag wraps the value, instead of replacing its tag
+    Explicit { kw: kw::explicit },
+    /// The field can be absent, and is recognized by its tag while decoding
+    Optional { kw: kw::optional },
+}
+
 #[derive(Clone)]
 #[allow(dead_code)]
 pub enum FlattenParam {
@@ -126,6 +192,15 @@ pub enum Modifier {
     ProtobufZZ {
         kw: kw::protobuf_zz,
     },
+    BincodeVarint {
+        kw: kw::bincode_varint,
+    },
+    PostcardVarint {
+        kw: kw::postcard_varint,
+    },
+    Asn1 {
+        kw: kw::asn1,
+    },
     BigEndian {
         kw: kw::big_endian,
     },
@@ -353,6 +428,8 @@ pub enum Flag {
         colon: Token![:],
         seek: Expr,
     },
+    /// The field should be encoded/decoded in a byte-counted region, prefixed by its size in bytes.
+    Sized { kw: kw::sized },
     /// The field must be borrowed from the encoder when deriving `BorrowDecode`
     Borrow {
         kw: kw::borrow,
@@ -374,6 +451,146 @@ pub enum Flag {
     },
     /// Forces a `Seek*` implementation
     Seek { kw: kw::seeking },
+    /// Also generates the `Async*` implementation
+    Async { kw: Token![async] },
+    /// The region of the stream described by the given type should be skipped while decoding,
+    /// and the field should be initialized with its default value
+    SkipValue {
+        kw: kw::skip_value,
+        colon: Token![:],
+        ty: Type,
+    },
+    /// The field should be encoded/decoded as a bit field of the given width
+    Bits {
+        kw: kw::bits,
+        colon: Token![:],
+        bits: Expr,
+    },
+    /// The bit stream should be aligned to the next byte boundary before this field or item
+    BitAlign { kw: kw::bit_align },
+    /// Forces a `Bit*` implementation
+    Bitwise { kw: kw::bitwise },
+    /// The field holds the length of another field, in elements or in bytes
+    LenOf {
+        kw: kw::len_of,
+        bytes: Option<kw::bytes>,
+        colon: Token![:],
+        target: Ident,
+    },
+    /// A constant that should be written before this field or item, and checked while decoding
+    Magic {
+        kw: kw::magic,
+        colon: Token![:],
+        magic: Expr,
+    },
+    /// The stream position should be aligned to the given boundary before this field or item
+    Align {
+        kw: kw::align,
+        colon: Token![:],
+        align: Expr,
+    },
+    /// The given number of padding bytes should be inserted before this field or item
+    Pad {
+        kw: kw::pad,
+        colon: Token![:],
+        pad: Expr,
+    },
+    /// The value of the padding bytes
+    Fill {
+        kw: kw::fill,
+        colon: Token![:],
+        fill: Expr,
+    },
+    /// The padding bytes should be checked while decoding
+    CheckPadding { kw: kw::check_padding },
+    /// The field holds a checksum of the given algorithm, computed over a range of fields
+    Checksum {
+        kw: kw::checksum,
+        colon: Token![:],
+        algorithm: Path,
+        over: Option<ChecksumRange>,
+    },
+    /// The values of the tag that identifies this variant, the first of which is used
+    /// while encoding
+    Tag {
+        kw: kw::tag,
+        colon: Token![:],
+        tags: Punctuated<Expr, Token![,]>,
+    },
+    /// The type of the tag that identifies the variants of this enum
+    TagType {
+        kw: kw::tag_type,
+        colon: Token![:],
+        ty: Type,
+    },
+    /// This variant catches all the unrecognized tags, preserving the tag and the payload
+    Unknown { kw: kw::unknown },
+    /// The field is only present starting from the given format version
+    Since {
+        kw: kw::since,
+        colon: Token![:],
+        version: Expr,
+    },
+    /// The field is only present up to the given format version
+    Until {
+        kw: kw::until,
+        colon: Token![:],
+        version: Expr,
+    },
+    /// The fields of the struct are encoded as the keyed records of the given message format
+    Format {
+        kw: kw::format,
+        colon: Token![:],
+        format: FormatParam,
+    },
+    /// The key that identifies this field in a message format
+    Field {
+        kw: kw::field,
+        colon: Token![:],
+        key: Expr,
+    },
+    /// The fields of the struct are encoded as tag-length-value records
+    Tlv { kw: kw::tlv },
+    /// The id that identifies this field in a tag-length-value struct
+    Id {
+        kw: kw::id,
+        colon: Token![:],
+        key: Expr,
+    },
+    /// The ASN.1 tag of this field, which replaces the tag of its value unless explicit
+    Asn1 {
+        kw: kw::asn1,
+        colon: Token![:],
+        class: Asn1ClassParam,
+        paren: Paren,
+        number: Expr,
+        params: Vec<(Token![,], Asn1Param)>,
+    },
+}
+
+/// The range of fields a checksum is computed over
+#[derive(Clone)]
+#[allow(dead_code)]
+pub struct ChecksumRange {
+    pub comma: Token![,],
+    pub kw: kw::over,
+    pub colon: Token![:],
+    pub from: Ident,
+    pub limits: RangeLimits,
+    pub to: Ident,
+}
+
+impl Parse for ChecksumRange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            comma: input.parse()?,
+            kw: input.parse()?,
+            colon: input.parse()?,
+            from: input.parse()?,
+            limits: input.parse()?,
+            to: input.parse()?,
+        })
+    }
 }
 
 impl Flag {
@@ -395,10 +612,33 @@ impl Flag {
             Flag::Modifiers { target, .. } => target.span(),
             Flag::Redir { kw, .. } => kw.span,
             Flag::Ptr { kw, .. } => kw.span,
+            Flag::Sized { kw } => kw.span,
             Flag::Borrow { kw, .. } => kw.span,
             Flag::Goto { kw, .. } => kw.span,
             Flag::PosTracker { kw, .. } => kw.span,
             Flag::Seek { kw } => kw.span,
+            Flag::Async { kw } => kw.span,
+            Flag::Bits { kw, .. } => kw.span,
+            Flag::SkipValue { kw, .. } => kw.span,
+            Flag::BitAlign { kw } => kw.span,
+            Flag::Bitwise { kw } => kw.span,
+            Flag::Magic { kw, .. } => kw.span,
+            Flag::LenOf { kw, .. } => kw.span,
+            Flag::Checksum { kw, .. } => kw.span,
+            Flag::Align { kw, .. } => kw.span,
+            Flag::Pad { kw, .. } => kw.span,
+            Flag::Fill { kw, .. } => kw.span,
+            Flag::CheckPadding { kw } => kw.span,
+            Flag::Tag { kw, .. } => kw.span,
+            Flag::TagType { kw, .. } => kw.span,
+            Flag::Unknown { kw } => kw.span,
+            Flag::Since { kw, .. } => kw.span,
+            Flag::Until { kw, .. } => kw.span,
+            Flag::Format { kw, .. } => kw.span,
+            Flag::Field { kw, .. } => kw.span,
+            Flag::Tlv { kw } => kw.span,
+            Flag::Id { kw, .. } => kw.span,
+            Flag::Asn1 { kw, .. } => kw.span,
         }
     }
 }
@@ -465,6 +705,51 @@ impl Parse for FlattenTarget {
     }
 }
 
+impl Parse for FormatParam {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::protobuf) {
+            Ok(Self::Protobuf { kw: input.parse()? })
+        } else {
+            Err(Error::new(
+                input.span(),
+                r#""format" flag: expected "protobuf""#,
+            ))
+        }
+    }
+}
+
+impl Parse for Asn1ClassParam {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::context) {
+            Ok(Self::Context { kw: input.parse()? })
+        } else if input.peek(kw::application) {
+            Ok(Self::Application { kw: input.parse()? })
+        } else if input.peek(kw::private) {
+            Ok(Self::Private { kw: input.parse()? })
+        } else {
+            Err(Error::new(
+                input.span(),
+                r#""asn1" flag: expected "context", "application" or "private""#,
+            ))
+        }
+    }
+}
+
+impl Parse for Asn1Param {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(kw::explicit) {
+            Ok(Self::Explicit { kw: input.parse()? })
+        } else if input.peek(kw::optional) {
+            Ok(Self::Optional { kw: input.parse()? })
+        } else {
+            Err(Error::new(
+                input.span(),
+                r#""asn1" flag: expected "explicit" or "optional""#,
+            ))
+        }
+    }
+}
+
 impl Parse for FlattenParam {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         Ok(Self::Expr(input.parse()?))
@@ -481,6 +766,12 @@ impl Parse for Modifier {
             Ok(Self::ProtobufWasteful { kw: input.parse()? })
         } else if input.peek(kw::protobuf_zz) {
             Ok(Self::ProtobufZZ { kw: input.parse()? })
+        } else if input.peek(kw::bincode_varint) {
+            Ok(Self::BincodeVarint { kw: input.parse()? })
+        } else if input.peek(kw::postcard_varint) {
+            Ok(Self::PostcardVarint { kw: input.parse()? })
+        } else if input.peek(kw::asn1) {
+            Ok(Self::Asn1 { kw: input.parse()? })
         } else if input.peek(kw::big_endian) {
             Ok(Self::BigEndian { kw: input.parse()? })
         } else if input.peek(kw::little_endian) {
@@ -699,6 +990,8 @@ impl Parse for Flag {
                 colon: input.parse()?,
                 seek: input.parse()?,
             })
+        } else if input.peek(kw::sized) {
+            Ok(Self::Sized { kw: input.parse()? })
         } else if input.peek(kw::borrow) {
             Ok(Self::Borrow {
                 kw: input.parse()?,
@@ -723,6 +1016,130 @@ impl Parse for Flag {
             })
         } else if input.peek(kw::seeking) {
             Ok(Self::Seek { kw: input.parse()? })
+        } else if input.peek(Token![async]) {
+            Ok(Self::Async { kw: input.parse()? })
+        } else if input.peek(kw::skip_value) {
+            Ok(Self::SkipValue {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                ty: input.parse()?,
+            })
+        } else if input.peek(kw::bits) {
+            Ok(Self::Bits {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                bits: input.parse()?,
+            })
+        } else if input.peek(kw::bit_align) {
+            Ok(Self::BitAlign { kw: input.parse()? })
+        } else if input.peek(kw::bitwise) {
+            Ok(Self::Bitwise { kw: input.parse()? })
+        } else if input.peek(kw::len_of) {
+            Ok(Self::LenOf {
+                kw: input.parse()?,
+                bytes: input.parse()?,
+                colon: input.parse()?,
+                target: input.parse()?,
+            })
+        } else if input.peek(kw::magic) {
+            Ok(Self::Magic {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                magic: input.parse()?,
+            })
+        } else if input.peek(kw::align) {
+            Ok(Self::Align {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                align: input.parse()?,
+            })
+        } else if input.peek(kw::pad) {
+            Ok(Self::Pad {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                pad: input.parse()?,
+            })
+        } else if input.peek(kw::fill) {
+            Ok(Self::Fill {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                fill: input.parse()?,
+            })
+        } else if input.peek(kw::check_padding) {
+            Ok(Self::CheckPadding { kw: input.parse()? })
+        } else if input.peek(kw::checksum) {
+            Ok(Self::Checksum {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                algorithm: input.parse()?,
+                over: if input.peek(Token![,]) {
+                    Some(input.parse()?)
+                } else {
+                    None
+                },
+            })
+        } else if input.peek(kw::tag_type) {
+            Ok(Self::TagType {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                ty: input.parse()?,
+            })
+        } else if input.peek(kw::since) {
+            Ok(Self::Since {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                version: input.parse()?,
+            })
+        } else if input.peek(kw::until) {
+            Ok(Self::Until {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                version: input.parse()?,
+            })
+        } else if input.peek(kw::format) {
+            Ok(Self::Format {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                format: input.parse()?,
+            })
+        } else if input.peek(kw::field) {
+            Ok(Self::Field {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                key: input.parse()?,
+            })
+        } else if input.peek(kw::tlv) {
+            Ok(Self::Tlv { kw: input.parse()? })
+        } else if input.peek(kw::id) {
+            Ok(Self::Id {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                key: input.parse()?,
+            })
+        } else if input.peek(kw::asn1) {
+            let inside;
+            Ok(Self::Asn1 {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                class: input.parse()?,
+                paren: parenthesized!(inside in input),
+                number: inside.parse()?,
+                params: {
+                    let mut params = Vec::new();
+                    while input.peek(Token![,]) {
+                        params.push((input.parse()?, input.parse()?));
+                    }
+                    params
+                },
+            })
+        } else if input.peek(kw::unknown) {
+            Ok(Self::Unknown { kw: input.parse()? })
+        } else if input.peek(kw::tag) {
+            Ok(Self::Tag {
+                kw: input.parse()?,
+                colon: input.parse()?,
+                tags: Punctuated::parse_separated_nonempty(input)?,
+            })
         } else {
             Err(Error::new(input.span(), FLAGS_USAGE))
         }
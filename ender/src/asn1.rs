@@ -0,0 +1,817 @@
+//! Building blocks of the [ASN.1](https://www.itu.int/rec/T-REC-X.690) Basic and Distinguished
+//! Encoding Rules (BER and DER), as used by X.509 certificates and SNMP messages.
+//!
+//! Every ASN.1 value is encoded as a [`Tag`], a length (see [`NumEncoding::Asn1`]) and its
+//! contents. The universal types are implemented by the wrappers in this module:
+//!
+//! | ASN.1 type                     | Rust type                               |
+//! |--------------------------------|-----------------------------------------|
+//! | `BOOLEAN`                      | [`Boolean`]                             |
+//! | `INTEGER`                      | [`Integer<T>`] for any primitive integer, [`BigInteger`] |
+//! | `OCTET STRING`                 | [`OctetString`]                         |
+//! | `NULL`                         | [`Null`]                                |
+//! | `OBJECT IDENTIFIER`            | [`ObjectIdentifier`]                    |
+//! | `SEQUENCE`, `SET`              | [`Sequence<T>`], [`Set<T>`]             |
+//! | `SEQUENCE OF`, `SET OF`        | [`SequenceOf<T>`], [`SetOf<T>`]         |
+//!
+//! A `SEQUENCE` is usually a struct deriving `Encode` and `Decode` wrapped in a [`Sequence`],
+//! whose fields can be given context-specific, application or private tags with the
+//! `asn1` flag of the derive macros, e.g. `#[ender(asn1: context(0), explicit)]`.
+//!
+//! Values are always encoded in their canonical (DER) form. While decoding, the
+//! [`Asn1Rules`] in the settings determine whether non-canonical values are accepted:
+//! by default they are rejected with an [`Asn1Error::NonCanonical`] error.
+//! Indefinite lengths and constructed strings are not supported.
+//!
+//! [`NumEncoding::Asn1`]: crate::NumEncoding::Asn1
+
+use crate::io::{Read, SizeLimit, SizeTrack, Write, Zero};
+use crate::{
+    Asn1Error, BitWidth, Decode, Encode, Encoder, EncodingError, EncodingResult, Endianness,
+    NumEncoding, Opaque,
+};
+use parse_display::Display;
+
+/// The ASN.1 encoding rules followed while decoding.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+pub enum Asn1Rules {
+    /// The Distinguished Encoding Rules: every value must be in its canonical form.
+    #[default]
+    Der,
+    /// The Basic Encoding Rules: non-canonical lengths, integers, booleans,
+    /// tags and `SET OF` orderings are accepted.
+    Ber,
+}
+
+/// The class of an ASN.1 [`Tag`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[repr(u8)]
+pub enum Class {
+    /// The tags of the types defined by the ASN.1 standard itself
+    #[display("UNIVERSAL")]
+    Universal = 0,
+    /// Tags that are unique within an application
+    #[display("APPLICATION")]
+    Application = 1,
+    /// Tags that are unique within the enclosing type, such as `[0]`
+    #[display("CONTEXT")]
+    ContextSpecific = 2,
+    /// Tags that are unique within an organization
+    #[display("PRIVATE")]
+    Private = 3,
+}
+
+impl Class {
+    /// Returns the class with the given value. Only the lowest two bits are considered.
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => Self::Universal,
+            1 => Self::Application,
+            2 => Self::ContextSpecific,
+            _ => Self::Private,
+        }
+    }
+}
+
+/// The identifier of an ASN.1 value: its class, its number and whether
+/// its contents are made of other values.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("[{class} {number}]")]
+pub struct Tag {
+    /// The class of the tag
+    pub class: Class,
+    /// Whether the value is constructed (made of other values) rather than primitive
+    pub constructed: bool,
+    /// The number of the tag within its class
+    pub number: u32,
+}
+
+impl Tag {
+    /// The universal tag of `BOOLEAN`
+    pub const BOOLEAN: Self = Self::universal(1);
+    /// The universal tag of `INTEGER`
+    pub const INTEGER: Self = Self::universal(2);
+    /// The universal tag of `OCTET STRING`
+    pub const OCTET_STRING: Self = Self::universal(4);
+    /// The universal tag of `NULL`
+    pub const NULL: Self = Self::universal(5);
+    /// The universal tag of `OBJECT IDENTIFIER`
+    pub const OBJECT_IDENTIFIER: Self = Self::universal(6);
+    /// The universal tag of `SEQUENCE` and `SEQUENCE OF`
+    pub const SEQUENCE: Self = Self::universal(16).constructed(true);
+    /// The universal tag of `SET` and `SET OF`
+    pub const SET: Self = Self::universal(17).constructed(true);
+
+    /// Constructs a new tag.
+    #[inline]
+    pub const fn new(class: Class, constructed: bool, number: u32) -> Self {
+        Self {
+            class,
+            constructed,
+            number,
+        }
+    }
+
+    /// Constructs a new primitive tag of the universal class.
+    #[inline]
+    pub const fn universal(number: u32) -> Self {
+        Self::new(Class::Universal, false, number)
+    }
+
+    /// Constructs a new primitive tag of the application class.
+    #[inline]
+    pub const fn application(number: u32) -> Self {
+        Self::new(Class::Application, false, number)
+    }
+
+    /// Constructs a new primitive context-specific tag.
+    #[inline]
+    pub const fn context(number: u32) -> Self {
+        Self::new(Class::ContextSpecific, false, number)
+    }
+
+    /// Constructs a new primitive tag of the private class.
+    #[inline]
+    pub const fn private(number: u32) -> Self {
+        Self::new(Class::Private, false, number)
+    }
+
+    /// Sets the **constructed** bit, then returns self.
+    #[inline]
+    pub const fn constructed(mut self, constructed: bool) -> Self {
+        self.constructed = constructed;
+        self
+    }
+
+    /// Returns whether the class and the number of the two tags are the same,
+    /// regardless of the constructed bit.
+    #[inline]
+    pub const fn matches(&self, other: &Tag) -> bool {
+        self.class as u8 == other.class as u8 && self.number == other.number
+    }
+
+    // Replaces the class and number with the ones of the implicit tag, if present
+    #[inline]
+    fn implicit(self, implicit: Option<Tag>) -> Self {
+        match implicit {
+            Some(implicit) => implicit.constructed(self.constructed),
+            None => self,
+        }
+    }
+}
+
+impl<T: Write> Encoder<'_, T> {
+    /// Writes an ASN.1 tag.
+    ///
+    /// If the [`asn1_tag`][`crate::Context::asn1_tag`] state variable is set, it's consumed and its
+    /// class and number are written in place of the given ones (implicit tagging).
+    pub fn write_asn1_tag(&mut self, tag: Tag) -> EncodingResult<()> {
+        let tag = tag.implicit(self.ctxt.consume_asn1_tag());
+        let first = (tag.class as u8) << 6 | (tag.constructed as u8) << 5;
+        if tag.number < 0x1F {
+            return self.write_byte(first | tag.number as u8);
+        }
+
+        // High tag numbers follow in base 128, most significant digit first
+        self.write_byte(first | 0x1F)?;
+        let mut buf = [0u8; 5];
+        let mut start = buf.len();
+        let mut number = tag.number;
+        loop {
+            start -= 1;
+            buf[start] = (number & 0x7F) as u8 | if start == buf.len() - 1 { 0 } else { 0x80 };
+            number >>= 7;
+            if number == 0 {
+                break;
+            }
+        }
+        self.write_bytes(&buf[start..])
+    }
+
+    /// Writes the length of an ASN.1 value, in its definite form.
+    #[inline]
+    pub fn write_asn1_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_u64_with(len as u64, NumEncoding::Asn1, Endianness::BigEndian)
+    }
+
+    /// Writes a primitive ASN.1 value with the given tag and contents.
+    #[inline]
+    pub fn write_asn1_primitive(&mut self, tag: Tag, contents: &[u8]) -> EncodingResult<()> {
+        self.write_asn1_tag(tag)?;
+        self.write_asn1_len(contents.len())?;
+        self.write_bytes(contents)
+    }
+
+    /// Writes an ASN.1 value with the given tag, whose contents are encoded by `f`.
+    ///
+    /// Since the length must be known in advance, the contents are first encoded by `measure`
+    /// only to compute their size: the two closures **must** encode the same data.
+    pub fn write_asn1_tlv<M, F>(&mut self, tag: Tag, measure: M, f: F) -> EncodingResult<()>
+    where
+        M: FnOnce(&mut Encoder<SizeTrack<Zero>>) -> EncodingResult<()>,
+        F: FnOnce(&mut Encoder<SizeLimit<&mut T>>) -> EncodingResult<()>,
+    {
+        // The tag comes first, so that the contents don't inherit an implicit tag
+        self.write_asn1_tag(tag)?;
+
+        let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), self.ctxt);
+        measure(&mut sz_encoder)?;
+        let size = sz_encoder.finish().0.size_written();
+
+        self.write_asn1_len(size)?;
+
+        let mut capped = Encoder::new(SizeLimit::new(&mut self.stream, size, 0), self.ctxt);
+        f(&mut capped)?;
+
+        let remaining = capped.stream.remaining_writable();
+        if remaining != 0 {
+            return Err(crate::val_error!(
+                "An ASN.1 value was {} bytes shorter than its measured size of {}",
+                remaining,
+                size
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    /// Reads an ASN.1 tag, or returns the one previously read by
+    /// [`peek_asn1_tag`][`Self::peek_asn1_tag`].
+    pub fn read_asn1_tag(&mut self) -> EncodingResult<Tag> {
+        if let Some(tag) = self.ctxt.asn1_peeked.take() {
+            return Ok(tag);
+        }
+
+        let first = self.read_byte()?;
+        let class = Class::from_bits(first >> 6);
+        let constructed = first & 0x20 != 0;
+        if first & 0x1F != 0x1F {
+            return Ok(Tag::new(class, constructed, (first & 0x1F) as u32));
+        }
+
+        let mut number = 0u32;
+        loop {
+            let byte = self.read_byte()?;
+            // The number must fit in 32 bits, and have no leading zero digits
+            if number > u32::MAX >> 7 || (number == 0 && byte == 0x80) {
+                return Err(Asn1Error::InvalidTag.into());
+            }
+            number = number << 7 | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        if number < 0x1F && self.ctxt.settings.asn1_rules == Asn1Rules::Der {
+            return Err(Asn1Error::NonCanonical.into());
+        }
+        Ok(Tag::new(class, constructed, number))
+    }
+
+    /// Reads the next ASN.1 tag without consuming it, so that the next call to
+    /// [`read_asn1_tag`][`Self::read_asn1_tag`] will return it again.
+    ///
+    /// Returns `None` if the end of the stream or of the value being decoded was reached.
+    pub fn peek_asn1_tag(&mut self) -> EncodingResult<Option<Tag>> {
+        if let Some(tag) = self.ctxt.asn1_peeked {
+            return Ok(Some(tag));
+        }
+        if self.stream.limit() == Some(0) {
+            return Ok(None);
+        }
+
+        // Only reaching the end before the first byte of the tag marks the end of the values
+        let mut tracked = Encoder::new(SizeTrack::new(&mut self.stream), self.ctxt);
+        let tag = match tracked.read_asn1_tag() {
+            Ok(tag) => tag,
+            Err(EncodingError::UnexpectedEnd) if tracked.stream.size_read() == 0 => {
+                return Ok(None)
+            }
+            Err(err) => return Err(err),
+        };
+        self.ctxt.asn1_peeked = Some(tag);
+        Ok(Some(tag))
+    }
+
+    /// Reads an ASN.1 tag, returning an [`Asn1Error::UnexpectedTag`] error if it's not
+    /// the expected one.
+    ///
+    /// If the [`asn1_tag`][`crate::Context::asn1_tag`] state variable is set, it's consumed and its
+    /// class and number are expected in place of the given ones (implicit tagging).
+    #[inline]
+    pub fn expect_asn1_tag(&mut self, expected: Tag) -> EncodingResult<()> {
+        let expected = expected.implicit(self.ctxt.consume_asn1_tag());
+        let found = self.read_asn1_tag()?;
+        if found != expected {
+            return Err(Asn1Error::UnexpectedTag { expected, found }.into());
+        }
+        Ok(())
+    }
+
+    /// Reads the length of an ASN.1 value.
+    #[inline]
+    pub fn read_asn1_len(&mut self) -> EncodingResult<usize> {
+        let len = self.read_u64_with(NumEncoding::Asn1, Endianness::BigEndian)?;
+        usize::try_from(len).map_err(|_| EncodingError::TooLarge {
+            value: Opaque::from(len),
+            requested_width: BitWidth::native(),
+        })
+    }
+
+    /// Reads an ASN.1 value with the expected tag, whose contents are decoded by `f`.
+    ///
+    /// All the contents must be consumed, otherwise an [`EncodingError::TrailingBytes`]
+    /// error is returned.
+    pub fn read_asn1_tlv<F, R>(&mut self, expected: Tag, f: F) -> EncodingResult<R>
+    where
+        F: FnOnce(&mut Encoder<SizeLimit<&mut T>>) -> EncodingResult<R>,
+    {
+        self.expect_asn1_tag(expected)?;
+        let len = self.read_asn1_len()?;
+
+        let mut capped = Encoder::new(SizeLimit::new(&mut self.stream, 0, len), self.ctxt);
+        let value = f(&mut capped)?;
+        // The allocation budget is shared with the contents
+        self.ctxt.alloc_budget = capped.ctxt.alloc_budget;
+        let remaining = capped.stream.remaining_readable();
+        if remaining != 0 {
+            return Err(EncodingError::TrailingBytes { remaining });
+        }
+        Ok(value)
+    }
+
+    // Returns whether DER canonical-form validation is in effect
+    #[inline]
+    fn asn1_der(&self) -> bool {
+        self.ctxt.settings.asn1_rules == Asn1Rules::Der
+    }
+}
+
+/// `BOOLEAN`. Encoded as `0xFF` when true, but any non-zero value is accepted by BER.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Boolean(pub bool);
+
+impl<W: Write> Encode<W> for Boolean {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_asn1_primitive(Tag::BOOLEAN, &[if self.0 { 0xFF } else { 0x00 }])
+    }
+}
+
+impl<R: Read> Decode<R> for Boolean {
+    #[inline]
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.expect_asn1_tag(Tag::BOOLEAN)?;
+        if decoder.read_asn1_len()? != 1 {
+            return Err(Asn1Error::InvalidContent("BOOLEAN").into());
+        }
+        match decoder.read_byte()? {
+            0x00 => Ok(Self(false)),
+            0xFF => Ok(Self(true)),
+            _ if decoder.asn1_der() => Err(Asn1Error::NonCanonical.into()),
+            _ => Ok(Self(true)),
+        }
+    }
+}
+
+/// `NULL`, which has no contents.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Null;
+
+impl<W: Write> Encode<W> for Null {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_asn1_primitive(Tag::NULL, &[])
+    }
+}
+
+impl<R: Read> Decode<R> for Null {
+    #[inline]
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.expect_asn1_tag(Tag::NULL)?;
+        if decoder.read_asn1_len()? != 0 {
+            return Err(Asn1Error::InvalidContent("NULL").into());
+        }
+        Ok(Self)
+    }
+}
+
+// Whether the first byte of an INTEGER is redundant, given the second one
+#[inline]
+fn redundant_int_byte(first: u8, second: u8) -> bool {
+    (first == 0x00 && second & 0x80 == 0) || (first == 0xFF && second & 0x80 != 0)
+}
+
+// Strips the redundant leading bytes of a two's complement big endian INTEGER
+#[inline]
+fn minimal_int(mut bytes: &[u8]) -> &[u8] {
+    while bytes.len() > 1 && redundant_int_byte(bytes[0], bytes[1]) {
+        bytes = &bytes[1..];
+    }
+    bytes
+}
+
+// Returns the biggest value with the same sign as an INTEGER starting with `first`
+#[inline]
+fn saturated_int(first: u8) -> Opaque {
+    if first & 0x80 != 0 {
+        Opaque::from(i128::MIN)
+    } else {
+        Opaque::from(u128::MAX)
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    // Reads the contents of an INTEGER, sign-extending them to 17 bytes
+    fn read_asn1_int(&mut self) -> EncodingResult<[u8; 17]> {
+        self.expect_asn1_tag(Tag::INTEGER)?;
+        let len = self.read_asn1_len()?;
+        if len == 0 {
+            return Err(Asn1Error::InvalidContent("INTEGER").into());
+        }
+
+        let mut buf = [0u8; 17];
+        if len > buf.len() {
+            // The value is saturated, since it doesn't fit anywhere
+            let first = self.read_byte()?;
+            return Err(EncodingError::TooLarge {
+                value: saturated_int(first),
+                requested_width: BitWidth::Bit128,
+            });
+        }
+
+        let start = buf.len() - len;
+        self.read_bytes(&mut buf[start..])?;
+        if len > 1 && redundant_int_byte(buf[start], buf[start + 1]) && self.asn1_der() {
+            return Err(Asn1Error::NonCanonical.into());
+        }
+        if buf[start] & 0x80 != 0 {
+            buf[..start].fill(0xFF);
+        }
+        Ok(buf)
+    }
+}
+
+/// `INTEGER`, backed by a primitive integer.
+///
+/// Decoding fails with an [`EncodingError::TooLarge`] error if the value doesn't fit `T`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Integer<T>(pub T);
+
+macro_rules! impl_integer {
+    ($($ty:ty => $($width:tt)::*);* $(;)?) => {
+	    $(
+	    impl<W: Write> Encode<W> for Integer<$ty> {
+		    #[inline]
+		    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+			    // One extra byte for the sign of unsigned values
+			    let mut buf = [0u8; 17];
+			    if <$ty>::MIN as i128 != 0 && (self.0 as i128) < 0 {
+				    buf[0] = 0xFF;
+			    }
+			    buf[1..].copy_from_slice(&(self.0 as i128).to_be_bytes());
+			    encoder.write_asn1_primitive(Tag::INTEGER, minimal_int(&buf))
+		    }
+	    }
+
+	    impl<R: Read> Decode<R> for Integer<$ty> {
+		    #[inline]
+		    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+			    let buf = decoder.read_asn1_int()?;
+			    let bits = u128::from_be_bytes(buf[1..].try_into().unwrap());
+			    let value = match buf[0] {
+				    0x00 => <$ty>::try_from(bits).map_err(|_| Opaque::from(bits)),
+				    0xFF if (bits as i128) < 0 => {
+					    let value = bits as i128;
+					    <$ty>::try_from(value).map_err(|_| Opaque::from(value))
+				    }
+				    // 17 significant bytes don't fit in 128 bits
+				    first => Err(saturated_int(first)),
+			    };
+			    value.map(Self).map_err(|value| EncodingError::TooLarge {
+				    value,
+				    requested_width: BitWidth::$($width)*,
+			    })
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_integer! {
+    u8 => Bit8;
+    u16 => Bit16;
+    u32 => Bit32;
+    u64 => Bit64;
+    u128 => Bit128;
+    usize => native::();
+    i8 => Bit8;
+    i16 => Bit16;
+    i32 => Bit32;
+    i64 => Bit64;
+    i128 => Bit128;
+    isize => native::();
+}
+
+/// `INTEGER` of arbitrary size, such as the serial number of a certificate,
+/// as its minimal big endian two's complement bytes.
+///
+/// The bytes are encoded as-is, so they must be minimal for the encoding to be canonical.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct BigInteger(pub alloc::vec::Vec<u8>);
+
+#[cfg(feature = "alloc")]
+impl<W: Write> Encode<W> for BigInteger {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        if self.0.is_empty() {
+            return Err(Asn1Error::InvalidContent("INTEGER").into());
+        }
+        encoder.write_asn1_primitive(Tag::INTEGER, &self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Read> Decode<R> for BigInteger {
+    #[inline]
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.expect_asn1_tag(Tag::INTEGER)?;
+        let len = decoder.read_asn1_len()?;
+        if len == 0 {
+            return Err(Asn1Error::InvalidContent("INTEGER").into());
+        }
+        let bytes = decoder.read_byte_vec(len)?;
+        if minimal_int(&bytes).len() != len && decoder.asn1_der() {
+            return Err(Asn1Error::NonCanonical.into());
+        }
+        Ok(Self(bytes))
+    }
+}
+
+/// `OCTET STRING`, in its primitive form.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct OctetString(pub alloc::vec::Vec<u8>);
+
+#[cfg(feature = "alloc")]
+impl<W: Write> Encode<W> for OctetString {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_asn1_primitive(Tag::OCTET_STRING, &self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Read> Decode<R> for OctetString {
+    #[inline]
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.expect_asn1_tag(Tag::OCTET_STRING)?;
+        let len = decoder.read_asn1_len()?;
+        Ok(Self(decoder.read_byte_vec(len)?))
+    }
+}
+
+/// `OBJECT IDENTIFIER`, as its arcs. E.G. `1.2.840.113549` is `[1, 2, 840, 113549]`.
+///
+/// There must be at least two arcs, the first must be `0`, `1` or `2`, and if it's
+/// not `2` the second must be lower than `40`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ObjectIdentifier(pub alloc::vec::Vec<u64>);
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for ObjectIdentifier {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, arc) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", arc)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ObjectIdentifier {
+    // Returns the sub-identifiers that are encoded: the first two arcs are combined
+    fn subidentifiers(&self) -> EncodingResult<impl Iterator<Item = u64> + '_> {
+        let invalid = || EncodingError::from(Asn1Error::InvalidContent("OBJECT IDENTIFIER"));
+        let (first, second) = match self.0.as_slice() {
+            [first @ 0..=1, second @ 0..=39, ..] => (*first, *second),
+            [2, second, ..] => (2, *second),
+            _ => return Err(invalid()),
+        };
+        let combined = (first * 40).checked_add(second).ok_or_else(invalid)?;
+        Ok(core::iter::once(combined).chain(self.0[2..].iter().copied()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<W: Write> Encode<W> for ObjectIdentifier {
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        // Every sub-identifier is in base 128, most significant digit first
+        let mut contents = alloc::vec::Vec::new();
+        for mut subid in self.subidentifiers()? {
+            let mut buf = [0u8; 10];
+            let mut start = buf.len();
+            loop {
+                start -= 1;
+                buf[start] = (subid & 0x7F) as u8 | if start == buf.len() - 1 { 0 } else { 0x80 };
+                subid >>= 7;
+                if subid == 0 {
+                    break;
+                }
+            }
+            contents.extend_from_slice(&buf[start..]);
+        }
+        encoder.write_asn1_primitive(Tag::OBJECT_IDENTIFIER, &contents)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Read> Decode<R> for ObjectIdentifier {
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        let invalid = || EncodingError::from(Asn1Error::InvalidContent("OBJECT IDENTIFIER"));
+
+        decoder.expect_asn1_tag(Tag::OBJECT_IDENTIFIER)?;
+        let len = decoder.read_asn1_len()?;
+        if len == 0 {
+            return Err(invalid());
+        }
+
+        let mut arcs = alloc::vec::Vec::new();
+        let mut subid = 0u64;
+        let mut digits = 0;
+        for _ in 0..len {
+            let byte = decoder.read_byte()?;
+            // Sub-identifiers must fit in 64 bits and have no leading zero digits
+            if subid > u64::MAX >> 7 || (digits == 0 && byte == 0x80) {
+                return Err(invalid());
+            }
+            subid = subid << 7 | (byte & 0x7F) as u64;
+            digits += 1;
+            if byte & 0x80 == 0 {
+                if arcs.is_empty() {
+                    let first = (subid / 40).min(2);
+                    arcs.push(first);
+                    arcs.push(subid - first * 40);
+                } else {
+                    arcs.push(subid);
+                }
+                subid = 0;
+                digits = 0;
+            }
+        }
+        if digits != 0 {
+            return Err(invalid());
+        }
+        Ok(Self(arcs))
+    }
+}
+
+/// `SEQUENCE`, whose contents are the encoding of `T`, usually a struct whose fields
+/// are ASN.1 values.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Sequence<T>(pub T);
+
+/// `SET`, whose contents are the encoding of `T`, usually a struct whose fields
+/// are ASN.1 values.
+///
+/// DER requires the fields to be in ascending order of their tags, so they
+/// should be declared in that order.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Set<T>(pub T);
+
+macro_rules! impl_constructed {
+    ($($ty:ident => $tag:ident);* $(;)?) => {
+	    $(
+	    impl<W: Write, T> Encode<W> for $ty<T>
+	    where
+		    T: Encode<SizeTrack<Zero>> + for<'a> Encode<SizeLimit<&'a mut W>>,
+	    {
+		    #[inline]
+		    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+			    encoder.write_asn1_tlv(Tag::$tag, |encoder| self.0.encode(encoder), |encoder| self.0.encode(encoder))
+		    }
+	    }
+
+	    impl<R: Read, T> Decode<R> for $ty<T>
+	    where
+		    T: for<'a> Decode<SizeLimit<&'a mut R>>,
+	    {
+		    #[inline]
+		    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+			    decoder.read_asn1_tlv(Tag::$tag, |decoder| T::decode(decoder)).map(Self)
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_constructed! {
+    Sequence => SEQUENCE;
+    Set => SET;
+}
+
+/// `SEQUENCE OF T`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SequenceOf<T>(pub alloc::vec::Vec<T>);
+
+#[cfg(feature = "alloc")]
+impl<W: Write, T> Encode<W> for SequenceOf<T>
+where
+    T: Encode<SizeTrack<Zero>> + for<'a> Encode<SizeLimit<&'a mut W>>,
+{
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_asn1_tlv(
+            Tag::SEQUENCE,
+            |encoder| self.0.iter().try_for_each(|x| x.encode(encoder)),
+            |encoder| self.0.iter().try_for_each(|x| x.encode(encoder)),
+        )
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Read, T> Decode<R> for SequenceOf<T>
+where
+    T: for<'a> Decode<SizeLimit<&'a mut R>>,
+{
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder
+            .read_asn1_tlv(Tag::SEQUENCE, |decoder| {
+                let mut values = alloc::vec::Vec::new();
+                while decoder.stream.remaining_readable() != 0 {
+                    values.push(T::decode(decoder)?);
+                }
+                Ok(values)
+            })
+            .map(Self)
+    }
+}
+
+/// `SET OF T`.
+///
+/// The elements are sorted by their encoding when encoded, as required by DER,
+/// and the order is checked when decoded with DER validation on.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SetOf<T>(pub alloc::vec::Vec<T>);
+
+#[cfg(feature = "alloc")]
+impl<W: Write, T> Encode<W> for SetOf<T>
+where
+    T: Encode<crate::io::VecStream>,
+{
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        // The tag comes first, so that the elements don't inherit an implicit tag
+        encoder.write_asn1_tag(Tag::SET)?;
+
+        let mut elements = alloc::vec::Vec::with_capacity(self.0.len());
+        for value in self.0.iter() {
+            let stream = crate::io::VecStream::new(alloc::vec::Vec::new(), 0);
+            let mut el_encoder = Encoder::new(stream, encoder.ctxt);
+            value.encode(&mut el_encoder)?;
+            elements.push(el_encoder.finish().0.into_inner());
+        }
+        elements.sort();
+
+        encoder.write_asn1_len(elements.iter().map(|x| x.len()).sum())?;
+        elements.iter().try_for_each(|x| encoder.write_bytes(x))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<R: Read, T> Decode<R> for SetOf<T>
+where
+    T: for<'a> Decode<SizeTrack<crate::io::Slice<'a>>>,
+{
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.expect_asn1_tag(Tag::SET)?;
+        let len = decoder.read_asn1_len()?;
+        let contents = decoder.read_byte_vec(len)?;
+
+        // The elements are decoded one at a time, so that their encodings can be compared
+        let mut values = alloc::vec::Vec::new();
+        let mut previous: &[u8] = &[];
+        let mut rest = contents.as_slice();
+        while !rest.is_empty() {
+            let stream = SizeTrack::new(crate::io::Slice::new(rest));
+            let mut el_decoder = Encoder::new(stream, decoder.ctxt);
+            values.push(T::decode(&mut el_decoder)?);
+            decoder.ctxt.alloc_budget = el_decoder.ctxt.alloc_budget;
+            let (element, next) = rest.split_at(el_decoder.stream.size_read());
+
+            if element < previous && decoder.asn1_der() {
+                return Err(Asn1Error::NonCanonical.into());
+            }
+            previous = element;
+            rest = next;
+        }
+        Ok(Self(values))
+    }
+}
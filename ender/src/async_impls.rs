@@ -0,0 +1,634 @@
+use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use core::ops::Deref;
+use core::time::Duration;
+
+use crate::io::{AsyncRead, AsyncWrite};
+use crate::{AsyncDecode, AsyncEncode, Encoder, EncodingResult};
+
+// Primitives
+
+macro_rules! impl_async_encode {
+    ($($ty:ty => $write:ident);* $(;)? ) => {
+	    $(
+	    impl<W: $crate::io::AsyncWrite> $crate::AsyncEncode<W> for $ty {
+		    #[inline]
+            async fn encode_async(&self, encoder: &mut $crate::Encoder<'_, W>) -> $crate::EncodingResult<()> {
+		        encoder.$write(*self).await
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_async_encode! {
+    u8 => write_u8_async;
+    u16 => write_u16_async;
+    u32 => write_u32_async;
+    u64 => write_u64_async;
+    u128 => write_u128_async;
+    i8 => write_i8_async;
+    i16 => write_i16_async;
+    i32 => write_i32_async;
+    i64 => write_i64_async;
+    i128 => write_i128_async;
+    bool => write_bool_async;
+    char => write_char_async;
+    f32 => write_f32_async;
+    f64 => write_f64_async;
+    usize => write_usize_async;
+    isize => write_isize_async;
+}
+
+impl<W: AsyncWrite> AsyncEncode<W> for () {
+    #[inline]
+    async fn encode_async(&self, _encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite, T: ?Sized + AsyncEncode<W>> AsyncEncode<W> for &T {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        <T as AsyncEncode<W>>::encode_async(self, encoder).await
+    }
+}
+
+impl<W: AsyncWrite, T: ?Sized + AsyncEncode<W>> AsyncEncode<W> for &mut T {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        <T as AsyncEncode<W>>::encode_async(self, encoder).await
+    }
+}
+
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for [T] {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for (i, v) in self.iter().enumerate() {
+            encoder
+                .with_index_async(async |encoder| v.encode_async(encoder).await, i)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite, T: AsyncEncode<W>, const SIZE: usize> AsyncEncode<W> for [T; SIZE] {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        for (i, v) in self.iter().enumerate() {
+            encoder
+                .with_index_async(async |encoder| v.encode_async(encoder).await, i)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! tuple_async_encode {
+    ($($name:ident)+) => {
+	    #[allow(non_snake_case)]
+	    impl<__W: $crate::io::AsyncWrite, $($name: $crate::AsyncEncode<__W>),+> $crate::AsyncEncode<__W> for ($($name),+) {
+		    #[inline]
+            async fn encode_async(&self, encoder: &mut $crate::Encoder<'_, __W>) -> $crate::EncodingResult<()> {
+		        let ($($name),*) = self;
+			    $(
+			        $crate::AsyncEncode::encode_async($name, encoder).await?;
+			    )+
+			    Ok(())
+		    }
+	    }
+    };
+}
+
+tuple_async_encode! { A B }
+tuple_async_encode! { A B C }
+tuple_async_encode! { A B C D }
+tuple_async_encode! { A B C D E }
+tuple_async_encode! { A B C D E F }
+tuple_async_encode! { A B C D E F G }
+tuple_async_encode! { A B C D E F G H }
+tuple_async_encode! { A B C D E F G H I }
+tuple_async_encode! { A B C D E F G H I J }
+tuple_async_encode! { A B C D E F G H I J K }
+tuple_async_encode! { A B C D E F G H I J K L }
+tuple_async_encode! { A B C D E F G H I J K L M }
+tuple_async_encode! { A B C D E F G H I J K L M N }
+tuple_async_encode! { A B C D E F G H I J K L M N O }
+tuple_async_encode! { A B C D E F G H I J K L M N O P } // Up to 16
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite> AsyncEncode<W> for alloc::string::String {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_str_async(self.chars()).await
+    }
+}
+
+impl<W: AsyncWrite> AsyncEncode<W> for str {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_str_async(self.chars()).await
+    }
+}
+
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for Option<T> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        match self {
+            None => encoder.write_bool_async(false).await,
+            Some(value) => {
+                encoder.write_bool_async(true).await?;
+                value.encode_async(encoder).await
+            }
+        }
+    }
+}
+
+impl<W: AsyncWrite, T: AsyncEncode<W>, E: AsyncEncode<W>> AsyncEncode<W> for Result<T, E> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        match self {
+            Err(err) => {
+                encoder.write_bool_async(false).await?;
+                err.encode_async(encoder).await?;
+            }
+            Ok(ok) => {
+                encoder.write_bool_async(true).await?;
+                ok.encode_async(encoder).await?;
+            }
+        };
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite, T> AsyncEncode<W> for PhantomData<T> {
+    #[inline]
+    async fn encode_async(&self, _encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        Ok(())
+    }
+}
+
+// The futures of smart pointers are boxed, so that recursive data structures
+// don't end up with an infinitely sized future.
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for alloc::boxed::Box<T> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        alloc::boxed::Box::pin(<T as AsyncEncode<W>>::encode_async(self.deref(), encoder)).await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for alloc::rc::Rc<T> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        alloc::boxed::Box::pin(<T as AsyncEncode<W>>::encode_async(self.deref(), encoder)).await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for alloc::sync::Arc<T> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        alloc::boxed::Box::pin(<T as AsyncEncode<W>>::encode_async(self.deref(), encoder)).await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, K: AsyncEncode<W>, V: AsyncEncode<W>> AsyncEncode<W>
+    for alloc::collections::BTreeMap<K, V>
+{
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for (k, v) in self.iter() {
+            k.encode_async(encoder).await?;
+            v.encode_async(encoder).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, K: AsyncEncode<W>> AsyncEncode<W> for alloc::collections::BTreeSet<K> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for k in self.iter() {
+            k.encode_async(encoder).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<W: AsyncWrite, K: AsyncEncode<W>, V: AsyncEncode<W>> AsyncEncode<W>
+    for std::collections::hash_map::HashMap<K, V>
+{
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for (k, v) in self.iter() {
+            k.encode_async(encoder).await?;
+            v.encode_async(encoder).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<W: AsyncWrite, K: AsyncEncode<W>> AsyncEncode<W> for std::collections::hash_set::HashSet<K> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for k in self.iter() {
+            k.encode_async(encoder).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for alloc::vec::Vec<T> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for (i, v) in self.iter().enumerate() {
+            encoder
+                .with_index_async(async |encoder| v.encode_async(encoder).await, i)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<W: AsyncWrite, T: AsyncEncode<W>> AsyncEncode<W> for alloc::collections::VecDeque<T> {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_usize_async(self.len()).await?;
+        for (i, v) in self.iter().enumerate() {
+            encoder
+                .with_index_async(async |encoder| v.encode_async(encoder).await, i)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: AsyncWrite> AsyncEncode<W> for Duration {
+    #[inline]
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()> {
+        encoder.write_u64_async(self.as_secs()).await?;
+        encoder.write_u32_async(self.subsec_nanos()).await
+    }
+}
+
+macro_rules! impl_async_decode {
+    ($($ty:ty => $read:ident);* $(;)? ) => {
+	    $(
+	    impl<R: $crate::io::AsyncRead> $crate::AsyncDecode<R> for $ty {
+		    #[inline]
+            async fn decode_async(decoder: &mut $crate::Encoder<'_, R>) -> $crate::EncodingResult<Self> {
+		        decoder.$read().await
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_async_decode! {
+    u8 => read_u8_async;
+    u16 => read_u16_async;
+    u32 => read_u32_async;
+    u64 => read_u64_async;
+    u128 => read_u128_async;
+    i8 => read_i8_async;
+    i16 => read_i16_async;
+    i32 => read_i32_async;
+    i64 => read_i64_async;
+    i128 => read_i128_async;
+    bool => read_bool_async;
+    char => read_char_async;
+    f32 => read_f32_async;
+    f64 => read_f64_async;
+    usize => read_usize_async;
+    isize => read_isize_async;
+}
+
+impl<R: AsyncRead> AsyncDecode<R> for () {
+    #[inline]
+    async fn decode_async(_decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        Ok(())
+    }
+}
+
+impl<R: AsyncRead, T: AsyncDecode<R>, const SIZE: usize> AsyncDecode<R> for [T; SIZE] {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        // The elements can't be awaited from inside an initializer closure,
+        // so decode them one by one, then unwrap them all at once
+        let mut array: [Option<T>; SIZE] = core::array::from_fn(|_| None);
+        for (i, v) in array.iter_mut().enumerate() {
+            *v = Some(
+                decoder
+                    .with_index_async(async |decoder| T::decode_async(decoder).await, i)
+                    .await?,
+            );
+        }
+
+        // PANIC SAFETY
+        // Every element has been initialized by the loop above
+        Ok(array.map(|x| x.unwrap()))
+    }
+}
+
+macro_rules! consume {
+    ($x:tt, $expr:expr) => {
+        $expr
+    };
+}
+
+macro_rules! tuple_async_decode {
+    ($($name:ident)+) => {
+	    #[allow(non_snake_case)]
+	    impl<R: $crate::io::AsyncRead, $($name: $crate::AsyncDecode<R>),+> $crate::AsyncDecode<R> for ($($name),+) {
+		    #[inline]
+            async fn decode_async(decoder: &mut $crate::Encoder<'_, R>) -> $crate::EncodingResult<Self>{
+			    Ok(($(
+		            consume!($name, <$name as $crate::AsyncDecode<R>>::decode_async(decoder).await?),
+		        )+))
+		    }
+	    }
+    };
+}
+
+tuple_async_decode! { A B }
+tuple_async_decode! { A B C }
+tuple_async_decode! { A B C D }
+tuple_async_decode! { A B C D E }
+tuple_async_decode! { A B C D E F }
+tuple_async_decode! { A B C D E F G }
+tuple_async_decode! { A B C D E F G H }
+tuple_async_decode! { A B C D E F G H I }
+tuple_async_decode! { A B C D E F G H I J }
+tuple_async_decode! { A B C D E F G H I J K }
+tuple_async_decode! { A B C D E F G H I J K L }
+tuple_async_decode! { A B C D E F G H I J K L M }
+tuple_async_decode! { A B C D E F G H I J K L M N }
+tuple_async_decode! { A B C D E F G H I J K L M N O }
+tuple_async_decode! { A B C D E F G H I J K L M N O P } // Up to 16
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead> AsyncDecode<R> for alloc::string::String {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        decoder.read_str_async().await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead> AsyncDecode<R> for alloc::boxed::Box<str> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let string = alloc::string::String::decode_async(decoder).await?;
+        Ok(string.into_boxed_str())
+    }
+}
+
+impl<R: AsyncRead, T: AsyncDecode<R>> AsyncDecode<R> for Option<T> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        Ok(match decoder.read_bool_async().await? {
+            true => Some(T::decode_async(decoder).await?),
+            false => None,
+        })
+    }
+}
+
+impl<R: AsyncRead, T: AsyncDecode<R>, E: AsyncDecode<R>> AsyncDecode<R> for Result<T, E> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        Ok(match decoder.read_bool_async().await? {
+            true => Ok(T::decode_async(decoder).await?),
+            false => Err(E::decode_async(decoder).await?),
+        })
+    }
+}
+
+impl<R: AsyncRead, T: ?Sized> AsyncDecode<R> for PhantomData<T> {
+    #[inline]
+    async fn decode_async(_decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        Ok(Self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, T: AsyncDecode<R>> AsyncDecode<R> for alloc::boxed::Box<T> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        decoder.charge_alloc::<T>(1)?;
+        let value = decoder
+            .with_nested_async(async |decoder| {
+                alloc::boxed::Box::pin(<T as AsyncDecode<R>>::decode_async(decoder)).await
+            })
+            .await?;
+        Ok(alloc::boxed::Box::new(value))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, T: AsyncDecode<R>> AsyncDecode<R> for alloc::rc::Rc<T> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        decoder.charge_alloc::<T>(1)?;
+        let value = decoder
+            .with_nested_async(async |decoder| {
+                alloc::boxed::Box::pin(<T as AsyncDecode<R>>::decode_async(decoder)).await
+            })
+            .await?;
+        Ok(alloc::rc::Rc::new(value))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, T: AsyncDecode<R>> AsyncDecode<R> for alloc::sync::Arc<T> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        decoder.charge_alloc::<T>(1)?;
+        let value = decoder
+            .with_nested_async(async |decoder| {
+                alloc::boxed::Box::pin(<T as AsyncDecode<R>>::decode_async(decoder)).await
+            })
+            .await?;
+        Ok(alloc::sync::Arc::new(value))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, K: Ord + AsyncDecode<R>, V: AsyncDecode<R>> AsyncDecode<R>
+    for alloc::collections::BTreeMap<K, V>
+{
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let len = decoder.read_usize_async().await?;
+        decoder.charge_alloc::<(K, V)>(len)?;
+        decoder
+            .with_nested_async(async |decoder| {
+                let mut map = Self::new();
+
+                for _ in 0..len {
+                    map.insert(K::decode_async(decoder).await?, V::decode_async(decoder).await?);
+                }
+
+                Ok(map)
+            })
+            .await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, K: Ord + AsyncDecode<R>> AsyncDecode<R> for alloc::collections::BTreeSet<K> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let len = decoder.read_usize_async().await?;
+        decoder.charge_alloc::<K>(len)?;
+        decoder
+            .with_nested_async(async |decoder| {
+                let mut set = Self::new();
+
+                for _ in 0..len {
+                    set.insert(K::decode_async(decoder).await?);
+                }
+
+                Ok(set)
+            })
+            .await
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: AsyncRead, K: core::hash::Hash + Eq + AsyncDecode<R>, V: AsyncDecode<R>> AsyncDecode<R>
+    for std::collections::hash_map::HashMap<K, V>
+{
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let len = decoder.read_usize_async().await?;
+        decoder.charge_alloc::<(K, V)>(len)?;
+        decoder
+            .with_nested_async(async |decoder| {
+                let mut map = Self::with_capacity(decoder.capacity_hint_async(len));
+
+                for _ in 0..len {
+                    map.insert(K::decode_async(decoder).await?, V::decode_async(decoder).await?);
+                }
+
+                Ok(map)
+            })
+            .await
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: AsyncRead, K: core::hash::Hash + Eq + AsyncDecode<R>> AsyncDecode<R>
+    for std::collections::hash_set::HashSet<K>
+{
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let len = decoder.read_usize_async().await?;
+        decoder.charge_alloc::<K>(len)?;
+        decoder
+            .with_nested_async(async |decoder| {
+                let mut set = Self::with_capacity(decoder.capacity_hint_async(len));
+
+                for _ in 0..len {
+                    set.insert(K::decode_async(decoder).await?);
+                }
+
+                Ok(set)
+            })
+            .await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, T: AsyncDecode<R>> AsyncDecode<R> for alloc::vec::Vec<T> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let len = decoder.read_usize_async().await?;
+        decoder.charge_alloc::<T>(len)?;
+        decoder
+            .with_nested_async(async |decoder| {
+                let mut vec = Self::with_capacity(decoder.capacity_hint_async(len));
+
+                for i in 0..len {
+                    vec.push(
+                        decoder
+                            .with_index_async(async |decoder| T::decode_async(decoder).await, i)
+                            .await?,
+                    );
+                }
+
+                Ok(vec)
+            })
+            .await
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: AsyncRead, T: AsyncDecode<R>> AsyncDecode<R> for alloc::collections::VecDeque<T> {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        let len = decoder.read_usize_async().await?;
+        decoder.charge_alloc::<T>(len)?;
+        decoder
+            .with_nested_async(async |decoder| {
+                let mut deque = Self::with_capacity(decoder.capacity_hint_async(len));
+
+                for i in 0..len {
+                    deque.push_back(
+                        decoder
+                            .with_index_async(async |decoder| T::decode_async(decoder).await, i)
+                            .await?,
+                    );
+                }
+
+                Ok(deque)
+            })
+            .await
+    }
+}
+
+impl<R: AsyncRead> AsyncDecode<R> for Duration {
+    #[inline]
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self> {
+        Ok(Self::new(
+            decoder.read_u64_async().await?,
+            decoder.read_u32_async().await?,
+        ))
+    }
+}
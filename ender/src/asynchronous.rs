@@ -0,0 +1,761 @@
+use crate::io::{AsyncRead, AsyncSeek, AsyncWrite, Seek, SeekFrom, SizeTrack, Slice, SliceMut, Zero};
+use crate::{
+    align_padding, magic_mismatch, Encoder, EncodingError, EncodingResult, Endianness, Format, FormatError, NumEncoding, Opaque, Sign, Signed,
+    StrEncoding, StrLen, StringError, Unsigned,
+};
+
+// Large enough to fit the longest possible encoding of a single primitive,
+// which is a 128-bit var-int (19 bytes)
+const SCRATCH_LEN: usize = 19;
+
+// The asynchronous primitives only know the layout of the binary format
+fn check_async_format(format: Format) -> EncodingResult<()> {
+    if format != Format::Binary {
+        return Err(FormatError::AsyncUnsupported(format).into());
+    }
+    Ok(())
+}
+
+macro_rules! debug_fn_async {
+    ($fn_name:ident, $variant_name:ident ( $ty:ty )) => {
+        #[inline]
+        pub async fn $fn_name<F, R>(&mut self, f: F, s: $ty) -> EncodingResult<R>
+        where
+            F: AsyncFnOnce(&mut Encoder<T>) -> EncodingResult<R>,
+        {
+            #[cfg(feature = "debug")]
+            {
+                #[cfg(feature = "alloc")]
+                {
+                    self.stack.frames.push(crate::source::Frame::$variant_name(s));
+                    let r = f(self).await?;
+                    self.stack.frames.pop();
+                    Ok(r)
+                }
+                #[cfg(not(feature = "alloc"))]
+                {
+                    let last_frame = self.stack.last_frame;
+                    self.stack.last_frame = crate::source::Frame::$variant_name(s);
+                    let r = f(self).await?;
+                    self.stack.last_frame = last_frame;
+                    Ok(r)
+                }
+            }
+            #[cfg(not(feature = "debug"))]
+            {
+                let _ = s;
+                f(self).await
+            }
+        }
+    };
+}
+
+impl<T> Encoder<'_, T> {
+    debug_fn_async!(with_item_async, Item(&'static str));
+    debug_fn_async!(with_variant_async, Variant(&'static str));
+    debug_fn_async!(with_field_async, Field(&'static str));
+    debug_fn_async!(with_index_async, Index(usize));
+
+    /// The asynchronous version of [`Encoder::with_nested`]
+    #[inline]
+    pub async fn with_nested_async<F, R>(&mut self, f: F) -> EncodingResult<R>
+    where
+        F: AsyncFnOnce(&mut Encoder<T>) -> EncodingResult<R>,
+    {
+        self.enter_nested()?;
+        let r = f(self).await;
+        self.exit_nested();
+        r
+    }
+}
+
+macro_rules! make_async_write_fns {
+    ($($ty:ty {
+        $write:ident => $write_async:ident,
+        $write_with:ident => $write_with_async:ident $(,)?
+    }),* $(,)?) => {
+        $(
+        #[doc = "The asynchronous version of [`Encoder::"]
+        #[doc = stringify!($write)]
+        #[doc = "`]"]
+        #[inline]
+        pub async fn $write_async(&mut self, value: $ty) -> EncodingResult<()> {
+            self.write_sync(|encoder| encoder.$write(value)).await?;
+            Ok(())
+        }
+
+        #[doc = "The asynchronous version of [`Encoder::"]
+        #[doc = stringify!($write_with)]
+        #[doc = "`]"]
+        #[inline]
+        pub async fn $write_with_async(&mut self, value: $ty, num_encoding: NumEncoding, endianness: Endianness) -> EncodingResult<()> {
+            self.write_sync(|encoder| encoder.$write_with(value, num_encoding, endianness)).await?;
+            Ok(())
+        }
+        )*
+    };
+}
+
+impl<T: AsyncWrite> Encoder<'_, T> {
+    /// Method for convenience.
+    ///
+    /// Encodes a value using `self` as the encoder.
+    ///
+    /// This method is not magic - it is literally defined as `value.encode_async(self).await`
+    #[inline]
+    pub async fn encode_value_async<V: AsyncEncode<T>>(&mut self, value: V) -> EncodingResult<()> {
+        value.encode_async(self).await
+    }
+
+    // Runs the synchronous encoding function `f` on a scratch buffer, then
+    // asynchronously writes the result to the underlying stream.
+    //
+    // The state of the scratch encoder is copied back into `self`, so that
+    // any flatten variable consumed by `f` is consumed here too.
+    //
+    // Returns the number of bytes written.
+    async fn write_sync<F>(&mut self, f: F) -> EncodingResult<usize>
+    where
+        F: FnOnce(&mut Encoder<SliceMut>) -> EncodingResult<()>,
+    {
+        check_async_format(self.ctxt.settings.format)?;
+        let mut buf = [0u8; SCRATCH_LEN];
+        let mut scratch = Encoder::new(SliceMut::new(&mut buf), self.ctxt);
+        f(&mut scratch)?;
+        let (mut stream, ctxt) = scratch.finish();
+        let len = stream.seek(SeekFrom::POSITION)?;
+
+        self.ctxt = ctxt;
+        self.stream.write(&buf[..len]).await?;
+        Ok(len)
+    }
+
+    make_async_write_fns! {
+        u8 { write_u8 => write_u8_async, write_u8_with => write_u8_with_async },
+        u16 { write_u16 => write_u16_async, write_u16_with => write_u16_with_async },
+        u32 { write_u32 => write_u32_async, write_u32_with => write_u32_with_async },
+        u64 { write_u64 => write_u64_async, write_u64_with => write_u64_with_async },
+        u128 { write_u128 => write_u128_async, write_u128_with => write_u128_with_async },
+        i8 { write_i8 => write_i8_async, write_i8_with => write_i8_with_async },
+        i16 { write_i16 => write_i16_async, write_i16_with => write_i16_with_async },
+        i32 { write_i32 => write_i32_async, write_i32_with => write_i32_with_async },
+        i64 { write_i64 => write_i64_async, write_i64_with => write_i64_with_async },
+        i128 { write_i128 => write_i128_async, write_i128_with => write_i128_with_async },
+    }
+
+    /// The asynchronous version of [`Encoder::write_usize`]
+    #[inline]
+    pub async fn write_usize_async(&mut self, value: usize) -> EncodingResult<()> {
+        self.write_sync(|encoder| encoder.write_usize(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_isize`]
+    #[inline]
+    pub async fn write_isize_async(&mut self, value: isize) -> EncodingResult<()> {
+        self.write_sync(|encoder| encoder.write_isize(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_uvariant`]
+    #[inline]
+    #[allow(private_bounds)]
+    pub async fn write_uvariant_async<V>(&mut self, value: V) -> EncodingResult<()>
+    where
+        Opaque: From<V>,
+        V: Sign<Sign = Unsigned>,
+    {
+        self.write_sync(|encoder| encoder.write_uvariant(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_ivariant`]
+    #[inline]
+    #[allow(private_bounds)]
+    pub async fn write_ivariant_async<V>(&mut self, value: V) -> EncodingResult<()>
+    where
+        Opaque: From<V>,
+        V: Sign<Sign = Signed>,
+    {
+        self.write_sync(|encoder| encoder.write_ivariant(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_bool`]
+    #[inline]
+    pub async fn write_bool_async(&mut self, value: bool) -> EncodingResult<()> {
+        self.write_sync(|encoder| encoder.write_bool(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_char`]
+    #[inline]
+    pub async fn write_char_async(&mut self, value: char) -> EncodingResult<()> {
+        self.write_sync(|encoder| encoder.write_char(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_f32`]
+    #[inline]
+    pub async fn write_f32_async(&mut self, value: f32) -> EncodingResult<()> {
+        self.write_sync(|encoder| encoder.write_f32(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_f64`]
+    #[inline]
+    pub async fn write_f64_async(&mut self, value: f64) -> EncodingResult<()> {
+        self.write_sync(|encoder| encoder.write_f64(value)).await?;
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_str`]
+    #[inline]
+    pub async fn write_str_async<S>(&mut self, string: S) -> EncodingResult<()>
+    where
+        S: IntoIterator<Item = char, IntoIter: Clone>,
+    {
+        let chars = string.into_iter();
+
+        match self.ctxt.settings.string_repr.len {
+            StrLen::LengthPrefixed => {
+                // Measure the length first, exactly like the synchronous version does
+                let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), self.ctxt);
+                for ch in chars.clone() {
+                    sz_encoder.write_char(ch)?;
+                }
+                let size = sz_encoder.finish().0.size_written();
+
+                self.write_usize_async(size).await?;
+                for ch in chars {
+                    self.write_char_async(ch).await?;
+                }
+            }
+            StrLen::NullTerminated => {
+                for ch in chars {
+                    self.write_char_async(ch).await?;
+                }
+                self.write_sync(|encoder| encoder.write_char_or_null(None))
+                    .await?;
+            }
+            StrLen::NullTerminatedFixed(max) => {
+                // Check the length before writing anything
+                let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), self.ctxt);
+                for ch in chars.clone() {
+                    sz_encoder.write_char(ch)?;
+                }
+                let size = sz_encoder.finish().0.size_written();
+                if size > max {
+                    return Err(EncodingError::StringError(StringError::TooLong));
+                }
+
+                for ch in chars {
+                    self.write_char_async(ch).await?;
+                }
+
+                // Fill the rest with zeroes
+                for _ in size..max {
+                    self.write_byte_async(0).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::write_byte`]
+    #[inline]
+    pub async fn write_byte_async(&mut self, byte: u8) -> EncodingResult<()> {
+        self.stream.write(&[byte]).await
+    }
+
+    /// The asynchronous version of [`Encoder::write_bytes`]
+    #[inline]
+    pub async fn write_bytes_async(&mut self, bytes: &[u8]) -> EncodingResult<()> {
+        self.stream.write(bytes).await
+    }
+
+    /// The asynchronous version of [`Encoder::write_magic`]
+    #[inline]
+    pub async fn write_magic_async(&mut self, magic: &'static [u8]) -> EncodingResult<()> {
+        self.stream.write(magic).await
+    }
+
+    /// The asynchronous version of [`Encoder::write_padding`]
+    #[inline]
+    pub async fn write_padding_async(&mut self, len: usize, fill: u8) -> EncodingResult<()> {
+        let buf = [fill; 16];
+        let mut remaining = len;
+        while remaining != 0 {
+            let len = remaining.min(buf.len());
+            self.stream.write(&buf[..len]).await?;
+            remaining -= len;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! make_async_read_fns {
+    ($($ty:ty {
+        $read:ident => $read_async:ident,
+        $read_with:ident => $read_with_async:ident $(,)?
+    }),* $(,)?) => {
+        $(
+        #[doc = "The asynchronous version of [`Encoder::"]
+        #[doc = stringify!($read)]
+        #[doc = "`]"]
+        #[inline]
+        pub async fn $read_async(&mut self) -> EncodingResult<$ty> {
+            let num_encoding = self.ctxt.settings.num_repr.num_encoding;
+            let endianness = self.ctxt.settings.num_repr.endianness;
+            self.$read_with_async(num_encoding, endianness).await
+        }
+
+        #[doc = "The asynchronous version of [`Encoder::"]
+        #[doc = stringify!($read_with)]
+        #[doc = "`]"]
+        #[inline]
+        pub async fn $read_with_async(&mut self, num_encoding: NumEncoding, endianness: Endianness) -> EncodingResult<$ty> {
+            let mut buf = [0u8; SCRATCH_LEN];
+            let len = self.read_num_bytes(core::mem::size_of::<$ty>(), num_encoding, &mut buf).await?;
+            self.read_sync(&buf[..len], |decoder| decoder.$read_with(num_encoding, endianness))
+        }
+        )*
+    };
+}
+
+impl<T: AsyncRead> Encoder<'_, T> {
+    /// Method for convenience.
+    ///
+    /// Decodes a value using `self` as the decoder.
+    ///
+    /// This method is not magic - it is literally defined as `V::decode_async(self).await`
+    #[inline]
+    pub async fn decode_value_async<V: AsyncDecode<T>>(&mut self) -> EncodingResult<V> {
+        V::decode_async(self).await
+    }
+
+    /// The asynchronous version of [`Encoder::capacity_hint`]
+    #[inline]
+    pub fn capacity_hint_async(&self, len: usize) -> usize {
+        self.stream.remaining_len().map_or(len, |x| x.min(len))
+    }
+
+    // Runs the synchronous decoding function `f` on the given bytes,
+    // which must have been previously read from the underlying stream.
+    //
+    // The state of the scratch decoder is copied back into `self`, so that
+    // any flatten variable consumed by `f` is consumed here too.
+    fn read_sync<F, R>(&mut self, buf: &[u8], f: F) -> EncodingResult<R>
+    where
+        F: FnOnce(&mut Encoder<Slice>) -> EncodingResult<R>,
+    {
+        check_async_format(self.ctxt.settings.format)?;
+        let mut scratch = Encoder::new(Slice::new(buf), self.ctxt);
+        let r = f(&mut scratch)?;
+        self.ctxt = scratch.ctxt;
+        Ok(r)
+    }
+
+    // Reads the bytes making up a number of `size` bytes encoded with `num_encoding`
+    // into `buf`, returning how many were read.
+    //
+    // Var-ints are read byte by byte until the last byte is found or `buf` is full,
+    // in which case the synchronous decoder will report the var-int as malformed.
+    async fn read_num_bytes(
+        &mut self,
+        size: usize,
+        num_encoding: NumEncoding,
+        buf: &mut [u8; SCRATCH_LEN],
+    ) -> EncodingResult<usize> {
+        match num_encoding {
+            NumEncoding::Fixed => {
+                self.stream.read(&mut buf[..size]).await?;
+                Ok(size)
+            }
+            NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if size == 1 => {
+                self.stream.read(&mut buf[..1]).await?;
+                Ok(1)
+            }
+            NumEncoding::BincodeVarint => {
+                self.stream.read(&mut buf[..1]).await?;
+                let len = match buf[0] {
+                    251 => 2,
+                    252 => 4,
+                    253 => 8,
+                    254 => 16,
+                    _ => 0,
+                };
+                self.stream.read(&mut buf[1..1 + len]).await?;
+                Ok(1 + len)
+            }
+            NumEncoding::Asn1 => {
+                self.stream.read(&mut buf[..1]).await?;
+                // Lengths that don't fit are left for the synchronous decoder to report
+                let len = match buf[0] {
+                    0..=0x80 => 0,
+                    byte => ((byte & 0x7F) as usize).min(SCRATCH_LEN - 1),
+                };
+                self.stream.read(&mut buf[1..1 + len]).await?;
+                Ok(1 + len)
+            }
+            NumEncoding::Leb128
+            | NumEncoding::ProtobufWasteful
+            | NumEncoding::ProtobufZigzag
+            | NumEncoding::PostcardVarint => {
+                for i in 0..SCRATCH_LEN {
+                    self.stream.read(&mut buf[i..i + 1]).await?;
+                    if buf[i] & 0b1000_0000 == 0 {
+                        return Ok(i + 1);
+                    }
+                }
+                Ok(SCRATCH_LEN)
+            }
+        }
+    }
+
+    make_async_read_fns! {
+        u8 { read_u8 => read_u8_async, read_u8_with => read_u8_with_async },
+        u16 { read_u16 => read_u16_async, read_u16_with => read_u16_with_async },
+        u32 { read_u32 => read_u32_async, read_u32_with => read_u32_with_async },
+        u64 { read_u64 => read_u64_async, read_u64_with => read_u64_with_async },
+        u128 { read_u128 => read_u128_async, read_u128_with => read_u128_with_async },
+        i8 { read_i8 => read_i8_async, read_i8_with => read_i8_with_async },
+        i16 { read_i16 => read_i16_async, read_i16_with => read_i16_with_async },
+        i32 { read_i32 => read_i32_async, read_i32_with => read_i32_with_async },
+        i64 { read_i64 => read_i64_async, read_i64_with => read_i64_with_async },
+        i128 { read_i128 => read_i128_async, read_i128_with => read_i128_with_async },
+    }
+
+    /// The asynchronous version of [`Encoder::read_usize`]
+    #[inline]
+    pub async fn read_usize_async(&mut self) -> EncodingResult<usize> {
+        let mut buf = [0u8; SCRATCH_LEN];
+        let len = if self.ctxt.size_flatten.is_some() {
+            0
+        } else {
+            let size_repr = self.ctxt.settings.size_repr;
+            self.read_num_bytes(size_repr.width.bytes(), size_repr.num_encoding, &mut buf)
+                .await?
+        };
+        self.read_sync(&buf[..len], |decoder| decoder.read_usize())
+    }
+
+    /// The asynchronous version of [`Encoder::read_isize`]
+    #[inline]
+    pub async fn read_isize_async(&mut self) -> EncodingResult<isize> {
+        let mut buf = [0u8; SCRATCH_LEN];
+        let size_repr = self.ctxt.settings.size_repr;
+        let len = self
+            .read_num_bytes(size_repr.width.bytes(), size_repr.num_encoding, &mut buf)
+            .await?;
+        self.read_sync(&buf[..len], |decoder| decoder.read_isize())
+    }
+
+    async fn read_variant_bytes(&mut self, buf: &mut [u8; SCRATCH_LEN]) -> EncodingResult<usize> {
+        if self.ctxt.variant_flatten.is_some() {
+            Ok(0)
+        } else {
+            let variant_repr = self.ctxt.settings.variant_repr;
+            self.read_num_bytes(variant_repr.width.bytes(), variant_repr.num_encoding, buf)
+                .await
+        }
+    }
+
+    /// The asynchronous version of [`Encoder::read_uvariant`]
+    #[inline]
+    #[allow(private_bounds)]
+    pub async fn read_uvariant_async<V>(&mut self) -> EncodingResult<V>
+    where
+        V: Sign<Sign = Unsigned>,
+        Opaque: TryInto<V, Error = EncodingError>,
+    {
+        let mut buf = [0u8; SCRATCH_LEN];
+        let len = self.read_variant_bytes(&mut buf).await?;
+        self.read_sync(&buf[..len], |decoder| decoder.read_uvariant())
+    }
+
+    /// The asynchronous version of [`Encoder::read_ivariant`]
+    #[inline]
+    #[allow(private_bounds)]
+    pub async fn read_ivariant_async<V>(&mut self) -> EncodingResult<V>
+    where
+        V: Sign<Sign = Signed>,
+        Opaque: TryInto<V, Error = EncodingError>,
+    {
+        let mut buf = [0u8; SCRATCH_LEN];
+        let len = self.read_variant_bytes(&mut buf).await?;
+        self.read_sync(&buf[..len], |decoder| decoder.read_ivariant())
+    }
+
+    /// The asynchronous version of [`Encoder::read_bool`]
+    #[inline]
+    pub async fn read_bool_async(&mut self) -> EncodingResult<bool> {
+        let mut buf = [0u8; 1];
+        let len = if self.ctxt.bool_flatten.is_some() {
+            0
+        } else {
+            self.stream.read(&mut buf).await?;
+            1
+        };
+        self.read_sync(&buf[..len], |decoder| decoder.read_bool())
+    }
+
+    /// The asynchronous version of [`Encoder::read_char`]
+    #[inline]
+    pub async fn read_char_async(&mut self) -> EncodingResult<char> {
+        Ok(self.read_char_or_null_async().await?.0.unwrap_or('\0'))
+    }
+
+    // Reads exactly the bytes making up the next char, then decodes them.
+    // Also returns how many bytes were read.
+    async fn read_char_or_null_async(&mut self) -> EncodingResult<(Option<char>, usize)> {
+        let mut buf = [0u8; 4];
+        let len = match self.ctxt.settings.string_repr.encoding {
+            StrEncoding::Ascii | StrEncoding::Windows1252 => {
+                self.stream.read(&mut buf[..1]).await?;
+                1
+            }
+            StrEncoding::Utf8 => {
+                self.stream.read(&mut buf[..1]).await?;
+
+                // The number of continuation bytes is declared by the first byte.
+                // Malformed first bytes are rejected by the synchronous decoder
+                let leading = buf[0].leading_ones() as usize;
+                let add = if (2..=4).contains(&leading) {
+                    leading - 1
+                } else {
+                    0
+                };
+                self.stream.read(&mut buf[1..1 + add]).await?;
+                1 + add
+            }
+            StrEncoding::Utf16 => {
+                self.stream.read(&mut buf[..2]).await?;
+                let unit = match self.ctxt.settings.string_repr.endianness {
+                    Endianness::LittleEndian => u16::from_le_bytes([buf[0], buf[1]]),
+                    Endianness::BigEndian => u16::from_be_bytes([buf[0], buf[1]]),
+                };
+
+                // A high surrogate must be followed by a low surrogate
+                if (0xD800..=0xDBFF).contains(&unit) {
+                    self.stream.read(&mut buf[2..4]).await?;
+                    4
+                } else {
+                    2
+                }
+            }
+            StrEncoding::Utf32 => {
+                self.stream.read(&mut buf).await?;
+                4
+            }
+        };
+        let ch = self.read_sync(&buf[..len], |decoder| decoder.read_char_or_null())?;
+        Ok((ch, len))
+    }
+
+    /// The asynchronous version of [`Encoder::read_f32`]
+    #[inline]
+    pub async fn read_f32_async(&mut self) -> EncodingResult<f32> {
+        Ok(f32::from_bits(
+            self.read_u32_with_async(NumEncoding::Fixed, self.ctxt.settings.num_repr.endianness)
+                .await?,
+        ))
+    }
+
+    /// The asynchronous version of [`Encoder::read_f64`]
+    #[inline]
+    pub async fn read_f64_async(&mut self) -> EncodingResult<f64> {
+        Ok(f64::from_bits(
+            self.read_u64_with_async(NumEncoding::Fixed, self.ctxt.settings.num_repr.endianness)
+                .await?,
+        ))
+    }
+
+    /// The asynchronous version of [`Encoder::read_str`].
+    ///
+    /// Since the chars are decoded one at a time, the string is built by extending
+    /// an empty `S` rather than collecting an iterator.
+    #[inline]
+    pub async fn read_str_async<S>(&mut self) -> EncodingResult<S>
+    where
+        S: Default + Extend<char>,
+    {
+        let mut string = S::default();
+
+        match self.ctxt.settings.string_repr.len {
+            StrLen::LengthPrefixed => {
+                let length = self.read_usize_async().await?;
+                self.charge_alloc::<u8>(length)?;
+                let mut read = 0;
+                while read < length {
+                    let (ch, len) = self.read_char_or_null_async().await?;
+                    read += len;
+                    if read > length {
+                        return Err(EncodingError::UnexpectedEnd);
+                    }
+                    string.extend(Some(ch.unwrap_or('\0')));
+                }
+            }
+            StrLen::NullTerminated => {
+                while let (Some(ch), _) = self.read_char_or_null_async().await? {
+                    self.charge_alloc::<u8>(ch.len_utf8())?;
+                    string.extend(Some(ch));
+                }
+            }
+            StrLen::NullTerminatedFixed(max) => {
+                self.charge_alloc::<u8>(max)?;
+                let mut read = 0;
+                while read < max {
+                    let (ch, len) = self.read_char_or_null_async().await?;
+                    read += len;
+                    if read > max {
+                        return Err(EncodingError::UnexpectedEnd);
+                    }
+                    if let Some(ch) = ch {
+                        string.extend(Some(ch));
+                    } else {
+                        // Null character found
+                        // Read all the remaining nulls
+                        for _ in read..max {
+                            if self.read_byte_async().await? != 0 {
+                                return Err(EncodingError::StringError(StringError::MissingNull));
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(string)
+    }
+
+    /// The asynchronous version of [`Encoder::read_byte`]
+    #[inline]
+    pub async fn read_byte_async(&mut self) -> EncodingResult<u8> {
+        let mut buf = [0u8; 1];
+        self.stream.read(&mut buf).await?;
+        Ok(buf[0])
+    }
+
+    /// The asynchronous version of [`Encoder::read_bytes`]
+    #[inline]
+    pub async fn read_bytes_async(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        self.stream.read(buf).await
+    }
+
+    /// The asynchronous version of [`Encoder::read_magic`]
+    #[inline]
+    pub async fn read_magic_async(&mut self, magic: &'static [u8]) -> EncodingResult<()> {
+        let mut buf = [0u8; 16];
+        for (idx, chunk) in magic.chunks(buf.len()).enumerate() {
+            let buf = &mut buf[..chunk.len()];
+            self.stream.read(buf).await?;
+            magic_mismatch(magic, idx * 16, buf, chunk)?;
+        }
+        Ok(())
+    }
+
+    /// The asynchronous version of [`Encoder::read_padding`]
+    #[inline]
+    pub async fn read_padding_async(&mut self, len: usize, check: Option<u8>) -> EncodingResult<()> {
+        let mut buf = [0u8; 16];
+        let mut remaining = len;
+        while remaining != 0 {
+            let len = remaining.min(buf.len());
+            self.stream.read(&mut buf[..len]).await?;
+            remaining -= len;
+
+            if let Some(expected) = check {
+                if let Some(&actual) = buf[..len].iter().find(|&&x| x != expected) {
+                    return Err(EncodingError::InvalidPadding { expected, actual });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsyncSeek> Encoder<'_, T> {
+    /// The asynchronous version of [`Encoder::stream_position`]
+    #[inline]
+    pub async fn stream_position_async(&mut self) -> EncodingResult<usize> {
+        self.stream.seek(SeekFrom::POSITION).await
+    }
+
+    /// The asynchronous version of [`Encoder::seek`]
+    #[inline]
+    pub async fn seek_async(&mut self, seek: SeekFrom) -> EncodingResult<usize> {
+        self.stream.seek(seek).await
+    }
+
+    /// The asynchronous version of [`Encoder::with_seek`]
+    #[inline]
+    pub async fn with_seek_async<F, R>(&mut self, f: F, seek: SeekFrom) -> EncodingResult<R>
+    where
+        F: AsyncFnOnce(&mut Encoder<T>) -> EncodingResult<R>,
+    {
+        // Track the current position
+        let prev = self.stream_position_async().await? as isize;
+
+        // Magic fn!
+        let ret = f(self).await;
+
+        // Seek to the desired location, and track the location now
+        let cur = self.stream.seek(seek).await? as isize;
+        // Find the difference
+        let diff = prev - cur;
+
+        self.stream.seek(SeekFrom::Current(diff)).await?;
+        ret
+    }
+}
+
+impl<T: AsyncWrite + AsyncSeek> Encoder<'_, T> {
+    /// The asynchronous version of [`Encoder::write_align`]
+    #[inline]
+    pub async fn write_align_async(&mut self, align: usize, fill: u8) -> EncodingResult<()> {
+        let pos = self.stream_position_async().await?;
+        self.write_padding_async(align_padding(pos, align), fill).await
+    }
+}
+
+impl<T: AsyncRead + AsyncSeek> Encoder<'_, T> {
+    /// The asynchronous version of [`Encoder::read_align`]
+    #[inline]
+    pub async fn read_align_async(&mut self, align: usize, check: Option<u8>) -> EncodingResult<()> {
+        let pos = self.stream_position_async().await?;
+        self.read_padding_async(align_padding(pos, align), check).await
+    }
+}
+
+/// The asynchronous counterpart of [`Encode`][`crate::Encode`].
+///
+/// Implementations should produce exactly the same output as the synchronous
+/// implementation would for the same value and settings.
+///
+/// The futures returned by this trait are not required to be `Send`.
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+#[allow(async_fn_in_trait)]
+pub trait AsyncEncode<W: AsyncWrite> {
+    /// Encodes `self` into its binary format.
+    ///
+    /// The same guarantees as [`Encode::encode`][`crate::Encode::encode`] apply.
+    async fn encode_async(&self, encoder: &mut Encoder<'_, W>) -> EncodingResult<()>;
+}
+
+/// The asynchronous counterpart of [`Decode`][`crate::Decode`].
+///
+/// Implementations should accept exactly the same input as the synchronous
+/// implementation would for the same settings.
+///
+/// The futures returned by this trait are not required to be `Send`.
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+#[allow(async_fn_in_trait)]
+pub trait AsyncDecode<R: AsyncRead>: Sized {
+    /// Decodes an owned version of `Self` from its binary format.
+    ///
+    /// The same guarantees as [`Decode::decode`][`crate::Decode::decode`] apply.
+    async fn decode_async(decoder: &mut Encoder<'_, R>) -> EncodingResult<Self>;
+}
@@ -0,0 +1,170 @@
+use crate::io::{BitRead, BitWrite};
+use crate::{Encoder, EncodingError, EncodingResult, Opaque};
+
+impl<T: BitWrite> Encoder<'_, T> {
+    /// Writes the lowest `bits` bits of `value`, in the order defined by the stream.
+    ///
+    /// Returns a [`EncodingError::BitFieldOverflow`] if `value` doesn't fit in `bits` bits.
+    #[inline]
+    pub fn write_bits(&mut self, value: u128, bits: u32) -> EncodingResult<()> {
+        if bits > u128::BITS || (bits < u128::BITS && value >> bits != 0) {
+            return Err(EncodingError::BitFieldOverflow {
+                value: Opaque::from(value),
+                bits,
+            });
+        }
+        self.stream.write_bits(value, bits)
+    }
+
+    /// Pads the current byte with zero bits, so that the next write starts on a byte boundary.
+    #[inline]
+    pub fn write_bit_align(&mut self) -> EncodingResult<()> {
+        self.stream.align_bits()
+    }
+}
+
+impl<T: BitRead> Encoder<'_, T> {
+    /// Reads `bits` bits, in the order defined by the stream, and returns them
+    /// as the lowest bits of the result.
+    ///
+    /// Returns a [`EncodingError::BitFieldOverflow`] if `bits` is greater than 128.
+    #[inline]
+    pub fn read_bits(&mut self, bits: u32) -> EncodingResult<u128> {
+        if bits > u128::BITS {
+            return Err(EncodingError::BitFieldOverflow {
+                value: Opaque::from(0u128),
+                bits,
+            });
+        }
+        self.stream.read_bits(bits)
+    }
+
+    /// Discards the unread bits of the current byte, so that the next read starts on a byte boundary.
+    #[inline]
+    pub fn read_bit_align(&mut self) -> EncodingResult<()> {
+        self.stream.align_bits()
+    }
+}
+
+/// A value that can be stored in a bit field of arbitrary width, for example
+/// a 3-bit integer or a 1-bit flag.
+///
+/// Unsigned integers are stored as-is, signed integers in two's complement
+/// and booleans as a `1` or a `0`.
+pub trait BitField: Sized {
+    /// Encodes `self` into exactly `bits` bits.
+    ///
+    /// Returns a [`EncodingError::BitFieldOverflow`] if the value can't be represented
+    /// in the given number of bits.
+    fn encode_bits<W: BitWrite>(&self, encoder: &mut Encoder<W>, bits: u32) -> EncodingResult<()>;
+
+    /// Decodes a value from exactly `bits` bits.
+    fn decode_bits<R: BitRead>(decoder: &mut Encoder<R>, bits: u32) -> EncodingResult<Self>;
+}
+
+macro_rules! impl_bit_field {
+    (unsigned $($ty:ty),* $(,)?) => {
+	    $(
+	    impl BitField for $ty {
+		    #[inline]
+		    fn encode_bits<W: BitWrite>(&self, encoder: &mut Encoder<W>, bits: u32) -> EncodingResult<()> {
+			    encoder.write_bits(*self as u128, bits)
+		    }
+
+		    #[inline]
+		    fn decode_bits<R: BitRead>(decoder: &mut Encoder<R>, bits: u32) -> EncodingResult<Self> {
+			    let value = decoder.read_bits(bits)?;
+			    <$ty>::try_from(value).map_err(|_| EncodingError::BitFieldOverflow {
+				    value: Opaque::from(value),
+				    bits,
+			    })
+		    }
+	    }
+	    )*
+    };
+    (signed $($ty:ty),* $(,)?) => {
+	    $(
+	    impl BitField for $ty {
+		    #[inline]
+		    fn encode_bits<W: BitWrite>(&self, encoder: &mut Encoder<W>, bits: u32) -> EncodingResult<()> {
+			    let value = *self as i128;
+			    if !fits_signed(value, bits) {
+				    return Err(EncodingError::BitFieldOverflow {
+					    value: Opaque::from(*self),
+					    bits,
+				    });
+			    }
+			    encoder.write_bits(value as u128 & mask(bits), bits)
+		    }
+
+		    #[inline]
+		    fn decode_bits<R: BitRead>(decoder: &mut Encoder<R>, bits: u32) -> EncodingResult<Self> {
+			    let value = sign_extend(decoder.read_bits(bits)?, bits);
+			    <$ty>::try_from(value).map_err(|_| EncodingError::BitFieldOverflow {
+				    value: Opaque::from(value),
+				    bits,
+			    })
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_bit_field!(unsigned u8, u16, u32, u64, u128, usize);
+impl_bit_field!(signed i8, i16, i32, i64, i128, isize);
+
+impl BitField for bool {
+    #[inline]
+    fn encode_bits<W: BitWrite>(&self, encoder: &mut Encoder<W>, bits: u32) -> EncodingResult<()> {
+        if bits == 0 {
+            return Err(EncodingError::BitFieldOverflow {
+                value: Opaque::from(*self as u8),
+                bits,
+            });
+        }
+        encoder.write_bits(*self as u128, bits)
+    }
+
+    #[inline]
+    fn decode_bits<R: BitRead>(decoder: &mut Encoder<R>, bits: u32) -> EncodingResult<Self> {
+        match decoder.read_bits(bits)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(EncodingError::InvalidBool),
+        }
+    }
+}
+
+/// Returns a mask with the lowest `bits` bits set
+#[inline]
+fn mask(bits: u32) -> u128 {
+    if bits >= u128::BITS {
+        u128::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// Whether a signed value can be represented in two's complement with the given number of bits
+#[inline]
+fn fits_signed(value: i128, bits: u32) -> bool {
+    if bits == 0 {
+        value == 0
+    } else if bits >= u128::BITS {
+        true
+    } else {
+        let half = 1i128 << (bits - 1);
+        -half <= value && value < half
+    }
+}
+
+/// Interprets the lowest `bits` bits of `value` as a two's complement number
+#[inline]
+fn sign_extend(value: u128, bits: u32) -> i128 {
+    if bits == 0 || bits >= u128::BITS {
+        value as i128
+    } else {
+        let shift = u128::BITS - bits;
+        ((value << shift) as i128) >> shift
+    }
+}
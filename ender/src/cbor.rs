@@ -0,0 +1,343 @@
+//! Support for the [CBOR](https://www.rfc-editor.org/rfc/rfc8949) data format,
+//! which is used by setting the [`format`][`crate::BinSettings::format`] to [`Format::Cbor`].
+//!
+//! The primitives of the [`Encoder`] are mapped to CBOR data items as follows,
+//! ignoring the numerical and string representations in the settings:
+//! - Integers, `usize`, `isize` and enum variants are unsigned or negative integers in their
+//!   shortest form. 128-bit values that don't fit in 64 bits are tagged bignums.
+//! - `bool`s are the simple values `false` and `true`.
+//! - `f32`s and `f64`s are single and double precision floats. While decoding, a half precision
+//!   float is accepted for both, and a single precision float is accepted for an `f64`.
+//! - Strings and `char`s are text strings, thus always UTF-8.
+//! - The lengths of sequences and maps are the headers of arrays and maps.
+//!
+//! Structs, tuples and the fields of enum variants are arrays of their fields, and enums are
+//! an array of two items: the variant followed by its fields. `Option`s are `null` when absent
+//! and an array holding the value when present, while `()` is `null`.
+//!
+//! Indefinite-length items are not supported.
+//!
+//! # Example
+//!
+//! ```
+//! use ender::{encode_bytes_with, BinSettings, Context, Format};
+//!
+//! let ctxt = Context::new().settings(BinSettings::new().format(Format::Cbor));
+//! let bytes = encode_bytes_with((1000u32, vec![-1i8, 2], "IETF"), ctxt).unwrap();
+//!
+//! assert_eq!(
+//!     bytes,
+//!     [0x83, 0x19, 0x03, 0xE8, 0x82, 0x20, 0x02, 0x64, b'I', b'E', b'T', b'F'],
+//! );
+//! ```
+
+use crate::format::Int;
+use crate::io::{Read, Write};
+use crate::{Encoder, EncodingResult, Endianness, Format, FormatError, NumEncoding};
+use parse_display::Display;
+
+/// The major type of a CBOR data item, stored in the 3 most significant bits of its initial byte.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[repr(u8)]
+pub enum Major {
+    /// An unsigned integer
+    Unsigned = 0,
+    /// A negative integer, whose value is `-1` minus the argument
+    Negative = 1,
+    /// A byte string
+    Bytes = 2,
+    /// A UTF-8 text string
+    Text = 3,
+    /// An array of items
+    Array = 4,
+    /// A map of pairs of items
+    Map = 5,
+    /// A tagged item
+    Tag = 6,
+    /// A float or a simple value
+    Simple = 7,
+}
+
+impl Major {
+    /// Returns the major type stored in the initial byte of an item.
+    #[inline]
+    pub const fn from_initial(initial: u8) -> Self {
+        match initial >> 5 {
+            0 => Self::Unsigned,
+            1 => Self::Negative,
+            2 => Self::Bytes,
+            3 => Self::Text,
+            4 => Self::Array,
+            5 => Self::Map,
+            6 => Self::Tag,
+            _ => Self::Simple,
+        }
+    }
+}
+
+/// The tag of an unsigned bignum
+const POSITIVE_BIGNUM: u64 = 2;
+/// The tag of a negative bignum
+const NEGATIVE_BIGNUM: u64 = 3;
+
+const FALSE: u8 = 0xF4;
+const TRUE: u8 = 0xF5;
+const NULL: u8 = 0xF6;
+const FLOAT16: u8 = 0xF9;
+const FLOAT32: u8 = 0xFA;
+const FLOAT64: u8 = 0xFB;
+
+// Converts a half precision float to single precision, which is always exact
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) as u32) << 31;
+    let exp = ((half >> 10) & 0x1F) as u32;
+    let mant = (half & 0x3FF) as u32;
+
+    let bits = match (exp, mant) {
+        (0, 0) => sign,
+        // Subnormal, which is normal in single precision
+        (0, _) => {
+            let shift = mant.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | ((mant << shift) & 0x3FF) << 13
+        }
+        (0x1F, _) => sign | 0x7F80_0000 | (mant << 13),
+        _ => sign | ((exp + 112) << 23) | (mant << 13),
+    };
+    f32::from_bits(bits)
+}
+
+fn unexpected(initial: u8, expected: &'static str) -> FormatError {
+    FormatError::UnexpectedItem {
+        format: Format::Cbor,
+        expected,
+        found: initial,
+    }
+}
+
+impl<T: Write> Encoder<'_, T> {
+    /// Writes the head of a CBOR data item: its major type and argument,
+    /// in the shortest form.
+    ///
+    /// This is the counterpart of [`read_cbor_head`][`Self::read_cbor_head`].
+    pub fn write_cbor_head(&mut self, major: Major, argument: u64) -> EncodingResult<()> {
+        let major = (major as u8) << 5;
+        match argument {
+            0..=23 => self.write_byte(major | argument as u8),
+            24..=0xFF => {
+                self.write_byte(major | 24)?;
+                self.write_byte(argument as u8)
+            }
+            0x100..=0xFFFF => {
+                self.write_byte(major | 25)?;
+                self.write_u16_with(argument as u16, NumEncoding::Fixed, Endianness::BigEndian)
+            }
+            0x1_0000..=0xFFFF_FFFF => {
+                self.write_byte(major | 26)?;
+                self.write_u32_with(argument as u32, NumEncoding::Fixed, Endianness::BigEndian)
+            }
+            _ => {
+                self.write_byte(major | 27)?;
+                self.write_u64_with(argument, NumEncoding::Fixed, Endianness::BigEndian)
+            }
+        }
+    }
+
+    pub(crate) fn write_cbor_int(&mut self, value: Int) -> EncodingResult<()> {
+        let (major, tag, magnitude) = match value {
+            Int::Unsigned(x) => (Major::Unsigned, POSITIVE_BIGNUM, x),
+            // -1 - x, which can't overflow
+            Int::Negative(x) => (Major::Negative, NEGATIVE_BIGNUM, !x as u128),
+        };
+
+        if let Ok(argument) = u64::try_from(magnitude) {
+            self.write_cbor_head(major, argument)
+        } else {
+            let bytes = magnitude.to_be_bytes();
+            let skip = magnitude.leading_zeros() as usize / 8;
+            self.write_cbor_head(Major::Tag, tag)?;
+            self.write_cbor_head(Major::Bytes, (bytes.len() - skip) as u64)?;
+            self.write_bytes(&bytes[skip..])
+        }
+    }
+
+    pub(crate) fn write_cbor_bool(&mut self, value: bool) -> EncodingResult<()> {
+        self.write_byte(if value { TRUE } else { FALSE })
+    }
+
+    pub(crate) fn write_cbor_f32(&mut self, value: f32) -> EncodingResult<()> {
+        self.write_byte(FLOAT32)?;
+        self.write_u32_with(value.to_bits(), NumEncoding::Fixed, Endianness::BigEndian)
+    }
+
+    pub(crate) fn write_cbor_f64(&mut self, value: f64) -> EncodingResult<()> {
+        self.write_byte(FLOAT64)?;
+        self.write_u64_with(value.to_bits(), NumEncoding::Fixed, Endianness::BigEndian)
+    }
+
+    pub(crate) fn write_cbor_str_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_cbor_head(Major::Text, len as u64)
+    }
+
+    pub(crate) fn write_cbor_seq_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_cbor_head(Major::Array, len as u64)
+    }
+
+    pub(crate) fn write_cbor_map_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_cbor_head(Major::Map, len as u64)
+    }
+
+    pub(crate) fn write_cbor_null(&mut self) -> EncodingResult<()> {
+        self.write_byte(NULL)
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    /// Reads the head of a CBOR data item, returning its major type and argument.
+    ///
+    /// This is the counterpart of [`write_cbor_head`][`Self::write_cbor_head`].
+    pub fn read_cbor_head(&mut self) -> EncodingResult<(Major, u64)> {
+        let initial = self.read_byte()?;
+        let argument = self.read_cbor_argument(initial)?;
+        Ok((Major::from_initial(initial), argument))
+    }
+
+    // Reads the argument following the initial byte of an item
+    fn read_cbor_argument(&mut self, initial: u8) -> EncodingResult<u64> {
+        Ok(match initial & 0x1F {
+            info @ 0..=23 => info as u64,
+            24 => self.read_byte()? as u64,
+            25 => self.read_u16_with(NumEncoding::Fixed, Endianness::BigEndian)? as u64,
+            26 => self.read_u32_with(NumEncoding::Fixed, Endianness::BigEndian)? as u64,
+            27 => self.read_u64_with(NumEncoding::Fixed, Endianness::BigEndian)?,
+            31 => return Err(FormatError::IndefiniteLength.into()),
+            _ => return Err(FormatError::InvalidHead(initial).into()),
+        })
+    }
+
+    // Reads a head of the given major type, returning its argument
+    fn expect_cbor_head(&mut self, expected: Major, name: &'static str) -> EncodingResult<u64> {
+        let initial = self.read_byte()?;
+        if Major::from_initial(initial) != expected {
+            return Err(unexpected(initial, name).into());
+        }
+        self.read_cbor_argument(initial)
+    }
+
+    pub(crate) fn read_cbor_int(&mut self) -> EncodingResult<Int> {
+        let initial = self.read_byte()?;
+        let argument = self.read_cbor_argument(initial)?;
+        match Major::from_initial(initial) {
+            Major::Unsigned => Ok(Int::Unsigned(argument as u128)),
+            Major::Negative => Ok(Int::Negative(-1 - argument as i128)),
+            Major::Tag if argument == POSITIVE_BIGNUM || argument == NEGATIVE_BIGNUM => {
+                let len = self.expect_cbor_head(Major::Bytes, "a bignum")?;
+
+                let mut magnitude: u128 = 0;
+                for _ in 0..len {
+                    let byte = self.read_byte()?;
+                    if magnitude.leading_zeros() < 8 {
+                        return Err(FormatError::IntegerOverflow.into());
+                    }
+                    magnitude = magnitude << 8 | byte as u128;
+                }
+
+                if argument == POSITIVE_BIGNUM {
+                    Ok(Int::Unsigned(magnitude))
+                } else {
+                    let magnitude =
+                        i128::try_from(magnitude).map_err(|_| FormatError::IntegerOverflow)?;
+                    Ok(Int::Negative(-1 - magnitude))
+                }
+            }
+            _ => Err(unexpected(initial, "an integer").into()),
+        }
+    }
+
+    pub(crate) fn read_cbor_bool(&mut self) -> EncodingResult<bool> {
+        match self.read_byte()? {
+            FALSE => Ok(false),
+            TRUE => Ok(true),
+            initial => Err(unexpected(initial, "a boolean").into()),
+        }
+    }
+
+    pub(crate) fn read_cbor_f32(&mut self) -> EncodingResult<f32> {
+        match self.read_byte()? {
+            FLOAT16 => Ok(f16_to_f32(self.read_u16_with(NumEncoding::Fixed, Endianness::BigEndian)?)),
+            FLOAT32 => Ok(f32::from_bits(self.read_u32_with(NumEncoding::Fixed, Endianness::BigEndian)?)),
+            initial => Err(unexpected(initial, "a single precision float").into()),
+        }
+    }
+
+    pub(crate) fn read_cbor_f64(&mut self) -> EncodingResult<f64> {
+        match self.read_byte()? {
+            FLOAT16 => Ok(f16_to_f32(self.read_u16_with(NumEncoding::Fixed, Endianness::BigEndian)?) as f64),
+            FLOAT32 => Ok(f32::from_bits(self.read_u32_with(NumEncoding::Fixed, Endianness::BigEndian)?) as f64),
+            FLOAT64 => Ok(f64::from_bits(self.read_u64_with(NumEncoding::Fixed, Endianness::BigEndian)?)),
+            initial => Err(unexpected(initial, "a float").into()),
+        }
+    }
+
+    pub(crate) fn read_cbor_str_len(&mut self) -> EncodingResult<usize> {
+        let len = self.expect_cbor_head(Major::Text, "a text string")?;
+        usize::try_from(Int::from(len))
+    }
+
+    pub(crate) fn read_cbor_seq_len(&mut self) -> EncodingResult<usize> {
+        let len = self.expect_cbor_head(Major::Array, "an array")?;
+        usize::try_from(Int::from(len))
+    }
+
+    pub(crate) fn read_cbor_map_len(&mut self) -> EncodingResult<usize> {
+        let len = self.expect_cbor_head(Major::Map, "a map")?;
+        usize::try_from(Int::from(len))
+    }
+
+    pub(crate) fn read_cbor_null(&mut self) -> EncodingResult<()> {
+        match self.read_byte()? {
+            NULL => Ok(()),
+            initial => Err(unexpected(initial, "null").into()),
+        }
+    }
+
+    /// Reads either null or the header of an array of one item, returning whether
+    /// it was the latter
+    pub(crate) fn read_cbor_option(&mut self) -> EncodingResult<bool> {
+        let initial = self.read_byte()?;
+        if initial == NULL {
+            return Ok(false);
+        }
+        if Major::from_initial(initial) != Major::Array || self.read_cbor_argument(initial)? != 1 {
+            return Err(unexpected(initial, "null or an array of one item").into());
+        }
+        Ok(true)
+    }
+}
+
+// The sizes of the items written above, used to compute encoded sizes without encoding
+
+/// The size of a head with the given argument
+pub(crate) const fn cbor_head_size(argument: u64) -> usize {
+    match argument {
+        0..=23 => 1,
+        24..=0xFF => 2,
+        0x100..=0xFFFF => 3,
+        0x1_0000..=0xFFFF_FFFF => 5,
+        _ => 9,
+    }
+}
+
+pub(crate) fn cbor_int_size(value: Int) -> usize {
+    let magnitude = match value {
+        Int::Unsigned(x) => x,
+        Int::Negative(x) => !x as u128,
+    };
+
+    if let Ok(argument) = u64::try_from(magnitude) {
+        cbor_head_size(argument)
+    } else {
+        // The tag and the byte string head both fit in a single byte
+        let len = 16 - magnitude.leading_zeros() as usize / 8;
+        2 + len
+    }
+}
@@ -49,6 +49,132 @@ pub fn decode_bytes_with<'a, R: AsRef<[u8]>, V: Decode<Slice<'a>>>(bytes: &'a R,
 	V::decode(&mut decoder)
 }
 
+#[cfg(feature = "serde")]
+pub(crate) mod serde {
+	use ::serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+	use super::{IntoRead, IntoWrite};
+	use crate::io::{Seek, SeekFrom, Slice, SliceMut};
+	use crate::serde::{BorrowDeserializer, SeekSerializer};
+	use crate::{Context, Encoder, EncodingResult};
+
+	/// Serializes the given value by constructing a serializer on the fly backed by a
+	/// [VecStream][`crate::io::VecStream`], then returning the wrapped vector of bytes.
+	///
+	/// Uses the default [`Context`]
+	///
+	/// # Example
+	///
+	/// ```
+	/// let bytes = ender::serde::to_vec(&(7u8, "seven")).unwrap();
+	/// let (number, name): (u8, &str) = ender::serde::from_slice(&bytes).unwrap();
+	/// assert_eq!((number, name), (7, "seven"));
+	/// ```
+	#[cfg(feature = "alloc")]
+	#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+	#[inline]
+	pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> EncodingResult<alloc::vec::Vec<u8>> {
+		to_vec_with(value, Context::default())
+	}
+
+	/// Serializes the given value by constructing a serializer on the fly backed by a
+	/// [VecStream][`crate::io::VecStream`], then returning the wrapped vector of bytes.
+	///
+	/// Uses the given [`Context`]
+	#[cfg(feature = "alloc")]
+	#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+	#[inline]
+	pub fn to_vec_with<T: ?Sized + Serialize>(value: &T, context: Context) -> EncodingResult<alloc::vec::Vec<u8>> {
+		let stream = crate::io::VecStream::new(alloc::vec::Vec::new(), 0);
+		let mut serializer = SeekSerializer::new(Encoder::new(stream, context));
+		value.serialize(&mut serializer)?;
+		Ok(serializer.into_inner().stream.into_inner())
+	}
+
+	/// Serializes the given value into the given byte slice, returning the part of the slice
+	/// that was written to.
+	///
+	/// Uses the default [`Context`]
+	#[inline]
+	pub fn to_slice<'a, T: ?Sized + Serialize>(value: &T, buf: &'a mut [u8]) -> EncodingResult<&'a mut [u8]> {
+		to_slice_with(value, buf, Context::default())
+	}
+
+	/// Serializes the given value into the given byte slice, returning the part of the slice
+	/// that was written to.
+	///
+	/// Uses the given [`Context`]
+	#[inline]
+	pub fn to_slice_with<'a, T: ?Sized + Serialize>(
+		value: &T,
+		buf: &'a mut [u8],
+		context: Context,
+	) -> EncodingResult<&'a mut [u8]> {
+		let mut serializer = SeekSerializer::new(Encoder::new(SliceMut::new(buf), context));
+		value.serialize(&mut serializer)?;
+		let mut stream = serializer.into_inner().stream;
+		let len = stream.seek(SeekFrom::POSITION)?;
+		Ok(&mut stream.into_inner()[..len])
+	}
+
+	/// Serializes the given value by constructing a serializer on the fly and using it to wrap
+	/// the writer, with the default context.
+	#[inline]
+	pub fn to_writer<W: IntoWrite, T: ?Sized + Serialize>(writer: W, value: &T) -> EncodingResult<()> {
+		to_writer_with(writer, Context::default(), value)
+	}
+
+	/// Serializes the given value by constructing a serializer on the fly and using it to wrap
+	/// the writer, with the given context.
+	#[inline]
+	pub fn to_writer_with<W: IntoWrite, T: ?Sized + Serialize>(
+		writer: W,
+		context: Context,
+		value: &T,
+	) -> EncodingResult<()> {
+		let mut encoder = Encoder::new(writer.into_write(), context);
+		value.serialize(&mut encoder)
+	}
+
+	/// Deserializes the given value by constructing a deserializer on the fly and using it
+	/// to wrap a byte slice.<br>
+	/// Strings and byte slices are borrowed from the slice whenever possible,
+	/// see [`BorrowDeserializer`].
+	///
+	/// Uses the default [`Context`]
+	#[inline]
+	pub fn from_slice<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> EncodingResult<T> {
+		from_slice_with(bytes, Context::default())
+	}
+
+	/// Deserializes the given value by constructing a deserializer on the fly and using it
+	/// to wrap a byte slice.<br>
+	/// Strings and byte slices are borrowed from the slice whenever possible,
+	/// see [`BorrowDeserializer`].
+	///
+	/// Uses the given [`Context`]
+	#[inline]
+	pub fn from_slice_with<'de, T: Deserialize<'de>>(bytes: &'de [u8], context: Context) -> EncodingResult<T> {
+		let mut deserializer = BorrowDeserializer::new(Encoder::new(Slice::new(bytes), context));
+		T::deserialize(&mut deserializer)
+	}
+
+	/// Deserializes the given value by constructing a deserializer on the fly and using it
+	/// to wrap the reader, with the default context.
+	#[inline]
+	pub fn from_reader<R: IntoRead, T: DeserializeOwned>(reader: R) -> EncodingResult<T> {
+		from_reader_with(reader, Context::default())
+	}
+
+	/// Deserializes the given value by constructing a deserializer on the fly and using it
+	/// to wrap the reader, with the given context.
+	#[inline]
+	pub fn from_reader_with<R: IntoRead, T: DeserializeOwned>(reader: R, context: Context) -> EncodingResult<T> {
+		let mut decoder = Encoder::new(reader.into_read(), context);
+		T::deserialize(&mut decoder)
+	}
+}
+
 /// Encodes the given value by constructing an encoder on the fly and using it to wrap the writer,
 /// with the default context.
 #[inline]
@@ -1,5 +1,7 @@
 use crate::source::Stack;
-use crate::{BitWidth, Endianness, NumEncoding, Opaque, StrEncoding};
+use crate::asn1::Tag;
+use crate::protobuf::WireType;
+use crate::{BitWidth, Endianness, Format, NumEncoding, Opaque, StrEncoding};
 use core::fmt;
 use core::fmt::Formatter;
 use embedded_io::{Error, ErrorKind, ReadExactError};
@@ -26,6 +28,67 @@ pub enum Signedness {
     Unsigned,
 }
 
+/// The [`Debug`][`fmt::Debug`] representation of an enum variant tag of arbitrary type,
+/// truncated to [`TagValue::CAPACITY`] bytes so that it can be stored without allocating.
+///
+/// This struct is provided for diagnostic purposes in [`EncodingError::InvalidTag`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TagValue {
+    buf: [u8; Self::CAPACITY],
+    len: usize,
+}
+
+impl TagValue {
+    /// The maximum length in bytes of the stored representation
+    pub const CAPACITY: usize = 32;
+
+    /// Captures the [`Debug`][`fmt::Debug`] representation of the given tag
+    pub fn new<T: fmt::Debug + ?Sized>(tag: &T) -> Self {
+        let mut value = Self {
+            buf: [0; Self::CAPACITY],
+            len: 0,
+        };
+        // Running out of space is the only way this can fail, and the truncated value is kept
+        let _ = fmt::write(&mut value, format_args!("{:?}", tag));
+        value
+    }
+
+    /// Returns the stored representation of the tag
+    pub fn as_str(&self) -> &str {
+        // Only whole chars are ever written to the buffer
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for TagValue {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let space = Self::CAPACITY - self.len;
+        let mut end = s.len().min(space);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.buf[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        if end < s.len() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for TagValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for TagValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Represents any kind of error that can happen during encoding and decoding
 #[derive(Debug, Display)]
 #[non_exhaustive]
@@ -51,15 +114,48 @@ pub enum EncodingError {
     /// Tried to write or read a `usize` greater than the max
     #[display("A size of {requested} exceeded the max allowed value of {max}")]
     MaxSizeExceeded { max: usize, requested: usize },
+    /// Tried to allocate more memory than what is left of the allocation budget
+    /// of the [`Context`][`crate::Context`] while decoding
+    #[display("An allocation of {requested} bytes exceeded the remaining budget of {remaining} bytes")]
+    AllocBudgetExceeded { remaining: usize, requested: usize },
+    /// The values being decoded were nested deeper than the max depth
+    /// of the [`Context`][`crate::Context`]
+    #[display("The max nesting depth of {max} was exceeded")]
+    MaxDepthExceeded { max: usize },
     /// Tried to decode an unrecognized enum variant
     #[display("Unrecognized enum variant ({0})")]
     InvalidVariant(Opaque),
+    /// Tried to decode an enum variant tag that doesn't match any of the variants
+    #[display("Unrecognized enum variant tag ({0})")]
+    InvalidTag(TagValue),
     /// Tried to squeeze a value into fewer bits than what is required to fully represent it.
     #[display(r#"A value of "{value}" is too large to fit in {requested_width}"#)]
     TooLarge {
         value: Opaque,
         requested_width: BitWidth,
     },
+    /// A magic number (or any other constant) read from the stream didn't match the expected one.
+    /// `actual` is the first byte that differed, found `offset` bytes into the magic number
+    #[display("Magic number mismatch: expected {expected:02X?}, got {actual:#04X} at offset {offset}")]
+    MagicMismatch {
+        expected: &'static [u8],
+        offset: usize,
+        actual: u8,
+    },
+    /// The checksum stored in the stream didn't match the one computed over the data
+    #[display("Checksum mismatch: computed {computed}, stored {stored}")]
+    ChecksumMismatch { computed: Opaque, stored: Opaque },
+    /// A padding byte didn't have the expected value
+    #[display("Invalid padding: expected {expected:#04X}, got {actual:#04X}")]
+    InvalidPadding { expected: u8, actual: u8 },
+    /// A byte-counted region wasn't fully read while decoding, and trailing bytes are rejected
+    /// by the [`Trailing`][`crate::Trailing`] setting
+    #[display("{remaining} unread trailing bytes at the end of a byte-counted region")]
+    TrailingBytes { remaining: usize },
+    /// Tried to squeeze a value into a bit field narrower than what is required to fully represent it,
+    /// or a bit field was too wide for its type.
+    #[display(r#"A value of "{value}" doesn't fit in a {bits}-bit field"#)]
+    BitFieldOverflow { value: Opaque, bits: u32 },
     /// Expected to get a value with a specific signedness, but got one with the opposite.
     /// E.G. Expected an `u8` but got an `i8`.
     #[display("Expected {expected} value, got {got} value instead")]
@@ -71,6 +167,9 @@ pub enum EncodingError {
     /// Example: `#[ender(flatten: some)]` applied on an `Option` containing the `None` variant
     #[display("Flatten error: {0}")]
     FlattenError(FlattenError),
+    /// A sequence was streamed with a different number of items than its declared length
+    #[display("Sequence length mismatch: declared {expected} items, got {got}")]
+    SeqLenMismatch { expected: usize, got: usize },
     /// An attempt was made to lock a RefCell/Mutex/RwLock or similar, but it failed.
     #[display("Lock error: couldn't lock a RefCell/Mutex/RwLock or similar")]
     LockError,
@@ -84,6 +183,15 @@ pub enum EncodingError {
         #[cfg(feature = "alloc")] alloc::string::String,
         #[cfg(not(feature = "alloc"))] &'static str,
     ),
+    /// A protobuf message was malformed, or contained a record incompatible with its field
+    #[display("Protobuf error: {0}")]
+    ProtobufError(ProtobufError),
+    /// An ASN.1 value was malformed, or didn't follow the encoding rules in effect
+    #[display("ASN.1 error: {0}")]
+    Asn1Error(Asn1Error),
+    /// A CBOR or MessagePack item was malformed, or of an unexpected type
+    #[display("Format error: {0}")]
+    FormatError(FormatError),
     /// A generic serde error occurred
     #[cfg(all(feature = "serde", feature = "alloc"))]
     #[cfg_attr(feature = "unstable", doc(cfg(feature = "serde")))]
@@ -127,6 +235,10 @@ impl EncodingError {
     {
         Self::InvalidVariant(Opaque::from(v))
     }
+
+    pub fn invalid_tag<T: fmt::Debug + ?Sized>(tag: &T) -> Self {
+        Self::InvalidTag(TagValue::new(tag))
+    }
 }
 
 impl Error for EncodingError {
@@ -191,6 +303,24 @@ impl From<BorrowError> for EncodingError {
     }
 }
 
+impl From<ProtobufError> for EncodingError {
+    fn from(value: ProtobufError) -> Self {
+        Self::ProtobufError(value)
+    }
+}
+
+impl From<Asn1Error> for EncodingError {
+    fn from(value: Asn1Error) -> Self {
+        Self::Asn1Error(value)
+    }
+}
+
+impl From<FormatError> for EncodingError {
+    fn from(value: FormatError) -> Self {
+        Self::FormatError(value)
+    }
+}
+
 impl From<SeekError> for EncodingError {
     fn from(value: SeekError) -> Self {
         Self::SeekError(value)
@@ -253,9 +383,15 @@ pub enum BorrowError {
     #[display("Non-borrowable numerical encoding: {num_encoding} can't be directly borrowed")]
     NonBorrowableNumEncoding { num_encoding: NumEncoding },
     #[display(
-        "Alignment mismatch: borrowing this data requires its alignment to match the system's"
+        "Alignment mismatch: borrowing {ty} requires an alignment of {required} bytes, but the data is {offset} bytes past an aligned address"
     )]
-    AlignmentMismatch,
+    AlignmentMismatch {
+        ty: &'static str,
+        required: usize,
+        offset: usize,
+    },
+    #[display("Non-borrowable format: {format} data can't be directly borrowed")]
+    NonBorrowableFormat { format: Format },
 }
 
 impl_error!(BorrowError);
@@ -273,6 +409,76 @@ pub enum SeekError {
 
 impl_error!(SeekError);
 
+/// Represents an error occurred while decoding a protobuf message
+#[derive(Debug, Display)]
+#[non_exhaustive]
+pub enum ProtobufError {
+    /// A record had a wire type that is unknown or unsupported (such as groups)
+    #[display("Invalid or unsupported wire type: {0}")]
+    InvalidWireType(u8),
+    /// A record had a field number of zero, or greater than the maximum of `2^29 - 1`
+    #[display("Invalid field number: {0}")]
+    InvalidFieldNumber(u64),
+    /// A known field was found with a wire type that doesn't match its type
+    #[display("Wire type mismatch: expected {expected}, found {found}")]
+    WireTypeMismatch { expected: WireType, found: WireType },
+}
+
+impl_error!(ProtobufError);
+
+/// Represents an error occurred while decoding an ASN.1 value
+#[derive(Debug, Display)]
+#[non_exhaustive]
+pub enum Asn1Error {
+    /// A value was found with a tag other than the expected one
+    #[display("Unexpected tag: expected {expected}, found {found}")]
+    UnexpectedTag { expected: Tag, found: Tag },
+    /// A tag was malformed, or its number exceeded the maximum of `2^32 - 1`
+    #[display("Malformed tag")]
+    InvalidTag,
+    /// A length used the indefinite form, which is not supported
+    #[display("Indefinite lengths are not supported")]
+    IndefiniteLength,
+    /// A value was not encoded in its canonical form, as required by DER
+    #[display("Value is not in DER canonical form")]
+    NonCanonical,
+    /// The content of a value was invalid for its type. E.G. an empty INTEGER
+    #[display("Invalid content for a value of type {0}")]
+    InvalidContent(&'static str),
+}
+
+impl_error!(Asn1Error);
+
+/// Represents an error occurred while decoding an item of a self-describing [`Format`]
+#[derive(Debug, Display)]
+#[non_exhaustive]
+pub enum FormatError {
+    /// An item was found where one of a different type was expected
+    #[display("Unexpected {format} item: expected {expected}, found initial byte {found}")]
+    UnexpectedItem {
+        format: Format,
+        expected: &'static str,
+        found: u8,
+    },
+    /// The initial byte of an item is reserved, or otherwise malformed
+    #[display("Malformed initial byte: {0}")]
+    InvalidHead(u8),
+    /// An item used the indefinite-length encoding, which is not supported
+    #[display("Indefinite-length items are not supported")]
+    IndefiniteLength,
+    /// An integer was too large to be represented in 128 bits
+    #[display("Integer overflows 128 bits")]
+    IntegerOverflow,
+    /// A struct, tuple or enum was encoded as an array with the wrong number of items
+    #[display("Expected an array of {expected} items, found {found}")]
+    LengthMismatch { expected: usize, found: usize },
+    /// A format other than [`Format::Binary`] was used with the asynchronous API
+    #[display("The {0} format is not supported by the asynchronous API")]
+    AsyncUnsupported(Format),
+}
+
+impl_error!(FormatError);
+
 /// An [`EncodingError`] which also displays all the error stack.
 /// This is useful for debugging, because the entire structure tree is displayed.
 #[derive(Debug)]
@@ -320,3 +526,4 @@ impl TaggedError {
 
 /// A convenience alias to `Result<T, EncodingError>`
 pub type EncodingResult<T> = Result<T, EncodingError>;
+
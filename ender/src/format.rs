@@ -0,0 +1,385 @@
+use crate::cbor::{cbor_head_size, cbor_int_size};
+use crate::io::{Read, SizeLimit, Write};
+use crate::msgpack::{msgpack_int_size, msgpack_map_len_size, msgpack_seq_len_size, msgpack_str_len_size};
+use crate::{
+    BitWidth, Encoder, EncodingError, EncodingResult, FlattenError, Format, Opaque, Signedness,
+    StrEncoding,
+};
+
+/// An integer of any width and sign, as it is represented by the self-describing formats,
+/// which don't distinguish between signed and unsigned types.
+///
+/// `Negative` always holds a value less than zero.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Int {
+    Unsigned(u128),
+    Negative(i128),
+}
+
+impl Int {
+    /// Converts an opaque value, such as an enum variant
+    pub(crate) fn from_opaque(value: Opaque) -> EncodingResult<Self> {
+        Ok(match value.sign() {
+            Signedness::Unsigned => Self::Unsigned(value.try_into()?),
+            Signedness::Signed => Self::from(TryInto::<i128>::try_into(value)?),
+        })
+    }
+
+    /// Converts the integer to an opaque value of the requested signedness
+    pub(crate) fn into_opaque(self, sign: Signedness) -> EncodingResult<Opaque> {
+        Ok(match sign {
+            Signedness::Unsigned => Opaque::from(u128::try_from(self)?),
+            Signedness::Signed => Opaque::from(i128::try_from(self)?),
+        })
+    }
+}
+
+macro_rules! int_impl {
+    (unsigned $($($width:tt)::* => $ty:ty);* $(;)?) => {
+	    $(
+	    impl From<$ty> for Int {
+		    #[inline]
+		    fn from(value: $ty) -> Self {
+			    Self::Unsigned(value as u128)
+		    }
+	    }
+	    int_impl!(@try_from $($width)::* => $ty);
+	    )*
+    };
+    (signed $($($width:tt)::* => $ty:ty);* $(;)?) => {
+	    $(
+	    impl From<$ty> for Int {
+		    #[inline]
+		    fn from(value: $ty) -> Self {
+			    if value < 0 {
+				    Self::Negative(value as i128)
+			    } else {
+				    Self::Unsigned(value as u128)
+			    }
+		    }
+	    }
+	    int_impl!(@try_from $($width)::* => $ty);
+	    )*
+    };
+    (@try_from $($width:tt)::* => $ty:ty) => {
+	    impl TryFrom<Int> for $ty {
+		    type Error = EncodingError;
+		    #[inline]
+		    fn try_from(value: Int) -> Result<Self, Self::Error> {
+			    match value {
+				    Int::Unsigned(x) => <$ty>::try_from(x).map_err(|_| EncodingError::TooLarge {
+					    value: Opaque::from(x),
+					    requested_width: BitWidth::$($width)*,
+				    }),
+				    Int::Negative(x) => <$ty>::try_from(x).map_err(|_| EncodingError::TooLarge {
+					    value: Opaque::from(x),
+					    requested_width: BitWidth::$($width)*,
+				    }),
+			    }
+		    }
+	    }
+    };
+}
+
+int_impl! {
+    unsigned
+    Bit8 => u8;
+    Bit16 => u16;
+    Bit32 => u32;
+    Bit64 => u64;
+    Bit128 => u128;
+    native::() => usize;
+}
+
+int_impl! {
+    signed
+    Bit8 => i8;
+    Bit16 => i16;
+    Bit32 => i32;
+    Bit64 => i64;
+    Bit128 => i128;
+    native::() => isize;
+}
+
+impl<T> Encoder<'_, T> {
+    /// Checks that a length doesn't exceed the max size, then returns it
+    pub(crate) fn check_max_size(&self, len: usize) -> EncodingResult<usize> {
+        let max = self.ctxt.settings.size_repr.max_size;
+        if len > max {
+            return Err(EncodingError::MaxSizeExceeded {
+                max,
+                requested: len,
+            });
+        }
+        Ok(len)
+    }
+}
+
+// These functions compute the sizes of the items written by the functions below,
+// and must also never be called with `Format::Binary`
+
+impl Format {
+    pub(crate) fn int_size(self, value: Int) -> EncodingResult<usize> {
+        match self {
+            Format::Cbor => Ok(cbor_int_size(value)),
+            Format::MessagePack => msgpack_int_size(value),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    /// The size of a `bool`, which is a single byte in every format
+    pub(crate) const fn bool_size(self) -> usize {
+        1
+    }
+
+    /// The size of null, which is a single byte in every format
+    pub(crate) const fn null_size(self) -> usize {
+        1
+    }
+
+    /// The size of an `f32`: a marker followed by the value
+    pub(crate) const fn f32_size(self) -> usize {
+        5
+    }
+
+    /// The size of an `f64`: a marker followed by the value
+    pub(crate) const fn f64_size(self) -> usize {
+        9
+    }
+
+    /// The size of the header of a string of `len` UTF-8 bytes
+    pub(crate) fn str_len_size(self, len: usize) -> EncodingResult<usize> {
+        match self {
+            Format::Cbor => Ok(cbor_head_size(len as u64)),
+            Format::MessagePack => msgpack_str_len_size(len),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn seq_len_size(self, len: usize) -> EncodingResult<usize> {
+        match self {
+            Format::Cbor => Ok(cbor_head_size(len as u64)),
+            Format::MessagePack => msgpack_seq_len_size(len),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn map_len_size(self, len: usize) -> EncodingResult<usize> {
+        match self {
+            Format::Cbor => Ok(cbor_head_size(len as u64)),
+            Format::MessagePack => msgpack_map_len_size(len),
+            Format::Binary => unreachable!(),
+        }
+    }
+}
+
+// These functions dispatch the primitives of the encoder to the backend of the
+// self-describing format in use. They must never be called with `Format::Binary`,
+// which is handled by the primitives themselves.
+
+impl<T: Write> Encoder<'_, T> {
+    pub(crate) fn write_fmt_int(&mut self, value: Int) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_int(value),
+            Format::MessagePack => self.write_msgpack_int(value),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn write_fmt_bool(&mut self, value: bool) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_bool(value),
+            Format::MessagePack => self.write_msgpack_bool(value),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn write_fmt_f32(&mut self, value: f32) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_f32(value),
+            Format::MessagePack => self.write_msgpack_f32(value),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn write_fmt_f64(&mut self, value: f64) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_f64(value),
+            Format::MessagePack => self.write_msgpack_f64(value),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    /// Writes the header of a string of `len` UTF-8 bytes
+    pub(crate) fn write_fmt_str_len(&mut self, len: usize) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_str_len(len),
+            Format::MessagePack => self.write_msgpack_str_len(len),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn write_fmt_seq_len(&mut self, len: usize) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_seq_len(len),
+            Format::MessagePack => self.write_msgpack_seq_len(len),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn write_fmt_map_len(&mut self, len: usize) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_map_len(len),
+            Format::MessagePack => self.write_msgpack_map_len(len),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn write_fmt_null(&mut self) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.write_cbor_null(),
+            Format::MessagePack => self.write_msgpack_nil(),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    /// Writes what precedes the value of an `Option`: null if there is none, or the header
+    /// of an array holding it otherwise. The `bool` flatten variable is only checked,
+    /// because the absence of the value must always be visible in the data.
+    pub(crate) fn write_fmt_option(&mut self, present: bool) -> EncodingResult<()> {
+        if let Some(expected) = self.ctxt.consume_bool_flatten() {
+            if expected != present {
+                return Err(FlattenError::BoolMismatch {
+                    expected,
+                    got: present,
+                }
+                .into());
+            }
+        }
+
+        if present {
+            self.write_fmt_seq_len(1)
+        } else {
+            self.write_fmt_null()
+        }
+    }
+
+    /// Writes a string as its header followed by its UTF-8 bytes
+    pub(crate) fn write_fmt_str<I>(&mut self, chars: I) -> EncodingResult<()>
+    where
+        I: Iterator<Item = char> + Clone,
+    {
+        let len = chars.clone().map(char::len_utf8).sum();
+        self.write_fmt_str_len(len)?;
+
+        let mut buf = [0u8; 4];
+        for ch in chars {
+            self.write_bytes(ch.encode_utf8(&mut buf).as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Read> Encoder<'a, T> {
+    pub(crate) fn read_fmt_int(&mut self) -> EncodingResult<Int> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_int(),
+            Format::MessagePack => self.read_msgpack_int(),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn read_fmt_bool(&mut self) -> EncodingResult<bool> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_bool(),
+            Format::MessagePack => self.read_msgpack_bool(),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn read_fmt_f32(&mut self) -> EncodingResult<f32> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_f32(),
+            Format::MessagePack => self.read_msgpack_f32(),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    pub(crate) fn read_fmt_f64(&mut self) -> EncodingResult<f64> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_f64(),
+            Format::MessagePack => self.read_msgpack_f64(),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    /// Reads the header of a string, returning its length in UTF-8 bytes
+    pub(crate) fn read_fmt_str_len(&mut self) -> EncodingResult<usize> {
+        let len = match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_str_len(),
+            Format::MessagePack => self.read_msgpack_str_len(),
+            Format::Binary => unreachable!(),
+        }?;
+        self.check_max_size(len)
+    }
+
+    pub(crate) fn read_fmt_seq_len(&mut self) -> EncodingResult<usize> {
+        let len = match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_seq_len(),
+            Format::MessagePack => self.read_msgpack_seq_len(),
+            Format::Binary => unreachable!(),
+        }?;
+        self.check_max_size(len)
+    }
+
+    pub(crate) fn read_fmt_map_len(&mut self) -> EncodingResult<usize> {
+        let len = match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_map_len(),
+            Format::MessagePack => self.read_msgpack_map_len(),
+            Format::Binary => unreachable!(),
+        }?;
+        self.check_max_size(len)
+    }
+
+    pub(crate) fn read_fmt_null(&mut self) -> EncodingResult<()> {
+        match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_null(),
+            Format::MessagePack => self.read_msgpack_nil(),
+            Format::Binary => unreachable!(),
+        }
+    }
+
+    /// Reads what precedes the value of an `Option`, returning whether the value is present.
+    ///
+    /// This is the counterpart of [`write_fmt_option`][`Self::write_fmt_option`].
+    pub(crate) fn read_fmt_option(&mut self) -> EncodingResult<bool> {
+        let expected = self.ctxt.consume_bool_flatten();
+        let present = match self.ctxt.settings.format {
+            Format::Cbor => self.read_cbor_option(),
+            Format::MessagePack => self.read_msgpack_option(),
+            Format::Binary => unreachable!(),
+        }?;
+
+        match expected {
+            Some(expected) if expected != present => Err(FlattenError::BoolMismatch {
+                expected,
+                got: present,
+            }
+            .into()),
+            _ => Ok(present),
+        }
+    }
+
+    /// Reads a string as its header followed by its UTF-8 bytes,
+    /// returning the encoder limited to the string data
+    pub(crate) fn read_fmt_str(&mut self) -> EncodingResult<Encoder<'a, SizeLimit<&mut T>>> {
+        let len = self.read_fmt_str_len()?;
+        self.charge_alloc::<u8>(len)?;
+
+        // The string data is decoded by the binary format
+        let mut ctxt = self.ctxt;
+        ctxt.settings.format = Format::Binary;
+        ctxt.settings.string_repr.encoding = StrEncoding::Utf8;
+        Ok(Encoder::new(SizeLimit::new(&mut self.stream, 0, len), ctxt))
+    }
+}
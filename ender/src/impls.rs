@@ -1,3 +1,4 @@
+use core::any::TypeId;
 use core::cell::{Cell, RefCell};
 use core::ffi::CStr;
 use core::marker::PhantomData;
@@ -7,8 +8,8 @@ use core::time::Duration;
 
 use crate::io::{BorrowRead, Read, Seek, Write};
 use crate::{
-    BorrowError, Decode, Encode, Encoder, EncodingError, EncodingResult, NumEncoding,
-    StrEncoding, StringError,
+    BorrowError, Decode, Encode, Encoder, EncodingError, EncodingResult, Endianness, Format,
+    NumEncoding, StrEncoding, StringError,
 };
 
 // Primitives
@@ -27,28 +28,76 @@ macro_rules! impl_encode {
 }
 
 impl_encode! {
-    u8 => write_u8;
-    u16 => write_u16;
-    u32 => write_u32;
-    u64 => write_u64;
-    u128 => write_u128;
-    i8 => write_i8;
-    i16 => write_i16;
-    i32 => write_i32;
-    i64 => write_i64;
-    i128 => write_i128;
     bool => write_bool;
     char => write_char;
-    f32 => write_f32;
-    f64 => write_f64;
     usize => write_usize;
     isize => write_isize;
 }
 
+/// Returns the endianness of a slice of numbers of the given kind and size if the binary
+/// format lays them out as-is, meaning they can be encoded and decoded in bulk
+#[inline]
+pub(crate) fn bulk_endianness<T>(
+    encoder: &Encoder<T>,
+    float: bool,
+    size: usize,
+) -> Option<Endianness> {
+    let settings = &encoder.ctxt.settings;
+    if settings.format != Format::Binary {
+        return None;
+    }
+
+    // Floats ignore the numerical encoding, while bincode and postcard write bytes as-is
+    if float || settings.num_repr.num_encoding.borrowable_with_size(size) {
+        Some(settings.num_repr.endianness)
+    } else {
+        None
+    }
+}
+
+macro_rules! impl_encode_bulk {
+    ($($ty:ty => $write:ident, $float:literal);* $(;)? ) => {
+	    $(
+	    impl<W: $crate::io::Write> $crate::Encode<W> for $ty {
+		    #[inline]
+            fn encode(&self, encoder: &mut $crate::Encoder<W>) -> $crate::EncodingResult<()> {
+		        encoder.$write(*self)
+		    }
+
+		    #[inline]
+		    fn encode_slice(data: &[Self], encoder: &mut $crate::Encoder<W>) -> $crate::EncodingResult<()> {
+			    if let Some(endianness) = bulk_endianness(encoder, $float, core::mem::size_of::<$ty>()) {
+				    return encoder.write_pod_slice(data, endianness);
+			    }
+			    for (i, v) in data.iter().enumerate() {
+				    encoder.with_index(|encoder| v.encode(encoder), i)?;
+			    }
+			    Ok(())
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_encode_bulk! {
+    u8 => write_u8, false;
+    u16 => write_u16, false;
+    u32 => write_u32, false;
+    u64 => write_u64, false;
+    u128 => write_u128, false;
+    i8 => write_i8, false;
+    i16 => write_i16, false;
+    i32 => write_i32, false;
+    i64 => write_i64, false;
+    i128 => write_i128, false;
+    f32 => write_f32, true;
+    f64 => write_f64, true;
+}
+
 impl<W: Write + Seek> Encode<W> for () {
     #[inline]
-    fn encode(&self, _encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        Ok(())
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_null()
     }
 }
 
@@ -69,21 +118,16 @@ impl<W: Write, T: ?Sized + Encode<W>> Encode<W> for &mut T {
 impl<W: Write, T: Encode<W>> Encode<W> for [T] {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
-        for i in 0..self.len() {
-            encoder.with_index(|encoder| self[i].encode(encoder), i)?;
-        }
-        Ok(())
+        encoder.write_seq_len(self.len())?;
+        T::encode_slice(self, encoder)
     }
 }
 
 impl<W: Write, T: Encode<W>, const SIZE: usize> Encode<W> for [T; SIZE] {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        for i in 0..SIZE {
-            encoder.with_index(|encoder| self[i].encode(encoder), i)?;
-        }
-        Ok(())
+        encoder.write_fields_len(SIZE)?;
+        T::encode_slice(self, encoder)
     }
 }
 
@@ -94,6 +138,7 @@ macro_rules! tuple_encode {
 		    #[inline]
             fn encode(&self, encoder: &mut $crate::Encoder<__W>) -> $crate::EncodingResult<()> {
 		        let ($($name),*) = self;
+			    encoder.write_fields_len([$(stringify!($name)),+].len())?;
 			    $(
 			        $crate::Encode::encode($name, encoder)?;
 			    )+
@@ -138,12 +183,16 @@ impl<W: Write> Encode<W> for str {
 impl<W: Write, T: Encode<W>> Encode<W> for Option<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        // The self-describing formats write null or an array holding the value,
+        // which keeps nested options apart
+        if encoder.ctxt.settings.format != Format::Binary {
+            encoder.write_fmt_option(self.is_some())?;
+        } else {
+            encoder.write_bool(self.is_some())?;
+        }
         match self {
-            None => encoder.write_bool(false),
-            Some(value) => {
-                encoder.write_bool(true)?;
-                value.encode(encoder)
-            }
+            None => Ok(()),
+            Some(value) => value.encode(encoder),
         }
     }
 }
@@ -151,24 +200,20 @@ impl<W: Write, T: Encode<W>> Encode<W> for Option<T> {
 impl<W: Write, T: Encode<W>, E: Encode<W>> Encode<W> for Result<T, E> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_enum_header()?;
+        encoder.write_bool(self.is_ok())?;
+        encoder.write_fields_len(1)?;
         match self {
-            Err(err) => {
-                encoder.write_bool(false)?;
-                err.encode(encoder)?;
-            }
-            Ok(ok) => {
-                encoder.write_bool(true)?;
-                ok.encode(encoder)?;
-            }
-        };
-        Ok(())
+            Err(err) => err.encode(encoder),
+            Ok(ok) => ok.encode(encoder),
+        }
     }
 }
 
 impl<W: Write, T> Encode<W> for PhantomData<T> {
     #[inline]
-    fn encode(&self, _encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        Ok(())
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_null()
     }
 }
 
@@ -261,7 +306,7 @@ impl<W: Write, T: Encode<W>> Encode<W> for std::sync::RwLock<T> {
 impl<W: Write, K: Encode<W>, V: Encode<W>> Encode<W> for alloc::collections::BTreeMap<K, V> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_map_len(self.len())?;
         for (k, v) in self.iter() {
             k.encode(encoder)?;
             v.encode(encoder)?;
@@ -275,7 +320,7 @@ impl<W: Write, K: Encode<W>, V: Encode<W>> Encode<W> for alloc::collections::BTr
 impl<W: Write, K: Encode<W>> Encode<W> for alloc::collections::BTreeSet<K> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_seq_len(self.len())?;
         for k in self.iter() {
             k.encode(encoder)?;
         }
@@ -288,7 +333,7 @@ impl<W: Write, K: Encode<W>> Encode<W> for alloc::collections::BTreeSet<K> {
 impl<W: Write, K: Encode<W>, V: Encode<W>> Encode<W> for std::collections::hash_map::HashMap<K, V> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_map_len(self.len())?;
         for (k, v) in self.iter() {
             k.encode(encoder)?;
             v.encode(encoder)?;
@@ -302,7 +347,7 @@ impl<W: Write, K: Encode<W>, V: Encode<W>> Encode<W> for std::collections::hash_
 impl<W: Write, K: Encode<W>> Encode<W> for std::collections::hash_set::HashSet<K> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_seq_len(self.len())?;
         for k in self.iter() {
             k.encode(encoder)?;
         }
@@ -315,7 +360,7 @@ impl<W: Write, K: Encode<W>> Encode<W> for std::collections::hash_set::HashSet<K
 impl<W: Write, T: Encode<W>> Encode<W> for alloc::collections::BinaryHeap<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_seq_len(self.len())?;
         for v in self.iter() {
             v.encode(encoder)?;
         }
@@ -328,7 +373,7 @@ impl<W: Write, T: Encode<W>> Encode<W> for alloc::collections::BinaryHeap<T> {
 impl<W: Write, T: Encode<W>> Encode<W> for alloc::collections::LinkedList<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_seq_len(self.len())?;
         for (i, v) in self.iter().enumerate() {
             encoder.with_index(|encoder| v.encode(encoder), i)?;
         }
@@ -341,11 +386,8 @@ impl<W: Write, T: Encode<W>> Encode<W> for alloc::collections::LinkedList<T> {
 impl<W: Write, T: Encode<W>> Encode<W> for alloc::vec::Vec<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
-        for (i, v) in self.iter().enumerate() {
-            encoder.with_index(|encoder| v.encode(encoder), i)?;
-        }
-        Ok(())
+        encoder.write_seq_len(self.len())?;
+        T::encode_slice(self, encoder)
     }
 }
 
@@ -354,7 +396,7 @@ impl<W: Write, T: Encode<W>> Encode<W> for alloc::vec::Vec<T> {
 impl<W: Write, T: Encode<W>> Encode<W> for alloc::collections::VecDeque<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        encoder.write_usize(self.len())?;
+        encoder.write_seq_len(self.len())?;
         for (i, v) in self.iter().enumerate() {
             encoder.with_index(|encoder| v.encode(encoder), i)?;
         }
@@ -407,6 +449,7 @@ impl<W: Write> Encode<W> for std::ffi::OsString {
 impl<W: Write> Encode<W> for Duration {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_fields_len(2)?;
         encoder.write_u64(self.as_secs())?;
         encoder.write_u32(self.subsec_nanos())
     }
@@ -445,6 +488,7 @@ impl<W: Write> Encode<W> for std::path::PathBuf {
 impl<W: Write, T: Encode<W>> Encode<W> for Range<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_fields_len(2)?;
         self.start.encode(encoder)?;
         self.end.encode(encoder)
     }
@@ -453,6 +497,7 @@ impl<W: Write, T: Encode<W>> Encode<W> for Range<T> {
 impl<W: Write, T: Encode<W>> Encode<W> for RangeInclusive<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_fields_len(2)?;
         self.start().encode(encoder)?;
         self.end().encode(encoder)
     }
@@ -474,8 +519,8 @@ impl<W: Write, T: Encode<W>> Encode<W> for RangeTo<T> {
 
 impl<W: Write> Encode<W> for RangeFull {
     #[inline]
-    fn encode(&self, _encoder: &mut Encoder<W>) -> EncodingResult<()> {
-        Ok(())
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_null()
     }
 }
 
@@ -510,16 +555,22 @@ impl_nz_encode! {
 impl<W: Write, T: Encode<W>> Encode<W> for Bound<T> {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_enum_header()?;
         match self {
             Bound::Included(x) => {
                 encoder.write_uvariant(0u8)?;
+                encoder.write_fields_len(1)?;
                 x.encode(encoder)
             }
             Bound::Excluded(x) => {
                 encoder.write_uvariant(1u8)?;
+                encoder.write_fields_len(1)?;
                 x.encode(encoder)
             }
-            Bound::Unbounded => encoder.write_uvariant(2u8),
+            Bound::Unbounded => {
+                encoder.write_uvariant(2u8)?;
+                encoder.write_fields_len(0)
+            }
         }
     }
 }
@@ -545,13 +596,16 @@ impl<W: Write> Encode<W> for ! {
 impl<W: Write> Encode<W> for std::net::IpAddr {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_enum_header()?;
         match self {
             Self::V4(x) => {
                 encoder.write_uvariant(0u8)?;
+                encoder.write_fields_len(1)?;
                 x.encode(encoder)
             }
             Self::V6(x) => {
                 encoder.write_uvariant(1u8)?;
+                encoder.write_fields_len(1)?;
                 x.encode(encoder)
             }
         }
@@ -581,13 +635,16 @@ impl<W: Write> Encode<W> for std::net::Ipv6Addr {
 impl<W: Write> Encode<W> for std::net::SocketAddr {
     #[inline]
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_enum_header()?;
         match self {
             Self::V4(x) => {
                 encoder.write_uvariant(0u8)?;
+                encoder.write_fields_len(1)?;
                 x.encode(encoder)
             }
             Self::V6(x) => {
                 encoder.write_uvariant(1u8)?;
+                encoder.write_fields_len(1)?;
                 x.encode(encoder)
             }
         }
@@ -626,35 +683,78 @@ macro_rules! impl_decode {
 }
 
 impl_decode! {
-    u8 => read_u8;
-    u16 => read_u16;
-    u32 => read_u32;
-    u64 => read_u64;
-    u128 => read_u128;
-    i8 => read_i8;
-    i16 => read_i16;
-    i32 => read_i32;
-    i64 => read_i64;
-    i128 => read_i128;
     bool => read_bool;
     char => read_char;
-    f32 => read_f32;
-    f64 => read_f64;
     usize => read_usize;
     isize => read_isize;
 }
 
+macro_rules! impl_decode_bulk {
+    ($($ty:ty => $read:ident, $float:literal);* $(;)? ) => {
+	    $(
+	    impl<R: $crate::io::Read> $crate::Decode<R> for $ty {
+		    #[inline]
+            fn decode(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<Self> where Self: Sized {
+		        decoder.$read()
+		    }
+
+		    #[inline]
+		    fn decode_array<const N: usize>(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<[Self; N]> {
+			    if let Some(endianness) = bulk_endianness(decoder, $float, core::mem::size_of::<$ty>()) {
+				    let mut array = [0 as $ty; N];
+				    decoder.read_pod_slice(&mut array, endianness)?;
+				    return Ok(array);
+			    }
+			    array_init::try_array_init(|i| decoder.with_index(|decoder| Self::decode(decoder), i))
+		    }
+
+		    #[cfg(feature = "alloc")]
+		    #[inline]
+		    fn decode_vec(decoder: &mut $crate::Encoder<R>, len: usize) -> $crate::EncodingResult<alloc::vec::Vec<Self>> {
+			    if let Some(endianness) = bulk_endianness(decoder, $float, core::mem::size_of::<$ty>()) {
+				    decoder.check_remaining(len.saturating_mul(core::mem::size_of::<$ty>()))?;
+				    let mut vec = alloc::vec![0 as $ty; len];
+				    decoder.read_pod_slice(&mut vec, endianness)?;
+				    return Ok(vec);
+			    }
+			    let mut vec = alloc::vec::Vec::with_capacity(decoder.capacity_hint(len));
+			    for i in 0..len {
+				    vec.push(decoder.with_index(|decoder| Self::decode(decoder), i)?);
+			    }
+			    Ok(vec)
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_decode_bulk! {
+    u8 => read_u8, false;
+    u16 => read_u16, false;
+    u32 => read_u32, false;
+    u64 => read_u64, false;
+    u128 => read_u128, false;
+    i8 => read_i8, false;
+    i16 => read_i16, false;
+    i32 => read_i32, false;
+    i64 => read_i64, false;
+    i128 => read_i128, false;
+    f32 => read_f32, true;
+    f64 => read_f64, true;
+}
+
 impl<R: Read> Decode<R> for () {
     #[inline]
-    fn decode(_decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(())
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.read_null()
     }
 }
 
 impl<R: Read, T: Decode<R>, const SIZE: usize> Decode<R> for [T; SIZE] {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        array_init::try_array_init(|i| decoder.with_index(|decoder| T::decode(decoder), i))
+        decoder.read_fields_len(SIZE)?;
+        T::decode_array(decoder)
     }
 }
 
@@ -670,6 +770,7 @@ macro_rules! tuple_decode {
 	    impl<R: $crate::io::Read, $($name: $crate::Decode<R>),+> $crate::Decode<R> for ($($name),+) {
 		    #[inline]
             fn decode(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<Self>{
+			    decoder.read_fields_len([$(stringify!($name)),+].len())?;
 			    Ok(($(
 		            consume!($name, $crate::Decode::decode(decoder)?),
 		        )+))
@@ -694,75 +795,45 @@ tuple_decode! { A B C D E F G H I J K L M N }
 tuple_decode! { A B C D E F G H I J K L M N O }
 tuple_decode! { A B C D E F G H I J K L M N O P } // Up to 16
 
-macro_rules! slice_borrow {
-    ($($ty:ty => $borrow:ident);* $(;)?) => {
-	    $(
-	    #[allow(non_snake_case)]
-	    impl<'data: 'a, 'a, R: BorrowRead<'data>> $crate::Decode<R> for &'a [$ty] {
-		    #[inline]
-            fn decode(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<Self>{
-			    let len = decoder.read_usize()?;
-			    let endianness = decoder.ctxt.settings.num_repr.endianness;
-			    let num_encoding = decoder.ctxt.settings.num_repr.num_encoding;
-			    decoder.$borrow(len, num_encoding, endianness)
-		    }
-	    }
-	    )*
-    };
-}
-
-slice_borrow! {
-    u16 => borrow_u16_slice;
-    u32 => borrow_u32_slice;
-    u64 => borrow_u64_slice;
-    u128 => borrow_u128_slice;
-    i16 => borrow_i16_slice;
-    i32 => borrow_i32_slice;
-    i64 => borrow_i64_slice;
-    i128 => borrow_i128_slice;
-    f32 => borrow_f32_slice;
-    f64 => borrow_f64_slice;
-}
-
-impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a [u8] {
-    #[inline]
-    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let num_encoding = decoder.ctxt.settings.num_repr.num_encoding;
-        decoder.borrow_u8_slice(len, num_encoding)
-    }
-}
-
-impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a [i8] {
-    #[inline]
-    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let num_encoding = decoder.ctxt.settings.num_repr.num_encoding;
-        decoder.borrow_i8_slice(len, num_encoding)
+// Slices can only be borrowed in the binary format, where their elements are laid out as-is
+#[inline]
+pub(crate) fn check_borrowable_format<R>(decoder: &Encoder<R>) -> EncodingResult<()> {
+    let format = decoder.ctxt.settings.format;
+    if format != Format::Binary {
+        return Err(BorrowError::NonBorrowableFormat { format }.into());
     }
+    Ok(())
 }
 
-impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a [usize] {
+/// Borrows a slice of any plain-old-data type, such as numbers or `#[repr(C)]` records
+/// whose fields are all numbers, as long as the data is laid out as in memory.
+///
+/// Numbers use the numerical representation, except `usize` and `isize`, which use the size
+/// representation. If the data can't be borrowed, a [`BorrowError`] describes why.
+impl<'data: 'a, 'a, R: BorrowRead<'data>, T: bytemuck::Pod> Decode<R> for &'a [T] {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        check_borrowable_format(decoder)?;
         let len = decoder.read_usize()?;
-        let num_encoding = decoder.ctxt.settings.size_repr.num_encoding;
-        let endianness = decoder.ctxt.settings.size_repr.endianness;
-        let bit_width = decoder.ctxt.settings.size_repr.width;
 
-        decoder.borrow_usize_slice(len, num_encoding, endianness, bit_width)
-    }
-}
-
-impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a [isize] {
-    #[inline]
-    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let num_encoding = decoder.ctxt.settings.size_repr.num_encoding;
-        let endianness = decoder.ctxt.settings.size_repr.endianness;
-        let bit_width = decoder.ctxt.settings.size_repr.width;
+        // Sizes have their own representation, and are checked against the max size
+        let ty = TypeId::of::<T>();
+        if ty == TypeId::of::<usize>() || ty == TypeId::of::<isize>() {
+            let size_repr = decoder.ctxt.settings.size_repr;
+            let (num_encoding, endianness, bit_width) =
+                (size_repr.num_encoding, size_repr.endianness, size_repr.width);
+
+            return if ty == TypeId::of::<usize>() {
+                let slice = decoder.borrow_usize_slice(len, num_encoding, endianness, bit_width)?;
+                Ok(bytemuck::cast_slice(slice))
+            } else {
+                let slice = decoder.borrow_isize_slice(len, num_encoding, endianness, bit_width)?;
+                Ok(bytemuck::cast_slice(slice))
+            };
+        }
 
-        decoder.borrow_isize_slice(len, num_encoding, endianness, bit_width)
+        let num_repr = decoder.ctxt.settings.num_repr;
+        decoder.borrow_pod_slice(len, num_repr.num_encoding, num_repr.endianness)
     }
 }
 
@@ -788,6 +859,13 @@ impl<R: Read> Decode<R> for alloc::boxed::Box<str> {
 impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a str {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        // The self-describing formats always use utf-8
+        if decoder.ctxt.settings.format != Format::Binary {
+            let len = decoder.read_fmt_str_len()?;
+            let bytes = decoder.borrow_u8_slice(len, NumEncoding::Fixed)?;
+            return Ok(core::str::from_utf8(bytes).map_err(|_| StringError::InvalidChar)?);
+        }
+
         // Can only be borrowed when the string encoding is utf-8
         // else the user might get some surprises if we just assume it to be utf-8
         let str_encoding = decoder.ctxt.settings.string_repr.encoding;
@@ -808,7 +886,12 @@ impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a str {
 impl<R: Read, T: Decode<R>> Decode<R> for Option<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(match decoder.read_bool()? {
+        let present = if decoder.ctxt.settings.format != Format::Binary {
+            decoder.read_fmt_option()?
+        } else {
+            decoder.read_bool()?
+        };
+        Ok(match present {
             true => Some(T::decode(decoder)?),
             false => None,
         })
@@ -818,7 +901,10 @@ impl<R: Read, T: Decode<R>> Decode<R> for Option<T> {
 impl<R: Read, T: Decode<R>, E: Decode<R>> Decode<R> for Result<T, E> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(match decoder.read_bool()? {
+        decoder.read_enum_header()?;
+        let ok = decoder.read_bool()?;
+        decoder.read_fields_len(1)?;
+        Ok(match ok {
             true => Ok(T::decode(decoder)?),
             false => Err(E::decode(decoder)?),
         })
@@ -827,7 +913,8 @@ impl<R: Read, T: Decode<R>, E: Decode<R>> Decode<R> for Result<T, E> {
 
 impl<R: Read, T: ?Sized> Decode<R> for PhantomData<T> {
     #[inline]
-    fn decode(_decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.read_null()?;
         Ok(Self)
     }
 }
@@ -837,7 +924,9 @@ impl<R: Read, T: ?Sized> Decode<R> for PhantomData<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::boxed::Box<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(alloc::boxed::Box::new(<T as Decode<R>>::decode(decoder)?))
+        decoder.charge_alloc::<T>(1)?;
+        let value = decoder.with_nested(|decoder| <T as Decode<R>>::decode(decoder))?;
+        Ok(alloc::boxed::Box::new(value))
     }
 }
 
@@ -846,14 +935,9 @@ impl<R: Read, T: Decode<R>> Decode<R> for alloc::boxed::Box<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::boxed::Box<[T]> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut vec = alloc::vec::Vec::new();
-        vec.reserve_exact(len);
-
-        for i in 0..len {
-            vec.push(decoder.with_index(|decoder| T::decode(decoder), i)?);
-        }
-
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<T>(len)?;
+        let vec = decoder.with_nested(|decoder| T::decode_vec(decoder, len))?;
         Ok(vec.into_boxed_slice())
     }
 }
@@ -926,7 +1010,9 @@ impl<R: Read, T: Decode<R>> Decode<R> for std::sync::RwLock<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::rc::Rc<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(Self::new(T::decode(decoder)?))
+        decoder.charge_alloc::<T>(1)?;
+        let value = decoder.with_nested(|decoder| T::decode(decoder))?;
+        Ok(Self::new(value))
     }
 }
 
@@ -935,7 +1021,9 @@ impl<R: Read, T: Decode<R>> Decode<R> for alloc::rc::Rc<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::sync::Arc<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(Self::new(T::decode(decoder)?))
+        decoder.charge_alloc::<T>(1)?;
+        let value = decoder.with_nested(|decoder| T::decode(decoder))?;
+        Ok(Self::new(value))
     }
 }
 
@@ -944,14 +1032,17 @@ impl<R: Read, T: Decode<R>> Decode<R> for alloc::sync::Arc<T> {
 impl<R: Read, K: Ord + Decode<R>, V: Decode<R>> Decode<R> for alloc::collections::BTreeMap<K, V> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut map = Self::new();
+        let len = decoder.read_map_len()?;
+        decoder.charge_alloc::<(K, V)>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut map = Self::new();
 
-        for _ in 0..len {
-            map.insert(K::decode(decoder)?, V::decode(decoder)?);
-        }
+            for _ in 0..len {
+                map.insert(K::decode(decoder)?, V::decode(decoder)?);
+            }
 
-        Ok(map)
+            Ok(map)
+        })
     }
 }
 
@@ -960,14 +1051,17 @@ impl<R: Read, K: Ord + Decode<R>, V: Decode<R>> Decode<R> for alloc::collections
 impl<R: Read, K: Ord + Decode<R>> Decode<R> for alloc::collections::BTreeSet<K> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut set = Self::new();
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<K>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut set = Self::new();
 
-        for _ in 0..len {
-            set.insert(K::decode(decoder)?);
-        }
+            for _ in 0..len {
+                set.insert(K::decode(decoder)?);
+            }
 
-        Ok(set)
+            Ok(set)
+        })
     }
 }
 
@@ -976,14 +1070,17 @@ impl<R: Read, K: Ord + Decode<R>> Decode<R> for alloc::collections::BTreeSet<K>
 impl<R: Read, T: Ord + Decode<R>> Decode<R> for alloc::collections::BinaryHeap<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut heap = Self::with_capacity(len);
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<T>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut heap = Self::with_capacity(decoder.capacity_hint(len));
 
-        for _ in 0..len {
-            heap.push(T::decode(decoder)?);
-        }
+            for _ in 0..len {
+                heap.push(T::decode(decoder)?);
+            }
 
-        Ok(heap)
+            Ok(heap)
+        })
     }
 }
 
@@ -994,14 +1091,17 @@ impl<R: Read, K: core::hash::Hash + Eq + Decode<R>, V: Decode<R>> Decode<R>
 {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut map = Self::with_capacity(len);
+        let len = decoder.read_map_len()?;
+        decoder.charge_alloc::<(K, V)>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut map = Self::with_capacity(decoder.capacity_hint(len));
 
-        for _ in 0..len {
-            map.insert(K::decode(decoder)?, V::decode(decoder)?);
-        }
+            for _ in 0..len {
+                map.insert(K::decode(decoder)?, V::decode(decoder)?);
+            }
 
-        Ok(map)
+            Ok(map)
+        })
     }
 }
 
@@ -1012,14 +1112,17 @@ impl<R: Read, K: core::hash::Hash + Eq + Decode<R>> Decode<R>
 {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut map = Self::with_capacity(len);
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<K>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut map = Self::with_capacity(decoder.capacity_hint(len));
 
-        for _ in 0..len {
-            map.insert(K::decode(decoder)?);
-        }
+            for _ in 0..len {
+                map.insert(K::decode(decoder)?);
+            }
 
-        Ok(map)
+            Ok(map)
+        })
     }
 }
 
@@ -1028,14 +1131,17 @@ impl<R: Read, K: core::hash::Hash + Eq + Decode<R>> Decode<R>
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::collections::LinkedList<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut list = Self::new();
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<T>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut list = Self::new();
 
-        for i in 0..len {
-            list.push_back(decoder.with_index(|decoder| T::decode(decoder), i)?)
-        }
+            for i in 0..len {
+                list.push_back(decoder.with_index(|decoder| T::decode(decoder), i)?)
+            }
 
-        Ok(list)
+            Ok(list)
+        })
     }
 }
 
@@ -1044,14 +1150,17 @@ impl<R: Read, T: Decode<R>> Decode<R> for alloc::collections::LinkedList<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::collections::VecDeque<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut deque = Self::with_capacity(len);
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<T>(len)?;
+        decoder.with_nested(|decoder| {
+            let mut deque = Self::with_capacity(decoder.capacity_hint(len));
 
-        for i in 0..len {
-            deque.push_back(decoder.with_index(|decoder| T::decode(decoder), i)?)
-        }
+            for i in 0..len {
+                deque.push_back(decoder.with_index(|decoder| T::decode(decoder), i)?)
+            }
 
-        Ok(deque)
+            Ok(deque)
+        })
     }
 }
 
@@ -1060,14 +1169,9 @@ impl<R: Read, T: Decode<R>> Decode<R> for alloc::collections::VecDeque<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for alloc::vec::Vec<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        let len = decoder.read_usize()?;
-        let mut vec = Self::with_capacity(len);
-
-        for i in 0..len {
-            vec.push(decoder.with_index(|decoder| T::decode(decoder), i)?);
-        }
-
-        Ok(vec)
+        let len = decoder.read_seq_len()?;
+        decoder.charge_alloc::<T>(len)?;
+        decoder.with_nested(|decoder| T::decode_vec(decoder, len))
     }
 }
 
@@ -1079,6 +1183,7 @@ impl<R: Read> Decode<R> for alloc::ffi::CString {
         let mut data = alloc::vec::Vec::new();
         loop {
             let val = decoder.read_byte()?;
+            decoder.charge_alloc::<u8>(1)?;
             data.push(val);
             if val == 0 {
                 break;
@@ -1157,6 +1262,7 @@ impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a std::ffi::OsStr {
 impl<R: Read> Decode<R> for Duration {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.read_fields_len(2)?;
         Ok(Self::new(decoder.read_u64()?, decoder.read_u32()?))
     }
 }
@@ -1203,6 +1309,7 @@ impl<'data: 'a, 'a, R: BorrowRead<'data>> Decode<R> for &'a std::path::Path {
 impl<R: Read, T: Decode<R>> Decode<R> for Range<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.read_fields_len(2)?;
         Ok(Self {
             start: T::decode(decoder)?,
             end: T::decode(decoder)?,
@@ -1213,6 +1320,7 @@ impl<R: Read, T: Decode<R>> Decode<R> for Range<T> {
 impl<R: Read, T: Decode<R>> Decode<R> for RangeInclusive<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.read_fields_len(2)?;
         Ok(Self::new(T::decode(decoder)?, T::decode(decoder)?))
     }
 }
@@ -1237,7 +1345,8 @@ impl<R: Read, T: Decode<R>> Decode<R> for RangeFrom<T> {
 
 impl<R: Read> Decode<R> for RangeFull {
     #[inline]
-    fn decode(_decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        decoder.read_null()?;
         Ok(Self)
     }
 }
@@ -1245,7 +1354,10 @@ impl<R: Read> Decode<R> for RangeFull {
 impl<R: Read, T: Decode<R>> Decode<R> for Bound<T> {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(match decoder.read_uvariant::<u8>()? {
+        decoder.read_enum_header()?;
+        let variant = decoder.read_uvariant::<u8>()?;
+        decoder.read_fields_len(if variant == 2 { 0 } else { 1 })?;
+        Ok(match variant {
             0 => Self::Included(T::decode(decoder)?),
             1 => Self::Excluded(T::decode(decoder)?),
             2 => Self::Unbounded,
@@ -1299,7 +1411,10 @@ impl<R: Read> Decode<R> for ! {
 impl<R: Read> Decode<R> for std::net::IpAddr {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(match decoder.read_uvariant::<u8>()? {
+        decoder.read_enum_header()?;
+        let variant = decoder.read_uvariant::<u8>()?;
+        decoder.read_fields_len(1)?;
+        Ok(match variant {
             0 => Self::V4(decoder.decode_value()?),
             1 => Self::V6(decoder.decode_value()?),
             x => return Err(EncodingError::invalid_variant(x)),
@@ -1330,7 +1445,10 @@ impl<R: Read> Decode<R> for std::net::Ipv6Addr {
 impl<R: Read> Decode<R> for std::net::SocketAddr {
     #[inline]
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
-        Ok(match decoder.read_uvariant::<u8>()? {
+        decoder.read_enum_header()?;
+        let variant = decoder.read_uvariant::<u8>()?;
+        decoder.read_fields_len(1)?;
+        Ok(match variant {
             0 => Self::V4(decoder.decode_value()?),
             1 => Self::V6(decoder.decode_value()?),
             x => return Err(EncodingError::invalid_variant(x)),
@@ -3,9 +3,19 @@
 //! with the library.<br>
 //! Custom [`Write`], [`Read`], [`BorrowRead`] traits are provided, as well
 //! as a compatibility layer with `std::io` (see [`Std`])
+//!
+//! Bit-granular encoding and decoding is possible through the [`BitWrite`] and [`BitRead`]
+//! traits, implemented by the [`BitWriter`] and [`BitReader`] wrappers.
+//!
+//! Checksums can be computed over the bytes passing through a stream with the
+//! [`ChecksumTrack`] wrapper and any [`Checksum`] implementation.
 
 use crate::{EncodingError, EncodingResult, SeekError};
 
+mod checksum;
+
+pub use checksum::*;
+
 #[allow(unused)]
 fn usize_to_u64(val: usize) -> u64 {
     // PANIC SAFETY
@@ -218,6 +228,21 @@ impl Read for SliceMut<'_> {
         self.pos += buf.len();
         Ok(())
     }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        let rem = self.slice.len() - self.pos;
+        if len > rem {
+            return Err(EncodingError::UnexpectedEnd);
+        }
+        self.pos += len;
+        Ok(())
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.slice.len() - self.pos)
+    }
 }
 
 impl Seek for SliceMut<'_> {
@@ -266,6 +291,16 @@ impl Read for Slice<'_> {
         self.pos += buf.len();
         Ok(())
     }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        self.borrow_read(len).map(drop)
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.slice.len() - self.pos)
+    }
 }
 
 impl<'data> BorrowRead<'data> for Slice<'data> {
@@ -373,6 +408,17 @@ impl Read for VecStream {
         self.pos += buf.len();
         Ok(())
     }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        // Unlike reading, skipping never extends the vector
+        let rem = self.vec.len().saturating_sub(self.pos);
+        if len > rem {
+            return Err(EncodingError::UnexpectedEnd);
+        }
+        self.pos += len;
+        Ok(())
+    }
 }
 
 #[cfg(feature = "alloc")]
@@ -384,7 +430,8 @@ impl Seek for VecStream {
             return Err(SeekError::UnknownRange.into());
         }
 
-        let offset = seek.as_buf_offset(self.pos, 0)?;
+        // The vector grows as needed, so the only upper bound is the maximum offset
+        let offset = seek.as_buf_offset(self.pos, isize::MAX as usize)?;
         self.ensure_capacity(offset);
         self.pos = offset;
         Ok(offset)
@@ -464,6 +511,23 @@ impl<T: Read> Read for SizeTrack<T> {
         self.rsize += buf.len();
         Ok(ok)
     }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        let ok = self.stream.skip(len)?;
+        self.rsize += len;
+        Ok(ok)
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        self.stream.limit()
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        self.stream.remaining_len()
+    }
 }
 
 impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for SizeTrack<T> {
@@ -479,6 +543,98 @@ impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for SizeTrack<T> {
     }
 }
 
+/// Wraps a stream that can't seek and keeps track of its position, by counting
+/// the bytes written and read from the given starting offset.
+///
+/// It implements [`Position`] but not [`Seek`], so that such a stream can still be used
+/// with the flags that only need the position, such as `align` and `pos_tracker`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PosTrack<T> {
+    stream: T,
+    pos: usize,
+}
+
+impl<T> PosTrack<T> {
+    /// Creates a new tracker for `T`, whose position starts at `start`.
+    #[inline]
+    pub fn new(stream: T, start: usize) -> Self {
+        Self { stream, pos: start }
+    }
+
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.stream
+    }
+
+    /// Mutable reference to `T`.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.stream
+    }
+
+    /// Unwraps `T`, returning it.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.stream
+    }
+}
+
+impl<T: Write> Write for PosTrack<T> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
+        let ok = self.stream.write(buf)?;
+        self.pos += buf.len();
+        Ok(ok)
+    }
+}
+
+impl<T: Read> Read for PosTrack<T> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        let ok = self.stream.read(buf)?;
+        self.pos += buf.len();
+        Ok(ok)
+    }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        let ok = self.stream.skip(len)?;
+        self.pos += len;
+        Ok(ok)
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        self.stream.limit()
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        self.stream.remaining_len()
+    }
+}
+
+impl<T> Position for PosTrack<T> {
+    #[inline]
+    fn position(&mut self) -> EncodingResult<usize> {
+        Ok(self.pos)
+    }
+}
+
+impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for PosTrack<T> {
+    #[inline]
+    fn peek(&self, len: usize) -> EncodingResult<&'data [u8]> {
+        self.stream.peek(len)
+    }
+    #[inline]
+    fn borrow_read(&mut self, len: usize) -> EncodingResult<&'data [u8]> {
+        let ok = self.stream.borrow_read(len)?;
+        self.pos += len;
+        Ok(ok)
+    }
+}
+
 /// Wraps any type that implements [`Write`] or [`Read`] and limits how many
 /// bytes can be written and read, separately.
 #[derive(Clone, Debug)]
@@ -558,6 +714,26 @@ impl<T: Read> Read for SizeLimit<T> {
         self.rsize -= buf.len();
         Ok(ok)
     }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        if len > self.rsize {
+            return Err(EncodingError::UnexpectedEnd);
+        }
+        let ok = self.stream.skip(len)?;
+        self.rsize -= len;
+        Ok(ok)
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        Some(self.stream.limit().map_or(self.rsize, |x| x.min(self.rsize)))
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.stream.remaining_len().map_or(self.rsize, |x| x.min(self.rsize)))
+    }
 }
 
 impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for SizeLimit<T> {
@@ -579,6 +755,357 @@ impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for SizeLimit<T> {
     }
 }
 
+/// Wraps a reference to any type that implements [`BorrowRead`], reading the data that
+/// follows the current position of the stream through [`BorrowRead::peek`], without advancing it.
+///
+/// This is useful to measure how many bytes a value occupies before actually borrowing them.
+#[derive(Debug)]
+pub struct Lookahead<'a, T> {
+    stream: &'a T,
+    pos: usize,
+}
+
+impl<'a, T> Lookahead<'a, T> {
+    /// Creates a new lookahead stream, starting at the current position of `stream`.
+    #[inline]
+    pub fn new(stream: &'a T) -> Self {
+        Self { stream, pos: 0 }
+    }
+
+    /// Returns the number of bytes read ahead so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        self.stream
+    }
+}
+
+impl<'data, T: BorrowRead<'data>> Read for Lookahead<'_, T> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        buf.copy_from_slice(self.borrow_read(buf.len())?);
+        Ok(())
+    }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        self.borrow_read(len).map(drop)
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        self.stream.limit().map(|x| x.saturating_sub(self.pos))
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        self.stream.remaining_len().map(|x| x.saturating_sub(self.pos))
+    }
+}
+
+impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for Lookahead<'_, T> {
+    #[inline]
+    fn peek(&self, len: usize) -> EncodingResult<&'data [u8]> {
+        let end = self.pos.checked_add(len).ok_or(EncodingError::UnexpectedEnd)?;
+        Ok(&self.stream.peek(end)?[self.pos..])
+    }
+    #[inline]
+    fn borrow_read(&mut self, len: usize) -> EncodingResult<&'data [u8]> {
+        let ok = self.peek(len)?;
+        self.pos += len;
+        Ok(ok)
+    }
+}
+
+/// The order in which a [`BitWriter`] fills and a [`BitReader`] consumes the bits of each byte.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BitOrder {
+    /// The first bit goes into the most significant bit of the byte, and multi-bit values
+    /// are written starting from their most significant bit (network order, E.G. IP headers).
+    MsbFirst,
+    /// The first bit goes into the least significant bit of the byte, and multi-bit values
+    /// are written starting from their least significant bit (E.G. DEFLATE).
+    LsbFirst,
+}
+
+/// Wraps any type that implements [`Write`], allowing it to be written one bit at a time
+/// through the [`BitWrite`] trait.
+///
+/// Bits are accumulated until a whole byte is formed, which is then written to the
+/// underlying stream. Whole-byte writes performed while the writer is not byte-aligned
+/// are shifted in one bit at a time, so the bitstream stays contiguous.
+#[derive(Clone, Debug)]
+pub struct BitWriter<T> {
+    stream: T,
+    order: BitOrder,
+    buf: u8,
+    len: u32,
+}
+
+impl<T> BitWriter<T> {
+    /// Creates a new bit writer for `T`, using the given [`BitOrder`].
+    #[inline]
+    pub fn new(stream: T, order: BitOrder) -> Self {
+        Self {
+            stream,
+            order,
+            buf: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the [`BitOrder`] of this writer.
+    #[inline]
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Returns true if no bits are pending, E.G. the next write starts on a byte boundary.
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.stream
+    }
+
+    /// Mutable reference to `T`.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.stream
+    }
+
+    /// Unwraps `T`, returning it.
+    ///
+    /// Any pending bits that don't form a whole byte yet are discarded,
+    /// use [`finish`][`Self::finish`] to write them out first.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.stream
+    }
+}
+
+impl<T: Write> BitWriter<T> {
+    /// Pads the pending bits (if any) with zeroes, writes them, then unwraps `T`, returning it.
+    #[inline]
+    pub fn finish(mut self) -> EncodingResult<T> {
+        self.align_bits()?;
+        Ok(self.stream)
+    }
+
+    #[inline]
+    fn push_bit(&mut self, bit: bool) -> EncodingResult<()> {
+        let shift = match self.order {
+            BitOrder::MsbFirst => 7 - self.len,
+            BitOrder::LsbFirst => self.len,
+        };
+        self.buf |= (bit as u8) << shift;
+        self.len += 1;
+
+        if self.len == 8 {
+            self.stream.write(&[self.buf])?;
+            self.buf = 0;
+            self.len = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Write> Write for BitWriter<T> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
+        if self.is_aligned() {
+            return self.stream.write(buf);
+        }
+
+        for byte in buf {
+            self.write_bits(*byte as u128, 8)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Write> BitWrite for BitWriter<T> {
+    #[inline]
+    fn write_bits(&mut self, value: u128, bits: u32) -> EncodingResult<()> {
+        assert!(bits <= u128::BITS);
+
+        match self.order {
+            BitOrder::MsbFirst => {
+                for i in (0..bits).rev() {
+                    self.push_bit((value >> i) & 1 != 0)?;
+                }
+            }
+            BitOrder::LsbFirst => {
+                for i in 0..bits {
+                    self.push_bit((value >> i) & 1 != 0)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn align_bits(&mut self) -> EncodingResult<()> {
+        if !self.is_aligned() {
+            self.stream.write(&[self.buf])?;
+            self.buf = 0;
+            self.len = 0;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps any type that implements [`Read`], allowing it to be read one bit at a time
+/// through the [`BitRead`] trait.
+///
+/// Whole-byte reads performed while the reader is not byte-aligned
+/// are shifted out one bit at a time, so the bitstream stays contiguous.
+#[derive(Clone, Debug)]
+pub struct BitReader<T> {
+    stream: T,
+    order: BitOrder,
+    buf: u8,
+    len: u32,
+}
+
+impl<T> BitReader<T> {
+    /// Creates a new bit reader for `T`, using the given [`BitOrder`].
+    #[inline]
+    pub fn new(stream: T, order: BitOrder) -> Self {
+        Self {
+            stream,
+            order,
+            buf: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the [`BitOrder`] of this reader.
+    #[inline]
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Returns true if no bits of the current byte are left unread,
+    /// E.G. the next read starts on a byte boundary.
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.stream
+    }
+
+    /// Mutable reference to `T`.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.stream
+    }
+
+    /// Unwraps `T`, returning it.
+    ///
+    /// Any unread bits of the current byte are discarded.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.stream
+    }
+}
+
+impl<T: Read> BitReader<T> {
+    #[inline]
+    fn pop_bit(&mut self) -> EncodingResult<bool> {
+        if self.len == 0 {
+            let mut byte = [0u8];
+            self.stream.read(&mut byte)?;
+            self.buf = byte[0];
+            self.len = 8;
+        }
+
+        let shift = match self.order {
+            BitOrder::MsbFirst => self.len - 1,
+            BitOrder::LsbFirst => 8 - self.len,
+        };
+        self.len -= 1;
+        Ok((self.buf >> shift) & 1 != 0)
+    }
+}
+
+impl<T: Read> Read for BitReader<T> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        if self.is_aligned() {
+            return self.stream.read(buf);
+        }
+
+        for byte in buf {
+            *byte = self.read_bits(8)? as u8;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        if self.is_aligned() {
+            return self.stream.skip(len);
+        }
+
+        for _ in 0..len {
+            self.read_bits(8)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        self.stream.limit()
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        self.stream.remaining_len()
+    }
+}
+
+impl<T: Read> BitRead for BitReader<T> {
+    #[inline]
+    fn read_bits(&mut self, bits: u32) -> EncodingResult<u128> {
+        assert!(bits <= u128::BITS);
+
+        let mut value = 0u128;
+        match self.order {
+            BitOrder::MsbFirst => {
+                for _ in 0..bits {
+                    value = (value << 1) | self.pop_bit()? as u128;
+                }
+            }
+            BitOrder::LsbFirst => {
+                for i in 0..bits {
+                    value |= (self.pop_bit()? as u128) << i;
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    #[inline]
+    fn align_bits(&mut self) -> EncodingResult<()> {
+        self.len = 0;
+        Ok(())
+    }
+}
+
 /// A NOP stream, that ignores write and seek calls,and responds to read calls
 /// with infinite zeroes.
 #[derive(Clone)]
@@ -599,6 +1126,11 @@ impl Read for Zero {
         }
         Ok(())
     }
+
+    #[inline]
+    fn skip(&mut self, _len: usize) -> EncodingResult<()> {
+        Ok(())
+    }
 }
 
 impl Seek for Zero {
@@ -620,6 +1152,39 @@ pub trait Write {
 pub trait Read {
     /// Reads `buf.len()` bytes into `buf`.
     fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()>;
+
+    /// Advances the stream by `len` bytes, discarding them.
+    ///
+    /// The default implementation reads the bytes into a small buffer, so it should be
+    /// overridden by the streams that can jump over the data instead, such as the ones that
+    /// lend it or that can seek.
+    #[inline]
+    fn skip(&mut self, mut len: usize) -> EncodingResult<()> {
+        let mut buf = [0u8; 256];
+        while len != 0 {
+            let chunk = len.min(buf.len());
+            self.read(&mut buf[..chunk])?;
+            len -= chunk;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of bytes left in the byte-counted region being read, such as the
+    /// one of a [`SizeLimit`], or `None` if the stream isn't bounded.
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the number of bytes left in the stream, or `None` if it's unknown.
+    ///
+    /// This is used to cap the memory reserved for the collections being decoded, which
+    /// can't be larger than the data left. The default implementation returns the
+    /// [`limit`][`Self::limit`] of the stream.
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        self.limit()
+    }
 }
 
 /// A buffer that is capable of lending data, in order to perform **zero copy decoding**.
@@ -701,6 +1266,51 @@ pub trait Seek {
     fn seek(&mut self, seek: SeekFrom) -> EncodingResult<usize>;
 }
 
+/// Any stream or buffer that can report the current position, even if it can't move it.
+///
+/// This is implemented by every [`Seek`] stream, and by [`PosTrack`] for the ones that can't seek.
+pub trait Position {
+    /// Returns the current position, as an offset from the start of the stream.
+    fn position(&mut self) -> EncodingResult<usize>;
+}
+
+impl<T: Seek> Position for T {
+    #[inline]
+    fn position(&mut self) -> EncodingResult<usize> {
+        self.seek(SeekFrom::POSITION)
+    }
+}
+
+/// A [`Write`] stream which can also be **encoded into** one bit at a time.
+pub trait BitWrite: Write {
+    /// Writes the lowest `bits` bits of `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 128.
+    fn write_bits(&mut self, value: u128, bits: u32) -> EncodingResult<()>;
+
+    /// Pads the current byte with zero bits, so that the next write starts on a byte boundary.
+    ///
+    /// Does nothing if the stream is already byte-aligned.
+    fn align_bits(&mut self) -> EncodingResult<()>;
+}
+
+/// A [`Read`] stream which can also be **decoded from** one bit at a time.
+pub trait BitRead: Read {
+    /// Reads `bits` bits, returning them as the lowest bits of the result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is greater than 128.
+    fn read_bits(&mut self, bits: u32) -> EncodingResult<u128>;
+
+    /// Discards the unread bits of the current byte, so that the next read starts on a byte boundary.
+    ///
+    /// Does nothing if the stream is already byte-aligned.
+    fn align_bits(&mut self) -> EncodingResult<()>;
+}
+
 impl<T: Write> Write for &mut T {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
@@ -713,6 +1323,21 @@ impl<T: Read> Read for &mut T {
     fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
         <T as Read>::read(self, buf)
     }
+
+    #[inline]
+    fn skip(&mut self, len: usize) -> EncodingResult<()> {
+        <T as Read>::skip(self, len)
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        <T as Read>::limit(self)
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        <T as Read>::remaining_len(self)
+    }
 }
 
 impl<T: Seek> Seek for &mut T {
@@ -732,3 +1357,257 @@ impl<'data, T: BorrowRead<'data>> BorrowRead<'data> for &mut T {
         <T as BorrowRead<'data>>::borrow_read(self, len)
     }
 }
+
+impl<T: BitWrite> BitWrite for &mut T {
+    #[inline]
+    fn write_bits(&mut self, value: u128, bits: u32) -> EncodingResult<()> {
+        <T as BitWrite>::write_bits(self, value, bits)
+    }
+    #[inline]
+    fn align_bits(&mut self) -> EncodingResult<()> {
+        <T as BitWrite>::align_bits(self)
+    }
+}
+
+impl<T: BitRead> BitRead for &mut T {
+    #[inline]
+    fn read_bits(&mut self, bits: u32) -> EncodingResult<u128> {
+        <T as BitRead>::read_bits(self, bits)
+    }
+    #[inline]
+    fn align_bits(&mut self) -> EncodingResult<()> {
+        <T as BitRead>::align_bits(self)
+    }
+}
+
+/// The asynchronous counterpart of [`Write`].
+///
+/// The futures returned by this trait are not required to be `Send`.
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+#[allow(async_fn_in_trait)]
+pub trait AsyncWrite {
+    /// Writes the entire contents of `buf`.
+    ///
+    /// No guarantees are made about flushing.
+    async fn write(&mut self, buf: &[u8]) -> EncodingResult<()>;
+}
+
+/// The asynchronous counterpart of [`Read`].
+///
+/// The futures returned by this trait are not required to be `Send`.
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+#[allow(async_fn_in_trait)]
+pub trait AsyncRead {
+    /// Reads `buf.len()` bytes into `buf`.
+    async fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()>;
+
+    /// Returns the number of bytes left in the stream, or `None` if it's unknown.
+    ///
+    /// See [`Read::remaining_len`].
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// The asynchronous counterpart of [`Seek`].
+///
+/// The futures returned by this trait are not required to be `Send`.
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+#[allow(async_fn_in_trait)]
+pub trait AsyncSeek {
+    /// Offsets the stream position using the given [`SeekFrom`] argument.
+    ///
+    /// Returns the new position, as an offset from the start of the stream.
+    async fn seek(&mut self, seek: SeekFrom) -> EncodingResult<usize>;
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T: AsyncWrite> AsyncWrite for &mut T {
+    #[inline]
+    async fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
+        <T as AsyncWrite>::write(self, buf).await
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T: AsyncRead> AsyncRead for &mut T {
+    #[inline]
+    async fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        <T as AsyncRead>::read(self, buf).await
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        <T as AsyncRead>::remaining_len(self)
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T: AsyncSeek> AsyncSeek for &mut T {
+    #[inline]
+    async fn seek(&mut self, seek: SeekFrom) -> EncodingResult<usize> {
+        <T as AsyncSeek>::seek(self, seek).await
+    }
+}
+
+/// A compatibility layer between this crate's async I/O traits and `embedded_io_async` traits.
+///
+/// If `T` implements either [`embedded_io_async::Write`], [`embedded_io_async::Read`],
+/// [`embedded_io_async::Seek`] or any combination of those, `Embedded<T>` will implement
+/// either [`AsyncWrite`], [`AsyncRead`], [`AsyncSeek`] or any combination of those.
+///
+/// The memory layout is always guaranteed to be that of `T`.
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+#[repr(transparent)]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Embedded<T>(T);
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T> Embedded<T> {
+    /// Wraps a `T`.
+    #[inline]
+    pub fn new(stream: T) -> Self {
+        Self(stream)
+    }
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.0
+    }
+    /// Mutable reference to `T`.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+    /// Unwraps `T` and returns it.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T: embedded_io_async::Write> AsyncWrite for Embedded<T> {
+    #[inline]
+    async fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
+        <T as embedded_io_async::Write>::write_all(&mut self.0, buf)
+            .await
+            .map_err(|err| EncodingError::IOError(embedded_io::Error::kind(&err)))
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T: embedded_io_async::Read> AsyncRead for Embedded<T> {
+    #[inline]
+    async fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        <T as embedded_io_async::Read>::read_exact(&mut self.0, buf)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+impl<T: embedded_io_async::Seek> AsyncSeek for Embedded<T> {
+    #[inline]
+    async fn seek(&mut self, seek: SeekFrom) -> EncodingResult<usize> {
+        let seek = match seek {
+            SeekFrom::Start(off) => embedded_io::SeekFrom::Start(usize_to_u64(off)),
+            SeekFrom::End(off) => embedded_io::SeekFrom::End(isize_to_i64(off)),
+            SeekFrom::Current(off) => embedded_io::SeekFrom::Current(isize_to_i64(off)),
+        };
+        match <T as embedded_io_async::Seek>::seek(&mut self.0, seek).await {
+            Ok(off) => Ok(u64_to_usize(off)),
+            Err(err) => Err(EncodingError::IOError(embedded_io::Error::kind(&err))),
+        }
+    }
+}
+
+/// A compatibility layer between this crate's async I/O traits and `tokio::io` traits.
+///
+/// If `T` implements either [`tokio::io::AsyncWrite`], [`tokio::io::AsyncRead`],
+/// [`tokio::io::AsyncSeek`] or any combination of those, `Tokio<T>` will implement
+/// either [`AsyncWrite`], [`AsyncRead`], [`AsyncSeek`] or any combination of those.
+///
+/// The memory layout is always guaranteed to be that of `T`.
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "tokio")))]
+#[repr(transparent)]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Tokio<T>(T);
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "tokio")))]
+impl<T> Tokio<T> {
+    /// Wraps a `T`.
+    #[inline]
+    pub fn new(stream: T) -> Self {
+        Self(stream)
+    }
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.0
+    }
+    /// Mutable reference to `T`.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+    /// Unwraps `T` and returns it.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "tokio")))]
+impl<T: tokio::io::AsyncWrite + Unpin> AsyncWrite for Tokio<T> {
+    #[inline]
+    async fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
+        tokio::io::AsyncWriteExt::write_all(&mut self.0, buf)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "tokio")))]
+impl<T: tokio::io::AsyncRead + Unpin> AsyncRead for Tokio<T> {
+    #[inline]
+    async fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        tokio::io::AsyncReadExt::read_exact(&mut self.0, buf)
+            .await
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "tokio")))]
+impl<T: tokio::io::AsyncSeek + Unpin> AsyncSeek for Tokio<T> {
+    #[inline]
+    async fn seek(&mut self, seek: SeekFrom) -> EncodingResult<usize> {
+        let seek = match seek {
+            SeekFrom::Start(off) => std::io::SeekFrom::Start(usize_to_u64(off)),
+            SeekFrom::End(off) => std::io::SeekFrom::End(isize_to_i64(off)),
+            SeekFrom::Current(off) => std::io::SeekFrom::Current(isize_to_i64(off)),
+        };
+        match tokio::io::AsyncSeekExt::seek(&mut self.0, seek).await {
+            Ok(off) => Ok(u64_to_usize(off)),
+            Err(x) => Err(x.into()),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use super::{BorrowRead, Read, Seek, SeekFrom, Write};
+use crate::EncodingResult;
+
+/// A checksum or hashing algorithm that can be fed bytes incrementally.
+///
+/// Built-in implementations are provided for [`Crc8`], [`Crc16`], [`Crc32`],
+/// [`Adler32`], [`Fletcher16`] and [`Fletcher32`].
+pub trait Checksum {
+    /// The type of the digest produced by this algorithm.
+    type Output: Copy;
+
+    /// Feeds the given bytes into the checksum.
+    fn update(&mut self, bytes: &[u8]);
+
+    /// Returns the digest of all the bytes fed so far.
+    fn digest(&self) -> Self::Output;
+}
+
+impl<C: Checksum> Checksum for &mut C {
+    type Output = C::Output;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        (**self).update(bytes)
+    }
+
+    #[inline]
+    fn digest(&self) -> Self::Output {
+        (**self).digest()
+    }
+}
+
+/// Wraps any type that implements [`Write`] or [`Read`] and feeds every byte
+/// that is written or read into a [`Checksum`].
+///
+/// Seeking is forwarded to the underlying stream, but the bytes that are seeked over
+/// aren't fed into the checksum. Bit-level streams aren't supported, because the bits
+/// can't be fed into the checksum as they are written or read.
+#[derive(Clone, Debug)]
+pub struct ChecksumTrack<T, C> {
+    stream: T,
+    checksum: C,
+}
+
+impl<T, C: Checksum> ChecksumTrack<T, C> {
+    /// Creates a new tracker for `T`, which feeds the given checksum.
+    #[inline]
+    pub fn new(stream: T, checksum: C) -> Self {
+        Self { stream, checksum }
+    }
+
+    /// Returns the digest of all the bytes written or read so far.
+    ///
+    /// Note that if a write or read call fails, the bytes involved aren't
+    /// fed into the checksum.
+    #[inline]
+    pub fn digest(&self) -> C::Output {
+        self.checksum.digest()
+    }
+
+    /// Read-only reference to the checksum.
+    #[inline]
+    pub fn checksum(&self) -> &C {
+        &self.checksum
+    }
+
+    /// Read-only reference to `T`.
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.stream
+    }
+
+    /// Mutable reference to `T`.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.stream
+    }
+
+    /// Unwraps `T` and the checksum, returning them.
+    #[inline]
+    pub fn into_parts(self) -> (T, C) {
+        (self.stream, self.checksum)
+    }
+}
+
+impl<T: Write, C: Checksum> Write for ChecksumTrack<T, C> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> EncodingResult<()> {
+        let ok = self.stream.write(buf)?;
+        self.checksum.update(buf);
+        Ok(ok)
+    }
+}
+
+impl<T: Read, C: Checksum> Read for ChecksumTrack<T, C> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+        let ok = self.stream.read(buf)?;
+        self.checksum.update(buf);
+        Ok(ok)
+    }
+
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        self.stream.limit()
+    }
+
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        self.stream.remaining_len()
+    }
+}
+
+impl<'data, T: BorrowRead<'data>, C: Checksum> BorrowRead<'data> for ChecksumTrack<T, C> {
+    #[inline]
+    fn peek(&self, len: usize) -> EncodingResult<&'data [u8]> {
+        self.stream.peek(len)
+    }
+    #[inline]
+    fn borrow_read(&mut self, len: usize) -> EncodingResult<&'data [u8]> {
+        let ok = self.stream.borrow_read(len)?;
+        self.checksum.update(ok);
+        Ok(ok)
+    }
+}
+
+impl<T: Seek, C: Checksum> Seek for ChecksumTrack<T, C> {
+    /// Seeks the underlying stream. The bytes that are seeked over
+    /// aren't fed into the checksum.
+    #[inline]
+    fn seek(&mut self, seek: SeekFrom) -> EncodingResult<usize> {
+        self.stream.seek(seek)
+    }
+}
+
+// Generates the lookup table of a CRC algorithm at compile time
+macro_rules! crc_table {
+    ($name:ident: $ty:ty, poly = $poly:expr, reflected = true) => {
+        const $name: [$ty; 256] = {
+            let mut table = [0; 256];
+            let mut i = 0;
+            while i < 256 {
+                let mut crc = i as $ty;
+                let mut bit = 0;
+                while bit < 8 {
+                    crc = if crc & 1 != 0 { (crc >> 1) ^ $poly } else { crc >> 1 };
+                    bit += 1;
+                }
+                table[i] = crc;
+                i += 1;
+            }
+            table
+        };
+    };
+    ($name:ident: $ty:ty, poly = $poly:expr, reflected = false) => {
+        const $name: [$ty; 256] = {
+            let mut table = [0; 256];
+            let mut i = 0;
+            while i < 256 {
+                let mut crc = (i as $ty) << (<$ty>::BITS - 8);
+                let mut bit = 0;
+                while bit < 8 {
+                    crc = if crc & (1 << (<$ty>::BITS - 1)) != 0 {
+                        (crc << 1) ^ $poly
+                    } else {
+                        crc << 1
+                    };
+                    bit += 1;
+                }
+                table[i] = crc;
+                i += 1;
+            }
+            table
+        };
+    };
+}
+
+crc_table!(CRC8_TABLE: u8, poly = 0x07, reflected = false);
+crc_table!(CRC16_TABLE: u16, poly = 0xA001, reflected = true);
+crc_table!(CRC32_TABLE: u32, poly = 0xEDB88320, reflected = true);
+
+/// The CRC-8 algorithm (also known as CRC-8/SMBUS): polynomial `0x07`, initial value `0`,
+/// not reflected.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Crc8 {
+    crc: u8,
+}
+
+impl Crc8 {
+    /// Creates a new CRC-8 checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { crc: 0 }
+    }
+}
+
+impl Checksum for Crc8 {
+    type Output = u8;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.crc = CRC8_TABLE[(self.crc ^ byte) as usize];
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> u8 {
+        self.crc
+    }
+}
+
+/// The CRC-16 algorithm (also known as CRC-16/ARC): polynomial `0x8005`, initial value `0`,
+/// reflected.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Crc16 {
+    crc: u16,
+}
+
+impl Crc16 {
+    /// Creates a new CRC-16 checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { crc: 0 }
+    }
+}
+
+impl Checksum for Crc16 {
+    type Output = u16;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.crc = (self.crc >> 8) ^ CRC16_TABLE[((self.crc as u8) ^ byte) as usize];
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> u16 {
+        self.crc
+    }
+}
+
+/// The CRC-32 algorithm used by zlib, PNG, Ethernet and many others
+/// (also known as CRC-32/ISO-HDLC): polynomial `0x04C11DB7`, reflected,
+/// with the initial value and final xor set to `0xFFFFFFFF`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    /// Creates a new CRC-32 checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { crc: 0xFFFFFFFF }
+    }
+}
+
+impl Default for Crc32 {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Crc32 {
+    type Output = u32;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.crc = (self.crc >> 8) ^ CRC32_TABLE[((self.crc as u8) ^ byte) as usize];
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> u32 {
+        !self.crc
+    }
+}
+
+/// The [Adler-32](https://en.wikipedia.org/wiki/Adler-32) checksum used by zlib.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    const MOD: u32 = 65521;
+    // The largest number of bytes that can be summed before `b` may overflow
+    const CHUNK: usize = 5552;
+
+    /// Creates a new Adler-32 checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+}
+
+impl Default for Adler32 {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Adler32 {
+    type Output = u32;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(Self::CHUNK) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= Self::MOD;
+            self.b %= Self::MOD;
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+/// The [Fletcher-16](https://en.wikipedia.org/wiki/Fletcher%27s_checksum) checksum,
+/// computed over 8-bit blocks.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Fletcher16 {
+    sum1: u16,
+    sum2: u16,
+}
+
+impl Fletcher16 {
+    /// Creates a new Fletcher-16 checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { sum1: 0, sum2: 0 }
+    }
+}
+
+impl Checksum for Fletcher16 {
+    type Output = u16;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.sum1 = (self.sum1 + byte as u16) % 255;
+            self.sum2 = (self.sum2 + self.sum1) % 255;
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> u16 {
+        (self.sum2 << 8) | self.sum1
+    }
+}
+
+/// The [Fletcher-32](https://en.wikipedia.org/wiki/Fletcher%27s_checksum) checksum,
+/// computed over little endian 16-bit blocks.
+///
+/// If the total number of bytes is odd, the last block is padded with a zero.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Fletcher32 {
+    sum1: u32,
+    sum2: u32,
+    // The first half of a block that has yet to be completed
+    pending: Option<u8>,
+}
+
+impl Fletcher32 {
+    /// Creates a new Fletcher-32 checksum.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            sum1: 0,
+            sum2: 0,
+            pending: None,
+        }
+    }
+
+    #[inline]
+    fn block(sum1: &mut u32, sum2: &mut u32, block: u16) {
+        *sum1 = (*sum1 + block as u32) % 65535;
+        *sum2 = (*sum2 + *sum1) % 65535;
+    }
+}
+
+impl Checksum for Fletcher32 {
+    type Output = u32;
+
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match self.pending.take() {
+                Some(low) => {
+                    Self::block(&mut self.sum1, &mut self.sum2, u16::from_le_bytes([low, byte]))
+                }
+                None => self.pending = Some(byte),
+            }
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> u32 {
+        let mut sum1 = self.sum1;
+        let mut sum2 = self.sum2;
+        if let Some(low) = self.pending {
+            Self::block(&mut sum1, &mut sum2, low as u16);
+        }
+        (sum2 << 16) | sum1
+    }
+}
@@ -123,9 +123,17 @@
 //! Of course, breaking API changes will be accompanied by a major version
 //! bump.
 //!
-//! # Future plans
+//! # Async
 //!
-//! I plan on adding support for `async` io through a feature gate.
+//! With the `async` feature enabled, the [`AsyncEncode`] and [`AsyncDecode`] traits
+//! become available, together with `_async` versions of the [`Encoder`] methods.
+//! The output is byte-for-byte identical to the one of the synchronous implementations.
+//!
+//! Any [`embedded-io-async`](https://crates.io/crates/embedded-io-async) stream can be used
+//! by wrapping it in [`io::Embedded`], and with the `tokio` feature any tokio stream can be used
+//! by wrapping it in [`io::Tokio`].
+//!
+//! The derive macros generate the async implementations when the `async` flag is present.
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
@@ -166,12 +174,22 @@ use parse_display::Display;
 /// When a flag is said to be a `seek` flag, it means that when used anywhere it will switch the
 /// impl to a seeking impl.
 ///
+/// A positional impl is an implementation of [`Encode`] or [`Decode`] that has an additional
+/// [`Position`] trait bound, which is implemented by every [`Seek`] stream.
+/// When a flag is said to be a `position` flag, it means that when used anywhere it will switch the
+/// impl to a positional impl, or to a seeking impl if the item also has `checksum` fields.
+///
 /// A borrowing impl is an implementation of [`Encode`] or [`Decode`] that has a [`BorrowRead`]
 /// trait bound rather than a [`Read`] one.
 /// When a flag is said to be a `borrow` flag, it means that when used anywhere it will switch the
 /// impl to a borrowing impl.
 ///
-/// Borrowing and seeking impls can be combined.
+/// A bit impl is an implementation of [`Encode`] or [`Decode`] that has a [`BitWrite`][`io::BitWrite`] or [`BitRead`][`io::BitRead`]
+/// trait bound rather than a [`Write`] or [`Read`] one.
+/// When a flag is said to be a `bit` flag, it means that when used anywhere it will switch the
+/// impl to a bit impl.
+///
+/// Borrowing, seeking and bit impls can be combined.
 ///
 /// The flags currently implemented are split into 5 groups:
 /// # 1. Setting Modifiers
@@ -186,7 +204,7 @@ use parse_display::Display;
 ///     - `size`
 ///     - `variant`
 ///     - `string`
-/// - Numerical encoding modifiers: `fixed`, `leb128`, `protobuf_wasteful`, `protobuf_zz`
+/// - Numerical encoding modifiers: `fixed`, `leb128`, `protobuf_wasteful`, `protobuf_zz`, `bincode_varint`, `postcard_varint`, `asn1`
 ///   - Available targets:
 ///     - `num`,
 ///     - `size`,
@@ -263,6 +281,10 @@ use parse_display::Display;
 /// (which must be of type usize or isize) relative to $seek - which can be
 /// "start", "end" or "cur"rrent - before encoding/decoding this field, then seeks back to the
 /// previous location.
+/// * `sized` - Encodes this field or item in a byte-counted region, prefixed by its size in bytes
+/// (see [`Encoder::write_sized`] and [`Encoder::read_sized`]). While decoding, the region can't be
+/// read past its end, and any unread trailing bytes are handled according to the [`Trailing`] setting.
+/// Keep in mind that the value is encoded twice, first to measure its size.
 /// ### Example:
 /// ```rust
 /// # use ender::{Encode, Decode};
@@ -322,6 +344,12 @@ use parse_display::Display;
 /// where `V` is the type of the field (the function is allowed to be generic over `V`).<br>
 ///     * If no scope is specified, the path must point to a module with encoding and decoding functions
 /// with the same signatures as above.
+/// * `bits: $expr` - This is a `bit` flag. Field will be encoded/decoded as a bit field that is
+/// exactly $expr bits wide, through the [`BitField`] trait (implemented for integers and `bool`).
+/// Consecutive bit fields are packed together. Can only be applied to fields.
+/// * `skip_value: $type` - The region of the stream described by $type is skipped through the
+/// [`Skip`] trait, and the field is initialized with its default value, as given by the `default`
+/// flag. Can only be applied to fields, in the Decode scope.
 /// ### Example:
 /// ```rust
 /// # use ender::{Encode, Decode};
@@ -448,12 +476,90 @@ use parse_display::Display;
 /// Can only be applied to items.
 /// * `if: $expr` - The field will only be encoded/decoded if the given expression
 /// evaluates to true, otherwise the default value is computed.
+/// * `since: $expr` - The field will only be encoded/decoded if the format version in the
+/// [`Context`] is greater than or equal to the given one, otherwise the default value is computed.
+/// Can be combined with `until` and `if`, in which case all the conditions must hold.
+/// * `until: $expr` - The field will only be encoded/decoded if the format version in the
+/// [`Context`] is less than or equal to the given one, otherwise the default value is computed.
 /// * `default: $expr` - Overrides the default fallback for when a value can't be
 /// decoded, which is `Default::default()`
 /// * `skip` - Will not encode/decode this field.
 /// When decoding, computes the default value.
+/// * `magic: $expr` - Writes the given constant (for example `b"RIFF"`) before encoding this field or item,
+/// and checks that the same bytes are present before decoding it, returning an
+/// [`EncodingError::MagicMismatch`] otherwise. The constant is not stored in any field.
+/// * `tag: $expr1, $expr2, ...` - Identifies this enum variant with the given tags instead of its index.
+/// When encoding, the first tag is written, and when decoding the variant is selected if the decoded
+/// tag is equal to any of them. Tags can also be ranges (for example `0x20..=0x2F`), which match any
+/// tag they contain; if the first tag is a range, its start is written. Variants without a tag keep
+/// their usual index. Can only be applied to variants.
+/// * `tag_type: $ty` - The tags of the variants of this enum are of the given type, rather than
+/// the integer type of the enum representation, and are encoded/decoded with its `Encode` and `Decode`
+/// implementations. The type must implement `PartialEq` and `Debug`, and each tag is converted to it with `Into`,
+/// so a four character code can be declared with `tag_type: [u8; 4]` and `tag: *b"IHDR"`.
+/// Every variant must have a tag, and unrecognized tags cause an [`EncodingError::InvalidTag`] holding the `Debug` representation of the tag. Can only be applied to enums.
+/// * `unknown` - Marks the variant that catches all the unrecognized tags while decoding, instead of
+/// returning an error. The variant must have exactly two fields: the first holds the tag, as an
+/// [`Opaque`] or as the `tag_type` if present, and the second is a `Vec<u8>` holding the remaining bytes
+/// of the byte-counted region being decoded (for example when the enum has the `sized` flag), or nothing
+/// if the stream isn't bounded. When encoding, the tag and the bytes are written back as-is.
+/// Can only be applied to one variant.
+/// * `format: protobuf` - Encodes the fields of this struct as a [protobuf](https://protobuf.dev) message,
+/// where each field is a record prefixed by a key made of its field number and [wire type][`protobuf::WireType`].
+/// Fields with the default value are omitted, `Option` fields are written whenever they are present,
+/// `Vec` fields are repeated fields (packed for scalars, `bytes` for `Vec<u8>`), and nested structs
+/// with the same flag are length-delimited submessages. When decoding, records are accepted in any order,
+/// unknown ones are skipped, the last record of a field wins and missing fields fall back to their default value.
+/// Integers are encoded as varints, unless changed with the `num` modifiers (for example `num: protobuf_zz`
+/// for `sint32`, or `num: fixed` for `fixed32`); see the [`protobuf`] module for the full mapping.
+/// A top-level message extends to the end of the stream. Can only be applied to structs, and can't be combined
+/// with flags that alter the layout of the stream, nor with `async`.
+/// * `field: $expr` - The field number of this field in a struct with the `format` flag.
+/// Every field that isn't skipped must have a unique one.
+/// * `tlv` - Encodes the fields of this struct as tag-length-value records, so that fields can be
+/// added or removed without breaking older readers. Each field is written as its id (an unsigned
+/// variant, following the `variant` modifiers), followed by the size in bytes of its value (following
+/// the `size` modifiers) and the value itself. When decoding, records are accepted in any order,
+/// unknown ids are skipped, the last record of a field wins and missing fields fall back to their
+/// default value. A top-level struct extends to the end of the stream, while nested ones are delimited
+/// by their record. Values can use any flag that only affects the value itself, such as `with`, `serde`,
+/// `sized` or the modifiers. Can only be applied to structs, and can't be combined with `async`.
+/// * `id: $expr` - The id of this field in a struct with the `tlv` flag.
+/// Every field that isn't skipped must have a unique one.
+/// * `asn1: $class($expr), explicit, optional` - Gives this field an [ASN.1][`asn1`] tag, where $class can
+/// be any of "context", "application" or "private", and $expr is the tag number. By default the tag replaces
+/// the one of the value (implicit tagging), while with `explicit` the value is wrapped in a constructed value
+/// with the given tag. With `optional`, the field must be an `Option`, which is only encoded when present, and
+/// recognized by its tag when decoding. Can only be applied to fields, and can't be combined with `async`.
+/// * `checksum: $algorithm, over: $first..$last` - Indicates that this integer field holds a checksum
+/// of the bytes of the fields from `$first` to `$last`, which must be declared before it. Like in Rust ranges,
+/// `$last` is excluded unless `..=` is used, and when `over` is omitted the checksum covers all the
+/// fields before this one. When encoding, the value is computed instead of being taken from this field,
+/// and when decoding it is verified, returning an [`EncodingError::ChecksumMismatch`] otherwise.
+/// $algorithm can be any of "crc8", "crc16", "crc32", "adler32", "fletcher16" or "fletcher32",
+/// or the path to a type implementing [`Checksum`][`io::Checksum`] and `Default`.
+/// Checksums can be nested, as long as the outer checksum also covers the field holding the inner one.
+/// The covered fields can use the `seek` flags, but the bytes that are seeked over aren't part of the checksum,
+/// and they can't use the `bits`, `bit_align` and `bitwise` flags.
 /// * `validate: $expr, $format_string, $arg1, $arg2, $arg3, ...` - Before encoding/after decoding, returns an error if the
 /// expression evaluates to false. The error message will use the given formatting (if present).
+/// * `pad: $expr` - Inserts the given number of padding bytes before encoding this field or item,
+/// and skips them before decoding it.
+/// * `align: $expr` - This is a `position` flag. Inserts padding bytes before encoding this field or item,
+/// until the stream position is a multiple of the given alignment, and skips them before decoding it.
+/// If both `pad` and `align` are present, `pad` is applied first.
+/// Streams that can't seek can be wrapped in a [`PosTrack`][`io::PosTrack`], which tracks the position by counting the bytes written or read.
+/// * `fill: $expr` - The value of the bytes inserted by `pad` and `align`, which is `0` by default.
+/// * `check_padding` - While decoding, checks that the bytes skipped by `pad` and `align` are
+/// equal to the `fill` value, returning an [`EncodingError::InvalidPadding`] otherwise.
+/// * `len_of: $field` - Indicates that this integer field holds the number of elements of `$field`,
+/// which must be declared after it. When encoding, the value is taken from `$field.len()` instead
+/// of this field, and when decoding it is used to `flatten` the size of `$field`.
+/// * `len_of bytes: $field` - This is a `seek` flag. Like `len_of`, but this field holds the size
+/// in bytes of the encoded `$field`. When encoding, this field is back-patched once `$field` has
+/// been encoded, and when decoding the amount of bytes read is checked against this field.
+/// So that it can be back-patched, this field always uses a fixed numerical encoding, and `num` or
+/// `size` modifiers with any other encoding are rejected.
 /// * `flatten: $expr` - Indicates that the length of the given field (for example
 /// a Vec or HashMap) doesn't need to be encoded/decoded, because it is known from the context.
 /// Can also be used with an `Option` in conjunction with the `if` flag and without the `$expr`
@@ -468,12 +574,19 @@ use parse_display::Display;
 /// type usize or isize relative to $seek.<br>
 /// If you need the stream position to be restored after encoding/decoding the field, see the
 /// `ptr` *stream modifier`.
-/// * `pos_tracker: $ident` - This is a `seek` flag. Stores the current stream position in a
+/// * `pos_tracker: $ident` - This is a `position` flag. Stores the current stream position in a
 /// variable with the given name.
 /// Note that the position is stored *before* the `ptr` and `goto` flags, if any.
 /// <br>
 /// `seeking` - This is a `seek` flag. Does nothing, but simply forces a seeking impl to be used.
 /// This can only be applied to the whole item, as it doesn't make sense on individual fields.
+/// * `bit_align` - This is a `bit` flag. Aligns the bit stream to the next byte boundary before encoding
+/// this field or item, padding the current byte with zeroes or discarding its remaining bits.
+/// * `bitwise` - This is a `bit` flag. Does nothing, but simply forces a bit impl to be used.
+/// Useful for items that contain bit fields in nested types. Can only be applied to items.
+/// * `async` - Also generates the [`AsyncEncode`] or [`AsyncDecode`] implementation
+/// (requires the `async` feature). Can only be applied to items.<br>
+/// The `serde`, `with`, `redir`, `sized`, `checksum`, `unknown`, `format`, `tlv`, `asn1`, `skip_value`, `borrow` and `bit` flags are not supported in async implementations.
 /// ### Example:
 ///
 /// ```rust
@@ -528,6 +641,28 @@ use parse_display::Display;
 ///   health: u64,
 /// }
 /// ```
+/// # Deriving `EncodedSize`
+///
+/// The [`EncodedSize`] derive macro accepts the same flags as `Encode`, and generates an impl that
+/// computes the number of bytes the `Encode` impl would write, without encoding the value.
+///
+/// Fields using the `with` or `redir` flags, and items or variants using `redir`, are measured by
+/// encoding them into a stream that discards the data, since their output is unknown.
+///
+/// The flags whose effect depends on the position in the stream or on the data itself are not
+/// supported, and will result in a compile error: the seek and position flags (`seek`, `ptr`, `pos_tracker`
+/// and `align`), the bit flags, `checksum`, `format`, `tlv` and `asn1`.
+///
+/// # Deriving `Skip`
+///
+/// The [`Skip`] derive macro accepts the same flags as `Decode`, and generates an impl that advances
+/// the stream past the value without building it.
+///
+/// Fields are skipped through their own `Skip` impl, unless the flags of the following fields refer
+/// to them (for example through `if` or `len_of`), in which case they are decoded. Fields using the
+/// `with`, `serde`, `bits` or `asn1` flags are decoded and dropped, since their layout is unknown.
+/// Validation and checksums are not checked, and the `format` and `tlv` flags are not supported.
+///
 /// # Relationship between seek flags
 ///
 /// ```
@@ -557,23 +692,45 @@ use parse_display::Display;
 /// ```
 #[cfg(feature = "derive")]
 #[cfg_attr(feature = "unstable", doc(cfg(feature = "derive")))]
-pub use ender_derive::{Decode, Encode};
+pub use ender_derive::{Decode, Encode, EncodedSize, Skip};
 pub use error::*;
 pub use opaque::*;
 pub use convenience::*;
+pub use bits::BitField;
+pub use seq::{LazySeq, SeqDecoder, SeqEncoder};
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "async")))]
+pub use asynchronous::{AsyncDecode, AsyncEncode};
 
-use crate::io::{BorrowRead, Read, Seek, SeekFrom, SizeLimit, SizeTrack, Write, Zero};
+use crate::io::{
+    BorrowRead, Checksum, ChecksumTrack, Position, Read, Seek, SeekFrom, SizeLimit, SizeTrack,
+    Write, Zero,
+};
 
 #[cfg(test)]
 mod test;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "async")]
+mod async_impls;
+pub mod asn1;
+mod bits;
+pub mod cbor;
 mod error;
 pub mod facade;
+mod format;
 mod impls;
 pub mod io;
+pub mod msgpack;
 mod opaque;
+pub mod protobuf;
 #[cfg(feature = "serde")]
-mod serde;
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "serde")))]
+pub mod serde;
+mod seq;
+mod size_impls;
+mod skip_impls;
 mod source;
 mod windows1252;
 mod convenience;
@@ -633,10 +790,33 @@ pub enum NumEncoding {
     /// carrying the sign.<br>
     /// The bytes are encoded in little endian order, ignoring the [`Endianness`].
     ProtobufZigzag,
+    /// The value's bits are encoded according to
+    /// [Bincode 2's varint encoding](https://github.com/bincode-org/bincode/blob/trunk/docs/spec.md#varintencoding),
+    /// where values lower than `251` are encoded as a single byte, and bigger values as a marker
+    /// byte (`251` to `254`) followed by the value as a `u16`, `u32`, `u64` or `u128`,
+    /// according to the [`Endianness`].
+    /// Signed values are first transformed in the same way as [ProtobufZigzag][`NumEncoding::ProtobufZigzag`].<br>
+    /// 8-bit values are always encoded as-is.
+    BincodeVarint,
+    /// The value's bits are encoded according to
+    /// [Postcard's varint encoding](https://postcard.jamesmunns.com/wire-format#varint-encoded-integers),
+    /// which is the same as [ProtobufZigzag][`NumEncoding::ProtobufZigzag`], except 8-bit values
+    /// are always encoded as-is.
+    PostcardVarint,
+    /// The value's bits are encoded according to the definite form of the
+    /// [ASN.1 BER/DER length encoding](https://en.wikipedia.org/wiki/X.690#Length_octets),
+    /// where values lower than `128` are encoded as a single byte, and bigger values as a byte
+    /// holding `0x80` plus the number of bytes that follow, followed by the value in big endian
+    /// order without leading zeroes, ignoring the [`Endianness`].<br>
+    /// Signed values are encoded as a reinterpret-cast of the bits to unsigned.<br>
+    /// When decoding, non-minimal lengths are only accepted if the [`Asn1Rules`][`asn1::Asn1Rules`]
+    /// in the settings are [`Ber`][`asn1::Asn1Rules::Ber`].
+    Asn1,
 }
 
 impl NumEncoding {
-    /// Determines whether a slice encoded with this encoding can be directly borrowed.
+    /// Determines whether a slice encoded with this encoding can be directly borrowed,
+    /// whatever the size of its elements.
     ///
     /// Currently only returns true with the [`Fixed`][`NumEncoding::Fixed`] variant
     #[inline]
@@ -646,6 +826,21 @@ impl NumEncoding {
             _ => false,
         }
     }
+
+    /// Determines whether a slice of numbers of `size` bytes encoded with this encoding
+    /// can be directly borrowed.
+    ///
+    /// Besides the [`Fixed`][`NumEncoding::Fixed`] variant, this is true for single bytes
+    /// with the [`BincodeVarint`][`NumEncoding::BincodeVarint`] and
+    /// [`PostcardVarint`][`NumEncoding::PostcardVarint`] variants, which write them as-is.
+    #[inline]
+    pub const fn borrowable_with_size(&self, size: usize) -> bool {
+        match self {
+            NumEncoding::Fixed => true,
+            NumEncoding::BincodeVarint | NumEncoding::PostcardVarint => size == 1,
+            _ => false,
+        }
+    }
 }
 
 /// How many bits a size or enum variant will occupy in the binary format. If the value
@@ -736,6 +931,17 @@ impl BitWidth {
     }
 }
 
+/// What to do with the bytes left unread at the end of a byte-counted region
+/// (see [`Encoder::read_sized`]).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+pub enum Trailing {
+    /// Return an [`EncodingError::TrailingBytes`] error.
+    #[default]
+    Reject,
+    /// Silently skip the bytes, so that decoding can continue after the region.
+    Skip,
+}
+
 /// The encoding method use for the length of a string.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
 pub enum StrLen {
@@ -825,19 +1031,21 @@ impl Default for NumRepr {
 
 /// Controls the binary representation of sizes.
 /// Specifically, controls the [`Endianness`], the [`NumEncoding`], the [`BitWidth`],
-/// and the greatest encodable/decodable size before an error is thrown
+/// the greatest encodable/decodable size before an error is thrown,
+/// and how [`Trailing`] bytes in byte-counted regions are handled
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
-#[display("endianness = {endianness} , encoding = {num_encoding}, bit_width = {width}, max_size = {max_size}")]
+#[display("endianness = {endianness} , encoding = {num_encoding}, bit_width = {width}, max_size = {max_size}, trailing = {trailing}")]
 pub struct SizeRepr {
     pub endianness: Endianness,
     pub num_encoding: NumEncoding,
     pub width: BitWidth,
     pub max_size: usize,
+    pub trailing: Trailing,
 }
 
 impl SizeRepr {
     /// Returns the default size representation: little endian, fixed encoding, 64 bit width,
-    /// and the max size set to `usize::MAX`
+    /// the max size set to `usize::MAX` and trailing bytes rejected
     #[inline]
     pub const fn new() -> Self {
         Self {
@@ -845,6 +1053,7 @@ impl SizeRepr {
             num_encoding: NumEncoding::Fixed,
             width: BitWidth::Bit64,
             max_size: usize::MAX,
+            trailing: Trailing::Reject,
         }
     }
 
@@ -875,6 +1084,13 @@ impl SizeRepr {
         self.max_size = max_size;
         self
     }
+
+    /// Sets the **trailing bytes** behaviour, then returns self.
+    #[inline]
+    pub const fn trailing(mut self, trailing: Trailing) -> Self {
+        self.trailing = trailing;
+        self
+    }
 }
 
 impl Default for SizeRepr {
@@ -991,14 +1207,46 @@ impl Default for StringRepr {
     }
 }
 
+/// The data format that the primitives of the [`Encoder`] are emitted in.
+///
+/// Every format other than [`Format::Binary`] is self-describing, and ignores
+/// most of the [`BinSettings`]: see the documentation of each format's module.
+///
+/// Only the synchronous API supports formats other than [`Format::Binary`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+pub enum Format {
+    /// The raw binary format described by the representations in the [`BinSettings`].
+    #[default]
+    Binary,
+    /// The [CBOR](https://www.rfc-editor.org/rfc/rfc8949) format. See the [`cbor`] module.
+    Cbor,
+    /// The [MessagePack](https://github.com/msgpack/msgpack/blob/master/spec.md) format.
+    /// See the [`msgpack`] module.
+    MessagePack,
+}
+
 /// An aggregation of [`NumRepr`], [`SizeRepr`], [`VariantRepr`], [`StringRepr`]
+/// the serde self-describing switch, the ASN.1 encoding rules and the data [`Format`]
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
-#[display("num_repr = ({num_repr}), size_repr = ({size_repr}), variant_repr = ({variant_repr}), string_repr = ({string_repr})")]
+#[display("num_repr = ({num_repr}), size_repr = ({size_repr}), variant_repr = ({variant_repr}), string_repr = ({string_repr}), self_describing = {self_describing}, asn1_rules = {asn1_rules}, format = {format}")]
 pub struct BinSettings {
     pub num_repr: NumRepr,
     pub size_repr: SizeRepr,
     pub variant_repr: VariantRepr,
     pub string_repr: StringRepr,
+    /// Whether the serde compatibility layer writes a compact type tag before each value,
+    /// making the data self-describing. This is required by `deserialize_any`, and thus
+    /// by untagged enums, flattened structs and skipping unknown fields.
+    ///
+    /// In this mode structs are encoded as maps keyed by field name, and enums as the
+    /// name of the variant, or as a single-entry map from the name to the content.
+    pub self_describing: bool,
+    /// The ASN.1 encoding rules followed by the types in the [`asn1`] module and
+    /// by the [`Asn1`][`NumEncoding::Asn1`] numerical encoding, which determine
+    /// whether non-canonical data is accepted while decoding.
+    pub asn1_rules: asn1::Asn1Rules,
+    /// The data format that numbers, strings, booleans, variants and lengths are emitted in.
+    pub format: Format,
 }
 
 impl BinSettings {
@@ -1011,9 +1259,148 @@ impl BinSettings {
             size_repr: SizeRepr::new(),
             variant_repr: VariantRepr::new(),
             string_repr: StringRepr::new(),
+            self_describing: false,
+            asn1_rules: asn1::Asn1Rules::Der,
+            format: Format::Binary,
         }
     }
 
+    /// Returns the options matching the default configuration of
+    /// [bincode 1](https://github.com/bincode-org/bincode/tree/v1.3.3): little endian fixed-width
+    /// numbers, sizes encoded as `u64`, enum variants encoded as `u32` and
+    /// length-prefixed UTF-8 strings.
+    ///
+    /// `char`s are encoded as their UTF-8 bytes, like bincode does.
+    #[inline]
+    pub const fn bincode_v1() -> Self {
+        Self::new()
+            .num_repr(
+                NumRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::Fixed),
+            )
+            .size_repr(
+                SizeRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::Fixed)
+                    .bit_width(BitWidth::Bit64),
+            )
+            .variant_repr(
+                VariantRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::Fixed)
+                    .bit_width(BitWidth::Bit32),
+            )
+            .string_repr(
+                StringRepr::new()
+                    .str_encoding(StrEncoding::Utf8)
+                    .len_encoding(StrLen::LengthPrefixed),
+            )
+    }
+
+    /// Returns the options matching
+    /// [bincode 2's standard configuration](https://docs.rs/bincode/2/bincode/config/fn.standard.html):
+    /// numbers, sizes (as `u64`) and enum variants (as `u32`) use the
+    /// [BincodeVarint][`NumEncoding::BincodeVarint`] encoding in little endian,
+    /// and strings are length-prefixed UTF-8.
+    ///
+    /// `char`s are encoded as their UTF-8 bytes, like bincode does.
+    #[inline]
+    pub const fn bincode_v2_standard() -> Self {
+        Self::new()
+            .num_repr(
+                NumRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::BincodeVarint),
+            )
+            .size_repr(
+                SizeRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::BincodeVarint)
+                    .bit_width(BitWidth::Bit64),
+            )
+            .variant_repr(
+                VariantRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::BincodeVarint)
+                    .bit_width(BitWidth::Bit32),
+            )
+            .string_repr(
+                StringRepr::new()
+                    .str_encoding(StrEncoding::Utf8)
+                    .len_encoding(StrLen::LengthPrefixed),
+            )
+    }
+
+    /// Returns the options matching the [postcard](https://postcard.jamesmunns.com/wire-format)
+    /// wire format: numbers, sizes and enum variants (as `u32`) use the
+    /// [PostcardVarint][`NumEncoding::PostcardVarint`] encoding, and strings are
+    /// length-prefixed UTF-8.
+    ///
+    /// Postcard encodes a `char` as a length-prefixed string, while ender always
+    /// encodes it as its UTF-8 bytes: use a `str` instead when compatibility is required.
+    #[inline]
+    pub const fn postcard() -> Self {
+        Self::new()
+            .num_repr(
+                NumRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::PostcardVarint),
+            )
+            .size_repr(
+                SizeRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::PostcardVarint)
+                    .bit_width(BitWidth::Bit64),
+            )
+            .variant_repr(
+                VariantRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::PostcardVarint)
+                    .bit_width(BitWidth::Bit32),
+            )
+            .string_repr(
+                StringRepr::new()
+                    .str_encoding(StrEncoding::Utf8)
+                    .len_encoding(StrLen::LengthPrefixed),
+            )
+    }
+
+    /// Returns options resembling the scalar encoding of
+    /// [Protocol Buffers](https://protobuf.dev/programming-guides/encoding/): numbers use the
+    /// [ProtobufZigzag][`NumEncoding::ProtobufZigzag`] encoding (like `uint32`, `uint64`,
+    /// `sint32` and `sint64`), sizes and enum variants are unsigned varints,
+    /// floats are fixed little endian and strings are length-prefixed UTF-8.
+    ///
+    /// Only the encoding of individual values matches: protobuf messages also carry
+    /// field tags, which these settings alone cannot produce.
+    #[inline]
+    pub const fn protobuf_like() -> Self {
+        Self::new()
+            .num_repr(
+                NumRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::ProtobufZigzag),
+            )
+            .size_repr(
+                SizeRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::Leb128)
+                    .bit_width(BitWidth::Bit64),
+            )
+            .variant_repr(
+                VariantRepr::new()
+                    .endianness(Endianness::LittleEndian)
+                    .num_encoding(NumEncoding::Leb128)
+                    .bit_width(BitWidth::Bit32),
+            )
+            .string_repr(
+                StringRepr::new()
+                    .str_encoding(StrEncoding::Utf8)
+                    .len_encoding(StrLen::LengthPrefixed),
+            )
+    }
+
     /// Sets the **number** representation settings, then returns self.
     #[inline]
     pub const fn num_repr(mut self, num_repr: NumRepr) -> Self {
@@ -1041,6 +1428,27 @@ impl BinSettings {
         self.string_repr = string_repr;
         self
     }
+
+    /// Sets whether serde data is **self-describing**, then returns self.
+    #[inline]
+    pub const fn self_describing(mut self, self_describing: bool) -> Self {
+        self.self_describing = self_describing;
+        self
+    }
+
+    /// Sets the **ASN.1 encoding rules**, then returns self.
+    #[inline]
+    pub const fn asn1_rules(mut self, asn1_rules: asn1::Asn1Rules) -> Self {
+        self.asn1_rules = asn1_rules;
+        self
+    }
+
+    /// Sets the data **format**, then returns self.
+    #[inline]
+    pub const fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
 }
 
 impl Default for BinSettings {
@@ -1088,6 +1496,35 @@ pub struct Context<'a> {
     /// and while **Decoding** it contains the length itself
     /// (it won't be read from the stream).
     pub size_flatten: Option<usize>,
+    /// The version of the format being encoded or decoded, `0` by default.
+    ///
+    /// Fields with the `since` and `until` flags are only present in the versions
+    /// in range.
+    pub version: u32,
+    /// The ASN.1 tag state variable.
+    ///
+    /// When present, the next ASN.1 value replaces the class and number of its own
+    /// tag with these, which is how implicit tagging is implemented.
+    pub asn1_tag: Option<asn1::Tag>,
+    /// An ASN.1 tag that was already read from the stream by
+    /// [`peek_asn1_tag`][`Encoder::peek_asn1_tag`], and will be
+    /// returned by the next read of a tag.
+    pub asn1_peeked: Option<asn1::Tag>,
+    /// The number of bytes that can still be allocated while decoding, or `None` if unlimited.
+    ///
+    /// Unlike [`SizeRepr::max_size`], which limits each length on its own, the budget is shared
+    /// by the whole decoding operation: every collection charges the memory taken by its items
+    /// to it before allocating them, and an [`EncodingError::AllocBudgetExceeded`]
+    /// is returned once it runs out.
+    pub alloc_budget: Option<usize>,
+    /// The maximum nesting depth of the collections, boxes and derived items being decoded,
+    /// or `None` if unlimited.
+    ///
+    /// Exceeding it returns an [`EncodingError::MaxDepthExceeded`], which keeps recursive
+    /// data structures from overflowing the stack.
+    pub max_depth: Option<usize>,
+    /// The nesting depth of the value being decoded, `0` at the top level.
+    pub depth: usize,
 }
 
 impl<'a> Context<'a> {
@@ -1100,6 +1537,12 @@ impl<'a> Context<'a> {
             bool_flatten: None,
             variant_flatten: None,
             size_flatten: None,
+            version: 0,
+            asn1_tag: None,
+            asn1_peeked: None,
+            alloc_budget: None,
+            max_depth: None,
+            depth: 0,
         }
     }
 
@@ -1119,6 +1562,12 @@ impl<'a> Context<'a> {
             bool_flatten: self.bool_flatten,
             variant_flatten: self.variant_flatten,
             size_flatten: self.size_flatten,
+            version: self.version,
+            asn1_tag: self.asn1_tag,
+            asn1_peeked: self.asn1_peeked,
+            alloc_budget: self.alloc_budget,
+            max_depth: self.max_depth,
+            depth: self.depth,
         };
         this
     }
@@ -1154,6 +1603,27 @@ impl<'a> Context<'a> {
         self.size_flatten = Some(value);
         self
     }
+
+    /// Replaces the format version with `version`, then returns self.
+    #[inline]
+    pub const fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Replaces the allocation budget with `Some(budget)`, then returns self.
+    #[inline]
+    pub const fn alloc_budget(mut self, budget: usize) -> Self {
+        self.alloc_budget = Some(budget);
+        self
+    }
+
+    /// Replaces the max nesting depth with `Some(depth)`, then returns self.
+    #[inline]
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
     
     /// Just like [`Self::new`] but uses the given settings instead of the default.
     #[inline]
@@ -1164,6 +1634,12 @@ impl<'a> Context<'a> {
             bool_flatten: None,
             variant_flatten: None,
             size_flatten: None,
+            version: 0,
+            asn1_tag: None,
+            asn1_peeked: None,
+            alloc_budget: None,
+            max_depth: None,
+            depth: 0,
         }
     }
 
@@ -1177,6 +1653,12 @@ impl<'a> Context<'a> {
             bool_flatten: None,
             variant_flatten: None,
             size_flatten: None,
+            version: 0,
+            asn1_tag: None,
+            asn1_peeked: None,
+            alloc_budget: None,
+            max_depth: None,
+            depth: 0,
         }
     }
 
@@ -1187,6 +1669,9 @@ impl<'a> Context<'a> {
         self.bool_flatten = None;
         self.variant_flatten = None;
         self.size_flatten = None;
+        self.asn1_tag = None;
+        self.asn1_peeked = None;
+        self.depth = 0;
     }
 
     /// Returns the state of the [`bool`] flatten variable, consuming it.
@@ -1206,6 +1691,12 @@ impl<'a> Context<'a> {
     pub fn consume_size_flatten(&mut self) -> Option<usize> {
         replace(&mut self.size_flatten, None)
     }
+
+    /// Returns the state of the ASN.1 tag variable, consuming it.
+    #[inline]
+    pub fn consume_asn1_tag(&mut self) -> Option<asn1::Tag> {
+        replace(&mut self.asn1_tag, None)
+    }
 }
 
 /// The base type for encoding/decoding. Wraps a stream, and a [`Context`].<br>
@@ -1294,6 +1785,94 @@ impl<'a, T> Encoder<'a, T> {
     debug_fn!(with_variant, Variant(&'static str));
     debug_fn!(with_field, Field(&'static str));
     debug_fn!(with_index, Index(usize));
+
+    /// Calls the closure with an encoder that feeds every byte written or read through it
+    /// into the given [`Checksum`], then returns the closure's result along with the digest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ender::{Context, Encoder};
+    /// use ender::io::{Crc32, VecStream};
+    ///
+    /// let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+    /// let ((), crc) = encoder.with_checksum(Crc32::new(), |encoder| {
+    ///     encoder.write_bytes(b"123456789")
+    /// }).unwrap();
+    ///
+    /// assert_eq!(crc, 0xCBF43926);
+    /// ```
+    pub fn with_checksum<C, F, R>(&mut self, checksum: C, f: F) -> EncodingResult<(R, C::Output)>
+    where
+        C: Checksum,
+        F: FnOnce(&mut Encoder<ChecksumTrack<&mut T, C>>) -> EncodingResult<R>,
+    {
+        let mut tracked = Encoder::new(ChecksumTrack::new(&mut self.stream, checksum), self.ctxt);
+        let value = f(&mut tracked)?;
+        let (stream, ctxt) = tracked.finish();
+        self.ctxt = ctxt;
+        Ok((value, stream.digest()))
+    }
+
+    /// Charges the memory taken by `len` values of type `V` to the allocation budget
+    /// of the context, returning an [`EncodingError::AllocBudgetExceeded`] if it runs out.
+    ///
+    /// This is called by the collections before allocating their items while decoding.
+    #[inline]
+    pub fn charge_alloc<V>(&mut self, len: usize) -> EncodingResult<()> {
+        let Some(remaining) = self.ctxt.alloc_budget else {
+            return Ok(());
+        };
+        let requested = len.saturating_mul(core::mem::size_of::<V>());
+        if requested > remaining {
+            return Err(EncodingError::AllocBudgetExceeded {
+                remaining,
+                requested,
+            });
+        }
+        self.ctxt.alloc_budget = Some(remaining - requested);
+        Ok(())
+    }
+
+    /// Increases the nesting depth of the context, returning an
+    /// [`EncodingError::MaxDepthExceeded`] if it would exceed the max depth.
+    ///
+    /// This must be paired with a call to [`exit_nested`][`Self::exit_nested`] once the
+    /// contents are decoded, whether successfully or not: [`with_nested`][`Self::with_nested`]
+    /// takes care of both.
+    #[inline]
+    pub fn enter_nested(&mut self) -> EncodingResult<()> {
+        if let Some(max) = self.ctxt.max_depth {
+            if self.ctxt.depth >= max {
+                return Err(EncodingError::MaxDepthExceeded { max });
+            }
+        }
+        self.ctxt.depth += 1;
+        Ok(())
+    }
+
+    /// Decreases the nesting depth of the context.
+    ///
+    /// This is the counterpart of [`enter_nested`][`Self::enter_nested`].
+    #[inline]
+    pub fn exit_nested(&mut self) {
+        self.ctxt.depth = self.ctxt.depth.saturating_sub(1);
+    }
+
+    /// Calls the closure one level of nesting deeper, restoring the depth once it returns,
+    /// even if it fails.
+    ///
+    /// This is called by the collections, boxes and derived items to decode their contents.
+    #[inline]
+    pub fn with_nested<F, R>(&mut self, f: F) -> EncodingResult<R>
+    where
+        F: FnOnce(&mut Encoder<T>) -> EncodingResult<R>,
+    {
+        self.enter_nested()?;
+        let r = f(self);
+        self.exit_nested();
+        r
+    }
 }
 
 impl<T: Write> Encoder<'_, T> {
@@ -1318,6 +1897,27 @@ impl<T: Read> Encoder<'_, T> {
     pub fn decode_value<V: Decode<T>>(&mut self) -> EncodingResult<V> {
         V::decode(self)
     }
+
+    /// Returns how many of the `len` items of a collection to reserve memory for upfront.
+    ///
+    /// Every item is assumed to take at least one byte, so when the number of bytes left in
+    /// the stream is known, the capacity is capped by it: a corrupted length can't make
+    /// the decoder reserve more memory than the data could ever fill.
+    #[inline]
+    pub fn capacity_hint(&self, len: usize) -> usize {
+        self.stream.remaining_len().map_or(len, |x| x.min(len))
+    }
+
+    // Returns an `UnexpectedEnd` error if the stream is known to hold less than `len` bytes,
+    // before a buffer of that size is allocated
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub(crate) fn check_remaining(&self, len: usize) -> EncodingResult<()> {
+        match self.stream.remaining_len() {
+            Some(remaining) if remaining < len => Err(EncodingError::UnexpectedEnd),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl<'a, T> Encoder<'a, T> {
@@ -1327,6 +1927,48 @@ impl<'a, T> Encoder<'a, T> {
     }
 }
 
+impl<T: Write> Encoder<'_, T> {
+    // Encodes an unsigned value of any width according to the `BincodeVarint` encoding
+    fn write_bincode_varint(&mut self, value: u128, endianness: Endianness) -> EncodingResult<()> {
+        if value < 251 {
+            self.write_byte(value as u8)
+        } else if value <= u16::MAX as u128 {
+            self.write_byte(251)?;
+            self.write_u16_with(value as u16, NumEncoding::Fixed, endianness)
+        } else if value <= u32::MAX as u128 {
+            self.write_byte(252)?;
+            self.write_u32_with(value as u32, NumEncoding::Fixed, endianness)
+        } else if value <= u64::MAX as u128 {
+            self.write_byte(253)?;
+            self.write_u64_with(value as u64, NumEncoding::Fixed, endianness)
+        } else {
+            self.write_byte(254)?;
+            self.write_u128_with(value, NumEncoding::Fixed, endianness)
+        }
+    }
+
+    // Encodes an unsigned value of any width according to the `Asn1` encoding
+    fn write_asn1_length(&mut self, value: u128) -> EncodingResult<()> {
+        if value < 0x80 {
+            return self.write_byte(value as u8);
+        }
+
+        let bytes = value.to_be_bytes();
+        let skip = value.leading_zeros() as usize / 8;
+        self.write_byte(0x80 | (bytes.len() - skip) as u8)?;
+        self.write_bytes(&bytes[skip..])
+    }
+}
+
+/// Reverses the bytes of each `size`-byte value in the buffer, converting them
+/// between little endian and big endian.
+#[inline]
+fn swap_bytes(bytes: &mut [u8], size: usize) {
+    for value in bytes.chunks_exact_mut(size) {
+        value.reverse();
+    }
+}
+
 macro_rules! make_write_fns {
     (
 	    type $uty:ty {
@@ -1365,6 +2007,9 @@ macro_rules! make_write_fns {
 	    #[doc = "` to the underlying stream, according to the endianness and numerical encoding in the encoder's state"]
         #[inline]
 	    pub fn $u_write(&mut self, value: $uty) -> EncodingResult<()> {
+		    if self.ctxt.settings.format != Format::Binary {
+			    return self.write_fmt_int(format::Int::from(value));
+		    }
 		    self.$u_write_direct(value, self.ctxt.settings.num_repr.num_encoding, self.ctxt.settings.num_repr.endianness)
 	    }
 
@@ -1378,7 +2023,16 @@ macro_rules! make_write_fns {
 		            };
 		            self.stream.write(&bytes)?;
 		        },
-		        NumEncoding::Leb128 | NumEncoding::ProtobufWasteful | NumEncoding::ProtobufZigzag => {
+		        NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if <$uty>::BITS == 8 => {
+			        self.stream.write(&value.to_le_bytes())?;
+		        }
+		        NumEncoding::BincodeVarint => {
+			        self.write_bincode_varint(value as u128, endianness)?;
+		        }
+		        NumEncoding::Asn1 => {
+			        self.write_asn1_length(value as u128)?;
+		        }
+		        NumEncoding::Leb128 | NumEncoding::ProtobufWasteful | NumEncoding::ProtobufZigzag | NumEncoding::PostcardVarint => {
 			        self.$uleb128_encode(value)?;
 		        }
 	        }
@@ -1409,6 +2063,9 @@ macro_rules! make_write_fns {
 	    #[doc = "` to the underlying stream, according to the endianness and numerical encoding in the encoder's state"]
         #[inline]
 	    pub fn $i_write(&mut self, value: $ity) -> EncodingResult<()> {
+		    if self.ctxt.settings.format != Format::Binary {
+			    return self.write_fmt_int(format::Int::from(value));
+		    }
 		    self.$i_write_direct(value, self.ctxt.settings.num_repr.num_encoding, self.ctxt.settings.num_repr.endianness)
 	    }
 
@@ -1422,6 +2079,9 @@ macro_rules! make_write_fns {
 		            };
 		            self.stream.write(&bytes)?;
 		        },
+		        NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if <$ity>::BITS == 8 => {
+			        self.stream.write(&value.to_le_bytes())?;
+		        }
 		        NumEncoding::Leb128 => {
 			        self.$leb128_encode(value)?;
 		        },
@@ -1429,11 +2089,20 @@ macro_rules! make_write_fns {
 			        let unsigned = <$uty>::from_ne_bytes(value.to_ne_bytes());
 			        self.$uleb128_encode(unsigned)?;
 		        }
-			    NumEncoding::ProtobufZigzag => {
+		        NumEncoding::Asn1 => {
+			        let unsigned = <$uty>::from_ne_bytes(value.to_ne_bytes());
+			        self.write_asn1_length(unsigned as u128)?;
+		        }
+			    NumEncoding::ProtobufZigzag | NumEncoding::PostcardVarint => {
 			        let shifted = (value << 1) ^ (value >> (<$ity>::BITS - 1));
 			        let unsigned = <$uty>::from_ne_bytes(shifted.to_ne_bytes());
 			        self.$uleb128_encode(unsigned)?;
 		        }
+			    NumEncoding::BincodeVarint => {
+			        let shifted = (value << 1) ^ (value >> (<$ity>::BITS - 1));
+			        let unsigned = <$uty>::from_ne_bytes(shifted.to_ne_bytes());
+			        self.write_bincode_varint(unsigned as u128, endianness)?;
+		        }
 	        }
             Ok(())
         }
@@ -1527,6 +2196,9 @@ impl<T: Write> Encoder<'_, T> {
                     requested: value,
                 });
             }
+            if self.ctxt.settings.format != Format::Binary {
+                return self.write_fmt_int(format::Int::from(value));
+            }
             let encoding = self.ctxt.settings.size_repr.num_encoding;
             let endianness = self.ctxt.settings.size_repr.endianness;
 
@@ -1547,6 +2219,9 @@ impl<T: Write> Encoder<'_, T> {
     /// numerical encoding and bit-width in the encoder's state.
     #[inline]
     pub fn write_isize(&mut self, value: isize) -> EncodingResult<()> {
+        if self.ctxt.settings.format != Format::Binary {
+            return self.write_fmt_int(format::Int::from(value));
+        }
         let encoding = self.ctxt.settings.size_repr.num_encoding;
         let endianness = self.ctxt.settings.size_repr.endianness;
 
@@ -1562,6 +2237,75 @@ impl<T: Write> Encoder<'_, T> {
         }
     }
 
+    /// Encodes the length of a sequence, such as a slice or a `Vec`.
+    ///
+    /// In the [`Binary`][`Format::Binary`] format this is the same as
+    /// [`write_usize`][`Self::write_usize`], while the self-describing formats write
+    /// the header of an array. In both cases, nothing is written if the size flatten
+    /// variable is set to `Some`.
+    #[inline]
+    pub fn write_seq_len(&mut self, len: usize) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary || self.ctxt.size_flatten.is_some() {
+            return self.write_usize(len);
+        }
+        self.check_max_size(len)?;
+        self.write_fmt_seq_len(len)
+    }
+
+    /// Encodes the length of a map, such as a `HashMap`.
+    ///
+    /// In the [`Binary`][`Format::Binary`] format this is the same as
+    /// [`write_usize`][`Self::write_usize`], while the self-describing formats write
+    /// the header of a map. In both cases, nothing is written if the size flatten
+    /// variable is set to `Some`.
+    #[inline]
+    pub fn write_map_len(&mut self, len: usize) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary || self.ctxt.size_flatten.is_some() {
+            return self.write_usize(len);
+        }
+        self.check_max_size(len)?;
+        self.write_fmt_map_len(len)
+    }
+
+    /// Encodes the number of items that make up a struct, a tuple or an enum variant.
+    ///
+    /// In the [`Binary`][`Format::Binary`] format nothing is written, while the
+    /// self-describing formats write the header of an array, so that the items
+    /// are grouped together.
+    #[inline]
+    pub fn write_fields_len(&mut self, len: usize) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary {
+            return Ok(());
+        }
+        self.write_fmt_seq_len(len)
+    }
+
+    /// Encodes what precedes the variant of an enum.
+    ///
+    /// In the [`Binary`][`Format::Binary`] format nothing is written, while the
+    /// self-describing formats write the header of an array of two items: the variant
+    /// and an array of its fields. The header is omitted if the variant flatten variable
+    /// is set to `Some`, since the variant itself won't be written.
+    #[inline]
+    pub fn write_enum_header(&mut self) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary || self.ctxt.variant_flatten.is_some() {
+            return Ok(());
+        }
+        self.write_fmt_seq_len(2)
+    }
+
+    /// Encodes the absence of a value, such as a field whose condition isn't met or `()`.
+    ///
+    /// In the [`Binary`][`Format::Binary`] format nothing is written, while the
+    /// self-describing formats write null.
+    #[inline]
+    pub fn write_null(&mut self) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary {
+            return Ok(());
+        }
+        self.write_fmt_null()
+    }
+
     /// Encodes an unsigned `Variant`.
     ///
     /// If the `Variant` flatten variable is set to `Some`,
@@ -1586,6 +2330,8 @@ impl<T: Write> Encoder<'_, T> {
                 .into());
             }
             Ok(())
+        } else if self.ctxt.settings.format != Format::Binary {
+            self.write_fmt_int(format::Int::from_opaque(value)?)
         } else {
             let width = self.ctxt.settings.variant_repr.width;
             let encoding = self.ctxt.settings.variant_repr.num_encoding;
@@ -1624,6 +2370,8 @@ impl<T: Write> Encoder<'_, T> {
                 .into());
             }
             Ok(())
+        } else if self.ctxt.settings.format != Format::Binary {
+            self.write_fmt_int(format::Int::from_opaque(value)?)
         } else {
             let width = self.ctxt.settings.variant_repr.width;
             let encoding = self.ctxt.settings.variant_repr.num_encoding;
@@ -1658,6 +2406,8 @@ impl<T: Write> Encoder<'_, T> {
                 .into());
             }
             Ok(())
+        } else if self.ctxt.settings.format != Format::Binary {
+            self.write_fmt_bool(value)
         } else {
             self.write_byte(if value { 1 } else { 0 })
         }
@@ -1667,7 +2417,10 @@ impl<T: Write> Encoder<'_, T> {
     /// in the encoder's state.
     #[inline]
     pub fn write_char(&mut self, value: char) -> EncodingResult<()> {
-        if value == '\0' {
+        if self.ctxt.settings.format != Format::Binary {
+            // A string made of a single char
+            self.write_fmt_str(core::iter::once(value))
+        } else if value == '\0' {
             self.write_char_or_null(None)
         } else {
             self.write_char_or_null(Some(value))
@@ -1726,6 +2479,9 @@ impl<T: Write> Encoder<'_, T> {
     /// encoding set to Fixed
     #[inline]
     pub fn write_f32(&mut self, value: f32) -> EncodingResult<()> {
+        if self.ctxt.settings.format != Format::Binary {
+            return self.write_fmt_f32(value);
+        }
         self.write_u32_with(
             value.to_bits(),
             NumEncoding::Fixed,
@@ -1738,6 +2494,9 @@ impl<T: Write> Encoder<'_, T> {
     /// encoding set to Fixed
     #[inline]
     pub fn write_f64(&mut self, value: f64) -> EncodingResult<()> {
+        if self.ctxt.settings.format != Format::Binary {
+            return self.write_fmt_f64(value);
+        }
         self.write_u64_with(
             value.to_bits(),
             NumEncoding::Fixed,
@@ -1764,17 +2523,14 @@ impl<T: Write> Encoder<'_, T> {
         S: IntoIterator<Item = char, IntoIter: Clone>,
     {
         let chars = string.into_iter();
+        if self.ctxt.settings.format != Format::Binary {
+            return self.write_fmt_str(chars);
+        }
 
         match self.ctxt.settings.string_repr.len {
             StrLen::LengthPrefixed => {
-                // We don't know the length of the string in advance
-
-                // Create a fake encoder that simply keeps track of the length
-                let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), self.ctxt);
-                for ch in chars.clone() {
-                    sz_encoder.write_char(ch)?;
-                }
-                let size = sz_encoder.finish().0.size_written();
+                // The length is computed from the size of each char
+                let size = self.ctxt.str_data_size(chars.clone())?;
 
                 // Now encode the length and the string data
                 self.write_usize(size)?;
@@ -1820,6 +2576,149 @@ impl<T: Write> Encoder<'_, T> {
     pub fn write_bytes(&mut self, bytes: &[u8]) -> EncodingResult<()> {
         self.stream.write(bytes)
     }
+
+    /// Writes a slice of plain-old-data values to the underlying stream, as if each value was
+    /// written with the fixed numerical encoding and the given endianness.
+    ///
+    /// When the endianness matches the system's, the slice is written as-is in a single call,
+    /// otherwise the bytes of each value are swapped one chunk at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ender::{Context, Encoder, Endianness};
+    /// use ender::io::VecStream;
+    ///
+    /// let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+    /// encoder.write_pod_slice(&[1u16, 2], Endianness::BigEndian).unwrap();
+    ///
+    /// assert_eq!(encoder.finish().0.into_inner(), [0, 1, 0, 2]);
+    /// ```
+    #[inline]
+    pub fn write_pod_slice<P: bytemuck::Pod>(
+        &mut self,
+        values: &[P],
+        endianness: Endianness,
+    ) -> EncodingResult<()> {
+        let size = core::mem::size_of::<P>();
+        if endianness == Endianness::native() || size <= 1 {
+            return self.stream.write(bytemuck::cast_slice(values));
+        }
+
+        let mut buf = [0u8; 1024];
+        for chunk in values.chunks(buf.len() / size) {
+            let bytes = &mut buf[..core::mem::size_of_val(chunk)];
+            bytes.copy_from_slice(bytemuck::cast_slice(chunk));
+            swap_bytes(bytes, size);
+            self.stream.write(bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a magic number (or any other constant) to the underlying stream as-is.
+    ///
+    /// This is the counterpart of [`read_magic`][`Self::read_magic`].
+    #[inline]
+    pub fn write_magic(&mut self, magic: &'static [u8]) -> EncodingResult<()> {
+        self.stream.write(magic)
+    }
+
+    /// Encodes a value into a byte-counted region: the size in bytes of the region is
+    /// written first as an `usize`, followed by the region itself.
+    ///
+    /// Since the size must be known in advance, the value is encoded twice: `measure` encodes
+    /// it into a fake encoder that simply keeps track of the length, then `f` encodes it into
+    /// the actual stream, which is limited to the measured size.
+    /// Both closures must produce the same data.
+    ///
+    /// This is the counterpart of [`read_sized`][`Self::read_sized`].
+    // Notice the `StreamModifier` signature
+    pub fn write_sized<M, F>(&mut self, measure: M, f: F) -> EncodingResult<()>
+    where
+        M: FnOnce(&mut Encoder<SizeTrack<Zero>>) -> EncodingResult<()>,
+        F: FnOnce(&mut Encoder<SizeLimit<&mut T>>) -> EncodingResult<()>,
+    {
+        // The size flatten variable belongs to the value, not to the size of the region
+        let size_flatten = self.ctxt.consume_size_flatten();
+
+        let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), self.ctxt);
+        sz_encoder.ctxt.size_flatten = size_flatten;
+        measure(&mut sz_encoder)?;
+        let size = sz_encoder.finish().0.size_written();
+
+        self.write_usize(size)?;
+
+        let mut capped = Encoder::new(SizeLimit::new(&mut self.stream, size, 0), self.ctxt);
+        capped.ctxt.size_flatten = size_flatten;
+        f(&mut capped)?;
+        self.ctxt = capped.ctxt;
+
+        let remaining = capped.stream.remaining_writable();
+        if remaining != 0 {
+            return Err(val_error!(
+                "A byte-counted region was {} bytes shorter than its measured size of {}",
+                remaining,
+                size
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes `len` padding bytes with the value `fill` to the underlying stream.
+    ///
+    /// This is the counterpart of [`read_padding`][`Self::read_padding`].
+    #[inline]
+    pub fn write_padding(&mut self, len: usize, fill: u8) -> EncodingResult<()> {
+        let buf = [fill; 16];
+        let mut remaining = len;
+        while remaining != 0 {
+            let len = remaining.min(buf.len());
+            self.stream.write(&buf[..len])?;
+            remaining -= len;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    // Decodes an unsigned value of any width according to the `BincodeVarint` encoding
+    fn read_bincode_varint(&mut self, endianness: Endianness) -> EncodingResult<u128> {
+        Ok(match self.read_byte()? {
+            251 => self.read_u16_with(NumEncoding::Fixed, endianness)? as u128,
+            252 => self.read_u32_with(NumEncoding::Fixed, endianness)? as u128,
+            253 => self.read_u64_with(NumEncoding::Fixed, endianness)? as u128,
+            254 => self.read_u128_with(NumEncoding::Fixed, endianness)?,
+            255 => return Err(EncodingError::VarIntError),
+            byte => byte as u128,
+        })
+    }
+
+    // Decodes an unsigned value of any width according to the `Asn1` encoding
+    fn read_asn1_length(&mut self) -> EncodingResult<u128> {
+        let first = self.read_byte()?;
+        if first < 0x80 {
+            return Ok(first as u128);
+        }
+
+        let len = (first & 0x7F) as usize;
+        if len == 0 {
+            return Err(Asn1Error::IndefiniteLength.into());
+        }
+        if len > 16 {
+            return Err(EncodingError::VarIntError);
+        }
+
+        let mut bytes = [0u8; 16];
+        self.stream.read(&mut bytes[16 - len..])?;
+        let value = u128::from_be_bytes(bytes);
+
+        // DER requires the shortest form
+        let canonical = bytes[16 - len] != 0 && value >= 0x80;
+        if !canonical && self.ctxt.settings.asn1_rules == asn1::Asn1Rules::Der {
+            return Err(Asn1Error::NonCanonical.into());
+        }
+        Ok(value)
+    }
 }
 
 macro_rules! make_read_fns {
@@ -1862,6 +2761,9 @@ macro_rules! make_read_fns {
 	    #[doc = "` from the underlying stream, according to the endianness and numerical encoding in the encoder's state"]
         #[inline]
 	    pub fn $u_read(&mut self) -> EncodingResult<$uty> {
+		    if self.ctxt.settings.format != Format::Binary {
+			    return self.read_fmt_int()?.try_into();
+		    }
 		    self.$u_read_direct(self.ctxt.settings.num_repr.num_encoding, self.ctxt.settings.num_repr.endianness)
 	    }
 
@@ -1877,7 +2779,20 @@ macro_rules! make_read_fns {
 			            Endianness::LittleEndian => <$uty>::from_le_bytes(bytes)
 		            }
 		        }
-		        NumEncoding::Leb128 | NumEncoding::ProtobufWasteful | NumEncoding::ProtobufZigzag => {
+		        NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if <$uty>::BITS == 8 => {
+			        let mut bytes: [u8; core::mem::size_of::<$uty>()] = [0u8; core::mem::size_of::<$uty>()];
+		            self.stream.read(&mut bytes)?;
+			        <$uty>::from_le_bytes(bytes)
+		        }
+		        NumEncoding::BincodeVarint => {
+			        let value = self.read_bincode_varint(endianness)?;
+			        <$uty>::try_from(value).map_err(|_| EncodingError::VarIntError)?
+		        }
+		        NumEncoding::Asn1 => {
+			        let value = self.read_asn1_length()?;
+			        <$uty>::try_from(value).map_err(|_| EncodingError::VarIntError)?
+		        }
+		        NumEncoding::Leb128 | NumEncoding::ProtobufWasteful | NumEncoding::ProtobufZigzag | NumEncoding::PostcardVarint => {
 			        self.$uleb128_decode()?
 		        }
 	        })
@@ -1913,6 +2828,9 @@ macro_rules! make_read_fns {
 	    #[doc = "` from the underlying stream, according to the endianness and numerical encoding in the encoder's context"]
         #[inline]
 	    pub fn $i_read(&mut self) -> EncodingResult<$ity> {
+		    if self.ctxt.settings.format != Format::Binary {
+			    return self.read_fmt_int()?.try_into();
+		    }
 		    self.$i_read_direct(self.ctxt.settings.num_repr.num_encoding, self.ctxt.settings.num_repr.endianness)
 	    }
 
@@ -1935,8 +2853,23 @@ macro_rules! make_read_fns {
 			        let unsigned = self.$uleb128_decode()?;
 			        <$ity>::from_ne_bytes(unsigned.to_ne_bytes())
 		        }
-		        NumEncoding::ProtobufZigzag => {
-			        let unsigned = self.$uleb128_decode()?;
+		        NumEncoding::Asn1 => {
+			        let value = self.read_asn1_length()?;
+			        let unsigned = <$uty>::try_from(value).map_err(|_| EncodingError::VarIntError)?;
+			        <$ity>::from_ne_bytes(unsigned.to_ne_bytes())
+		        }
+		        NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if <$ity>::BITS == 8 => {
+			        let mut bytes: [u8; core::mem::size_of::<$ity>()] = [0u8; core::mem::size_of::<$ity>()];
+		            self.stream.read(&mut bytes)?;
+			        <$ity>::from_le_bytes(bytes)
+		        }
+		        NumEncoding::ProtobufZigzag | NumEncoding::PostcardVarint | NumEncoding::BincodeVarint => {
+			        let unsigned = if num_encoding == NumEncoding::BincodeVarint {
+				        let value = self.read_bincode_varint(endianness)?;
+				        <$uty>::try_from(value).map_err(|_| EncodingError::VarIntError)?
+			        } else {
+				        self.$uleb128_decode()?
+			        };
 			        let neg = (unsigned & 1) != 0;
 			        let transformed = if neg {
 				        !(unsigned >> 1)
@@ -2024,6 +2957,9 @@ impl<T: Read> Encoder<'_, T> {
     pub fn read_usize(&mut self) -> EncodingResult<usize> {
         if let Some(size) = self.ctxt.consume_size_flatten() {
             Ok(size)
+        } else if self.ctxt.settings.format != Format::Binary {
+            let value = self.read_fmt_int()?.try_into()?;
+            self.check_max_size(value)
         } else {
             let encoding = self.ctxt.settings.size_repr.num_encoding;
             let endianness = self.ctxt.settings.size_repr.endianness;
@@ -2050,6 +2986,9 @@ impl<T: Read> Encoder<'_, T> {
     /// numerical encoding and bit-width in the encoder's state
     #[inline]
     pub fn read_isize(&mut self) -> EncodingResult<isize> {
+        if self.ctxt.settings.format != Format::Binary {
+            return self.read_fmt_int()?.try_into();
+        }
         let encoding = self.ctxt.settings.size_repr.num_encoding;
         let endianness = self.ctxt.settings.size_repr.endianness;
         match self.ctxt.settings.size_repr.width {
@@ -2062,6 +3001,70 @@ impl<T: Read> Encoder<'_, T> {
         .try_into()
     }
 
+    /// Decodes the length of a sequence, such as a slice or a `Vec`.
+    ///
+    /// This is the counterpart of [`write_seq_len`][`Self::write_seq_len`].
+    #[inline]
+    pub fn read_seq_len(&mut self) -> EncodingResult<usize> {
+        if self.ctxt.settings.format == Format::Binary || self.ctxt.size_flatten.is_some() {
+            return self.read_usize();
+        }
+        self.read_fmt_seq_len()
+    }
+
+    /// Decodes the length of a map, such as a `HashMap`.
+    ///
+    /// This is the counterpart of [`write_map_len`][`Self::write_map_len`].
+    #[inline]
+    pub fn read_map_len(&mut self) -> EncodingResult<usize> {
+        if self.ctxt.settings.format == Format::Binary || self.ctxt.size_flatten.is_some() {
+            return self.read_usize();
+        }
+        self.read_fmt_map_len()
+    }
+
+    /// Decodes the number of items that make up a struct, a tuple or an enum variant,
+    /// returning an error if it isn't `len`.
+    ///
+    /// This is the counterpart of [`write_fields_len`][`Self::write_fields_len`].
+    #[inline]
+    pub fn read_fields_len(&mut self, len: usize) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary {
+            return Ok(());
+        }
+        let found = self.read_fmt_seq_len()?;
+        if found != len {
+            return Err(FormatError::LengthMismatch {
+                expected: len,
+                found,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Decodes what precedes the variant of an enum.
+    ///
+    /// This is the counterpart of [`write_enum_header`][`Self::write_enum_header`].
+    #[inline]
+    pub fn read_enum_header(&mut self) -> EncodingResult<()> {
+        if self.ctxt.variant_flatten.is_some() {
+            return Ok(());
+        }
+        self.read_fields_len(2)
+    }
+
+    /// Decodes the absence of a value.
+    ///
+    /// This is the counterpart of [`write_null`][`Self::write_null`].
+    #[inline]
+    pub fn read_null(&mut self) -> EncodingResult<()> {
+        if self.ctxt.settings.format == Format::Binary {
+            return Ok(());
+        }
+        self.read_fmt_null()
+    }
+
     /// Decodes an unsigned `Variant`.
     ///
     /// If the `Variant` flatten variable is set to `Some`, this function
@@ -2077,6 +3080,8 @@ impl<T: Read> Encoder<'_, T> {
     {
         if let Some(variant) = self.ctxt.consume_variant_flatten() {
             variant.try_into()
+        } else if self.ctxt.settings.format != Format::Binary {
+            self.read_fmt_int()?.into_opaque(Signedness::Unsigned)?.try_into()
         } else {
             let width = self.ctxt.settings.variant_repr.width;
             let encoding = self.ctxt.settings.variant_repr.num_encoding;
@@ -2108,6 +3113,8 @@ impl<T: Read> Encoder<'_, T> {
     {
         if let Some(variant) = self.ctxt.consume_variant_flatten() {
             variant.try_into()
+        } else if self.ctxt.settings.format != Format::Binary {
+            self.read_fmt_int()?.into_opaque(Signedness::Signed)?.try_into()
         } else {
             let width = self.ctxt.settings.variant_repr.width;
             let encoding = self.ctxt.settings.variant_repr.num_encoding;
@@ -2138,6 +3145,8 @@ impl<T: Read> Encoder<'_, T> {
     pub fn read_bool(&mut self) -> EncodingResult<bool> {
         if let Some(boolean) = self.ctxt.consume_bool_flatten() {
             Ok(boolean)
+        } else if self.ctxt.settings.format != Format::Binary {
+            self.read_fmt_bool()
         } else {
             match self.read_byte()? {
                 0 => Ok(false),
@@ -2151,6 +3160,20 @@ impl<T: Read> Encoder<'_, T> {
     /// in the encoder's state.
     #[inline]
     pub fn read_char(&mut self) -> EncodingResult<char> {
+        if self.ctxt.settings.format != Format::Binary {
+            // A string made of a single char
+            let mut buf = [0u8; 4];
+            let len = self.read_fmt_str_len()?;
+            let bytes = buf.get_mut(..len).ok_or(StringError::InvalidChar)?;
+            self.read_bytes(bytes)?;
+
+            let string = core::str::from_utf8(bytes).map_err(|_| StringError::InvalidChar)?;
+            let mut chars = string.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(ch), None) => Ok(ch),
+                _ => Err(StringError::InvalidChar.into()),
+            };
+        }
         Ok(self.read_char_or_null()?.unwrap_or('\0'))
     }
 
@@ -2195,7 +3218,6 @@ impl<T: Read> Encoder<'_, T> {
 
                 let mut ch: u32 = ((u8::MAX >> rshift) & buf) as u32;
 
-                let mut shift = 0;
                 for _ in 0..add {
                     buf = self.read_byte()?;
 
@@ -2204,8 +3226,8 @@ impl<T: Read> Encoder<'_, T> {
                         return Err(StringError::InvalidChar.into());
                     }
 
-                    shift += 6;
-                    ch = (ch << shift) | ((buf & 0b0011_1111) as u32);
+                    // Each continuation byte carries 6 bits
+                    ch = (ch << 6) | ((buf & 0b0011_1111) as u32);
                 }
 
                 Ok(Some(char::from_u32(ch).ok_or(
@@ -2278,6 +3300,9 @@ impl<T: Read> Encoder<'_, T> {
     /// encoding set to [`NumEncoding::Fixed`].
     #[inline]
     pub fn read_f32(&mut self) -> EncodingResult<f32> {
+        if self.ctxt.settings.format != Format::Binary {
+            return self.read_fmt_f32();
+        }
         Ok(f32::from_bits(self.read_u32_with(
             NumEncoding::Fixed,
             self.ctxt.settings.num_repr.endianness,
@@ -2289,6 +3314,9 @@ impl<T: Read> Encoder<'_, T> {
     /// encoding set to [`NumEncoding::Fixed`].
     #[inline]
     pub fn read_f64(&mut self) -> EncodingResult<f64> {
+        if self.ctxt.settings.format != Format::Binary {
+            return self.read_fmt_f64();
+        }
         Ok(f64::from_bits(self.read_u64_with(
             NumEncoding::Fixed,
             self.ctxt.settings.num_repr.endianness,
@@ -2297,6 +3325,8 @@ impl<T: Read> Encoder<'_, T> {
 
     /// Decodes a String from the underlying stream, according to the endianness,
     /// and string encoding in the encoder's state.
+    ///
+    /// The bytes of the string are charged to the allocation budget before they're decoded.
     #[inline]
     pub fn read_str<S>(&mut self) -> EncodingResult<S>
     where
@@ -2315,7 +3345,8 @@ impl<T: Read> Encoder<'_, T> {
                     return None;
                 };
                 
-                Some(self.encoder.read_char())
+                // Not `read_char`, which would recurse for the self-describing formats
+                Some(self.encoder.read_char_or_null().map(|ch| ch.unwrap_or('\0')))
             }
         }
 
@@ -2332,8 +3363,8 @@ impl<T: Read> Encoder<'_, T> {
                         None // => STOP!
                     }
                     Ok(Some(x)) => {
-                        // Just a char
-                        Some(Ok(x)) // ==> Continue
+                        // Just a char, charged as it comes since the length isn't known upfront
+                        Some(self.encoder.charge_alloc::<u8>(x.len_utf8()).map(|_| x)) // ==> Continue
                     }
                     Err(x) => {
                         // An unrelated error occurred
@@ -2385,13 +3416,20 @@ impl<T: Read> Encoder<'_, T> {
             }
         }
 
+        if self.ctxt.settings.format != Format::Binary {
+            let iter = LenPrefixCharIter {
+                encoder: self.read_fmt_str()?,
+            };
+            return iter.collect();
+        }
+
         match self.ctxt.settings.string_repr.len {
             StrLen::LengthPrefixed => {
                 let length = self.read_usize()?;
+                self.charge_alloc::<u8>(length)?;
                 let iter = LenPrefixCharIter {
                     encoder: Encoder::new(SizeLimit::new(&mut self.stream, 0, length), self.ctxt),
                 };
-
                 iter.collect()
             }
             StrLen::NullTerminated => {
@@ -2399,6 +3437,7 @@ impl<T: Read> Encoder<'_, T> {
                 iter.collect()
             }
             StrLen::NullTerminatedFixed(max) => {
+                self.charge_alloc::<u8>(max)?;
                 let iter = NullTermWithMaxCharIter {
                     encoder: Encoder::new(SizeLimit::new(&mut self.stream, 0, max), self.ctxt),
                 };
@@ -2407,6 +3446,39 @@ impl<T: Read> Encoder<'_, T> {
         }
     }
 
+    /// Skips a String in the underlying stream without decoding it, according to the
+    /// string representation in the encoder's state.
+    ///
+    /// Length-prefixed and fixed-length strings are skipped as a whole, while null-terminated
+    /// ones are read one code unit at a time until the terminator. In any case, the characters
+    /// of the string are not validated.
+    #[inline]
+    pub fn skip_str(&mut self) -> EncodingResult<()> {
+        if self.ctxt.settings.format != Format::Binary {
+            let len = self.read_fmt_str_len()?;
+            return self.skip_bytes(len);
+        }
+
+        match self.ctxt.settings.string_repr.len {
+            StrLen::LengthPrefixed => {
+                let len = self.read_usize()?;
+                self.skip_bytes(len)
+            }
+            StrLen::NullTerminated => {
+                // The terminator is a code unit made entirely of null bytes
+                let unit = self.ctxt.settings.string_repr.encoding.bytes();
+                let mut buf = [0u8; 4];
+                loop {
+                    self.read_bytes(&mut buf[..unit])?;
+                    if buf[..unit].iter().all(|x| *x == 0) {
+                        return Ok(());
+                    }
+                }
+            }
+            StrLen::NullTerminatedFixed(max) => self.skip_bytes(max),
+        }
+    }
+
     /// Reads a single byte from the stream.
     #[inline]
     pub fn read_byte(&mut self) -> EncodingResult<u8> {
@@ -2420,6 +3492,165 @@ impl<T: Read> Encoder<'_, T> {
     pub fn read_bytes(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
         self.stream.read(buf)
     }
+
+    /// Advances the stream by `len` bytes, discarding them without copying them
+    /// when the stream supports it (see [`Read::skip`]).
+    #[inline]
+    pub fn skip_bytes(&mut self, len: usize) -> EncodingResult<()> {
+        self.stream.skip(len)
+    }
+
+    /// Reads a slice of plain-old-data values from the stream, as if each value was
+    /// read with the fixed numerical encoding and the given endianness.
+    ///
+    /// This is the counterpart of [`write_pod_slice`][`Encoder::write_pod_slice`].
+    #[inline]
+    pub fn read_pod_slice<P: bytemuck::Pod>(
+        &mut self,
+        values: &mut [P],
+        endianness: Endianness,
+    ) -> EncodingResult<()> {
+        let size = core::mem::size_of::<P>();
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(values);
+        self.stream.read(bytes)?;
+        if endianness != Endianness::native() && size > 1 {
+            swap_bytes(bytes, size);
+        }
+        Ok(())
+    }
+
+    /// Reads all the bytes left in the byte-counted region being decoded, as reported
+    /// by [`Read::limit`], or none if the stream isn't bounded.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn read_remaining(&mut self) -> EncodingResult<alloc::vec::Vec<u8>> {
+        let len = self.stream.limit().unwrap_or(0);
+        self.charge_alloc::<u8>(len)?;
+        let mut buf = alloc::vec![0; len];
+        self.stream.read(&mut buf)?;
+        Ok(buf)
+    }
+
+    // Reads `len` bytes into a new `Vec`, where `len` comes from the data itself.
+    // The length is checked against the max size, the bytes left in the stream
+    // and the allocation budget before anything is allocated
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub(crate) fn read_byte_vec(&mut self, len: usize) -> EncodingResult<alloc::vec::Vec<u8>> {
+        self.check_max_size(len)?;
+        self.check_remaining(len)?;
+        self.charge_alloc::<u8>(len)?;
+        let mut buf = alloc::vec![0; len];
+        self.read_bytes(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Skips all the bytes left in the byte-counted region being decoded, as reported
+    /// by [`Read::limit`], or none if the stream isn't bounded.
+    ///
+    /// This is the counterpart of [`read_remaining`][`Self::read_remaining`] used while skipping.
+    #[inline]
+    pub fn skip_remaining(&mut self) -> EncodingResult<()> {
+        let len = self.stream.limit().unwrap_or(0);
+        self.stream.skip(len)
+    }
+
+    /// Reads `magic.len()` bytes from the stream and checks that they match the
+    /// given magic number (or any other constant).
+    ///
+    /// Returns an [`EncodingError::MagicMismatch`] if they don't.
+    #[inline]
+    pub fn read_magic(&mut self, magic: &'static [u8]) -> EncodingResult<()> {
+        let mut buf = [0u8; 16];
+        for (idx, chunk) in magic.chunks(buf.len()).enumerate() {
+            let buf = &mut buf[..chunk.len()];
+            self.stream.read(buf)?;
+            magic_mismatch(magic, idx * 16, buf, chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Reads an `usize` size in bytes, then decodes a value from a region of that size,
+    /// which the closure is unable to read past.
+    ///
+    /// Any bytes left unread at the end of the region are handled according to
+    /// the [`Trailing`] setting in the [`SizeRepr`].
+    ///
+    /// This is the counterpart of [`write_sized`][`Self::write_sized`].
+    // Notice the `StreamModifier` signature
+    pub fn read_sized<F, R>(&mut self, f: F) -> EncodingResult<R>
+    where
+        F: FnOnce(&mut Encoder<SizeLimit<&mut T>>) -> EncodingResult<R>,
+    {
+        // The size flatten variable belongs to the value, not to the size of the region
+        let size_flatten = self.ctxt.consume_size_flatten();
+        let size = self.read_usize()?;
+
+        let mut capped = Encoder::new(SizeLimit::new(&mut self.stream, 0, size), self.ctxt);
+        capped.ctxt.size_flatten = size_flatten;
+        let value = f(&mut capped)?;
+        self.ctxt = capped.ctxt;
+        let remaining = capped.stream.remaining_readable();
+
+        if remaining != 0 {
+            match self.ctxt.settings.size_repr.trailing {
+                Trailing::Reject => return Err(EncodingError::TrailingBytes { remaining }),
+                Trailing::Skip => self.read_padding(remaining, None)?,
+            }
+        }
+        Ok(value)
+    }
+
+    /// Reads an `usize` size in bytes, then skips a region of that size.
+    ///
+    /// This is how an unknown byte-counted region written by [`write_sized`][`Self::write_sized`]
+    /// is ignored.
+    #[inline]
+    pub fn skip_sized(&mut self) -> EncodingResult<()> {
+        let size = self.read_usize()?;
+        self.read_padding(size, None)
+    }
+
+    /// Decodes the id of a tag-length-value record as an unsigned `Variant`,
+    /// or returns `None` if the end of the stream or of the byte-counted region
+    /// being decoded was reached.
+    ///
+    /// This is used by the `tlv` flag of the derive macros.
+    pub fn read_tlv_id(&mut self) -> EncodingResult<Option<u64>> {
+        if self.stream.limit() == Some(0) {
+            return Ok(None);
+        }
+
+        // Only reaching the end before the first byte of the id marks the end of the records
+        let mut tracked = Encoder::new(SizeTrack::new(&mut self.stream), self.ctxt);
+        match tracked.read_uvariant() {
+            Ok(id) => Ok(Some(id)),
+            Err(EncodingError::UnexpectedEnd) if tracked.stream.size_read() == 0 => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Skips `len` padding bytes. If `check` is `Some`, every byte is checked to be equal
+    /// to the given value, returning an [`EncodingError::InvalidPadding`] otherwise.
+    ///
+    /// This is the counterpart of [`write_padding`][`Self::write_padding`].
+    #[inline]
+    pub fn read_padding(&mut self, len: usize, check: Option<u8>) -> EncodingResult<()> {
+        let mut buf = [0u8; 16];
+        let mut remaining = len;
+        while remaining != 0 {
+            let len = remaining.min(buf.len());
+            self.stream.read(&mut buf[..len])?;
+            remaining -= len;
+
+            if let Some(expected) = check {
+                if let Some(&actual) = buf[..len].iter().find(|&&x| x != expected) {
+                    return Err(EncodingError::InvalidPadding { expected, actual });
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 macro_rules! make_borrow_slice_fn {
@@ -2428,7 +3659,7 @@ macro_rules! make_borrow_slice_fn {
         #[doc = stringify!($ty)]
         #[doc = "` slice of `length` length from the encoder, checking"]
         #[doc = "that the [`Endianness`] and alignment match those of the system"]
-        #[doc = "and that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable`]"]
+        #[doc = "and that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable_with_size`]"]
         #[inline]
         pub fn $name(
             &mut self,
@@ -2436,31 +3667,7 @@ macro_rules! make_borrow_slice_fn {
             num_encoding: NumEncoding,
             endianness: Endianness,
         ) -> EncodingResult<&'data [$ty]> {
-            // Assert the num encoding is borrowable
-            if !num_encoding.borrowable() {
-                return Err(EncodingError::BorrowError(
-                    BorrowError::NonBorrowableNumEncoding { num_encoding },
-                ));
-            }
-
-            // Assert the endianness matches, else we would be borrowing garbage-looking data.
-            if endianness != Endianness::native() {
-                return Err(EncodingError::BorrowError(
-                    BorrowError::EndiannessMismatch {
-                        found: endianness,
-                        system: Endianness::native(),
-                    },
-                ));
-            }
-
-            const BYTES: usize = core::mem::size_of::<$ty>();
-
-            let u8_slice: &[u8] = self.stream.borrow_read(length * BYTES)?;
-
-            // Depending on the alignment of the target system, this might fail.
-            let conv: &[$ty] = bytemuck::try_cast_slice(u8_slice)
-                .map_err(|_| EncodingError::BorrowError(BorrowError::AlignmentMismatch))?;
-            Ok(conv)
+            self.borrow_pod_slice(length, num_encoding, endianness)
         }
     };
 }
@@ -2498,7 +3705,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
     }
 
     /// Borrows a `u8` slice of length `length` from the encoder,
-    /// checking that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable`].
+    /// checking that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable_with_size`].
     #[inline]
     pub fn borrow_u8_slice(
         &mut self,
@@ -2506,7 +3713,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
         num_encoding: NumEncoding,
     ) -> EncodingResult<&'data [u8]> {
         // Assert the num encoding is borrowable
-        if !num_encoding.borrowable() {
+        if !num_encoding.borrowable_with_size(1) {
             return Err(EncodingError::BorrowError(
                 BorrowError::NonBorrowableNumEncoding { num_encoding },
             ));
@@ -2515,13 +3722,86 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
         self.stream.borrow_read(len)
     }
 
+    /// Borrows a slice of `len` plain-old-data values from the encoder, such as numbers or
+    /// `#[repr(C)]` records whose fields are all numbers.
+    ///
+    /// Checks that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable_with_size`], that the
+    /// [`Endianness`] matches the system's (unless the values are single bytes) and that
+    /// the data is aligned for `P`.
+    ///
+    /// If any of the checks fails, the stream is not advanced, so the values can still be
+    /// decoded as owned values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ender::{BorrowError, Context, Encoder, EncodingError, Endianness, NumEncoding};
+    /// use ender::io::Slice;
+    ///
+    /// let data = [0u8, 1, 0, 2];
+    /// let mut encoder = Encoder::new(Slice::new(&data), Context::new());
+    ///
+    /// let values: Vec<u16> = match encoder.borrow_pod_slice::<u16>(2, NumEncoding::Fixed, Endianness::BigEndian) {
+    ///     Ok(values) => values.to_vec(),
+    ///     // The data can't be borrowed, so decode it element by element
+    ///     Err(EncodingError::BorrowError(_)) => (0..2).map(|_| encoder.read_u16_with(
+    ///         NumEncoding::Fixed,
+    ///         Endianness::BigEndian
+    ///     )).collect::<Result<_, _>>().unwrap(),
+    ///     Err(err) => panic!("{err}"),
+    /// };
+    ///
+    /// assert_eq!(values, [1, 2]);
+    /// ```
+    #[inline]
+    pub fn borrow_pod_slice<P: bytemuck::Pod>(
+        &mut self,
+        len: usize,
+        num_encoding: NumEncoding,
+        endianness: Endianness,
+    ) -> EncodingResult<&'data [P]> {
+        // Assert the num encoding is borrowable
+        if !num_encoding.borrowable_with_size(core::mem::size_of::<P>()) {
+            return Err(EncodingError::BorrowError(
+                BorrowError::NonBorrowableNumEncoding { num_encoding },
+            ));
+        }
+
+        // Assert the endianness matches, else we would be borrowing garbage-looking data.
+        if core::mem::size_of::<P>() > 1 && endianness != Endianness::native() {
+            return Err(EncodingError::BorrowError(
+                BorrowError::EndiannessMismatch {
+                    found: endianness,
+                    system: Endianness::native(),
+                },
+            ));
+        }
+
+        // An overflowing size can never be borrowed, so it's reported as the end of the stream
+        let size = len.saturating_mul(core::mem::size_of::<P>());
+        let u8_slice: &[u8] = self.stream.peek(size)?;
+
+        // Depending on the alignment of the target system, this might fail.
+        let conv: &[P] = bytemuck::try_cast_slice(u8_slice).map_err(|_| {
+            let required = core::mem::align_of::<P>();
+            BorrowError::AlignmentMismatch {
+                ty: core::any::type_name::<P>(),
+                required,
+                offset: u8_slice.as_ptr() as usize % required,
+            }
+        })?;
+
+        self.stream.borrow_read(size)?;
+        Ok(conv)
+    }
+
     make_borrow_slice_fn!(borrow_u16_slice -> u16);
     make_borrow_slice_fn!(borrow_u32_slice -> u32);
     make_borrow_slice_fn!(borrow_u64_slice -> u64);
     make_borrow_slice_fn!(borrow_u128_slice -> u128);
 
     /// Borrows a `u8` slice of length `length` from the encoder,
-    /// checking that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable`].
+    /// checking that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable_with_size`].
     #[inline]
     pub fn borrow_i8_slice(
         &mut self,
@@ -2529,14 +3809,14 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
         num_encoding: NumEncoding,
     ) -> EncodingResult<&'data [i8]> {
         // Assert the num encoding is borrowable
-        if !num_encoding.borrowable() {
+        if !num_encoding.borrowable_with_size(1) {
             return Err(EncodingError::BorrowError(
                 BorrowError::NonBorrowableNumEncoding { num_encoding },
             ));
         }
 
         let u8_slice: &[u8] = self.stream.borrow_read(len)?;
-        Ok(bytemuck::try_cast_slice(u8_slice).map_err(|_| BorrowError::AlignmentMismatch)?)
+        Ok(bytemuck::cast_slice(u8_slice))
     }
 
     make_borrow_slice_fn!(borrow_i16_slice -> i16);
@@ -2550,7 +3830,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
     /// Borrows a `usize` slice of length `length` from the encoder.
     ///
     /// Checks that the [`Endianness`] and [`BitWidth`] match those of the target system,
-    /// and that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable`]
+    /// and that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable_with_size`]
     #[inline]
     pub fn borrow_usize_slice(
         &mut self,
@@ -2559,25 +3839,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
         endianness: Endianness,
         bit_width: BitWidth,
     ) -> EncodingResult<&'data [usize]> {
-        // Assert the num encoding is borrowable
-        if !num_encoding.borrowable() {
-            return Err(EncodingError::BorrowError(
-                BorrowError::NonBorrowableNumEncoding { num_encoding },
-            ));
-        }
-
-        // If the system endianness doesn't match, we would be borrowing
-        // garbage-looking data
-        if endianness != Endianness::native() {
-            return Err(EncodingError::BorrowError(
-                BorrowError::EndiannessMismatch {
-                    found: endianness,
-                    system: Endianness::native(),
-                },
-            ));
-        }
-
-        // Again, if the system bit width doesn't match, we would be borrowing a different
+        // If the system bit width doesn't match, we would be borrowing a different
         // number of bytes than what the user expects
         if bit_width != BitWidth::native() {
             return Err(EncodingError::BorrowError(BorrowError::BitWidthMismatch {
@@ -2586,11 +3848,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
             }));
         }
 
-        let u8_slice = self.stream.borrow_read(bit_width.bytes() * len)?;
-
-        // Depending on the alignment of the target system, this might fail.
-        let conv: &[usize] = bytemuck::try_cast_slice(u8_slice)
-            .map_err(|_| EncodingError::BorrowError(BorrowError::AlignmentMismatch))?;
+        let conv: &[usize] = self.borrow_pod_slice(len, num_encoding, endianness)?;
 
         // Check that none of the elements exceed the max size
         for &elem in conv {
@@ -2609,7 +3867,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
     /// Borrows a `isize` slice of length `length` from the encoder.
     ///
     /// Checks that the [`Endianness`] and [`BitWidth`] match those of the target system,
-    /// and that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable`]
+    /// and that the [`NumEncoding`] is [`borrowable`][`NumEncoding::borrowable_with_size`]
     #[inline]
     pub fn borrow_isize_slice(
         &mut self,
@@ -2618,25 +3876,7 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
         endianness: Endianness,
         bit_width: BitWidth,
     ) -> EncodingResult<&'data [isize]> {
-        // Assert the num encoding is borrowable
-        if !num_encoding.borrowable() {
-            return Err(EncodingError::BorrowError(
-                BorrowError::NonBorrowableNumEncoding { num_encoding },
-            ));
-        }
-
-        // If the system endianness doesn't match, we would be borrowing
-        // garbage-looking data
-        if endianness != Endianness::native() {
-            return Err(EncodingError::BorrowError(
-                BorrowError::EndiannessMismatch {
-                    found: endianness,
-                    system: Endianness::native(),
-                },
-            ));
-        }
-
-        // Again, if the system bit width doesn't match, we would be borrowing a different
+        // If the system bit width doesn't match, we would be borrowing a different
         // number of bytes than what the user expects
         if bit_width != BitWidth::native() {
             return Err(EncodingError::BorrowError(BorrowError::BitWidthMismatch {
@@ -2645,22 +3885,19 @@ impl<'data, T: BorrowRead<'data>> Encoder<'_, T> {
             }));
         }
 
-        let u8_slice = self.stream.borrow_read(bit_width.bytes() * len)?;
-
-        // Depending on the alignment of the target system, this might fail.
-        let conv: &[isize] = bytemuck::try_cast_slice(u8_slice)
-            .map_err(|_| EncodingError::BorrowError(BorrowError::AlignmentMismatch))?;
-        Ok(conv)
+        self.borrow_pod_slice(len, num_encoding, endianness)
     }
 }
 
-impl<T: Seek> Encoder<'_, T> {
+impl<T: Position> Encoder<'_, T> {
     /// Returns the current stream position as a byte offset from the start.
     #[inline]
     pub fn stream_position(&mut self) -> EncodingResult<usize> {
-        self.stream.seek(SeekFrom::POSITION)
+        self.stream.position()
     }
+}
 
+impl<T: Seek> Encoder<'_, T> {
     /// Performs a seek operation on the underlying stream using the given `seek`
     /// argument.
     #[inline]
@@ -2693,6 +3930,65 @@ impl<T: Seek> Encoder<'_, T> {
     }
 }
 
+/// Returns how many bytes are needed to go from `pos` to the next multiple of `align`.
+#[inline]
+pub(crate) const fn align_padding(pos: usize, align: usize) -> usize {
+    if align == 0 {
+        0
+    } else {
+        (align - pos % align) % align
+    }
+}
+
+/// Compares the bytes read from the stream with the chunk of the magic number starting at `offset`,
+/// returning an [`EncodingError::MagicMismatch`] for the first one that differs.
+#[inline]
+pub(crate) fn magic_mismatch(
+    magic: &'static [u8],
+    offset: usize,
+    read: &[u8],
+    chunk: &[u8],
+) -> EncodingResult<()> {
+    match read.iter().zip(chunk).position(|(a, b)| a != b) {
+        Some(idx) => Err(EncodingError::MagicMismatch {
+            expected: magic,
+            offset: offset + idx,
+            actual: read[idx],
+        }),
+        None => Ok(()),
+    }
+}
+
+impl<T: Write + Position> Encoder<'_, T> {
+    /// Writes padding bytes with the value `fill` until the stream position
+    /// is a multiple of `align`.
+    ///
+    /// Streams that can't seek can be wrapped in a [`PosTrack`][`io::PosTrack`], which tracks the position
+    /// by counting the bytes written.
+    ///
+    /// This is the counterpart of [`read_align`][`Self::read_align`].
+    #[inline]
+    pub fn write_align(&mut self, align: usize, fill: u8) -> EncodingResult<()> {
+        let pos = self.stream_position()?;
+        self.write_padding(align_padding(pos, align), fill)
+    }
+}
+
+impl<T: Read + Position> Encoder<'_, T> {
+    /// Skips padding bytes until the stream position is a multiple of `align`,
+    /// checking their value like [`read_padding`][`Self::read_padding`] does.
+    ///
+    /// Streams that can't seek can be wrapped in a [`PosTrack`][`io::PosTrack`], which tracks the position
+    /// by counting the bytes read.
+    ///
+    /// This is the counterpart of [`write_align`][`Self::write_align`].
+    #[inline]
+    pub fn read_align(&mut self, align: usize, check: Option<u8>) -> EncodingResult<()> {
+        let pos = self.stream_position()?;
+        self.read_padding(align_padding(pos, align), check)
+    }
+}
+
 /// A binary data structure specification which can be **encoded** into its binary representation.
 ///
 /// Implementations that need to **seek** should implement for `W: Write + Seek`.
@@ -2712,6 +4008,64 @@ pub trait Encode<W: Write> {
     /// no guarantees are made about the state of the encoder,
     /// and users should reset it before reuse.
     fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()>;
+
+    /// Encodes every value in the slice, one after the other, without a length prefix.
+    ///
+    /// This is used by the slice, array and `Vec` implementations, and is overridden by the
+    /// primitive numeric types to write the whole slice at once when the data is laid out
+    /// as-is (binary format with the fixed numerical encoding).
+    #[inline]
+    fn encode_slice(data: &[Self], encoder: &mut Encoder<W>) -> EncodingResult<()>
+    where
+        Self: Sized,
+    {
+        for (i, v) in data.iter().enumerate() {
+            encoder.with_index(|encoder| v.encode(encoder), i)?;
+        }
+        Ok(())
+    }
+}
+
+/// A binary data structure whose **encoded size** can be computed without encoding it.
+///
+/// The size is computed arithmetically from the value and the settings, which is much cheaper
+/// than encoding the value into a [`SizeTrack`], and is useful to pre-size buffers.
+///
+/// The derive macro computes the size of each field in the same way, only encoding
+/// into a [`SizeTrack`] the fields with a `with` or `redir` flag, whose output is unknown.
+///
+/// # Example
+///
+/// ```
+/// # use ender::{encode_bytes_with, Context, Encode, EncodedSize, NumEncoding};
+/// #[derive(Encode, EncodedSize)]
+/// # #[ender(crate: ender)]
+/// struct Message {
+///     id: u64,
+///     tags: Vec<String>,
+/// }
+///
+/// let message = Message {
+///     id: 1000,
+///     tags: vec!["hello".to_owned(), "world".to_owned()],
+/// };
+/// let mut ctxt = Context::new();
+/// ctxt.settings.num_repr.num_encoding = NumEncoding::Leb128;
+///
+/// let size = message.encoded_size(&ctxt).unwrap();
+/// assert_eq!(size, encode_bytes_with(&message, ctxt).unwrap().len());
+/// ```
+pub trait EncodedSize {
+    /// Returns the number of bytes that encoding `self` with the given context would write.
+    ///
+    /// Errors that encoding would return, such as a value being too large for the bit-width
+    /// in the settings, should also be returned here when they can be detected.
+    ///
+    /// The flatten variables of the context are taken into account and consumed by the
+    /// value they apply to, but since `ctxt` isn't modified, they are not consumed for the
+    /// values that follow: they should only be set for the value that consumes them,
+    /// which is how the derive macros use them.
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize>;
 }
 
 /// A binary data structure specification which can be **decoded** from its binary representation.
@@ -2756,4 +4110,83 @@ pub trait Decode<R: Read>: Sized {
     /// no guarantees are made about the state of the encoder,
     /// and users should reset it before reuse.
     fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self>;
+
+    /// Decodes `N` values, one after the other.
+    ///
+    /// This is used by the array implementation, and is overridden by the primitive
+    /// numeric types in the same way as [`Encode::encode_slice`].
+    #[inline]
+    fn decode_array<const N: usize>(decoder: &mut Encoder<R>) -> EncodingResult<[Self; N]> {
+        array_init::try_array_init(|i| decoder.with_index(|decoder| Self::decode(decoder), i))
+    }
+
+    /// Decodes `len` values, one after the other, into a `Vec`.
+    ///
+    /// This is used by the `Vec` and boxed slice implementations, and is overridden by the
+    /// primitive numeric types in the same way as [`Encode::encode_slice`].
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn decode_vec(decoder: &mut Encoder<R>, len: usize) -> EncodingResult<alloc::vec::Vec<Self>> {
+        let mut vec = alloc::vec::Vec::with_capacity(decoder.capacity_hint(len));
+        for i in 0..len {
+            vec.push(decoder.with_index(|decoder| Self::decode(decoder), i)?);
+        }
+        Ok(vec)
+    }
+}
+
+/// A binary data structure that can be **skipped** while decoding, advancing the decoder
+/// past its binary representation without decoding it.
+///
+/// This is cheaper than decoding the value and dropping it: nothing is allocated, and the data
+/// whose size is known upfront, such as length-prefixed strings or slices of numbers laid out
+/// as-is, is jumped over at once through [`Read::skip`], which borrows or seeks
+/// when the stream supports it.
+///
+/// The derive macro skips each field in the same way, only decoding the fields whose values
+/// are needed by the flags of the fields that follow them (E.G. in an `if` condition).
+///
+/// # Example
+///
+/// ```
+/// # use ender::{encode_bytes_with, Context, Decode, Encode, Encoder, Skip};
+/// # use ender::io::Slice;
+/// #[derive(Encode, Decode, Skip)]
+/// # #[ender(crate: ender)]
+/// struct Entry {
+///     name: String,
+///     payload: Vec<u8>,
+/// }
+///
+/// let entries = vec![
+///     Entry { name: "first".to_owned(), payload: vec![0; 1000] },
+///     Entry { name: "second".to_owned(), payload: vec![1, 2, 3] },
+/// ];
+/// let data = encode_bytes_with(&entries, Context::new()).unwrap();
+///
+/// let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+/// assert_eq!(decoder.read_seq_len().unwrap(), 2);
+/// Entry::skip(&mut decoder).unwrap();
+///
+/// let second = Entry::decode(&mut decoder).unwrap();
+/// assert_eq!(second.name, "second");
+/// ```
+pub trait Skip<R: Read> {
+    /// Advances the decoder past a value of this type, without decoding it.
+    ///
+    /// The data isn't validated like [`Decode::decode`] would: for example, the characters
+    /// of a string are not checked, so skipping may succeed where decoding would fail.
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()>;
+
+    /// Skips `len` values, one after the other.
+    ///
+    /// This is used by the array and collection implementations, and is overridden by the
+    /// primitive numeric types in the same way as [`Encode::encode_slice`].
+    #[inline]
+    fn skip_seq(decoder: &mut Encoder<R>, len: usize) -> EncodingResult<()> {
+        for i in 0..len {
+            decoder.with_index(|decoder| Self::skip(decoder), i)?;
+        }
+        Ok(())
+    }
 }
\ No newline at end of file
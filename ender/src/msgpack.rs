@@ -0,0 +1,331 @@
+//! Support for the [MessagePack](https://github.com/msgpack/msgpack/blob/master/spec.md)
+//! data format, which is used by setting the [`format`][`crate::BinSettings::format`]
+//! to [`Format::MessagePack`].
+//!
+//! The primitives of the [`Encoder`] are mapped to MessagePack objects as follows,
+//! ignoring the numerical and string representations in the settings:
+//! - Integers, `usize`, `isize` and enum variants are integers in their shortest form.
+//!   Values that don't fit in 64 bits can't be represented.
+//! - `bool`s are the `false` and `true` objects.
+//! - `f32`s and `f64`s are `float 32` and `float 64` objects. While decoding,
+//!   a `float 32` is also accepted for an `f64`.
+//! - Strings and `char`s are `str` objects, thus always UTF-8.
+//! - The lengths of sequences and maps are the headers of arrays and maps.
+//!
+//! Structs, tuples and the fields of enum variants are arrays of their fields, and enums are
+//! an array of two objects: the variant followed by its fields. `Option`s are `nil` when absent
+//! and an array holding the value when present, while `()` is `nil`.
+//!
+//! # Example
+//!
+//! ```
+//! use ender::{encode_bytes_with, BinSettings, Context, Format};
+//!
+//! let ctxt = Context::new().settings(BinSettings::new().format(Format::MessagePack));
+//! let bytes = encode_bytes_with((1000u32, vec![-1i8, 2], "IETF"), ctxt).unwrap();
+//!
+//! assert_eq!(
+//!     bytes,
+//!     [0x93, 0xCD, 0x03, 0xE8, 0x92, 0xFF, 0x02, 0xA4, b'I', b'E', b'T', b'F'],
+//! );
+//! ```
+
+use crate::format::Int;
+use crate::io::{Read, Write};
+use crate::{BitWidth, Encoder, EncodingError, EncodingResult, Endianness, Format, FormatError, NumEncoding, Opaque};
+
+const FALSE: u8 = 0xC2;
+const TRUE: u8 = 0xC3;
+const NIL: u8 = 0xC0;
+const FLOAT32: u8 = 0xCA;
+const FLOAT64: u8 = 0xCB;
+const UINT8: u8 = 0xCC;
+const UINT16: u8 = 0xCD;
+const UINT32: u8 = 0xCE;
+const UINT64: u8 = 0xCF;
+const INT8: u8 = 0xD0;
+const INT16: u8 = 0xD1;
+const INT32: u8 = 0xD2;
+const INT64: u8 = 0xD3;
+const STR8: u8 = 0xD9;
+const STR16: u8 = 0xDA;
+const STR32: u8 = 0xDB;
+const ARRAY16: u8 = 0xDC;
+const ARRAY32: u8 = 0xDD;
+const MAP16: u8 = 0xDE;
+const MAP32: u8 = 0xDF;
+
+/// The markers of a kind of length-prefixed object: its fix form, with the length
+/// in the bits not covered by the mask, and the forms with an 8, 16 and 32 bit length
+struct Markers {
+    fix: u8,
+    fix_mask: u8,
+    len8: Option<u8>,
+    len16: u8,
+    len32: u8,
+    name: &'static str,
+}
+
+const STR: Markers = Markers {
+    fix: 0xA0,
+    fix_mask: 0xE0,
+    len8: Some(STR8),
+    len16: STR16,
+    len32: STR32,
+    name: "a string",
+};
+
+const ARRAY: Markers = Markers {
+    fix: 0x90,
+    fix_mask: 0xF0,
+    len8: None,
+    len16: ARRAY16,
+    len32: ARRAY32,
+    name: "an array",
+};
+
+const MAP: Markers = Markers {
+    fix: 0x80,
+    fix_mask: 0xF0,
+    len8: None,
+    len16: MAP16,
+    len32: MAP32,
+    name: "a map",
+};
+
+fn unexpected(marker: u8, expected: &'static str) -> FormatError {
+    FormatError::UnexpectedItem {
+        format: Format::MessagePack,
+        expected,
+        found: marker,
+    }
+}
+
+impl<T: Write> Encoder<'_, T> {
+    // Writes a marker followed by a big endian number
+    fn write_msgpack_marked(&mut self, marker: u8, bytes: &[u8]) -> EncodingResult<()> {
+        self.write_byte(marker)?;
+        self.write_bytes(bytes)
+    }
+
+    pub(crate) fn write_msgpack_int(&mut self, value: Int) -> EncodingResult<()> {
+        match value {
+            Int::Unsigned(x) if x < 0x80 => self.write_byte(x as u8),
+            Int::Unsigned(x) if x <= u8::MAX as u128 => self.write_msgpack_marked(UINT8, &[x as u8]),
+            Int::Unsigned(x) if x <= u16::MAX as u128 => self.write_msgpack_marked(UINT16, &(x as u16).to_be_bytes()),
+            Int::Unsigned(x) if x <= u32::MAX as u128 => self.write_msgpack_marked(UINT32, &(x as u32).to_be_bytes()),
+            Int::Unsigned(x) if x <= u64::MAX as u128 => self.write_msgpack_marked(UINT64, &(x as u64).to_be_bytes()),
+            Int::Negative(x) if x >= -32 => self.write_byte(x as u8),
+            Int::Negative(x) if x >= i8::MIN as i128 => self.write_msgpack_marked(INT8, &[x as u8]),
+            Int::Negative(x) if x >= i16::MIN as i128 => self.write_msgpack_marked(INT16, &(x as i16).to_be_bytes()),
+            Int::Negative(x) if x >= i32::MIN as i128 => self.write_msgpack_marked(INT32, &(x as i32).to_be_bytes()),
+            Int::Negative(x) if x >= i64::MIN as i128 => self.write_msgpack_marked(INT64, &(x as i64).to_be_bytes()),
+            Int::Unsigned(x) => Err(EncodingError::TooLarge {
+                value: Opaque::from(x),
+                requested_width: BitWidth::Bit64,
+            }),
+            Int::Negative(x) => Err(EncodingError::TooLarge {
+                value: Opaque::from(x),
+                requested_width: BitWidth::Bit64,
+            }),
+        }
+    }
+
+    pub(crate) fn write_msgpack_bool(&mut self, value: bool) -> EncodingResult<()> {
+        self.write_byte(if value { TRUE } else { FALSE })
+    }
+
+    pub(crate) fn write_msgpack_f32(&mut self, value: f32) -> EncodingResult<()> {
+        self.write_msgpack_marked(FLOAT32, &value.to_be_bytes())
+    }
+
+    pub(crate) fn write_msgpack_f64(&mut self, value: f64) -> EncodingResult<()> {
+        self.write_msgpack_marked(FLOAT64, &value.to_be_bytes())
+    }
+
+    // Writes the header of a length-prefixed object in the shortest form
+    fn write_msgpack_len(&mut self, markers: Markers, len: usize) -> EncodingResult<()> {
+        let fix_max = !markers.fix_mask as usize;
+        match (len, markers.len8) {
+            (len, _) if len <= fix_max => self.write_byte(markers.fix | len as u8),
+            (len, Some(len8)) if len <= u8::MAX as usize => self.write_msgpack_marked(len8, &[len as u8]),
+            (len, _) if len <= u16::MAX as usize => self.write_msgpack_marked(markers.len16, &(len as u16).to_be_bytes()),
+            (len, _) if len as u64 <= u32::MAX as u64 => self.write_msgpack_marked(markers.len32, &(len as u32).to_be_bytes()),
+            (len, _) => Err(EncodingError::TooLarge {
+                value: Opaque::from(len),
+                requested_width: BitWidth::Bit32,
+            }),
+        }
+    }
+
+    pub(crate) fn write_msgpack_str_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_msgpack_len(STR, len)
+    }
+
+    pub(crate) fn write_msgpack_seq_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_msgpack_len(ARRAY, len)
+    }
+
+    pub(crate) fn write_msgpack_map_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_msgpack_len(MAP, len)
+    }
+
+    pub(crate) fn write_msgpack_nil(&mut self) -> EncodingResult<()> {
+        self.write_byte(NIL)
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    pub(crate) fn read_msgpack_int(&mut self) -> EncodingResult<Int> {
+        let fixed = NumEncoding::Fixed;
+        let be = Endianness::BigEndian;
+        Ok(match self.read_byte()? {
+            marker @ 0x00..=0x7F => Int::Unsigned(marker as u128),
+            marker @ 0xE0..=0xFF => Int::Negative(marker as i8 as i128),
+            UINT8 => Int::from(self.read_u8_with(fixed, be)?),
+            UINT16 => Int::from(self.read_u16_with(fixed, be)?),
+            UINT32 => Int::from(self.read_u32_with(fixed, be)?),
+            UINT64 => Int::from(self.read_u64_with(fixed, be)?),
+            INT8 => Int::from(self.read_i8_with(fixed, be)?),
+            INT16 => Int::from(self.read_i16_with(fixed, be)?),
+            INT32 => Int::from(self.read_i32_with(fixed, be)?),
+            INT64 => Int::from(self.read_i64_with(fixed, be)?),
+            marker => return Err(unexpected(marker, "an integer").into()),
+        })
+    }
+
+    pub(crate) fn read_msgpack_bool(&mut self) -> EncodingResult<bool> {
+        match self.read_byte()? {
+            FALSE => Ok(false),
+            TRUE => Ok(true),
+            marker => Err(unexpected(marker, "a boolean").into()),
+        }
+    }
+
+    pub(crate) fn read_msgpack_f32(&mut self) -> EncodingResult<f32> {
+        match self.read_byte()? {
+            FLOAT32 => Ok(f32::from_bits(self.read_u32_with(NumEncoding::Fixed, Endianness::BigEndian)?)),
+            marker => Err(unexpected(marker, "a float 32").into()),
+        }
+    }
+
+    pub(crate) fn read_msgpack_f64(&mut self) -> EncodingResult<f64> {
+        match self.read_byte()? {
+            FLOAT32 => Ok(f32::from_bits(self.read_u32_with(NumEncoding::Fixed, Endianness::BigEndian)?) as f64),
+            FLOAT64 => Ok(f64::from_bits(self.read_u64_with(NumEncoding::Fixed, Endianness::BigEndian)?)),
+            marker => Err(unexpected(marker, "a float").into()),
+        }
+    }
+
+    // Reads the header of a length-prefixed object in any form
+    fn read_msgpack_len(&mut self, markers: Markers) -> EncodingResult<usize> {
+        let marker = self.read_byte()?;
+        self.read_msgpack_len_after(marker, markers)
+    }
+
+    // Reads the rest of the header of a length-prefixed object, given its marker
+    fn read_msgpack_len_after(&mut self, marker: u8, markers: Markers) -> EncodingResult<usize> {
+        let len = if marker & markers.fix_mask == markers.fix {
+            (marker & !markers.fix_mask) as u32
+        } else if Some(marker) == markers.len8 {
+            self.read_byte()? as u32
+        } else if marker == markers.len16 {
+            self.read_u16_with(NumEncoding::Fixed, Endianness::BigEndian)? as u32
+        } else if marker == markers.len32 {
+            self.read_u32_with(NumEncoding::Fixed, Endianness::BigEndian)?
+        } else {
+            return Err(unexpected(marker, markers.name).into());
+        };
+        usize::try_from(Int::from(len))
+    }
+
+    pub(crate) fn read_msgpack_str_len(&mut self) -> EncodingResult<usize> {
+        self.read_msgpack_len(STR)
+    }
+
+    pub(crate) fn read_msgpack_seq_len(&mut self) -> EncodingResult<usize> {
+        self.read_msgpack_len(ARRAY)
+    }
+
+    pub(crate) fn read_msgpack_map_len(&mut self) -> EncodingResult<usize> {
+        self.read_msgpack_len(MAP)
+    }
+
+    pub(crate) fn read_msgpack_nil(&mut self) -> EncodingResult<()> {
+        match self.read_byte()? {
+            NIL => Ok(()),
+            marker => Err(unexpected(marker, "nil").into()),
+        }
+    }
+
+    /// Reads either nil or the header of an array of one object, returning whether
+    /// it was the latter
+    pub(crate) fn read_msgpack_option(&mut self) -> EncodingResult<bool> {
+        let marker = self.read_byte()?;
+        if marker == NIL {
+            return Ok(false);
+        }
+        match self.read_msgpack_len_after(marker, ARRAY) {
+            Ok(1) => Ok(true),
+            Ok(_) | Err(EncodingError::FormatError(FormatError::UnexpectedItem { .. })) => {
+                Err(unexpected(marker, "nil or an array of one object").into())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+// The sizes of the objects written above, used to compute encoded sizes without encoding
+
+pub(crate) fn msgpack_int_size(value: Int) -> EncodingResult<usize> {
+    Ok(match value {
+        Int::Unsigned(x) if x < 0x80 => 1,
+        Int::Unsigned(x) if x <= u8::MAX as u128 => 2,
+        Int::Unsigned(x) if x <= u16::MAX as u128 => 3,
+        Int::Unsigned(x) if x <= u32::MAX as u128 => 5,
+        Int::Unsigned(x) if x <= u64::MAX as u128 => 9,
+        Int::Negative(x) if x >= -32 => 1,
+        Int::Negative(x) if x >= i8::MIN as i128 => 2,
+        Int::Negative(x) if x >= i16::MIN as i128 => 3,
+        Int::Negative(x) if x >= i32::MIN as i128 => 5,
+        Int::Negative(x) if x >= i64::MIN as i128 => 9,
+        Int::Unsigned(x) => {
+            return Err(EncodingError::TooLarge {
+                value: Opaque::from(x),
+                requested_width: BitWidth::Bit64,
+            })
+        }
+        Int::Negative(x) => {
+            return Err(EncodingError::TooLarge {
+                value: Opaque::from(x),
+                requested_width: BitWidth::Bit64,
+            })
+        }
+    })
+}
+
+fn msgpack_len_size(markers: Markers, len: usize) -> EncodingResult<usize> {
+    let fix_max = !markers.fix_mask as usize;
+    match (len, markers.len8) {
+        (len, _) if len <= fix_max => Ok(1),
+        (len, Some(_)) if len <= u8::MAX as usize => Ok(2),
+        (len, _) if len <= u16::MAX as usize => Ok(3),
+        (len, _) if len as u64 <= u32::MAX as u64 => Ok(5),
+        (len, _) => Err(EncodingError::TooLarge {
+            value: Opaque::from(len),
+            requested_width: BitWidth::Bit32,
+        }),
+    }
+}
+
+pub(crate) fn msgpack_str_len_size(len: usize) -> EncodingResult<usize> {
+    msgpack_len_size(STR, len)
+}
+
+pub(crate) fn msgpack_seq_len_size(len: usize) -> EncodingResult<usize> {
+    msgpack_len_size(ARRAY, len)
+}
+
+pub(crate) fn msgpack_map_len_size(len: usize) -> EncodingResult<usize> {
+    msgpack_len_size(MAP, len)
+}
@@ -0,0 +1,755 @@
+//! Building blocks of the [protobuf](https://protobuf.dev/programming-guides/encoding/)
+//! message format, used by the `#[ender(format: protobuf)]` derive mode.
+//!
+//! A message is encoded as a sequence of records, each made of a key (the field number and
+//! the [`WireType`]) followed by the value. Fields with the default value are omitted,
+//! and while decoding they can appear in any order, while unknown fields are skipped.
+//!
+//! The mapping between Rust and protobuf types is implemented by [`ProtoEncode`] and
+//! [`ProtoDecode`], and it depends on the numerical encoding in effect:
+//!
+//! | Rust type                      | Numerical encoding                  | Protobuf type                  |
+//! |--------------------------------|-------------------------------------|--------------------------------|
+//! | `u8`, `u16`, `u32`, `u64`      | [`ProtobufWasteful`] (the default)  | `uint32`, `uint64`             |
+//! | `i8`, `i16`, `i32`, `i64`      | [`ProtobufWasteful`] (the default)  | `int32`, `int64`               |
+//! | `i8`, `i16`, `i32`, `i64`      | [`ProtobufZigzag`]                  | `sint32`, `sint64`             |
+//! | `u32`, `u64`                   | [`Fixed`]                           | `fixed32`, `fixed64`           |
+//! | `i32`, `i64`                   | [`Fixed`]                           | `sfixed32`, `sfixed64`         |
+//! | `bool`                         | any                                 | `bool`                         |
+//! | `f32`, `f64`                   | any                                 | `float`, `double`              |
+//! | `String`, `&str`               | any                                 | `string`                       |
+//! | `Vec<u8>`                      | any                                 | `bytes`                        |
+//! | `Vec<T>`                       | any                                 | `repeated T` (packed if scalar)|
+//! | `Option<T>`                    | any                                 | `optional T`                   |
+//! | structs deriving the format    | any                                 | nested messages                |
+//!
+//! [`ProtobufWasteful`]: crate::NumEncoding::ProtobufWasteful
+//! [`ProtobufZigzag`]: crate::NumEncoding::ProtobufZigzag
+//! [`Fixed`]: crate::NumEncoding::Fixed
+//!
+//! Groups (wire types 3 and 4) are deprecated and not supported.
+
+use crate::io::{Read, SizeLimit, SizeTrack, Write, Zero};
+use crate::{
+    BitWidth, Context, Decode, Encode, Encoder, EncodingError, EncodingResult, Endianness,
+    NumEncoding, NumRepr, Opaque, ProtobufError,
+};
+use parse_display::Display;
+
+/// The numerical representation in effect while the fields of a protobuf message
+/// are encoded or decoded: little endian varints, with negative numbers sign-extended
+/// to 64 bits.
+pub const NUM_REPR: NumRepr = NumRepr::new()
+    .endianness(Endianness::LittleEndian)
+    .num_encoding(NumEncoding::ProtobufWasteful);
+
+/// The type of a protobuf record, which determines how its value is delimited.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[repr(u8)]
+pub enum WireType {
+    /// A varint
+    #[display("VARINT")]
+    Varint = 0,
+    /// A fixed 8-byte value
+    #[display("I64")]
+    I64 = 1,
+    /// A length-delimited value: strings, bytes, nested messages and packed repeated fields
+    #[display("LEN")]
+    Len = 2,
+    /// A fixed 4-byte value
+    #[display("I32")]
+    I32 = 5,
+}
+
+impl WireType {
+    /// Returns the wire type with the given value, or `None` if it's unknown or unsupported.
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(Self::Varint),
+            1 => Some(Self::I64),
+            2 => Some(Self::Len),
+            5 => Some(Self::I32),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Write> Encoder<'_, T> {
+    /// Writes the key of a protobuf record: the field number and the wire type.
+    #[inline]
+    pub fn write_proto_key(&mut self, field: u32, wire_type: WireType) -> EncodingResult<()> {
+        let key = ((field as u64) << 3) | wire_type as u64;
+        self.write_u64_with(key, NumEncoding::Leb128, Endianness::LittleEndian)
+    }
+
+    /// Writes the length of a length-delimited protobuf value.
+    #[inline]
+    pub fn write_proto_len(&mut self, len: usize) -> EncodingResult<()> {
+        self.write_u64_with(len as u64, NumEncoding::Leb128, Endianness::LittleEndian)
+    }
+
+    /// Writes a nested protobuf message, prefixed by its length.
+    ///
+    /// Since the length must be known in advance, the message is encoded twice:
+    /// the first time only to measure it.
+    pub fn write_proto_message<M>(&mut self, message: &M) -> EncodingResult<()>
+    where
+        M: Encode<T> + Encode<SizeTrack<Zero>>,
+    {
+        let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), self.ctxt);
+        message.encode(&mut sz_encoder)?;
+        let size = sz_encoder.finish().0.size_written();
+
+        self.write_proto_len(size)?;
+        message.encode(self)
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    /// Reads the key of a protobuf record: the field number and the wire type.
+    ///
+    /// Returns `None` once the end of the message is reached: either the end of its
+    /// length-delimited region, or the end of the stream for a top-level message.
+    pub fn read_proto_key(&mut self) -> EncodingResult<Option<(u32, WireType)>> {
+        if self.stream.limit() == Some(0) {
+            return Ok(None);
+        }
+        let first = match self.read_byte() {
+            Ok(byte) => byte,
+            Err(EncodingError::UnexpectedEnd) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        // The key is an u32 var-int, so it's at most 5 bytes long
+        let mut key = (first & 0x7F) as u64;
+        let mut byte = first;
+        let mut shift = 7;
+        while byte & 0x80 != 0 {
+            if shift >= 35 {
+                return Err(EncodingError::VarIntError);
+            }
+            byte = self.read_byte()?;
+            key |= ((byte & 0x7F) as u64) << shift;
+            shift += 7;
+        }
+
+        let field = key >> 3;
+        if field == 0 || field > u32::MAX as u64 >> 3 {
+            return Err(ProtobufError::InvalidFieldNumber(field).into());
+        }
+        let wire_type = WireType::from_bits((key & 0b111) as u8)
+            .ok_or(ProtobufError::InvalidWireType((key & 0b111) as u8))?;
+        Ok(Some((field as u32, wire_type)))
+    }
+
+    /// Reads the length of a length-delimited protobuf value.
+    #[inline]
+    pub fn read_proto_len(&mut self) -> EncodingResult<usize> {
+        let len = self.read_u64_with(NumEncoding::Leb128, Endianness::LittleEndian)?;
+        usize::try_from(len).map_err(|_| EncodingError::TooLarge {
+            value: Opaque::from(len),
+            requested_width: BitWidth::native(),
+        })
+    }
+
+    /// Reads a nested protobuf message, prefixed by its length.
+    pub fn read_proto_message<M>(&mut self) -> EncodingResult<M>
+    where
+        M: for<'a> Decode<SizeLimit<&'a mut T>>,
+    {
+        let len = self.read_proto_len()?;
+        let mut capped = Encoder::new(SizeLimit::new(&mut self.stream, 0, len), self.ctxt);
+        let message = M::decode(&mut capped)?;
+        // The allocation budget is shared with the nested message
+        self.ctxt.alloc_budget = capped.ctxt.alloc_budget;
+        let remaining = capped.stream.remaining_readable();
+        if remaining != 0 {
+            return Err(EncodingError::TrailingBytes { remaining });
+        }
+        Ok(message)
+    }
+
+    /// Skips the value of a protobuf record of the given wire type.
+    /// This is how unknown fields are ignored.
+    pub fn skip_proto_value(&mut self, wire_type: WireType) -> EncodingResult<()> {
+        match wire_type {
+            WireType::Varint => {
+                self.read_u64_with(NumEncoding::Leb128, Endianness::LittleEndian)?;
+            }
+            WireType::I64 => self.read_padding(8, None)?,
+            WireType::Len => {
+                let len = self.read_proto_len()?;
+                self.read_padding(len, None)?;
+            }
+            WireType::I32 => self.read_padding(4, None)?,
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the wire type of a record doesn't match the expected one.
+    #[inline]
+    pub fn expect_wire_type(&self, expected: WireType, found: WireType) -> EncodingResult<()> {
+        if expected != found {
+            return Err(ProtobufError::WireTypeMismatch { expected, found }.into());
+        }
+        Ok(())
+    }
+
+    // Calls `f` for every value of a packed repeated field
+    #[cfg(feature = "alloc")]
+    fn read_packed<F>(&mut self, mut f: F) -> EncodingResult<()>
+    where
+        F: FnMut(&mut Encoder<SizeLimit<&mut T>>) -> EncodingResult<()>,
+    {
+        let len = self.read_proto_len()?;
+        let mut capped = Encoder::new(SizeLimit::new(&mut self.stream, 0, len), self.ctxt);
+        while capped.stream.remaining_readable() != 0 {
+            f(&mut capped)?;
+        }
+        self.ctxt.alloc_budget = capped.ctxt.alloc_budget;
+        Ok(())
+    }
+}
+
+/// A type that can be encoded as the value of a protobuf field.
+///
+/// The `#[ender(format: protobuf)]` derive mode implements this for messages, which
+/// can then be nested in other messages.
+pub trait ProtoEncode: Sized {
+    /// Returns the wire type of this type, according to the settings in effect.
+    fn wire_type(ctxt: &Context) -> WireType;
+
+    /// Encodes the value, without the key.
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()>;
+
+    /// Whether this is the default value, which protobuf doesn't write.
+    #[inline]
+    fn is_default(&self) -> bool {
+        false
+    }
+
+    /// Encodes the value as the field with the given number, unless it's the default value.
+    #[inline]
+    fn encode_field<W: Write>(&self, field: u32, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        if self.is_default() {
+            return Ok(());
+        }
+        encoder.write_proto_key(field, Self::wire_type(&encoder.ctxt))?;
+        self.encode_value(encoder)
+    }
+
+    /// Encodes the given values as a repeated field with the given number.
+    ///
+    /// Length-delimited values are written as one record each,
+    /// while every other value is written in a single packed record.
+    fn encode_repeated<W: Write>(
+        values: &[Self],
+        field: u32,
+        encoder: &mut Encoder<W>,
+    ) -> EncodingResult<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let wire_type = Self::wire_type(&encoder.ctxt);
+        if wire_type == WireType::Len {
+            for value in values {
+                encoder.write_proto_key(field, wire_type)?;
+                value.encode_value(encoder)?;
+            }
+            return Ok(());
+        }
+
+        let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), encoder.ctxt);
+        for value in values {
+            value.encode_value(&mut sz_encoder)?;
+        }
+        let size = sz_encoder.finish().0.size_written();
+
+        encoder.write_proto_key(field, WireType::Len)?;
+        encoder.write_proto_len(size)?;
+        for value in values {
+            value.encode_value(encoder)?;
+        }
+        Ok(())
+    }
+}
+
+/// A type that can be decoded from the value of a protobuf field.
+///
+/// The `#[ender(format: protobuf)]` derive mode implements this for messages, which
+/// can then be nested in other messages.
+pub trait ProtoDecode: Sized {
+    /// Returns the wire type of this type, according to the settings in effect.
+    fn wire_type(ctxt: &Context) -> WireType;
+
+    /// Decodes the value, whose key has already been read.
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self>;
+
+    /// Decodes a record of the field this value belongs to, whose key has already been read.
+    ///
+    /// Every record replaces the previous value, so the last one wins.
+    #[inline]
+    fn merge_field<R: Read>(
+        slot: &mut Option<Self>,
+        wire_type: WireType,
+        decoder: &mut Encoder<R>,
+    ) -> EncodingResult<()> {
+        decoder.expect_wire_type(Self::wire_type(&decoder.ctxt), wire_type)?;
+        *slot = Some(Self::decode_value(decoder)?);
+        Ok(())
+    }
+
+    /// Decodes a record of a repeated field, whose key has already been read,
+    /// appending its values to `values`.
+    ///
+    /// Both packed and unpacked records are accepted.
+    #[cfg(feature = "alloc")]
+    fn merge_repeated<R: Read>(
+        values: &mut alloc::vec::Vec<Self>,
+        wire_type: WireType,
+        decoder: &mut Encoder<R>,
+    ) -> EncodingResult<()> {
+        let expected = Self::wire_type(&decoder.ctxt);
+        if wire_type == WireType::Len && expected != WireType::Len {
+            return decoder.read_packed(|decoder| {
+                values.push(Self::decode_value(decoder)?);
+                Ok(())
+            });
+        }
+        decoder.expect_wire_type(expected, wire_type)?;
+        values.push(Self::decode_value(decoder)?);
+        Ok(())
+    }
+}
+
+// Returns the wire type of an integer according to the numerical encoding
+#[inline]
+fn int_wire_type(ctxt: &Context, fixed: WireType) -> WireType {
+    match ctxt.settings.num_repr.num_encoding {
+        NumEncoding::Fixed => fixed,
+        _ => WireType::Varint,
+    }
+}
+
+macro_rules! impl_proto_int {
+    ($($ty:ty => $fixed:ident, $write:ident, $read:ident);* $(;)?) => {
+	    $(
+	    impl ProtoEncode for $ty {
+		    #[inline]
+		    fn wire_type(ctxt: &Context) -> WireType {
+			    int_wire_type(ctxt, WireType::$fixed)
+		    }
+
+		    #[inline]
+		    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+			    encoder.$write(*self)
+		    }
+
+		    #[inline]
+		    fn is_default(&self) -> bool {
+			    *self == 0
+		    }
+	    }
+
+	    impl ProtoDecode for $ty {
+		    #[inline]
+		    fn wire_type(ctxt: &Context) -> WireType {
+			    int_wire_type(ctxt, WireType::$fixed)
+		    }
+
+		    #[inline]
+		    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+			    decoder.$read()
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_proto_int! {
+    u32 => I32, write_u32, read_u32;
+    u64 => I64, write_u64, read_u64;
+    i64 => I64, write_i64, read_i64;
+}
+
+// Protobuf sign-extends negative `int32` values to 64 bits
+impl ProtoEncode for i32 {
+    #[inline]
+    fn wire_type(ctxt: &Context) -> WireType {
+        int_wire_type(ctxt, WireType::I32)
+    }
+
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        match encoder.ctxt.settings.num_repr.num_encoding {
+            NumEncoding::ProtobufWasteful => encoder.write_i64(*self as i64),
+            _ => encoder.write_i32(*self),
+        }
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        *self == 0
+    }
+}
+
+impl ProtoDecode for i32 {
+    #[inline]
+    fn wire_type(ctxt: &Context) -> WireType {
+        int_wire_type(ctxt, WireType::I32)
+    }
+
+    #[inline]
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        match decoder.ctxt.settings.num_repr.num_encoding {
+            NumEncoding::ProtobufWasteful => Ok(decoder.read_i64()? as i32),
+            _ => decoder.read_i32(),
+        }
+    }
+}
+
+// Protobuf has no integers narrower than 32 bits, so they are widened
+macro_rules! impl_proto_narrow {
+    ($($ty:ty => $wide:ty, $width:ident);* $(;)?) => {
+	    $(
+	    impl ProtoEncode for $ty {
+		    #[inline]
+		    fn wire_type(ctxt: &Context) -> WireType {
+			    <$wide as ProtoEncode>::wire_type(ctxt)
+		    }
+
+		    #[inline]
+		    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+			    (*self as $wide).encode_value(encoder)
+		    }
+
+		    #[inline]
+		    fn is_default(&self) -> bool {
+			    *self == 0
+		    }
+	    }
+
+	    impl ProtoDecode for $ty {
+		    #[inline]
+		    fn wire_type(ctxt: &Context) -> WireType {
+			    <$wide as ProtoDecode>::wire_type(ctxt)
+		    }
+
+		    #[inline]
+		    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+			    let value = <$wide as ProtoDecode>::decode_value(decoder)?;
+			    <$ty>::try_from(value).map_err(|_| EncodingError::TooLarge {
+				    value: Opaque::from(value),
+				    requested_width: BitWidth::$width,
+			    })
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_proto_narrow! {
+    u16 => u32, Bit16;
+    i8 => i32, Bit8;
+    i16 => i32, Bit16;
+}
+
+// `u8` is narrowed like the other integers, but a `Vec<u8>` is encoded as `bytes`
+impl ProtoEncode for u8 {
+    #[inline]
+    fn wire_type(ctxt: &Context) -> WireType {
+        <u32 as ProtoEncode>::wire_type(ctxt)
+    }
+
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        (*self as u32).encode_value(encoder)
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        *self == 0
+    }
+
+    #[inline]
+    fn encode_repeated<W: Write>(
+        values: &[Self],
+        field: u32,
+        encoder: &mut Encoder<W>,
+    ) -> EncodingResult<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        encoder.write_proto_key(field, WireType::Len)?;
+        encoder.write_proto_len(values.len())?;
+        encoder.write_bytes(values)
+    }
+}
+
+impl ProtoDecode for u8 {
+    #[inline]
+    fn wire_type(ctxt: &Context) -> WireType {
+        <u32 as ProtoDecode>::wire_type(ctxt)
+    }
+
+    #[inline]
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        let value = <u32 as ProtoDecode>::decode_value(decoder)?;
+        u8::try_from(value).map_err(|_| EncodingError::TooLarge {
+            value: Opaque::from(value),
+            requested_width: BitWidth::Bit8,
+        })
+    }
+
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn merge_repeated<R: Read>(
+        values: &mut alloc::vec::Vec<Self>,
+        wire_type: WireType,
+        decoder: &mut Encoder<R>,
+    ) -> EncodingResult<()> {
+        decoder.expect_wire_type(WireType::Len, wire_type)?;
+        let len = decoder.read_proto_len()?;
+        let bytes = decoder.read_byte_vec(len)?;
+        values.extend_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+impl ProtoEncode for bool {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Varint
+    }
+
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_byte(*self as u8)
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        !*self
+    }
+}
+
+impl ProtoDecode for bool {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Varint
+    }
+
+    #[inline]
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        Ok(decoder.read_u64_with(NumEncoding::Leb128, Endianness::LittleEndian)? != 0)
+    }
+}
+
+macro_rules! impl_proto_float {
+    ($($ty:ty => $wire:ident, $write:ident, $read:ident);* $(;)?) => {
+	    $(
+	    impl ProtoEncode for $ty {
+		    #[inline]
+		    fn wire_type(_ctxt: &Context) -> WireType {
+			    WireType::$wire
+		    }
+
+		    #[inline]
+		    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+			    encoder.$write(*self)
+		    }
+
+		    #[inline]
+		    fn is_default(&self) -> bool {
+			    self.to_bits() == 0
+		    }
+	    }
+
+	    impl ProtoDecode for $ty {
+		    #[inline]
+		    fn wire_type(_ctxt: &Context) -> WireType {
+			    WireType::$wire
+		    }
+
+		    #[inline]
+		    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+			    decoder.$read()
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_proto_float! {
+    f32 => I32, write_f32, read_f32;
+    f64 => I64, write_f64, read_f64;
+}
+
+impl ProtoEncode for &str {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Len
+    }
+
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_proto_len(self.len())?;
+        encoder.write_bytes(self.as_bytes())
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ProtoEncode for alloc::string::String {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Len
+    }
+
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        self.as_str().encode_value(encoder)
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ProtoDecode for alloc::string::String {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Len
+    }
+
+    #[inline]
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        let len = decoder.read_proto_len()?;
+        let bytes = decoder.read_byte_vec(len)?;
+        alloc::string::String::from_utf8(bytes)
+            .map_err(|_| crate::StringError::InvalidChar.into())
+    }
+}
+
+impl<T: ProtoEncode> ProtoEncode for Option<T> {
+    #[inline]
+    fn wire_type(ctxt: &Context) -> WireType {
+        T::wire_type(ctxt)
+    }
+
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        match self {
+            Some(value) => value.encode_value(encoder),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        self.is_none()
+    }
+
+    // A present value is always written, even if it's the default one
+    #[inline]
+    fn encode_field<W: Write>(&self, field: u32, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        match self {
+            Some(value) => {
+                encoder.write_proto_key(field, T::wire_type(&encoder.ctxt))?;
+                value.encode_value(encoder)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: ProtoDecode> ProtoDecode for Option<T> {
+    #[inline]
+    fn wire_type(ctxt: &Context) -> WireType {
+        T::wire_type(ctxt)
+    }
+
+    #[inline]
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        Ok(Some(T::decode_value(decoder)?))
+    }
+
+    #[inline]
+    fn merge_field<R: Read>(
+        slot: &mut Option<Self>,
+        wire_type: WireType,
+        decoder: &mut Encoder<R>,
+    ) -> EncodingResult<()> {
+        let mut inner = slot.take().flatten();
+        T::merge_field(&mut inner, wire_type, decoder)?;
+        *slot = Some(inner);
+        Ok(())
+    }
+}
+
+/// Repeated fields. The values of a `Vec<T>` are encoded by [`ProtoEncode::encode_repeated`],
+/// so a `Vec<u8>` is encoded as `bytes`.
+#[cfg(feature = "alloc")]
+impl<T: ProtoEncode> ProtoEncode for alloc::vec::Vec<T> {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Len
+    }
+
+    /// Encodes the values as the payload of a packed record
+    #[inline]
+    fn encode_value<W: Write>(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        let mut sz_encoder = Encoder::new(SizeTrack::new(Zero), encoder.ctxt);
+        for value in self {
+            value.encode_value(&mut sz_encoder)?;
+        }
+        encoder.write_proto_len(sz_encoder.finish().0.size_written())?;
+        for value in self {
+            value.encode_value(encoder)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+
+    #[inline]
+    fn encode_field<W: Write>(&self, field: u32, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        T::encode_repeated(self, field, encoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ProtoDecode> ProtoDecode for alloc::vec::Vec<T> {
+    #[inline]
+    fn wire_type(_ctxt: &Context) -> WireType {
+        WireType::Len
+    }
+
+    /// Decodes the values from the payload of a packed record
+    #[inline]
+    fn decode_value<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        let mut values = alloc::vec::Vec::new();
+        T::merge_repeated(&mut values, WireType::Len, decoder)?;
+        Ok(values)
+    }
+
+    #[inline]
+    fn merge_field<R: Read>(
+        slot: &mut Option<Self>,
+        wire_type: WireType,
+        decoder: &mut Encoder<R>,
+    ) -> EncodingResult<()> {
+        T::merge_repeated(slot.get_or_insert_with(alloc::vec::Vec::new), wire_type, decoder)
+    }
+}
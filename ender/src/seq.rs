@@ -0,0 +1,336 @@
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use crate::io::{BorrowRead, Lookahead, Read, Slice, Write};
+use crate::{
+    BinSettings, Context, Decode, Encode, EncodedSize, Encoder, EncodingError, EncodingResult, Skip,
+};
+
+/// An iterator that decodes the items of a sequence on demand, one at a time.
+///
+/// The length prefix is read once when the iterator is created through
+/// [`Encoder::seq_decoder`] or [`LazySeq::iter`], then each call to `next` decodes the
+/// following item. If an item fails to decode, the error is returned and the iteration ends.
+///
+/// # Example
+///
+/// ```
+/// use ender::{encode_bytes_with, Context, Encoder, EncodingResult};
+/// use ender::io::Slice;
+///
+/// let data = encode_bytes_with(vec![1u32, 2, 3], Context::new()).unwrap();
+/// let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+///
+/// let mut sum = 0;
+/// for value in decoder.seq_decoder::<u32>().unwrap() {
+///     sum += value.unwrap();
+/// }
+///
+/// assert_eq!(sum, 6);
+/// ```
+pub struct SeqDecoder<'e, R, T> {
+    decoder: Encoder<'e, R>,
+    // The allocation budget of the encoder the sequence is read from, if any,
+    // which is kept in sync with the one charged while decoding the items
+    budget: Option<&'e mut Option<usize>>,
+    len: usize,
+    index: usize,
+    failed: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'e, R, T> SeqDecoder<'e, R, T> {
+    #[inline]
+    fn new(decoder: Encoder<'e, R>, budget: Option<&'e mut Option<usize>>, len: usize) -> Self {
+        Self {
+            decoder,
+            budget,
+            len,
+            index: 0,
+            failed: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the length of the whole sequence, as read from the length prefix.
+    #[inline]
+    pub fn seq_len(&self) -> usize {
+        self.len
+    }
+
+    /// Unwraps the encoder the items are decoded from, returning it.
+    #[inline]
+    pub fn into_inner(self) -> Encoder<'e, R> {
+        self.decoder
+    }
+}
+
+impl<R: Read, T: Decode<R>> Iterator for SeqDecoder<'_, R, T> {
+    type Item = EncodingResult<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.index == self.len {
+            return None;
+        }
+
+        let index = self.index;
+        let item = self.decoder.with_index(|decoder| T::decode(decoder), index);
+        if let Some(budget) = &mut self.budget {
+            **budget = self.decoder.ctxt.alloc_budget;
+        }
+        match item {
+            Ok(_) => self.index += 1,
+            Err(_) => self.failed = true,
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = if self.failed {
+            0
+        } else {
+            self.len - self.index
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R: Read, T: Decode<R>> ExactSizeIterator for SeqDecoder<'_, R, T> {}
+
+impl<R: Read, T: Decode<R>> FusedIterator for SeqDecoder<'_, R, T> {}
+
+impl<R: Read> Encoder<'_, R> {
+    /// Reads the length prefix of a sequence, such as a slice or a `Vec`, and returns
+    /// an iterator that decodes its items on demand, instead of collecting them.
+    ///
+    /// The items must all be decoded before anything that follows the sequence.
+    #[inline]
+    pub fn seq_decoder<T>(&mut self) -> EncodingResult<SeqDecoder<'_, &mut R, T>> {
+        let len = self.read_seq_len()?;
+        let decoder = Encoder::new(&mut self.stream, self.ctxt);
+        Ok(SeqDecoder::new(decoder, Some(&mut self.ctxt.alloc_budget), len))
+    }
+}
+
+/// A sequence whose items are produced by an iterator and encoded on demand, one at a time,
+/// in the same format as a slice or a `Vec`.
+///
+/// The iterator is cloned every time the value is encoded, which may happen more than once
+/// (for example, to measure a value before encoding it). If the number of items produced
+/// doesn't match the length reported by the iterator, a
+/// [`EncodingError::SeqLenMismatch`] is returned.
+///
+/// # Example
+///
+/// ```
+/// use ender::{encode_bytes_with, Context, SeqEncoder};
+///
+/// let squares = SeqEncoder::new((1u32..4).map(|x| x * x));
+/// let data = encode_bytes_with(&squares, Context::new()).unwrap();
+///
+/// assert_eq!(data, encode_bytes_with(vec![1u32, 4, 9], Context::new()).unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SeqEncoder<I> {
+    iter: I,
+}
+
+impl<I: ExactSizeIterator + Clone> SeqEncoder<I> {
+    /// Wraps an iterator, which will produce the items of the sequence.
+    #[inline]
+    pub fn new<T: IntoIterator<IntoIter = I>>(iter: T) -> Self {
+        Self {
+            iter: iter.into_iter(),
+        }
+    }
+
+    /// Unwraps the iterator, returning it.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<W: Write, I> Encode<W> for SeqEncoder<I>
+where
+    I: ExactSizeIterator + Clone,
+    I::Item: Encode<W>,
+{
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        let len = self.iter.len();
+        encoder.write_seq_len(len)?;
+
+        let mut count = 0;
+        for v in self.iter.clone() {
+            if count == len {
+                return Err(EncodingError::SeqLenMismatch {
+                    expected: len,
+                    got: count + 1,
+                });
+            }
+            encoder.with_index(|encoder| v.encode(encoder), count)?;
+            count += 1;
+        }
+
+        if count != len {
+            return Err(EncodingError::SeqLenMismatch {
+                expected: len,
+                got: count,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<I> EncodedSize for SeqEncoder<I>
+where
+    I: ExactSizeIterator + Clone,
+    I::Item: EncodedSize,
+{
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.iter.len())?;
+        for v in self.iter.clone() {
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+/// A sequence borrowed from the encoder, whose items are only decoded on demand.
+///
+/// While decoding, the length prefix is read once and the items are skipped ahead of the stream
+/// to find out where the sequence ends: only the bytes they occupy are borrowed, and the items
+/// aren't validated. Iterating through [`iter`][`Self::iter`] decodes them one at a time,
+/// with the settings that were in effect while decoding the sequence.
+///
+/// While encoding, the borrowed bytes are written back as-is.
+///
+/// # Example
+///
+/// ```
+/// use ender::{encode_bytes_with, Context, Decode, Encode, Encoder, LazySeq};
+/// use ender::io::Slice;
+///
+/// #[derive(Encode, Decode)]
+/// #[ender(crate: ender)]
+/// struct Recording<'data> {
+///     rate: u32,
+///     #[ender(borrow)]
+///     samples: LazySeq<'data, i16>,
+///     checksum: u8,
+/// }
+///
+/// let data = encode_bytes_with((44100u32, vec![-1i16, 0, 1], 7u8), Context::new()).unwrap();
+/// let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+/// let recording = Recording::decode(&mut decoder).unwrap();
+///
+/// assert_eq!(recording.samples.len(), 3);
+/// assert_eq!(recording.checksum, 7);
+///
+/// let max = recording.samples.iter().map(Result::unwrap).max();
+/// assert_eq!(max, Some(1));
+/// ```
+pub struct LazySeq<'data, T> {
+    len: usize,
+    data: &'data [u8],
+    settings: BinSettings,
+    version: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'data, T> LazySeq<'data, T> {
+    /// Returns the number of items in the sequence.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the sequence has no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the encoded items, without the length prefix.
+    #[inline]
+    pub fn as_bytes(&self) -> &'data [u8] {
+        self.data
+    }
+
+    /// Returns an iterator that decodes the items of the sequence on demand.
+    #[inline]
+    pub fn iter(&self) -> SeqDecoder<'data, Slice<'data>, T> {
+        let ctxt = Context::new().settings(self.settings).version(self.version);
+        SeqDecoder::new(Encoder::new(Slice::new(self.data), ctxt), None, self.len)
+    }
+}
+
+impl<T> Clone for LazySeq<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for LazySeq<'_, T> {}
+
+impl<T> core::fmt::Debug for LazySeq<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LazySeq")
+            .field("len", &self.len)
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+impl<'data: 'a, 'a, R: BorrowRead<'data>, T> Decode<R> for LazySeq<'a, T>
+where
+    T: for<'p> Skip<Lookahead<'p, R>>,
+{
+    #[inline]
+    fn decode(decoder: &mut Encoder<R>) -> EncodingResult<Self> {
+        let len = decoder.read_seq_len()?;
+
+        // Skip the items ahead of the stream to find out where the sequence ends
+        let mut lookahead = Encoder::new(Lookahead::new(&decoder.stream), decoder.ctxt);
+        T::skip_seq(&mut lookahead, len)?;
+        let size = lookahead.stream.position();
+
+        Ok(Self {
+            len,
+            data: decoder.stream.borrow_read(size)?,
+            settings: decoder.ctxt.settings,
+            version: decoder.ctxt.version,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for LazySeq<'_, T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        T::skip_seq(decoder, len)
+    }
+}
+
+impl<W: Write, T> Encode<W> for LazySeq<'_, T> {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder<W>) -> EncodingResult<()> {
+        encoder.write_seq_len(self.len)?;
+        encoder.write_bytes(self.data)
+    }
+}
+
+impl<T> EncodedSize for LazySeq<'_, T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        Ok(ctxt.seq_len_size(self.len)? + self.data.len())
+    }
+}
@@ -1,14 +1,54 @@
+//! The serde compatibility layer.
+//!
+//! `&mut Encoder<T>` implements [`Serializer`] and [`Deserializer`], while [`BorrowDeserializer`]
+//! deserializes zero-copy from a [`BorrowRead`] stream, and [`SeekSerializer`] back-patches
+//! the length of sequences and maps that isn't known upfront into a [`Seek`] stream.
+//!
+//! The `to_*` and `from_*` functions construct them on the fly, in the same way
+//! as [`encode`][`crate::encode`] and [`decode`][`crate::decode`] do for an [`Encoder`].
+
 use core::fmt::Display;
+#[cfg(not(feature = "alloc"))]
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
 
-use serde::de::{DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::de::{
+    DeserializeSeed, EnumAccess, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
 use serde::ser::{
     SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
     SerializeTupleStruct, SerializeTupleVariant,
 };
-use serde::{de, ser, Deserializer, Serialize, Serializer};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "alloc")]
+use crate::io::VecStream;
+use crate::io::{BorrowRead, Read, Seek, SeekFrom, Write};
+#[cfg(not(feature = "alloc"))]
+use crate::Context;
+use crate::{
+    Encode, Encoder, EncodingError, EncodingResult, FlattenError, NumEncoding, StrEncoding, StrLen,
+    StringError,
+};
+
+#[cfg(feature = "alloc")]
+pub use crate::convenience::serde::{to_vec, to_vec_with};
+pub use crate::convenience::serde::{
+    from_reader, from_reader_with, from_slice, from_slice_with, to_slice, to_slice_with, to_writer,
+    to_writer_with,
+};
+
+/// The serde [`Serializer`] of an [`Encoder`].
+///
+/// This is only a name for `&mut Encoder<T>`, which can be handy when the serializer
+/// has to be stored or passed around, for example to `serde_transcode`.
+pub type EncoderSerializer<'a, 'b, T> = &'a mut Encoder<'b, T>;
 
-use crate::io::{Read, Write};
-use crate::{Encode, Encoder, EncodingError};
+/// The serde [`Deserializer`] of an [`Encoder`].
+///
+/// This is only a name for `&mut Encoder<T>`, which can be handy when the deserializer
+/// has to be stored or passed around, for example to `serde_transcode`.
+pub type EncoderDeserializer<'a, 'b, T> = &'a mut Encoder<'b, T>;
 
 fn serde_error(_msg: &'static str) -> EncodingError {
     #[cfg(feature = "alloc")]
@@ -21,6 +61,113 @@ fn serde_error(_msg: &'static str) -> EncodingError {
     }
 }
 
+/// The type tag written before each value when the data is self-describing,
+/// see [`BinSettings::self_describing`][`crate::BinSettings::self_describing`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[repr(u8)]
+enum TypeTag {
+    None = 0,
+    Some = 1,
+    Unit = 2,
+    Bool = 3,
+    I8 = 4,
+    I16 = 5,
+    I32 = 6,
+    I64 = 7,
+    I128 = 8,
+    U8 = 9,
+    U16 = 10,
+    U32 = 11,
+    U64 = 12,
+    U128 = 13,
+    F32 = 14,
+    F64 = 15,
+    Char = 16,
+    Str = 17,
+    Bytes = 18,
+    Seq = 19,
+    Map = 20,
+}
+
+impl TypeTag {
+    const ALL: [Self; 21] = [
+        Self::None,
+        Self::Some,
+        Self::Unit,
+        Self::Bool,
+        Self::I8,
+        Self::I16,
+        Self::I32,
+        Self::I64,
+        Self::I128,
+        Self::U8,
+        Self::U16,
+        Self::U32,
+        Self::U64,
+        Self::U128,
+        Self::F32,
+        Self::F64,
+        Self::Char,
+        Self::Str,
+        Self::Bytes,
+        Self::Seq,
+        Self::Map,
+    ];
+}
+
+impl<T: Write> Encoder<'_, T> {
+    /// Writes the type tag of the value that follows, if the data is self-describing
+    fn write_type_tag(&mut self, tag: TypeTag) -> EncodingResult<()> {
+        if self.ctxt.settings.self_describing {
+            self.write_byte(tag as u8)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes the header of a tuple, which is only needed if the data is self-describing
+    fn write_tuple_header(&mut self, len: usize) -> EncodingResult<()> {
+        if self.ctxt.settings.self_describing {
+            self.write_type_tag(TypeTag::Seq)?;
+            self.write_usize(len)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the header of a struct, which is only needed if the data is self-describing
+    fn write_struct_header(&mut self, len: usize) -> EncodingResult<()> {
+        if self.ctxt.settings.self_describing {
+            self.write_type_tag(TypeTag::Map)?;
+            self.write_usize(len)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the header of an enum variant with some content: its index, or a single-entry map
+    /// from its name to the content if the data is self-describing
+    fn write_variant_header(&mut self, variant_index: u32, variant: &str) -> EncodingResult<()> {
+        if self.ctxt.settings.self_describing {
+            self.write_type_tag(TypeTag::Map)?;
+            self.write_usize(1)?;
+            self.write_type_tag(TypeTag::Str)?;
+            variant.encode(self)
+        } else {
+            self.write_uvariant(variant_index)
+        }
+    }
+}
+
+impl<T: Read> Encoder<'_, T> {
+    /// Reads the type tag of the value that follows
+    fn read_type_tag(&mut self) -> EncodingResult<TypeTag> {
+        let tag = self.read_byte()?;
+        TypeTag::ALL
+            .get(tag as usize)
+            .copied()
+            .ok_or(serde_error("Invalid type tag"))
+    }
+}
+
 impl ser::Error for EncodingError {
     fn custom<Msg>(_msg: Msg) -> Self
     where
@@ -53,651 +200,1330 @@ impl de::Error for EncodingError {
     }
 }
 
-impl<T: Write> Serializer for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-    type SerializeSeq = Self;
-    type SerializeTuple = Self;
-    type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
-
-    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.write_bool(v)
-    }
-
-    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        self.write_i8(v)
-    }
-
-    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        self.write_i16(v)
-    }
-
-    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        self.write_i32(v)
-    }
-
-    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        self.write_i64(v)
-    }
-
-    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
-        self.write_i128(v)
-    }
-
-    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        self.write_u8(v)
-    }
-
-    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        self.write_u16(v)
-    }
-
-    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        self.write_u32(v)
-    }
-
-    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        self.write_u64(v)
-    }
-
-    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
-        self.write_u128(v)
-    }
-
-    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        self.write_f32(v)
-    }
-
-    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.write_f64(v)
-    }
-
-    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        self.write_char(v)
-    }
-
-    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        v.encode(self)
-    }
-
-    #[cfg(not(feature = "alloc"))]
-    fn collect_str<G: ?Sized>(self, _value: &G) -> Result<Self::Ok, Self::Error>
-    where
-        G: Display,
-    {
-        Err(serde_error(
-            "Types implementing Display cannot be serialized without the alloc feature",
-        ))
-    }
-
-    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.write_bytes(v)
-    }
-
-    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        self.write_bool(false)?;
-        Ok(())
-    }
-
-    fn serialize_some<G: ?Sized>(self, value: &G) -> Result<Self::Ok, Self::Error>
-    where
-        G: Serialize,
-    {
-        self.write_bool(true)?;
-        value.serialize(self)
-    }
-
-    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
-    }
-
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        Ok(())
-    }
-
-    fn serialize_unit_variant(
-        self,
-        _name: &'static str,
-        variant_index: u32,
-        _variant: &'static str,
-    ) -> Result<Self::Ok, Self::Error> {
-        self.write_uvariant(variant_index)
-    }
-
-    fn serialize_newtype_struct<G: ?Sized>(
-        self,
-        _name: &'static str,
-        value: &G,
-    ) -> Result<Self::Ok, Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(self)
-    }
-
-    fn serialize_newtype_variant<G: ?Sized>(
-        self,
-        _name: &'static str,
-        variant_index: u32,
-        _variant: &'static str,
-        value: &G,
-    ) -> Result<Self::Ok, Self::Error>
-    where
-        G: Serialize,
-    {
-        self.write_uvariant(variant_index)?;
-        value.serialize(self)
-    }
-
-    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        let len = len.ok_or(serde_error("Length must be known upfront"))?;
-        self.write_usize(len)?;
-        Ok(self)
-    }
-
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Ok(self)
-    }
-
-    fn serialize_tuple_struct(
-        self,
-        _name: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Ok(self)
-    }
-
-    fn serialize_tuple_variant(
-        self,
-        _name: &'static str,
-        variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.write_uvariant(variant_index)?;
-        Ok(self)
-    }
-
-    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        let len = len.ok_or(serde_error("Length must be known upfront"))?;
-        self.write_usize(len)?;
-        Ok(self)
-    }
-
-    fn serialize_struct(
-        self,
-        _name: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeStruct, Self::Error> {
-        Ok(self)
-    }
-
-    fn serialize_struct_variant(
-        self,
-        _name: &'static str,
-        variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        self.write_uvariant(variant_index)?;
-        Ok(self)
-    }
-
-    fn is_human_readable(&self) -> bool {
-        false
-    }
+/// A serde [`Serializer`] which back-patches the length of sequences and maps that isn't
+/// known upfront (such as the ones produced by [`Serializer::collect_seq`] over a filtering
+/// iterator), by seeking back into the stream once all the elements have been written.
+///
+/// `&mut Encoder<T>` supports unknown lengths too, but has to buffer the elements instead,
+/// which requires the `alloc` feature.
+/// Either way the output is identical to the one with a known length.
+///
+/// Back-patching requires a [`Fixed`][`crate::NumEncoding::Fixed`] size encoding, because
+/// the length prefix has to keep the same size. Otherwise the elements are buffered.
+///
+/// # Example
+///
+/// ```
+/// use ender::{Context, Encoder};
+/// use ender::io::SliceMut;
+/// use ender::serde::SeekSerializer;
+/// use serde::Serializer;
+///
+/// let mut buf = [0u8; 64];
+/// let mut serializer = SeekSerializer::new(Encoder::new(SliceMut::new(&mut buf), Context::new()));
+/// serializer.collect_seq((0u16..10).filter(|x| x % 3 == 0)).unwrap();
+///
+/// let mut encoder = serializer.into_inner();
+/// assert_eq!(encoder.stream_position().unwrap(), 16);
+/// ```
+pub struct SeekSerializer<'a, T> {
+    encoder: Encoder<'a, T>,
 }
 
-impl<T: Write> SerializeSeq for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-
-    fn serialize_element<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
+impl<'a, T> SeekSerializer<'a, T> {
+    /// Wraps the given encoder.
+    #[inline]
+    pub fn new(encoder: Encoder<'a, T>) -> Self {
+        Self { encoder }
     }
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+    /// Unwraps the encoder, returning it.
+    #[inline]
+    pub fn into_inner(self) -> Encoder<'a, T> {
+        self.encoder
     }
 }
 
-impl<T: Write> SerializeTuple for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-
-    fn serialize_element<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
-    }
+impl<'a, T> Deref for SeekSerializer<'a, T> {
+    type Target = Encoder<'a, T>;
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.encoder
     }
 }
 
-impl<T: Write> SerializeTupleStruct for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-
-    fn serialize_field<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
-    }
-
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+impl<T> DerefMut for SeekSerializer<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.encoder
     }
 }
 
-impl<T: Write> SerializeTupleVariant for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-
-    fn serialize_field<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
-    }
-
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
-    }
+/// Serializes the elements of a sequence or the entries of a map, keeping track of
+/// their number if the length wasn't known upfront.
+pub struct Compound<'a, 'b, S> {
+    serializer: &'a mut S,
+    len: usize,
+    pending: Option<PendingLen<'b>>,
 }
 
-impl<T: Write> SerializeMap for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-
-    fn serialize_key<G: ?Sized>(&mut self, key: &G) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        key.serialize(&mut **self)
-    }
+/// A length that will only be known once all the elements have been serialized
+enum PendingLen<'a> {
+    /// The length isn't written, but must match the `usize` flatten state variable
+    Flatten(usize),
+    /// The length will be written at the given position in the stream
+    Patch(usize),
+    /// The elements are written to a buffer, to be copied to the stream after the length
+    #[cfg(feature = "alloc")]
+    Buffer(Encoder<'a, VecStream>),
+    /// Never constructed, only keeps the lifetime in use when there is no buffer
+    #[cfg(not(feature = "alloc"))]
+    #[allow(dead_code)]
+    Unused(PhantomData<Context<'a>>),
+}
 
-    fn serialize_value<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
-    }
+/// Writes the length of sequences and maps.
+trait SerializeLen<'a> {
+    /// Writes the length if it's known, otherwise prepares for it to be written later
+    fn begin_len(&mut self, len: Option<usize>) -> EncodingResult<Option<PendingLen<'a>>>;
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
-    }
+    /// Writes the length left pending by `begin_len`, now that it's known
+    fn end_len(&mut self, pending: PendingLen<'a>, len: usize) -> EncodingResult<()>;
 }
 
-impl<T: Write> SerializeStruct for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
+impl<'a, T: Write> SerializeLen<'a> for Encoder<'a, T> {
+    fn begin_len(&mut self, len: Option<usize>) -> EncodingResult<Option<PendingLen<'a>>> {
+        if let Some(len) = len {
+            self.write_usize(len)?;
+            return Ok(None);
+        }
 
-    fn serialize_field<G: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        value: &G,
-    ) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
+        if let Some(size) = self.ctxt.consume_size_flatten() {
+            return Ok(Some(PendingLen::Flatten(size)));
+        }
+
+        #[cfg(feature = "alloc")]
+        {
+            let buffer = VecStream::new(alloc::vec::Vec::new(), 0);
+            Ok(Some(PendingLen::Buffer(Encoder::new(buffer, self.ctxt))))
+        }
+        #[cfg(not(feature = "alloc"))]
+        {
+            Err(serde_error(
+                "Length must be known upfront, unless the stream is seekable or alloc is enabled",
+            ))
+        }
     }
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+    fn end_len(&mut self, pending: PendingLen<'a>, len: usize) -> EncodingResult<()> {
+        match pending {
+            PendingLen::Flatten(size) if size != len => {
+                Err(EncodingError::FlattenError(FlattenError::LenMismatch {
+                    expected: size,
+                    got: len,
+                }))
+            }
+            PendingLen::Flatten(_) => Ok(()),
+            PendingLen::Patch(_) => unreachable!("Only seekable streams can back-patch the length"),
+            #[cfg(not(feature = "alloc"))]
+            PendingLen::Unused(_) => unreachable!(),
+            #[cfg(feature = "alloc")]
+            PendingLen::Buffer(buffer) => {
+                self.write_usize(len)?;
+
+                // The elements may have changed the context, for example by consuming a flatten state
+                let (buffer, ctxt) = buffer.finish();
+                self.ctxt = ctxt;
+                self.write_bytes(buffer.inner())
+            }
+        }
     }
 }
 
-impl<T: Write> SerializeStructVariant for &mut Encoder<'_, T> {
-    type Ok = ();
-    type Error = EncodingError;
-
-    fn serialize_field<G: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        value: &G,
-    ) -> Result<(), Self::Error>
-    where
-        G: Serialize,
-    {
-        value.serialize(&mut **self)
-    }
+impl<'a, T: Write + Seek> SerializeLen<'a> for SeekSerializer<'a, T> {
+    fn begin_len(&mut self, len: Option<usize>) -> EncodingResult<Option<PendingLen<'a>>> {
+        let patchable = self.ctxt.size_flatten.is_none()
+            && self.ctxt.settings.size_repr.num_encoding == NumEncoding::Fixed;
+        if len.is_some() || !patchable {
+            return self.encoder.begin_len(len);
+        }
 
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        // Write a placeholder of the same size
+        let pos = self.stream_position()?;
+        self.write_usize(0)?;
+        Ok(Some(PendingLen::Patch(pos)))
+    }
+
+    fn end_len(&mut self, pending: PendingLen<'a>, len: usize) -> EncodingResult<()> {
+        match pending {
+            PendingLen::Patch(pos) => {
+                let end = self.stream_position()?;
+                self.seek(SeekFrom::Start(pos))?;
+                self.write_usize(len)?;
+                self.seek(SeekFrom::Start(end))?;
+                Ok(())
+            }
+            pending => self.encoder.end_len(pending, len),
+        }
     }
 }
 
-impl<'de, T: Read> Deserializer<'de> for &mut Encoder<'de, T> {
-    type Error = EncodingError;
+// Implements the serde serializer for both a plain and a seeking serializer, which only differ
+// in how they write the length of sequences and maps that isn't known upfront (see `SerializeLen`)
+macro_rules! impl_serializer {
+    ($this:ty where $stream:ident: $($bound:tt)*) => {
+        impl<'a, 'b, $stream> Serializer for &'a mut $this
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+            type SerializeSeq = Compound<'a, 'b, $this>;
+            type SerializeTuple = Self;
+            type SerializeTupleStruct = Self;
+            type SerializeTupleVariant = Self;
+            type SerializeMap = Compound<'a, 'b, $this>;
+            type SerializeStruct = Self;
+            type SerializeStructVariant = Self;
+
+            fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::Bool)?;
+                self.write_bool(v)
+            }
+
+            fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::I8)?;
+                self.write_i8(v)
+            }
+
+            fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::I16)?;
+                self.write_i16(v)
+            }
+
+            fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::I32)?;
+                self.write_i32(v)
+            }
+
+            fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::I64)?;
+                self.write_i64(v)
+            }
+
+            fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::I128)?;
+                self.write_i128(v)
+            }
+
+            fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::U8)?;
+                self.write_u8(v)
+            }
+
+            fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::U16)?;
+                self.write_u16(v)
+            }
+
+            fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::U32)?;
+                self.write_u32(v)
+            }
+
+            fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::U64)?;
+                self.write_u64(v)
+            }
+
+            fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::U128)?;
+                self.write_u128(v)
+            }
+
+            fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::F32)?;
+                self.write_f32(v)
+            }
+
+            fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::F64)?;
+                self.write_f64(v)
+            }
+
+            fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::Char)?;
+                self.write_char(v)
+            }
+
+            fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::Str)?;
+                v.encode(self)
+            }
+
+            #[cfg(not(feature = "alloc"))]
+            fn collect_str<G: ?Sized>(self, _value: &G) -> Result<Self::Ok, Self::Error>
+            where
+                G: Display,
+            {
+                Err(serde_error(
+                    "Types implementing Display cannot be serialized without the alloc feature",
+                ))
+            }
+
+            fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+                if self.ctxt.settings.self_describing {
+                    self.write_type_tag(TypeTag::Bytes)?;
+                    self.write_usize(v.len())?;
+                }
+                self.write_bytes(v)
+            }
+
+            fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+                if self.ctxt.settings.self_describing {
+                    return self.write_type_tag(TypeTag::None);
+                }
+                self.write_bool(false)?;
+                Ok(())
+            }
+
+            fn serialize_some<G: ?Sized>(self, value: &G) -> Result<Self::Ok, Self::Error>
+            where
+                G: Serialize,
+            {
+                if self.ctxt.settings.self_describing {
+                    self.write_type_tag(TypeTag::Some)?;
+                } else {
+                    self.write_bool(true)?;
+                }
+                value.serialize(self)
+            }
+
+            fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::Unit)
+            }
+
+            fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+                self.write_type_tag(TypeTag::Unit)
+            }
+
+            fn serialize_unit_variant(
+                self,
+                _name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+            ) -> Result<Self::Ok, Self::Error> {
+                if self.ctxt.settings.self_describing {
+                    self.write_type_tag(TypeTag::Str)?;
+                    return variant.encode(self);
+                }
+                self.write_uvariant(variant_index)
+            }
+
+            fn serialize_newtype_struct<G: ?Sized>(
+                self,
+                _name: &'static str,
+                value: &G,
+            ) -> Result<Self::Ok, Self::Error>
+            where
+                G: Serialize,
+            {
+                value.serialize(self)
+            }
+
+            fn serialize_newtype_variant<G: ?Sized>(
+                self,
+                _name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+                value: &G,
+            ) -> Result<Self::Ok, Self::Error>
+            where
+                G: Serialize,
+            {
+                self.write_variant_header(variant_index, variant)?;
+                value.serialize(self)
+            }
+
+            fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+                self.write_type_tag(TypeTag::Seq)?;
+                let pending = SerializeLen::begin_len(&mut *self, len)?;
+                Ok(Compound {
+                    serializer: self,
+                    len: 0,
+                    pending,
+                })
+            }
+
+            fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+                self.write_tuple_header(len)?;
+                Ok(self)
+            }
+
+            fn serialize_tuple_struct(
+                self,
+                _name: &'static str,
+                len: usize,
+            ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+                self.write_tuple_header(len)?;
+                Ok(self)
+            }
+
+            fn serialize_tuple_variant(
+                self,
+                _name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+                len: usize,
+            ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+                self.write_variant_header(variant_index, variant)?;
+                self.write_tuple_header(len)?;
+                Ok(self)
+            }
+
+            fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+                self.write_type_tag(TypeTag::Map)?;
+                let pending = SerializeLen::begin_len(&mut *self, len)?;
+                Ok(Compound {
+                    serializer: self,
+                    len: 0,
+                    pending,
+                })
+            }
+
+            fn serialize_struct(
+                self,
+                _name: &'static str,
+                len: usize,
+            ) -> Result<Self::SerializeStruct, Self::Error> {
+                self.write_struct_header(len)?;
+                Ok(self)
+            }
+
+            fn serialize_struct_variant(
+                self,
+                _name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+                len: usize,
+            ) -> Result<Self::SerializeStructVariant, Self::Error> {
+                self.write_variant_header(variant_index, variant)?;
+                self.write_struct_header(len)?;
+                Ok(self)
+            }
+
+            fn is_human_readable(&self) -> bool {
+                false
+            }
+        }
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        Err(serde_error(
-            "deserialize_any: This data format is non-describing",
-        ))
-    }
+        impl<'b, $stream> SerializeSeq for Compound<'_, 'b, $this>
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_element<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                self.len += 1;
+                match &mut self.pending {
+                    #[cfg(feature = "alloc")]
+                    Some(PendingLen::Buffer(buffer)) => value.serialize(buffer),
+                    _ => value.serialize(&mut *self.serializer),
+                }
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                match self.pending {
+                    Some(pending) => SerializeLen::end_len(self.serializer, pending, self.len),
+                    None => Ok(()),
+                }
+            }
+        }
 
-    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_bool(self.read_bool()?)
-    }
+        impl<'b, $stream> SerializeTuple for &mut $this
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_element<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(())
+            }
+        }
 
-    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_i8(self.read_i8()?)
-    }
+        impl<'b, $stream> SerializeTupleStruct for &mut $this
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_field<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(())
+            }
+        }
 
-    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_i16(self.read_i16()?)
-    }
+        impl<'b, $stream> SerializeTupleVariant for &mut $this
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_field<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(())
+            }
+        }
 
-    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_i32(self.read_i32()?)
-    }
+        impl<'b, $stream> SerializeMap for Compound<'_, 'b, $this>
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_key<G: ?Sized>(&mut self, key: &G) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                self.len += 1;
+                match &mut self.pending {
+                    #[cfg(feature = "alloc")]
+                    Some(PendingLen::Buffer(buffer)) => key.serialize(buffer),
+                    _ => key.serialize(&mut *self.serializer),
+                }
+            }
+
+            fn serialize_value<G: ?Sized>(&mut self, value: &G) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                match &mut self.pending {
+                    #[cfg(feature = "alloc")]
+                    Some(PendingLen::Buffer(buffer)) => value.serialize(buffer),
+                    _ => value.serialize(&mut *self.serializer),
+                }
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                match self.pending {
+                    Some(pending) => SerializeLen::end_len(self.serializer, pending, self.len),
+                    None => Ok(()),
+                }
+            }
+        }
 
-    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_i64(self.read_i64()?)
-    }
+        impl<'b, $stream> SerializeStruct for &mut $this
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_field<G: ?Sized>(
+                &mut self,
+                key: &'static str,
+                value: &G,
+            ) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                if self.ctxt.settings.self_describing {
+                    key.serialize(&mut **self)?;
+                }
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(())
+            }
+        }
 
-    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_i128(self.read_i128()?)
-    }
+        impl<'b, $stream> SerializeStructVariant for &mut $this
+        where
+            $stream: $($bound)*,
+        {
+            type Ok = ();
+            type Error = EncodingError;
+
+            fn serialize_field<G: ?Sized>(
+                &mut self,
+                key: &'static str,
+                value: &G,
+            ) -> Result<(), Self::Error>
+            where
+                G: Serialize,
+            {
+                if self.ctxt.settings.self_describing {
+                    key.serialize(&mut **self)?;
+                }
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                Ok(())
+            }
+        }
+    };
+}
 
-    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_u8(self.read_u8()?)
-    }
+impl_serializer!(Encoder<'b, T> where T: Write);
+impl_serializer!(SeekSerializer<'b, T> where T: Write + Seek);
+
+/// A serde [`Deserializer`] which lends `str` and `[u8]` data straight from a [`BorrowRead`]
+/// stream, by calling [`Visitor::visit_borrowed_str`] and [`Visitor::visit_borrowed_bytes`].
+///
+/// Where `&mut Encoder<T>` can only hand out temporary or owned data, this allows types
+/// with `#[serde(borrow)] &'de str` fields to be deserialized zero-copy, even without `alloc`.
+///
+/// Strings that can't be borrowed, such as the ones encoded as UTF-16 or null-terminated,
+/// and byte slices with a non-borrowable [`NumEncoding`][`crate::NumEncoding`] are still
+/// copied, requiring the `alloc` feature.
+///
+/// # Example
+///
+/// ```
+/// use ender::{Context, Encoder};
+/// use ender::io::{Slice, VecStream};
+/// use ender::serde::BorrowDeserializer;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Entry<'a> {
+///     id: u32,
+///     #[serde(borrow)]
+///     name: &'a str,
+/// }
+///
+/// let entry = Entry { id: 7, name: "seven" };
+/// let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+/// entry.serialize(&mut encoder).unwrap();
+/// let data = encoder.finish().0.into_inner();
+///
+/// let mut deserializer = BorrowDeserializer::new(Encoder::new(Slice::new(&data), Context::new()));
+/// let decoded = Entry::deserialize(&mut deserializer).unwrap();
+/// assert_eq!(decoded, entry);
+/// ```
+pub struct BorrowDeserializer<'a, T> {
+    encoder: Encoder<'a, T>,
+}
 
-    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_u16(self.read_u16()?)
+impl<'a, T> BorrowDeserializer<'a, T> {
+    /// Wraps the given encoder.
+    #[inline]
+    pub fn new(encoder: Encoder<'a, T>) -> Self {
+        Self { encoder }
     }
 
-    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_u32(self.read_u32()?)
+    /// Unwraps the encoder, returning it.
+    #[inline]
+    pub fn into_inner(self) -> Encoder<'a, T> {
+        self.encoder
     }
+}
 
-    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_u64(self.read_u64()?)
-    }
+impl<'a, T> Deref for BorrowDeserializer<'a, T> {
+    type Target = Encoder<'a, T>;
 
-    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_u128(self.read_u128()?)
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.encoder
     }
+}
 
-    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_f32(self.read_f32()?)
+impl<T> DerefMut for BorrowDeserializer<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.encoder
     }
+}
 
-    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+/// Hands the `str` and `[u8]` data in the stream to a visitor.
+///
+/// `owned` is a hint that the visitor would rather take ownership of the data.
+trait DeserializeData<'de> {
+    fn deserialize_str_data<V>(&mut self, visitor: V, owned: bool) -> EncodingResult<V::Value>
     where
-        V: Visitor<'de>,
-    {
-        visitor.visit_f64(self.read_f64()?)
-    }
+        V: Visitor<'de>;
 
-    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bytes_data<V>(&mut self, visitor: V, owned: bool) -> EncodingResult<V::Value>
     where
-        V: Visitor<'de>,
-    {
-        visitor.visit_char(self.read_char()?)
-    }
+        V: Visitor<'de>;
+}
 
-    fn deserialize_str<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+impl<'de, T: Read> DeserializeData<'de> for Encoder<'_, T> {
+    fn deserialize_str_data<V>(&mut self, _visitor: V, _owned: bool) -> EncodingResult<V::Value>
     where
         V: Visitor<'de>,
     {
         #[cfg(feature = "alloc")]
         {
             use crate::Decode;
-            _visitor.visit_str(&alloc::string::String::decode(self)?)
+            let string = alloc::string::String::decode(self)?;
+            if _owned {
+                _visitor.visit_string(string)
+            } else {
+                _visitor.visit_str(&string)
+            }
         }
         #[cfg(not(feature = "alloc"))]
         {
             Err(serde_error(
-                "Zero-copy str decoding without alloc is not supported yet",
+                "str decoding without alloc is only supported by BorrowDeserializer",
             ))
         }
     }
 
-    fn deserialize_string<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bytes_data<V>(&mut self, _visitor: V, _owned: bool) -> EncodingResult<V::Value>
     where
         V: Visitor<'de>,
     {
         #[cfg(feature = "alloc")]
         {
             use crate::Decode;
-            _visitor.visit_string(alloc::string::String::decode(self)?)
+            let bytes = if self.ctxt.settings.self_describing {
+                // Self-describing bytes are always written raw
+                let mut bytes = alloc::vec![0; self.read_usize()?];
+                self.read_bytes(&mut bytes)?;
+                bytes
+            } else {
+                alloc::vec::Vec::decode(self)?
+            };
+            if _owned {
+                _visitor.visit_byte_buf(bytes)
+            } else {
+                _visitor.visit_bytes(&bytes)
+            }
         }
         #[cfg(not(feature = "alloc"))]
         {
             Err(serde_error(
-                "Zero-copy str decoding without alloc is not supported yet",
+                "[u8] decoding without alloc is only supported by BorrowDeserializer",
             ))
         }
     }
+}
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+impl<'de, T: BorrowRead<'de>> DeserializeData<'de> for BorrowDeserializer<'_, T> {
+    fn deserialize_str_data<V>(&mut self, visitor: V, owned: bool) -> EncodingResult<V::Value>
     where
         V: Visitor<'de>,
     {
-        #[cfg(feature = "alloc")]
-        {
-            use crate::Decode;
-            _visitor.visit_bytes(&alloc::vec::Vec::decode(self)?)
+        let string_repr = self.ctxt.settings.string_repr;
+        if string_repr.encoding != StrEncoding::Utf8 || string_repr.len != StrLen::LengthPrefixed {
+            return self.encoder.deserialize_str_data(visitor, owned);
         }
-        #[cfg(not(feature = "alloc"))]
-        {
-            Err(serde_error(
-                "Zero-copy [u8] decoding without alloc is not supported yet",
-            ))
-        }
-    }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        #[cfg(feature = "alloc")]
-        {
-            use crate::Decode;
-            _visitor.visit_byte_buf(alloc::vec::Vec::decode(self)?)
-        }
-        #[cfg(not(feature = "alloc"))]
-        {
-            Err(serde_error(
-                "Zero-copy [u8] decoding without alloc is not supported yet",
-            ))
-        }
+        let len = self.read_usize()?;
+        let bytes = self.borrow_byte_slice(len)?;
+        visitor
+            .visit_borrowed_str(core::str::from_utf8(bytes).map_err(|_| StringError::InvalidChar)?)
     }
 
-    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bytes_data<V>(&mut self, visitor: V, owned: bool) -> EncodingResult<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.read_bool()? {
-            visitor.visit_some(self)
-        } else {
-            visitor.visit_none()
+        // Self-describing bytes are always written raw
+        let settings = self.ctxt.settings;
+        if !settings.self_describing && !settings.num_repr.num_encoding.borrowable_with_size(1) {
+            return self.encoder.deserialize_bytes_data(visitor, owned);
         }
-    }
 
-    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_unit()
+        let len = self.read_usize()?;
+        visitor.visit_borrowed_bytes(self.borrow_byte_slice(len)?)
     }
+}
 
-    fn deserialize_unit_struct<V>(
-        self,
-        _name: &'static str,
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_unit()
-    }
+/// The name of a unit variant in self-describing data, which is handed to the visitor
+/// as its identifier.
+struct VariantName<'a, D> {
+    deserializer: &'a mut D,
+}
 
-    fn deserialize_newtype_struct<V>(
-        self,
-        _name: &'static str,
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
+impl<'de, D: DeserializeData<'de>> Deserializer<'de> for VariantName<'_, D> {
+    type Error = EncodingError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        self.deserializer.deserialize_str_data(visitor, false)
     }
 
-    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        let len = self.read_usize()?;
-        visitor.visit_seq(SeqAccessEncoder {
-            encoder: self,
-            length: len,
-        })
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
     }
+}
 
-    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+impl<'de, D: DeserializeData<'de>> EnumAccess<'de> for VariantName<'_, D> {
+    type Error = EncodingError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
     where
-        V: Visitor<'de>,
+        V: DeserializeSeed<'de>,
     {
-        visitor.visit_seq(SeqAccessEncoder {
-            encoder: self,
-            length: len,
-        })
+        let val = seed.deserialize(VariantName {
+            deserializer: &mut *self.deserializer,
+        })?;
+        Ok((val, self))
     }
+}
 
-    fn deserialize_tuple_struct<V>(
-        self,
-        _name: &'static str,
-        len: usize,
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_seq(SeqAccessEncoder {
-            encoder: self,
-            length: len,
-        })
+impl<'de, D: DeserializeData<'de>> VariantAccess<'de> for VariantName<'_, D> {
+    type Error = EncodingError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
     }
 
-    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn newtype_variant_seed<G>(self, _seed: G) -> Result<G::Value, Self::Error>
     where
-        V: Visitor<'de>,
+        G: DeserializeSeed<'de>,
     {
-        let len = self.read_usize()?;
-        visitor.visit_map(MapAccessEncoder {
-            encoder: self,
-            length: len,
-        })
+        Err(serde_error("Expected a unit variant"))
     }
 
-    fn deserialize_struct<V>(
-        self,
-        _name: &'static str,
-        fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(SeqAccessEncoder {
-            encoder: self,
-            length: fields.len(),
-        })
+        Err(serde_error("Expected a unit variant"))
     }
 
-    fn deserialize_enum<V>(
+    fn struct_variant<V>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
-        visitor: V,
+        _fields: &'static [&'static str],
+        _visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(self)
+        Err(serde_error("Expected a unit variant"))
     }
+}
 
-    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        visitor.visit_u32(self.read_uvariant()?)
-    }
+// Implements the serde deserializer and the enum access for both a copying and a borrowing
+// deserializer, which only differ in how they read `str` and `[u8]` (see `DeserializeData`)
+macro_rules! impl_deserializer {
+    ($this:ty where $stream:ident: $($bound:tt)*) => {
+        impl<'de, $stream> Deserializer<'de> for $this
+        where
+            $stream: $($bound)*,
+        {
+            type Error = EncodingError;
+
+            fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if !self.ctxt.settings.self_describing {
+                    return Err(serde_error(
+                        "deserialize_any: This data format is non-describing",
+                    ));
+                }
+
+                match self.read_type_tag()? {
+                    TypeTag::None => visitor.visit_none(),
+                    TypeTag::Some => visitor.visit_some(self),
+                    TypeTag::Unit => visitor.visit_unit(),
+                    TypeTag::Bool => visitor.visit_bool(self.read_bool()?),
+                    TypeTag::I8 => visitor.visit_i8(self.read_i8()?),
+                    TypeTag::I16 => visitor.visit_i16(self.read_i16()?),
+                    TypeTag::I32 => visitor.visit_i32(self.read_i32()?),
+                    TypeTag::I64 => visitor.visit_i64(self.read_i64()?),
+                    TypeTag::I128 => visitor.visit_i128(self.read_i128()?),
+                    TypeTag::U8 => visitor.visit_u8(self.read_u8()?),
+                    TypeTag::U16 => visitor.visit_u16(self.read_u16()?),
+                    TypeTag::U32 => visitor.visit_u32(self.read_u32()?),
+                    TypeTag::U64 => visitor.visit_u64(self.read_u64()?),
+                    TypeTag::U128 => visitor.visit_u128(self.read_u128()?),
+                    TypeTag::F32 => visitor.visit_f32(self.read_f32()?),
+                    TypeTag::F64 => visitor.visit_f64(self.read_f64()?),
+                    TypeTag::Char => visitor.visit_char(self.read_char()?),
+                    TypeTag::Str => {
+                        DeserializeData::deserialize_str_data(&mut *self, visitor, true)
+                    }
+                    TypeTag::Bytes => {
+                        DeserializeData::deserialize_bytes_data(&mut *self, visitor, true)
+                    }
+                    TypeTag::Seq => {
+                        let len = self.read_usize()?;
+                        visitor.visit_seq(SeqAccessEncoder {
+                            encoder: self,
+                            length: len,
+                        })
+                    }
+                    TypeTag::Map => {
+                        let len = self.read_usize()?;
+                        visitor.visit_map(MapAccessEncoder {
+                            encoder: self,
+                            length: len,
+                        })
+                    }
+                }
+            }
+
+            fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_bool(self.read_bool()?)
+            }
+
+            fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_i8(self.read_i8()?)
+            }
+
+            fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_i16(self.read_i16()?)
+            }
+
+            fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_i32(self.read_i32()?)
+            }
+
+            fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_i64(self.read_i64()?)
+            }
+
+            fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_i128(self.read_i128()?)
+            }
+
+            fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_u8(self.read_u8()?)
+            }
+
+            fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_u16(self.read_u16()?)
+            }
+
+            fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_u32(self.read_u32()?)
+            }
+
+            fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_u64(self.read_u64()?)
+            }
+
+            fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_u128(self.read_u128()?)
+            }
+
+            fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_f32(self.read_f32()?)
+            }
+
+            fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_f64(self.read_f64()?)
+            }
+
+            fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_char(self.read_char()?)
+            }
+
+            fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                DeserializeData::deserialize_str_data(&mut *self, visitor, false)
+            }
+
+            fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                DeserializeData::deserialize_str_data(&mut *self, visitor, true)
+            }
+
+            fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                DeserializeData::deserialize_bytes_data(&mut *self, visitor, false)
+            }
+
+            fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                DeserializeData::deserialize_bytes_data(&mut *self, visitor, true)
+            }
+
+            fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return match self.read_type_tag()? {
+                        TypeTag::None | TypeTag::Unit => visitor.visit_none(),
+                        TypeTag::Some => visitor.visit_some(self),
+                        _ => Err(serde_error("Expected an optional value")),
+                    };
+                }
+
+                if self.read_bool()? {
+                    visitor.visit_some(self)
+                } else {
+                    visitor.visit_none()
+                }
+            }
+
+            fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_unit()
+            }
+
+            fn deserialize_unit_struct<V>(
+                self,
+                _name: &'static str,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_unit()
+            }
+
+            fn deserialize_newtype_struct<V>(
+                self,
+                _name: &'static str,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.visit_newtype_struct(self)
+            }
+
+            fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                let len = self.read_usize()?;
+                visitor.visit_seq(SeqAccessEncoder {
+                    encoder: self,
+                    length: len,
+                })
+            }
+
+            fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_seq(SeqAccessEncoder {
+                    encoder: self,
+                    length: len,
+                })
+            }
+
+            fn deserialize_tuple_struct<V>(
+                self,
+                _name: &'static str,
+                len: usize,
+                visitor: V,
+            ) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_seq(SeqAccessEncoder {
+                    encoder: self,
+                    length: len,
+                })
+            }
+
+            fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                let len = self.read_usize()?;
+                visitor.visit_map(MapAccessEncoder {
+                    encoder: self,
+                    length: len,
+                })
+            }
+
+            fn deserialize_struct<V>(
+                self,
+                _name: &'static str,
+                fields: &'static [&'static str],
+                visitor: V,
+            ) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_seq(SeqAccessEncoder {
+                    encoder: self,
+                    length: fields.len(),
+                })
+            }
+
+            fn deserialize_enum<V>(
+                self,
+                _name: &'static str,
+                _variants: &'static [&'static str],
+                visitor: V,
+            ) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    // Enums are either the name of a unit variant, or a single-entry map
+                    // from the name of the variant to its content
+                    return match self.read_type_tag()? {
+                        TypeTag::Str => visitor.visit_enum(VariantName { deserializer: self }),
+                        TypeTag::Map if self.read_usize()? == 1 => visitor.visit_enum(self),
+                        _ => Err(serde_error("Expected an enum variant")),
+                    };
+                }
+
+                visitor.visit_enum(self)
+            }
+
+            fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                visitor.visit_u32(self.read_uvariant()?)
+            }
+
+            fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                if self.ctxt.settings.self_describing {
+                    return self.deserialize_any(visitor);
+                }
+                Err(serde_error(
+                    "deserialize_ignored_any: This data format is non-describing",
+                ))
+            }
+
+            fn is_human_readable(&self) -> bool {
+                false
+            }
+        }
 
-    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        Err(serde_error(
-            "deserialize_ignored_any: This data format is non-describing",
-        ))
-    }
+        impl<'de, $stream> EnumAccess<'de> for $this
+        where
+            $stream: $($bound)*,
+        {
+            type Error = EncodingError;
+            type Variant = Self;
+
+            fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+            where
+                V: DeserializeSeed<'de>,
+            {
+                let val = seed.deserialize(&mut *self)?;
+                Ok((val, self))
+            }
+        }
 
-    fn is_human_readable(&self) -> bool {
-        false
-    }
+        impl<'de, $stream> VariantAccess<'de> for $this
+        where
+            $stream: $($bound)*,
+        {
+            type Error = EncodingError;
+
+            fn unit_variant(self) -> Result<(), Self::Error> {
+                if self.ctxt.settings.self_describing {
+                    // The content of a unit variant is only present if it was written as a map
+                    IgnoredAny::deserialize(self)?;
+                }
+                Ok(())
+            }
+
+            fn newtype_variant_seed<G>(self, seed: G) -> Result<G::Value, Self::Error>
+            where
+                G: DeserializeSeed<'de>,
+            {
+                seed.deserialize(self)
+            }
+
+            fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.deserialize_tuple(len, visitor)
+            }
+
+            fn struct_variant<V>(
+                self,
+                fields: &'static [&'static str],
+                visitor: V,
+            ) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.deserialize_struct("", fields, visitor)
+            }
+        }
+    };
 }
 
-struct SeqAccessEncoder<'a, 'de, T: Read> {
-    encoder: &'a mut Encoder<'de, T>,
+impl_deserializer!(&mut Encoder<'_, T> where T: Read);
+impl_deserializer!(&mut BorrowDeserializer<'_, T> where T: BorrowRead<'de>);
+
+struct SeqAccessEncoder<'a, D> {
+    encoder: &'a mut D,
     length: usize,
 }
 
-impl<'de, T: Read> SeqAccess<'de> for SeqAccessEncoder<'_, 'de, T> {
+impl<'de, D> SeqAccess<'de> for SeqAccessEncoder<'_, D>
+where
+    for<'a> &'a mut D: Deserializer<'de, Error = EncodingError>,
+{
     type Error = EncodingError;
 
     fn next_element_seed<G>(&mut self, seed: G) -> Result<Option<G::Value>, Self::Error>
@@ -717,12 +1543,15 @@ impl<'de, T: Read> SeqAccess<'de> for SeqAccessEncoder<'_, 'de, T> {
     }
 }
 
-struct MapAccessEncoder<'a, 'de, T: Read> {
-    encoder: &'a mut Encoder<'de, T>,
+struct MapAccessEncoder<'a, D> {
+    encoder: &'a mut D,
     length: usize,
 }
 
-impl<'de, T: Read> MapAccess<'de> for MapAccessEncoder<'_, 'de, T> {
+impl<'de, D> MapAccess<'de> for MapAccessEncoder<'_, D>
+where
+    for<'a> &'a mut D: Deserializer<'de, Error = EncodingError>,
+{
     type Error = EncodingError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
@@ -748,49 +1577,3 @@ impl<'de, T: Read> MapAccess<'de> for MapAccessEncoder<'_, 'de, T> {
         Some(self.length)
     }
 }
-
-impl<'de, T: Read> EnumAccess<'de> for &mut Encoder<'de, T> {
-    type Error = EncodingError;
-    type Variant = Self;
-
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
-    where
-        V: DeserializeSeed<'de>,
-    {
-        let val = seed.deserialize(&mut *self)?;
-        Ok((val, self))
-    }
-}
-
-impl<'de, T: Read> VariantAccess<'de> for &mut Encoder<'de, T> {
-    type Error = EncodingError;
-
-    fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
-    }
-
-    fn newtype_variant_seed<G>(self, seed: G) -> Result<G::Value, Self::Error>
-    where
-        G: DeserializeSeed<'de>,
-    {
-        seed.deserialize(self)
-    }
-
-    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        self.deserialize_tuple(len, visitor)
-    }
-
-    fn struct_variant<V>(
-        self,
-        fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        self.deserialize_struct("", fields, visitor)
-    }
-}
@@ -0,0 +1,988 @@
+use core::cell::{Cell, RefCell};
+use core::ffi::CStr;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo};
+use core::time::Duration;
+
+use crate::format::Int;
+use crate::{
+    windows1252, BitWidth, Context, EncodedSize, EncodingError, EncodingResult, FlattenError,
+    Format, NumEncoding, Opaque, Sign, Signed, StrEncoding, StrLen, StringError, Unsigned,
+};
+
+// Arithmetic counterparts of the primitives of the encoder
+
+/// The number of bits of a value of the given bit-width
+const fn width_bits(width: BitWidth) -> u32 {
+    match width {
+        BitWidth::Bit8 => 8,
+        BitWidth::Bit16 => 16,
+        BitWidth::Bit32 => 32,
+        BitWidth::Bit64 => 64,
+        BitWidth::Bit128 => 128,
+    }
+}
+
+/// Converts an opaque value to the given unsigned bit-width, failing just like the encoder does
+fn fit_unsigned(value: Opaque, width: BitWidth) -> EncodingResult<u128> {
+    Ok(match width {
+        BitWidth::Bit8 => TryInto::<u8>::try_into(value)? as u128,
+        BitWidth::Bit16 => TryInto::<u16>::try_into(value)? as u128,
+        BitWidth::Bit32 => TryInto::<u32>::try_into(value)? as u128,
+        BitWidth::Bit64 => TryInto::<u64>::try_into(value)? as u128,
+        BitWidth::Bit128 => TryInto::<u128>::try_into(value)?,
+    })
+}
+
+/// Converts an opaque value to the given signed bit-width, failing just like the encoder does
+fn fit_signed(value: Opaque, width: BitWidth) -> EncodingResult<i128> {
+    Ok(match width {
+        BitWidth::Bit8 => TryInto::<i8>::try_into(value)? as i128,
+        BitWidth::Bit16 => TryInto::<i16>::try_into(value)? as i128,
+        BitWidth::Bit32 => TryInto::<i32>::try_into(value)? as i128,
+        BitWidth::Bit64 => TryInto::<i64>::try_into(value)? as i128,
+        BitWidth::Bit128 => TryInto::<i128>::try_into(value)?,
+    })
+}
+
+const fn uleb128_size(value: u128) -> usize {
+    if value == 0 {
+        1
+    } else {
+        (u128::BITS - value.leading_zeros()).div_ceil(7) as usize
+    }
+}
+
+const fn leb128_size(value: i128) -> usize {
+    // The significant bits, plus the sign bit
+    let magnitude = if value < 0 { !value } else { value };
+    (u128::BITS - magnitude.leading_zeros() + 1).div_ceil(7) as usize
+}
+
+const fn bincode_varint_size(value: u128) -> usize {
+    if value < 251 {
+        1
+    } else if value <= u16::MAX as u128 {
+        3
+    } else if value <= u32::MAX as u128 {
+        5
+    } else if value <= u64::MAX as u128 {
+        9
+    } else {
+        17
+    }
+}
+
+const fn asn1_length_size(value: u128) -> usize {
+    if value < 0x80 {
+        1
+    } else {
+        1 + 16 - value.leading_zeros() as usize / 8
+    }
+}
+
+/// The size of an unsigned value `bits` wide, according to the given numerical encoding
+const fn uint_size(value: u128, bits: u32, num_encoding: NumEncoding) -> usize {
+    match num_encoding {
+        NumEncoding::Fixed => bits as usize / 8,
+        NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if bits == 8 => 1,
+        NumEncoding::BincodeVarint => bincode_varint_size(value),
+        NumEncoding::Asn1 => asn1_length_size(value),
+        NumEncoding::Leb128
+        | NumEncoding::ProtobufWasteful
+        | NumEncoding::ProtobufZigzag
+        | NumEncoding::PostcardVarint => uleb128_size(value),
+    }
+}
+
+/// The size of a signed value `bits` wide, according to the given numerical encoding
+const fn int_size(value: i128, bits: u32, num_encoding: NumEncoding) -> usize {
+    // The bits reinterpreted as an unsigned value of the same width
+    let unsigned = if bits == u128::BITS {
+        value as u128
+    } else {
+        value as u128 & ((1 << bits) - 1)
+    };
+    let zigzag = ((value << 1) ^ (value >> (i128::BITS - 1))) as u128;
+
+    match num_encoding {
+        NumEncoding::Fixed => bits as usize / 8,
+        NumEncoding::BincodeVarint | NumEncoding::PostcardVarint if bits == 8 => 1,
+        NumEncoding::Leb128 => leb128_size(value),
+        NumEncoding::ProtobufWasteful => uleb128_size(unsigned),
+        NumEncoding::Asn1 => asn1_length_size(unsigned),
+        NumEncoding::ProtobufZigzag | NumEncoding::PostcardVarint => uleb128_size(zigzag),
+        NumEncoding::BincodeVarint => bincode_varint_size(zigzag),
+    }
+}
+
+impl Context<'_> {
+    /// Computes the size of an `usize` without encoding it, which is what
+    /// [`write_usize`][`crate::Encoder::write_usize`] would write with this context.
+    ///
+    /// Just like while encoding, the size flatten variable is consumed and checked
+    /// against `value`, in which case the size is `0`.
+    pub fn usize_size(&mut self, value: usize) -> EncodingResult<usize> {
+        if let Some(size) = self.consume_size_flatten() {
+            if size != value {
+                return Err(EncodingError::FlattenError(FlattenError::LenMismatch {
+                    expected: size,
+                    got: value,
+                }));
+            }
+            return Ok(0);
+        }
+        if value > self.settings.size_repr.max_size {
+            return Err(EncodingError::MaxSizeExceeded {
+                max: self.settings.size_repr.max_size,
+                requested: value,
+            });
+        }
+        if self.settings.format != Format::Binary {
+            return self.settings.format.int_size(Int::from(value));
+        }
+
+        let width = self.settings.size_repr.width;
+        let value = fit_unsigned(Opaque::from(value), width)?;
+        Ok(uint_size(value, width_bits(width), self.settings.size_repr.num_encoding))
+    }
+
+    /// Computes the size of the length of a sequence without encoding it, which is what
+    /// [`write_seq_len`][`crate::Encoder::write_seq_len`] would write with this context.
+    pub fn seq_len_size(&mut self, len: usize) -> EncodingResult<usize> {
+        if self.settings.format == Format::Binary || self.size_flatten.is_some() {
+            return self.usize_size(len);
+        }
+        self.check_max_size(len)?;
+        self.settings.format.seq_len_size(len)
+    }
+
+    /// Computes the size of the length of a map without encoding it, which is what
+    /// [`write_map_len`][`crate::Encoder::write_map_len`] would write with this context.
+    pub fn map_len_size(&mut self, len: usize) -> EncodingResult<usize> {
+        if self.settings.format == Format::Binary || self.size_flatten.is_some() {
+            return self.usize_size(len);
+        }
+        self.check_max_size(len)?;
+        self.settings.format.map_len_size(len)
+    }
+
+    /// Computes the size of the number of items of a struct, tuple or enum variant, which is
+    /// what [`write_fields_len`][`crate::Encoder::write_fields_len`] would write with this context.
+    pub fn fields_len_size(&self, len: usize) -> EncodingResult<usize> {
+        if self.settings.format == Format::Binary {
+            return Ok(0);
+        }
+        self.settings.format.seq_len_size(len)
+    }
+
+    /// Computes the size of what precedes the variant of an enum, which is what
+    /// [`write_enum_header`][`crate::Encoder::write_enum_header`] would write with this context.
+    pub fn enum_header_size(&self) -> EncodingResult<usize> {
+        if self.variant_flatten.is_some() {
+            return Ok(0);
+        }
+        self.fields_len_size(2)
+    }
+
+    /// Computes the size of the absence of a value, which is what
+    /// [`write_null`][`crate::Encoder::write_null`] would write with this context.
+    pub fn null_size(&self) -> usize {
+        if self.settings.format == Format::Binary {
+            return 0;
+        }
+        self.settings.format.null_size()
+    }
+
+    /// Computes the size of an unsigned `Variant` without encoding it, which is what
+    /// [`write_uvariant`][`crate::Encoder::write_uvariant`] would write with this context.
+    ///
+    /// Just like while encoding, the variant flatten variable is consumed and checked
+    /// against `value`, in which case the size is `0`.
+    #[allow(private_bounds)]
+    pub fn uvariant_size<V>(&mut self, value: V) -> EncodingResult<usize>
+    where
+        Opaque: From<V>,
+        V: Sign<Sign = Unsigned>,
+    {
+        let value = Opaque::from(value);
+        if let Some(variant) = self.consume_variant_flatten() {
+            self.check_variant_flatten(variant, value)
+        } else if self.settings.format != Format::Binary {
+            self.settings.format.int_size(Int::from_opaque(value)?)
+        } else {
+            let width = self.settings.variant_repr.width;
+            let value = fit_unsigned(value, width)?;
+            Ok(uint_size(value, width_bits(width), self.settings.variant_repr.num_encoding))
+        }
+    }
+
+    /// Computes the size of a signed `Variant` without encoding it, which is what
+    /// [`write_ivariant`][`crate::Encoder::write_ivariant`] would write with this context.
+    ///
+    /// Just like while encoding, the variant flatten variable is consumed and checked
+    /// against `value`, in which case the size is `0`.
+    #[allow(private_bounds)]
+    pub fn ivariant_size<V>(&mut self, value: V) -> EncodingResult<usize>
+    where
+        Opaque: From<V>,
+        V: Sign<Sign = Signed>,
+    {
+        let value = Opaque::from(value);
+        if let Some(variant) = self.consume_variant_flatten() {
+            self.check_variant_flatten(variant, value)
+        } else if self.settings.format != Format::Binary {
+            self.settings.format.int_size(Int::from_opaque(value)?)
+        } else {
+            let width = self.settings.variant_repr.width;
+            let value = fit_signed(value, width)?;
+            Ok(int_size(value, width_bits(width), self.settings.variant_repr.num_encoding))
+        }
+    }
+
+    fn check_variant_flatten(&self, expected: Opaque, got: Opaque) -> EncodingResult<usize> {
+        if expected != got {
+            return Err(FlattenError::VariantMismatch { expected, got }.into());
+        }
+        Ok(0)
+    }
+
+    fn check_max_size(&self, len: usize) -> EncodingResult<usize> {
+        let max = self.settings.size_repr.max_size;
+        if len > max {
+            return Err(EncodingError::MaxSizeExceeded {
+                max,
+                requested: len,
+            });
+        }
+        Ok(len)
+    }
+
+    fn bool_size(&mut self, value: bool) -> EncodingResult<usize> {
+        if let Some(boolean) = self.consume_bool_flatten() {
+            if boolean != value {
+                return Err(FlattenError::BoolMismatch {
+                    expected: boolean,
+                    got: value,
+                }
+                .into());
+            }
+            Ok(0)
+        } else if self.settings.format != Format::Binary {
+            Ok(self.settings.format.bool_size())
+        } else {
+            Ok(1)
+        }
+    }
+
+    /// The size of a single char of a string in the binary format
+    pub(crate) fn char_data_size(&self, value: char) -> EncodingResult<usize> {
+        let encoding = self.settings.string_repr.encoding;
+        if value == '\0' {
+            return Ok(encoding.bytes());
+        }
+
+        match encoding {
+            StrEncoding::Ascii if value.is_ascii() => Ok(1),
+            StrEncoding::Utf8 => Ok(value.len_utf8()),
+            StrEncoding::Utf16 => Ok(value.len_utf16() * 2),
+            StrEncoding::Utf32 => Ok(4),
+            StrEncoding::Windows1252 if windows1252::dec_to_enc(value).is_some() => Ok(1),
+            _ => Err(StringError::InvalidChar.into()),
+        }
+    }
+
+    /// The size of the chars of a string in the binary format, excluding its length or terminator
+    pub(crate) fn str_data_size<I>(&self, chars: I) -> EncodingResult<usize>
+    where
+        I: Iterator<Item = char>,
+    {
+        let mut size = 0;
+        for ch in chars {
+            size += self.char_data_size(ch)?;
+        }
+        Ok(size)
+    }
+
+    fn char_size(&self, value: char) -> EncodingResult<usize> {
+        if self.settings.format != Format::Binary {
+            // A string made of a single char
+            let len = value.len_utf8();
+            return Ok(self.settings.format.str_len_size(len)? + len);
+        }
+        self.char_data_size(value)
+    }
+
+    fn str_size(&mut self, string: &str) -> EncodingResult<usize> {
+        if self.settings.format != Format::Binary {
+            return Ok(self.settings.format.str_len_size(string.len())? + string.len());
+        }
+
+        let size = self.str_data_size(string.chars())?;
+        match self.settings.string_repr.len {
+            StrLen::LengthPrefixed => Ok(self.usize_size(size)? + size),
+            StrLen::NullTerminated => Ok(size + self.settings.string_repr.encoding.bytes()),
+            StrLen::NullTerminatedFixed(max) if size > max => Err(StringError::TooLong.into()),
+            StrLen::NullTerminatedFixed(max) => Ok(max),
+        }
+    }
+}
+
+// Primitives
+
+macro_rules! impl_int_size {
+    ($($ty:ty => $size:ident);* $(;)? ) => {
+	    $(
+	    impl $crate::EncodedSize for $ty {
+		    #[inline]
+		    fn encoded_size(&self, ctxt: &$crate::Context) -> $crate::EncodingResult<usize> {
+			    if ctxt.settings.format != Format::Binary {
+				    return ctxt.settings.format.int_size(Int::from(*self));
+			    }
+			    Ok($size(*self as _, <$ty>::BITS, ctxt.settings.num_repr.num_encoding))
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_int_size! {
+    u8 => uint_size;
+    u16 => uint_size;
+    u32 => uint_size;
+    u64 => uint_size;
+    u128 => uint_size;
+    i8 => int_size;
+    i16 => int_size;
+    i32 => int_size;
+    i64 => int_size;
+    i128 => int_size;
+}
+
+impl EncodedSize for char {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        ctxt.char_size(*self)
+    }
+}
+
+impl EncodedSize for bool {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        ctxt.bool_size(*self)
+    }
+}
+
+impl EncodedSize for f32 {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        if ctxt.settings.format != Format::Binary {
+            return Ok(ctxt.settings.format.f32_size());
+        }
+        Ok(4)
+    }
+}
+
+impl EncodedSize for f64 {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        if ctxt.settings.format != Format::Binary {
+            return Ok(ctxt.settings.format.f64_size());
+        }
+        Ok(8)
+    }
+}
+
+impl EncodedSize for usize {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        ctxt.usize_size(*self)
+    }
+}
+
+impl EncodedSize for isize {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        if ctxt.settings.format != Format::Binary {
+            return ctxt.settings.format.int_size(Int::from(*self));
+        }
+
+        let width = ctxt.settings.size_repr.width;
+        let value = fit_signed(Opaque::from(*self), width)?;
+        Ok(int_size(value, width_bits(width), ctxt.settings.size_repr.num_encoding))
+    }
+}
+
+impl EncodedSize for () {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        Ok(ctxt.null_size())
+    }
+}
+
+impl<T: ?Sized + EncodedSize> EncodedSize for &T {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        <T as EncodedSize>::encoded_size(self, ctxt)
+    }
+}
+
+impl<T: ?Sized + EncodedSize> EncodedSize for &mut T {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        <T as EncodedSize>::encoded_size(self, ctxt)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for [T] {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.len())?;
+        for v in self.iter() {
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+impl<T: EncodedSize, const SIZE: usize> EncodedSize for [T; SIZE] {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut size = ctxt.fields_len_size(SIZE)?;
+        for v in self.iter() {
+            size += v.encoded_size(ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+macro_rules! tuple_size {
+    ($($name:ident)+) => {
+	    #[allow(non_snake_case)]
+	    impl<$($name: $crate::EncodedSize),+> $crate::EncodedSize for ($($name),+) {
+		    #[inline]
+		    fn encoded_size(&self, ctxt: &$crate::Context) -> $crate::EncodingResult<usize> {
+			    let ($($name),*) = self;
+			    let mut size = ctxt.fields_len_size([$(stringify!($name)),+].len())?;
+			    $(
+			        size += $crate::EncodedSize::encoded_size($name, ctxt)?;
+			    )+
+			    Ok(size)
+		    }
+	    }
+    };
+}
+
+tuple_size! { A B }
+tuple_size! { A B C }
+tuple_size! { A B C D }
+tuple_size! { A B C D E }
+tuple_size! { A B C D E F }
+tuple_size! { A B C D E F G }
+tuple_size! { A B C D E F G H }
+tuple_size! { A B C D E F G H I }
+tuple_size! { A B C D E F G H I J }
+tuple_size! { A B C D E F G H I J K }
+tuple_size! { A B C D E F G H I J K L }
+tuple_size! { A B C D E F G H I J K L M }
+tuple_size! { A B C D E F G H I J K L M N }
+tuple_size! { A B C D E F G H I J K L M N O }
+tuple_size! { A B C D E F G H I J K L M N O P } // Up to 16
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl EncodedSize for alloc::string::String {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        ctxt.str_size(self)
+    }
+}
+
+impl EncodedSize for str {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        ctxt.str_size(self)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for Option<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let header = match ctxt.bool_size(self.is_some())? {
+            // The self-describing formats always write null or the header of an array
+            _ if ctxt.settings.format != Format::Binary => ctxt.null_size(),
+            size => size,
+        };
+        match self {
+            None => Ok(header),
+            Some(value) => Ok(header + value.encoded_size(&ctxt)?),
+        }
+    }
+}
+
+impl<T: EncodedSize, E: EncodedSize> EncodedSize for Result<T, E> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let header = ctxt.enum_header_size()? + ctxt.bool_size(self.is_ok())? + ctxt.fields_len_size(1)?;
+        match self {
+            Err(err) => Ok(header + err.encoded_size(&ctxt)?),
+            Ok(ok) => Ok(header + ok.encoded_size(&ctxt)?),
+        }
+    }
+}
+
+impl<T> EncodedSize for PhantomData<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        Ok(ctxt.null_size())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::boxed::Box<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        <T as EncodedSize>::encoded_size(self.deref(), ctxt)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::rc::Rc<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        <T as EncodedSize>::encoded_size(self.deref(), ctxt)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::sync::Arc<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        <T as EncodedSize>::encoded_size(self.deref(), ctxt)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<'a, T: ?Sized + alloc::borrow::ToOwned> EncodedSize for alloc::borrow::Cow<'a, T>
+where
+    T: EncodedSize,
+    <T as alloc::borrow::ToOwned>::Owned: EncodedSize,
+{
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        match self {
+            Self::Borrowed(x) => x.encoded_size(ctxt),
+            Self::Owned(x) => x.encoded_size(ctxt),
+        }
+    }
+}
+
+impl<T: EncodedSize + Copy> EncodedSize for Cell<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.get().encoded_size(ctxt)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for RefCell<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        match self.try_borrow() {
+            Ok(ok) => <T as EncodedSize>::encoded_size(ok.deref(), ctxt),
+            Err(_) => Err(EncodingError::LockError),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<T: EncodedSize> EncodedSize for std::sync::Mutex<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        match self.lock() {
+            Ok(ok) => <T as EncodedSize>::encoded_size(ok.deref(), ctxt),
+            Err(_) => Err(EncodingError::LockError),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<T: EncodedSize> EncodedSize for std::sync::RwLock<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        match self.read() {
+            Ok(ok) => <T as EncodedSize>::encoded_size(ok.deref(), ctxt),
+            Err(_) => Err(EncodingError::LockError),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<K: EncodedSize, V: EncodedSize> EncodedSize for alloc::collections::BTreeMap<K, V> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.map_len_size(self.len())?;
+        for (k, v) in self.iter() {
+            size += k.encoded_size(&ctxt)?;
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<K: EncodedSize> EncodedSize for alloc::collections::BTreeSet<K> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.len())?;
+        for k in self.iter() {
+            size += k.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<K: EncodedSize, V: EncodedSize> EncodedSize for std::collections::hash_map::HashMap<K, V> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.map_len_size(self.len())?;
+        for (k, v) in self.iter() {
+            size += k.encoded_size(&ctxt)?;
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<K: EncodedSize> EncodedSize for std::collections::hash_set::HashSet<K> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.len())?;
+        for k in self.iter() {
+            size += k.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::collections::BinaryHeap<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.len())?;
+        for v in self.iter() {
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::collections::LinkedList<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.len())?;
+        for v in self.iter() {
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::vec::Vec<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.as_slice().encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<T: EncodedSize> EncodedSize for alloc::collections::VecDeque<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let mut size = ctxt.seq_len_size(self.len())?;
+        for v in self.iter() {
+            size += v.encoded_size(&ctxt)?;
+        }
+        Ok(size)
+    }
+}
+
+impl EncodedSize for CStr {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.to_str()
+            .map_err(|_| StringError::ConversionError)?
+            .encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl EncodedSize for alloc::ffi::CString {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.to_str()
+            .map_err(|_| StringError::ConversionError)?
+            .encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::ffi::OsStr {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.to_str()
+            .ok_or(StringError::ConversionError)?
+            .encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::ffi::OsString {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.to_str()
+            .ok_or(StringError::ConversionError)?
+            .encoded_size(ctxt)
+    }
+}
+
+impl EncodedSize for Duration {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        Ok(ctxt.fields_len_size(2)?
+            + self.as_secs().encoded_size(ctxt)?
+            + self.subsec_nanos().encoded_size(ctxt)?)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::time::SystemTime {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let since_epoch = self
+            .duration_since(Self::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        since_epoch.encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::path::Path {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.as_os_str().encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::path::PathBuf {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.as_os_str().encoded_size(ctxt)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for Range<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        Ok(ctxt.fields_len_size(2)? + self.start.encoded_size(ctxt)? + self.end.encoded_size(ctxt)?)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for RangeInclusive<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        Ok(ctxt.fields_len_size(2)? + self.start().encoded_size(ctxt)? + self.end().encoded_size(ctxt)?)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for RangeFrom<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.start.encoded_size(ctxt)
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for RangeTo<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.end.encoded_size(ctxt)
+    }
+}
+
+impl EncodedSize for RangeFull {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        Ok(ctxt.null_size())
+    }
+}
+
+macro_rules! impl_nz_size {
+    ($($ty:ident),* $(,)?) => {
+	    $(
+	    impl $crate::EncodedSize for core::num::$ty {
+		    #[inline]
+		    fn encoded_size(&self, ctxt: &$crate::Context) -> $crate::EncodingResult<usize> {
+			    self.get().encoded_size(ctxt)
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_nz_size! {
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+    NonZeroUsize,
+    NonZeroIsize,
+}
+
+impl<T: EncodedSize> EncodedSize for Bound<T> {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let header = ctxt.enum_header_size()?;
+        match self {
+            Bound::Included(x) => Ok(header
+                + ctxt.uvariant_size(0u8)?
+                + ctxt.fields_len_size(1)?
+                + x.encoded_size(&ctxt)?),
+            Bound::Excluded(x) => Ok(header
+                + ctxt.uvariant_size(1u8)?
+                + ctxt.fields_len_size(1)?
+                + x.encoded_size(&ctxt)?),
+            Bound::Unbounded => Ok(header + ctxt.uvariant_size(2u8)? + ctxt.fields_len_size(0)?),
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "unstable")))]
+impl EncodedSize for ! {
+    #[inline]
+    fn encoded_size(&self, _ctxt: &Context) -> EncodingResult<usize> {
+        *self
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::net::IpAddr {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let header = ctxt.enum_header_size()?;
+        match self {
+            Self::V4(x) => Ok(header
+                + ctxt.uvariant_size(0u8)?
+                + ctxt.fields_len_size(1)?
+                + x.encoded_size(&ctxt)?),
+            Self::V6(x) => Ok(header
+                + ctxt.uvariant_size(1u8)?
+                + ctxt.fields_len_size(1)?
+                + x.encoded_size(&ctxt)?),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::net::Ipv4Addr {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.octets().encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::net::Ipv6Addr {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        self.octets().encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::net::SocketAddr {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        let mut ctxt = *ctxt;
+        let header = ctxt.enum_header_size()?;
+        match self {
+            Self::V4(x) => Ok(header
+                + ctxt.uvariant_size(0u8)?
+                + ctxt.fields_len_size(1)?
+                + x.encoded_size(&ctxt)?),
+            Self::V6(x) => Ok(header
+                + ctxt.uvariant_size(1u8)?
+                + ctxt.fields_len_size(1)?
+                + x.encoded_size(&ctxt)?),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::net::SocketAddrV4 {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        (self.ip(), self.port()).encoded_size(ctxt)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl EncodedSize for std::net::SocketAddrV6 {
+    #[inline]
+    fn encoded_size(&self, ctxt: &Context) -> EncodingResult<usize> {
+        (self.ip(), self.port(), self.flowinfo(), self.scope_id()).encoded_size(ctxt)
+    }
+}
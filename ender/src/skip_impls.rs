@@ -0,0 +1,635 @@
+use core::any::TypeId;
+use core::cell::{Cell, RefCell};
+use core::ffi::CStr;
+use core::marker::PhantomData;
+use core::ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo};
+use core::time::Duration;
+
+use crate::impls::{bulk_endianness, check_borrowable_format};
+use crate::io::Read;
+use crate::{Encoder, EncodingError, EncodingResult, Format, Skip};
+
+// Primitives
+
+macro_rules! impl_skip {
+    ($($ty:ty => $read:ident);* $(;)? ) => {
+	    $(
+	    impl<R: $crate::io::Read> $crate::Skip<R> for $ty {
+		    #[inline]
+		    fn skip(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<()> {
+			    decoder.$read().map(drop)
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_skip! {
+    bool => read_bool;
+    char => read_char;
+    usize => read_usize;
+    isize => read_isize;
+}
+
+macro_rules! impl_skip_bulk {
+    ($($ty:ty => $read:ident, $float:literal);* $(;)? ) => {
+	    $(
+	    impl<R: $crate::io::Read> $crate::Skip<R> for $ty {
+		    #[inline]
+		    fn skip(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<()> {
+			    Self::skip_seq(decoder, 1)
+		    }
+
+		    #[inline]
+		    fn skip_seq(decoder: &mut $crate::Encoder<R>, len: usize) -> $crate::EncodingResult<()> {
+			    let size = core::mem::size_of::<$ty>();
+			    if bulk_endianness(decoder, $float, size).is_some() {
+				    return decoder.skip_bytes(len.saturating_mul(size));
+			    }
+			    for i in 0..len {
+				    decoder.with_index(|decoder| decoder.$read().map(drop), i)?;
+			    }
+			    Ok(())
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_skip_bulk! {
+    u8 => read_u8, false;
+    u16 => read_u16, false;
+    u32 => read_u32, false;
+    u64 => read_u64, false;
+    u128 => read_u128, false;
+    i8 => read_i8, false;
+    i16 => read_i16, false;
+    i32 => read_i32, false;
+    i64 => read_i64, false;
+    i128 => read_i128, false;
+    f32 => read_f32, true;
+    f64 => read_f64, true;
+}
+
+impl<R: Read> Skip<R> for () {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_null()
+    }
+}
+
+impl<R: Read, T: Skip<R>, const SIZE: usize> Skip<R> for [T; SIZE] {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_fields_len(SIZE)?;
+        T::skip_seq(decoder, SIZE)
+    }
+}
+
+macro_rules! tuple_skip {
+    ($($name:ident)+) => {
+	    impl<R: $crate::io::Read, $($name: $crate::Skip<R>),+> $crate::Skip<R> for ($($name),+) {
+		    #[inline]
+		    fn skip(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<()> {
+			    decoder.read_fields_len([$(stringify!($name)),+].len())?;
+			    $(
+			    <$name as $crate::Skip<R>>::skip(decoder)?;
+			    )+
+			    Ok(())
+		    }
+	    }
+    };
+}
+
+tuple_skip! { A B }
+tuple_skip! { A B C }
+tuple_skip! { A B C D }
+tuple_skip! { A B C D E }
+tuple_skip! { A B C D E F }
+tuple_skip! { A B C D E F G }
+tuple_skip! { A B C D E F G H }
+tuple_skip! { A B C D E F G H I }
+tuple_skip! { A B C D E F G H I J }
+tuple_skip! { A B C D E F G H I J K }
+tuple_skip! { A B C D E F G H I J K L }
+tuple_skip! { A B C D E F G H I J K L M }
+tuple_skip! { A B C D E F G H I J K L M N }
+tuple_skip! { A B C D E F G H I J K L M N O }
+tuple_skip! { A B C D E F G H I J K L M N O P } // Up to 16
+
+/// Skips a borrowed slice of plain-old-data values, which is laid out as-is.
+///
+/// Just like while decoding, `usize` and `isize` use the bit-width of the size representation.
+impl<R: Read, T: bytemuck::Pod> Skip<R> for &[T] {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        check_borrowable_format(decoder)?;
+        let len = decoder.read_usize()?;
+
+        let ty = TypeId::of::<T>();
+        let size = if ty == TypeId::of::<usize>() || ty == TypeId::of::<isize>() {
+            decoder.ctxt.settings.size_repr.width.bytes()
+        } else {
+            core::mem::size_of::<T>()
+        };
+        decoder.skip_bytes(len.saturating_mul(size))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read> Skip<R> for alloc::string::String {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.skip_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read> Skip<R> for alloc::boxed::Box<str> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.skip_str()
+    }
+}
+
+impl<R: Read> Skip<R> for &str {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        // Borrowed strings are always length-prefixed, just like while decoding
+        if decoder.ctxt.settings.format != Format::Binary {
+            return decoder.skip_str();
+        }
+        let len = decoder.read_usize()?;
+        decoder.skip_bytes(len)
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for Option<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let present = if decoder.ctxt.settings.format != Format::Binary {
+            decoder.read_fmt_option()?
+        } else {
+            decoder.read_bool()?
+        };
+        match present {
+            true => T::skip(decoder),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<R: Read, T: Skip<R>, E: Skip<R>> Skip<R> for Result<T, E> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_enum_header()?;
+        let ok = decoder.read_bool()?;
+        decoder.read_fields_len(1)?;
+        match ok {
+            true => T::skip(decoder),
+            false => E::skip(decoder),
+        }
+    }
+}
+
+impl<R: Read, T: ?Sized> Skip<R> for PhantomData<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_null()
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::boxed::Box<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::boxed::Box<[T]> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        T::skip_seq(decoder, len)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<'a, R: Read, T: ?Sized + alloc::borrow::ToOwned> Skip<R> for alloc::borrow::Cow<'a, T>
+where
+    &'a T: Skip<R>,
+{
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <&T>::skip(decoder)
+    }
+}
+
+impl<R: Read, T: Copy + Skip<R>> Skip<R> for Cell<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for RefCell<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read, T: Skip<R>> Skip<R> for std::sync::Mutex<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read, T: Skip<R>> Skip<R> for std::sync::RwLock<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::rc::Rc<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::sync::Arc<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, K: Skip<R>, V: Skip<R>> Skip<R> for alloc::collections::BTreeMap<K, V> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_map_len()?;
+        for _ in 0..len {
+            K::skip(decoder)?;
+            V::skip(decoder)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, K: Skip<R>> Skip<R> for alloc::collections::BTreeSet<K> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        K::skip_seq(decoder, len)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::collections::BinaryHeap<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        T::skip_seq(decoder, len)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read, K: Skip<R>, V: Skip<R>> Skip<R> for std::collections::hash_map::HashMap<K, V> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_map_len()?;
+        for _ in 0..len {
+            K::skip(decoder)?;
+            V::skip(decoder)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read, K: Skip<R>> Skip<R> for std::collections::hash_set::HashSet<K> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        K::skip_seq(decoder, len)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::collections::LinkedList<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        T::skip_seq(decoder, len)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::collections::VecDeque<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        T::skip_seq(decoder, len)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read, T: Skip<R>> Skip<R> for alloc::vec::Vec<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        let len = decoder.read_seq_len()?;
+        T::skip_seq(decoder, len)
+    }
+}
+
+/// Skips a null-terminated C string, one byte at a time
+#[inline]
+fn skip_c_str<R: Read>(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+    while decoder.read_byte()? != 0 {}
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read> Skip<R> for alloc::ffi::CString {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        skip_c_str(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "alloc")))]
+impl<R: Read> Skip<R> for alloc::boxed::Box<CStr> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        skip_c_str(decoder)
+    }
+}
+
+impl<R: Read> Skip<R> for &CStr {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        skip_c_str(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::ffi::OsString {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.skip_str()
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for alloc::boxed::Box<std::ffi::OsStr> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.skip_str()
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for &std::ffi::OsStr {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <&str>::skip(decoder)
+    }
+}
+
+impl<R: Read> Skip<R> for Duration {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_fields_len(2)?;
+        u64::skip(decoder)?;
+        u32::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::time::SystemTime {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        Duration::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::path::PathBuf {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.skip_str()
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for alloc::boxed::Box<std::path::Path> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.skip_str()
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for &std::path::Path {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <&str>::skip(decoder)
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for Range<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_fields_len(2)?;
+        T::skip(decoder)?;
+        T::skip(decoder)
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for RangeInclusive<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_fields_len(2)?;
+        T::skip(decoder)?;
+        T::skip(decoder)
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for RangeTo<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for RangeFrom<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        T::skip(decoder)
+    }
+}
+
+impl<R: Read> Skip<R> for RangeFull {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_null()
+    }
+}
+
+impl<R: Read, T: Skip<R>> Skip<R> for Bound<T> {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_enum_header()?;
+        match decoder.read_uvariant::<u8>()? {
+            0 | 1 => {
+                decoder.read_fields_len(1)?;
+                T::skip(decoder)
+            }
+            2 => decoder.read_fields_len(0),
+            x => Err(EncodingError::invalid_variant(x)),
+        }
+    }
+}
+
+// The values aren't checked to be non-zero
+macro_rules! impl_nz_skip {
+    ($($ty:ident => $inner:ty);* $(;)? ) => {
+	    $(
+	    impl<R: $crate::io::Read> $crate::Skip<R> for core::num::$ty {
+		    #[inline]
+		    fn skip(decoder: &mut $crate::Encoder<R>) -> $crate::EncodingResult<()> {
+			    <$inner as $crate::Skip<R>>::skip(decoder)
+		    }
+	    }
+	    )*
+    };
+}
+
+impl_nz_skip! {
+    NonZeroU8 => u8;
+    NonZeroU16 => u16;
+    NonZeroU32 => u32;
+    NonZeroU64 => u64;
+    NonZeroU128 => u128;
+    NonZeroI8 => i8;
+    NonZeroI16 => i16;
+    NonZeroI32 => i32;
+    NonZeroI64 => i64;
+    NonZeroI128 => i128;
+    NonZeroUsize => usize;
+    NonZeroIsize => isize;
+}
+
+#[cfg(feature = "unstable")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "unstable")))]
+impl<R: Read> Skip<R> for ! {
+    #[inline]
+    fn skip(_decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        Err(EncodingError::invalid_variant(0usize))
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::net::IpAddr {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_enum_header()?;
+        let variant = decoder.read_uvariant::<u8>()?;
+        decoder.read_fields_len(1)?;
+        match variant {
+            0 => std::net::Ipv4Addr::skip(decoder),
+            1 => std::net::Ipv6Addr::skip(decoder),
+            x => Err(EncodingError::invalid_variant(x)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::net::Ipv4Addr {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <[u8; 4]>::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::net::Ipv6Addr {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <[u8; 16]>::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::net::SocketAddr {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        decoder.read_enum_header()?;
+        let variant = decoder.read_uvariant::<u8>()?;
+        decoder.read_fields_len(1)?;
+        match variant {
+            0 => std::net::SocketAddrV4::skip(decoder),
+            1 => std::net::SocketAddrV6::skip(decoder),
+            x => Err(EncodingError::invalid_variant(x)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::net::SocketAddrV4 {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <(std::net::Ipv4Addr, u16)>::skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "unstable", doc(cfg(feature = "std")))]
+impl<R: Read> Skip<R> for std::net::SocketAddrV6 {
+    #[inline]
+    fn skip(decoder: &mut Encoder<R>) -> EncodingResult<()> {
+        <(std::net::Ipv6Addr, u16, u32, u32)>::skip(decoder)
+    }
+}
@@ -111,11 +111,13 @@
 //     name: String,
 // }
 
+use crate::asn1::Asn1Rules;
 use crate::io::{Slice, SliceMut, VecStream};
 use crate::{
-    BinSettings, BitWidth, Context, Decode, Encode, Encoder, Endianness, NumEncoding, NumRepr,
-    SizeRepr, StrEncoding, StrLen, StringRepr, VariantRepr,
+    BinSettings, BitWidth, Context, Decode, Encode, Encoder, EncodingResult, Endianness, Format,
+    NumEncoding, NumRepr, SizeRepr, StrEncoding, StrLen, StringRepr, Trailing, VariantRepr,
 };
+use core::fmt::Debug;
 use std::hash::{DefaultHasher, Hasher};
 
 const SETTINGS: BinSettings = BinSettings {
@@ -128,6 +130,7 @@ const SETTINGS: BinSettings = BinSettings {
         num_encoding: NumEncoding::Fixed,
         width: BitWidth::Bit64,
         max_size: isize::MAX as usize,
+        trailing: Trailing::Reject,
     },
     variant_repr: VariantRepr {
         endianness: Endianness::LittleEndian,
@@ -139,8 +142,49 @@ const SETTINGS: BinSettings = BinSettings {
         endianness: Endianness::LittleEndian,
         len: StrLen::LengthPrefixed,
     },
+    self_describing: false,
+    asn1_rules: Asn1Rules::Der,
+    format: Format::Binary,
 };
 
+/// Encodes the value into a new vector, panicking on errors
+fn encode<T: Encode<VecStream> + ?Sized>(value: &T, ctxt: Context) -> Vec<u8> {
+    let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+    value.encode(&mut encoder).unwrap();
+    encoder.finish().0.into_inner()
+}
+
+/// Decodes a value from the bytes, checking that the nesting depth is restored
+fn decode<T: for<'a> Decode<Slice<'a>>>(bytes: &[u8], ctxt: Context) -> EncodingResult<T> {
+    let mut decoder = Encoder::new(Slice::new(bytes), ctxt);
+    let value = T::decode(&mut decoder)?;
+    assert_eq!(decoder.ctxt.depth, 0);
+    Ok(value)
+}
+
+/// Checks that the value encodes to the given fixture, and that the fixture decodes back
+fn fixture<T>(value: T, bytes: &[u8], ctxt: Context)
+where
+    T: Encode<VecStream> + for<'a> Decode<Slice<'a>> + Debug + PartialEq,
+{
+    assert_eq!(encode(&value, ctxt), bytes, "encoding {value:?}");
+    assert_eq!(decode::<T>(bytes, ctxt).unwrap(), value);
+}
+
+/// Encodes a number as its decimal representation, and decodes it back
+mod decimal {
+    use crate::io::{Read, Write};
+    use crate::{Decode, Encode, Encoder, EncodingResult};
+
+    pub fn encode<T: Write>(value: &u32, encoder: &mut Encoder<T>) -> EncodingResult<()> {
+        value.to_string().encode(encoder)
+    }
+
+    pub fn decode<T: Read>(decoder: &mut Encoder<T>) -> EncodingResult<u32> {
+        Ok(String::decode(decoder)?.parse().unwrap())
+    }
+}
+
 macro_rules! test_num_encoding {
     ($fn_name:ident, $encoding_name:ident) => {
         #[test]
@@ -241,6 +285,21 @@ test_str_encoding! {
     }
 }
 
+#[test]
+pub fn utf8_multi_byte() {
+    // Two, three and four bytes long
+    const STRING: &str = "Héllo, 世界! 🦀";
+
+    let mut data = vec![0u8; 1000];
+    let mut encoder = Encoder::new(SliceMut::new(&mut data), Context::with_settings(SETTINGS));
+    encoder.write_str(STRING.chars()).unwrap();
+    assert_eq!(&data[8..8 + STRING.len()], STRING.as_bytes());
+
+    let mut decoder = Encoder::new(Slice::new(&data), Context::with_settings(SETTINGS));
+    let string: String = decoder.read_str().unwrap();
+    assert_eq!(&string as &str, STRING);
+}
+
 #[test]
 pub fn test() {
     // let mut mem = [0u8; 1024];
@@ -258,3 +317,3768 @@ pub fn test() {
     // println!("{:#0130b}", val);
     // println!("{val}");
 }
+
+#[cfg(feature = "tokio")]
+mod asynchronous {
+    use super::SETTINGS;
+    use crate::io::{Tokio, VecStream};
+    use crate::{
+        AsyncDecode, AsyncEncode, Context, Decode, Encode, Encoder, NumEncoding, StrEncoding,
+        StrLen,
+    };
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(async)]
+    struct Inventory {
+        owner: String,
+        #[ender(string: utf16, null_term)]
+        nickname: String,
+        slots: Vec<Option<Item>>,
+        prices: HashMap<u32, f64>,
+        #[ender(num: leb128)]
+        coins: i128,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(async)]
+    #[ender(variant: protobuf_zz)]
+    enum Item {
+        Sword { damage: u16, enchanted: bool },
+        Potion(char, f32),
+        Nothing,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(async)]
+    struct Indirect {
+        pointer: u64,
+        #[ender(pos_tracker: back)]
+        #[ender(goto start: *pointer as usize)]
+        value: u32,
+        #[ender(goto start: back)]
+        after: u8,
+    }
+
+    fn inventory() -> Inventory {
+        Inventory {
+            owner: String::from("Steve"),
+            nickname: String::from("Herobrine? ĦĒŔØ"),
+            slots: vec![
+                Some(Item::Sword {
+                    damage: 300,
+                    enchanted: true,
+                }),
+                None,
+                Some(Item::Potion('ä', -1.5)),
+                Some(Item::Nothing),
+            ],
+            prices: HashMap::from([(7, 19.99)]),
+            coins: -123456789,
+        }
+    }
+
+    #[tokio::test]
+    async fn async_matches_sync() {
+        for encoding in [
+            NumEncoding::Fixed,
+            NumEncoding::Leb128,
+            NumEncoding::ProtobufWasteful,
+            NumEncoding::ProtobufZigzag,
+        ] {
+            for str_encoding in [StrEncoding::Utf8, StrEncoding::Utf16, StrEncoding::Utf32] {
+                for len in [
+                    StrLen::LengthPrefixed,
+                    StrLen::NullTerminated,
+                    StrLen::NullTerminatedFixed(40),
+                ] {
+                    let mut settings = SETTINGS;
+                    settings.num_repr.num_encoding = encoding;
+                    settings.size_repr.num_encoding = encoding;
+                    settings.variant_repr.num_encoding = encoding;
+                    settings.string_repr.encoding = str_encoding;
+                    settings.string_repr.len = len;
+
+                    let orig = inventory();
+
+                    let mut encoder = Encoder::new(
+                        VecStream::new(Vec::new(), 0),
+                        Context::with_settings(settings),
+                    );
+                    orig.encode(&mut encoder).unwrap();
+                    let sync_data = encoder.finish().0.into_inner();
+
+                    let mut encoder =
+                        Encoder::new(Tokio::new(Vec::new()), Context::with_settings(settings));
+                    orig.encode_async(&mut encoder).await.unwrap();
+                    let async_data = encoder.finish().0.into_inner();
+
+                    assert_eq!(sync_data, async_data);
+
+                    let mut decoder =
+                        Encoder::new(Tokio::new(&async_data[..]), Context::with_settings(settings));
+                    let decoded = Inventory::decode_async(&mut decoder).await.unwrap();
+
+                    assert_eq!(orig, decoded);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn async_seek() {
+        let orig = Indirect {
+            pointer: 32,
+            value: 0xDEADBEEF,
+            after: 5,
+        };
+
+        let mut encoder = Encoder::new(Tokio::new(Cursor::new(Vec::new())), Context::new());
+        orig.encode_async(&mut encoder).await.unwrap();
+        let data = encoder.finish().0.into_inner().into_inner();
+
+        assert_eq!(&data[32..36], &0xDEADBEEFu32.to_le_bytes());
+        assert_eq!(data[8], 5);
+
+        let mut decoder = Encoder::new(Tokio::new(Cursor::new(data)), Context::new());
+        let decoded = Indirect::decode_async(&mut decoder).await.unwrap();
+
+        assert_eq!(orig, decoded);
+    }
+}
+
+mod bits {
+    use crate::io::{BitOrder, BitReader, BitWriter, VecStream};
+    use crate::{Context, Decode, Encode, Encoder, EncodingError, Endianness};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(num: big_endian)]
+    struct Ipv4Header {
+        #[ender(bits: 4)]
+        version: u8,
+        #[ender(bits: 4)]
+        ihl: u8,
+        #[ender(bits: 6)]
+        dscp: u8,
+        #[ender(bits: 2)]
+        ecn: u8,
+        total_length: u16,
+        identification: u16,
+        #[ender(bits: 1)]
+        reserved: bool,
+        #[ender(bits: 1)]
+        dont_fragment: bool,
+        #[ender(bits: 1)]
+        more_fragments: bool,
+        #[ender(bits: 13)]
+        fragment_offset: u16,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Packed {
+        #[ender(bits: 3)]
+        small: i8,
+        #[ender(bits: 1)]
+        flag: bool,
+        #[ender(bit_align)]
+        aligned: u8,
+        #[ender(bits: 5)]
+        tail: u32,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(bitwise)]
+    struct Outer {
+        first: Packed,
+        second: Packed,
+    }
+
+    #[test]
+    fn msb_first_header() {
+        let header = Ipv4Header {
+            version: 4,
+            ihl: 5,
+            dscp: 0,
+            ecn: 0,
+            total_length: 0x0054,
+            identification: 0xABCD,
+            reserved: false,
+            dont_fragment: true,
+            more_fragments: false,
+            fragment_offset: 0,
+        };
+
+        let mut encoder = Encoder::new(
+            BitWriter::new(VecStream::new(Vec::new(), 0), BitOrder::MsbFirst),
+            Context::new(),
+        );
+        header.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.finish().unwrap().into_inner();
+
+        assert_eq!(data, [0x45, 0x00, 0x00, 0x54, 0xAB, 0xCD, 0x40, 0x00]);
+
+        let mut decoder = Encoder::new(
+            BitReader::new(VecStream::new(data, 0), BitOrder::MsbFirst),
+            Context::new(),
+        );
+        assert_eq!(Ipv4Header::decode(&mut decoder).unwrap(), header);
+    }
+
+    #[test]
+    fn lsb_first_packing() {
+        let orig = Outer {
+            first: Packed {
+                small: -3,
+                flag: true,
+                aligned: 0xAA,
+                tail: 17,
+            },
+            second: Packed {
+                small: 2,
+                flag: false,
+                aligned: 0x55,
+                tail: 31,
+            },
+        };
+
+        let mut encoder = Encoder::new(
+            BitWriter::new(VecStream::new(Vec::new(), 0), BitOrder::LsbFirst),
+            Context::new(),
+        );
+        orig.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.finish().unwrap().into_inner();
+
+        // 0b1_101 = flag + (-3 & 0b111), then the remaining 4 bits are padding
+        assert_eq!(data[0], 0b0000_1101);
+        assert_eq!(data[1], 0xAA);
+        // `tail` (5 bits) shares its byte with `second.small` (3 bits) and spills `second.flag`
+        // into the next byte, before being aligned again
+        assert_eq!(data[2], 0b010_10001);
+        assert_eq!(data[3], 0b0000_0000);
+        assert_eq!(data[4], 0x55);
+
+        let mut decoder = Encoder::new(
+            BitReader::new(VecStream::new(data, 0), BitOrder::LsbFirst),
+            Context::new(),
+        );
+        assert_eq!(Outer::decode(&mut decoder).unwrap(), orig);
+    }
+
+    #[test]
+    fn unaligned_bytes() {
+        let mut encoder = Encoder::new(
+            BitWriter::new(VecStream::new(Vec::new(), 0), BitOrder::MsbFirst),
+            Context::new(),
+        );
+        encoder.write_bits(0b101, 3).unwrap();
+        encoder
+            .write_u16_with(0xF00F, crate::NumEncoding::Fixed, Endianness::BigEndian)
+            .unwrap();
+        let data = encoder.finish().0.finish().unwrap().into_inner();
+
+        assert_eq!(data, [0b101_11110, 0b000_00001, 0b111_00000]);
+
+        let mut decoder = Encoder::new(
+            BitReader::new(VecStream::new(data, 0), BitOrder::MsbFirst),
+            Context::new(),
+        );
+        assert_eq!(decoder.read_bits(3).unwrap(), 0b101);
+        assert_eq!(
+            decoder
+                .read_u16_with(crate::NumEncoding::Fixed, Endianness::BigEndian)
+                .unwrap(),
+            0xF00F
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let mut encoder = Encoder::new(
+            BitWriter::new(VecStream::new(Vec::new(), 0), BitOrder::MsbFirst),
+            Context::new(),
+        );
+        let packed = Packed {
+            small: 4,
+            flag: false,
+            aligned: 0,
+            tail: 0,
+        };
+        assert!(matches!(
+            packed.encode(&mut encoder),
+            Err(EncodingError::BitFieldOverflow { bits: 3, .. })
+        ));
+        assert!(matches!(
+            encoder.write_bits(32, 5),
+            Err(EncodingError::BitFieldOverflow { bits: 5, .. })
+        ));
+    }
+}
+
+mod magic {
+    use crate::io::{Slice, VecStream};
+    use crate::{Context, Decode, Encode, Encoder, EncodingError};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(magic: b"\x89PNG\r\n\x1a\n")]
+    struct Png {
+        #[ender(num: big_endian)]
+        length: u32,
+        #[ender(magic: b"IHDR"; num: big_endian)]
+        width: u32,
+        #[ender(num: big_endian)]
+        height: u32,
+    }
+
+    #[test]
+    fn magic_round_trip() {
+        let png = Png {
+            length: 13,
+            width: 640,
+            height: 480,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        png.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&data[12..16], b"IHDR");
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Png::decode(&mut decoder).unwrap(), png);
+    }
+
+    #[test]
+    fn magic_mismatch() {
+        let mut data = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0DIHDX\x00\x00\x00\x01\x00\x00\x00\x01".to_vec();
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        match Png::decode(&mut decoder) {
+            Err(EncodingError::MagicMismatch {
+                expected,
+                offset,
+                actual,
+            }) => {
+                assert_eq!(expected, b"IHDR");
+                assert_eq!(offset, 3);
+                assert_eq!(actual, b'X');
+            }
+            other => panic!("Expected a magic mismatch, got {:?}", other),
+        }
+
+        data[1] = b'J';
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert!(matches!(
+            Png::decode(&mut decoder),
+            Err(EncodingError::MagicMismatch {
+                offset: 1,
+                actual: b'J',
+                ..
+            })
+        ));
+    }
+}
+
+mod len_of {
+    use crate::io::{Slice, VecStream};
+    use crate::{BinSettings, Context, Decode, Encode, Encoder, NumEncoding, NumRepr, SizeRepr};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Counted {
+        #[ender(len_of: items)]
+        count: u8,
+        name: String,
+        items: Vec<u16>,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Measured {
+        #[ender(len_of bytes: payload)]
+        length: u16,
+        payload: Vec<String>,
+        trailer: u8,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    enum Message {
+        Ping,
+        Data {
+            #[ender(len_of: data)]
+            len: u32,
+            data: Vec<u8>,
+        },
+    }
+
+    #[test]
+    fn len_of_elements() {
+        let counted = Counted {
+            count: 0,
+            name: "Ferris".to_string(),
+            items: vec![1, 2, 3],
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        counted.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        // The count is filled in and the vector's own length isn't written
+        assert_eq!(data[0], 3);
+        assert_eq!(&data[data.len() - 6..], &[1, 0, 2, 0, 3, 0]);
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Counted::decode(&mut decoder).unwrap(),
+            Counted { count: 3, ..counted }
+        );
+    }
+
+    #[test]
+    fn len_of_enum() {
+        let message = Message::Data {
+            len: 0,
+            data: vec![0xAA; 5],
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        message.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Message::decode(&mut decoder).unwrap(),
+            Message::Data {
+                len: 5,
+                data: vec![0xAA; 5],
+            }
+        );
+    }
+
+    #[test]
+    fn len_of_bytes() {
+        let measured = Measured {
+            length: 0,
+            payload: vec!["Hello".to_string(), "World".to_string()],
+            trailer: 0xFF,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        measured.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        // The length is back-patched and the trailer follows the payload
+        let length = u16::from_le_bytes([data[0], data[1]]) as usize;
+        assert_eq!(length, data.len() - 3);
+        assert_eq!(data[data.len() - 1], 0xFF);
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Measured::decode(&mut decoder).unwrap(),
+            Measured {
+                length: length as u16,
+                ..measured
+            }
+        );
+
+        // A length that doesn't match the payload is rejected
+        let mut data = data;
+        data[0] += 1;
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert!(Measured::decode(&mut decoder).is_err());
+    }
+
+    #[test]
+    fn len_of_bytes_varint() {
+        let settings = BinSettings::new()
+            .num_repr(NumRepr::new().num_encoding(NumEncoding::Leb128))
+            .size_repr(SizeRepr::new().num_encoding(NumEncoding::Leb128));
+        let ctxt = Context::with_settings(settings);
+        let measured = Measured {
+            length: 0,
+            payload: vec!["Hello, world!".to_string(); 20],
+            trailer: 0x7F,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        measured.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        // The length always has a fixed encoding, even if it doesn't fit in a single varint byte
+        let length = u16::from_le_bytes([data[0], data[1]]) as usize;
+        assert_eq!(length, data.len() - 3);
+        assert!(length >= 128);
+
+        let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+        assert_eq!(
+            Measured::decode(&mut decoder).unwrap(),
+            Measured {
+                length: length as u16,
+                ..measured
+            }
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn len_of_async() {
+        use crate::io::Tokio;
+        use crate::{AsyncDecode, AsyncEncode};
+        use std::io::Cursor;
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        #[ender(async)]
+        struct Frame {
+            #[ender(len_of bytes: body)]
+            size: u32,
+            body: String,
+        }
+
+        let frame = Frame {
+            size: 0,
+            body: "Ferris".to_string(),
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        frame.encode(&mut encoder).unwrap();
+        let sync_data = encoder.finish().0.into_inner();
+
+        let mut encoder = Encoder::new(Tokio::new(Cursor::new(Vec::new())), Context::new());
+        frame.encode_async(&mut encoder).await.unwrap();
+        let async_data = encoder.finish().0.into_inner().into_inner();
+
+        assert_eq!(sync_data, async_data);
+
+        let mut decoder = Encoder::new(Tokio::new(Cursor::new(async_data)), Context::new());
+        let decoded = Frame::decode_async(&mut decoder).await.unwrap();
+        assert_eq!(decoded.body, frame.body);
+        assert_eq!(decoded.size as usize, sync_data.len() - 4);
+    }
+}
+
+mod sized {
+    use super::encode;
+    use crate::io::Slice;
+    use crate::{
+        BinSettings, BitWidth, Context, Decode, Encode, EncodedSize, Encoder, EncodingError,
+        SizeRepr, Trailing,
+    };
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Header {
+        version: u16,
+        name: String,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct HeaderV2 {
+        version: u16,
+        name: String,
+        flags: u32,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(size: bit32)]
+    struct File {
+        #[ender(sized)]
+        header: Header,
+        checksum: u8,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(size: bit32)]
+    struct FileV2 {
+        #[ender(sized)]
+        header: HeaderV2,
+        checksum: u8,
+    }
+
+    #[test]
+    fn sized_round_trip() {
+        let file = File {
+            header: Header {
+                version: 1,
+                name: "Ferris".to_string(),
+            },
+            checksum: 0xCC,
+        };
+
+        let data = encode(&file, Context::new());
+
+        // The region size doesn't include the size itself nor the fields after the region
+        let size = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
+        assert_eq!(size, data.len() - 5);
+        assert_eq!(data[data.len() - 1], 0xCC);
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(File::decode(&mut decoder).unwrap(), file);
+    }
+
+    #[test]
+    fn sized_trailing_bytes() {
+        let file = FileV2 {
+            header: HeaderV2 {
+                version: 2,
+                name: "Ferris".to_string(),
+                flags: 0xDEADBEEF,
+            },
+            checksum: 0xCC,
+        };
+
+        let data = encode(&file, Context::new());
+
+        // Trailing bytes are rejected by default
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert!(matches!(
+            File::decode(&mut decoder),
+            Err(EncodingError::TrailingBytes { remaining: 4 })
+        ));
+
+        // But they can be skipped
+        let settings =
+            BinSettings::new().size_repr(SizeRepr::new().bit_width(BitWidth::Bit32).trailing(Trailing::Skip));
+        let mut decoder = Encoder::new(Slice::new(&data), Context::with_settings(settings));
+        assert_eq!(
+            File::decode(&mut decoder).unwrap(),
+            File {
+                header: Header {
+                    version: 2,
+                    name: "Ferris".to_string(),
+                },
+                checksum: 0xCC,
+            }
+        );
+
+        // A region can't be read past its end
+        let mut decoder = Encoder::new(Slice::new(&data[..10]), Context::new());
+        assert!(FileV2::decode(&mut decoder).is_err());
+    }
+
+    #[derive(Encode, Decode, EncodedSize, Debug, PartialEq)]
+    #[ender(size: bit32)]
+    struct Flattened {
+        count: u8,
+        #[ender(flatten size: *count as usize; sized)]
+        items: Vec<u16>,
+        trailer: u8,
+    }
+
+    #[derive(Encode, Decode, EncodedSize, Debug, PartialEq)]
+    #[ender(size: bit32)]
+    struct Counted {
+        #[ender(len_of: items)]
+        count: u8,
+        #[ender(sized)]
+        items: Vec<u16>,
+        trailer: u8,
+    }
+
+    #[test]
+    fn sized_flatten() {
+        // The flattened length belongs to the value, while the region's size is still written
+        let flattened = Flattened {
+            count: 3,
+            items: vec![1, 2, 3],
+            trailer: 0xFF,
+        };
+        let data = encode(&flattened, Context::new());
+        assert_eq!(data, [3, 6, 0, 0, 0, 1, 0, 2, 0, 3, 0, 0xFF]);
+        assert_eq!(flattened.encoded_size(&Context::new()).unwrap(), data.len());
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Flattened::decode(&mut decoder).unwrap(), flattened);
+
+        let counted = Counted {
+            count: 0,
+            items: vec![1, 2, 3],
+            trailer: 0xFF,
+        };
+        let data = encode(&counted, Context::new());
+        assert_eq!(data, [3, 6, 0, 0, 0, 1, 0, 2, 0, 3, 0, 0xFF]);
+        assert_eq!(counted.encoded_size(&Context::new()).unwrap(), data.len());
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Counted::decode(&mut decoder).unwrap(),
+            Counted { count: 3, ..counted }
+        );
+    }
+}
+
+mod checksum {
+    use crate::io::{
+        Adler32, Checksum, Crc16, Crc32, Crc8, Fletcher16, Fletcher32, Slice, VecStream,
+    };
+    use crate::{Context, Decode, Encode, Encoder, EncodingError, Opaque};
+
+    fn digest<C: Checksum>(mut checksum: C, data: &[u8]) -> C::Output {
+        // Feed the data in uneven chunks to exercise incremental updates
+        for chunk in data.chunks(3) {
+            checksum.update(chunk);
+        }
+        checksum.digest()
+    }
+
+    #[test]
+    fn checksum_check_values() {
+        assert_eq!(digest(Crc8::new(), b"123456789"), 0xF4);
+        assert_eq!(digest(Crc16::new(), b"123456789"), 0xBB3D);
+        assert_eq!(digest(Crc32::new(), b"123456789"), 0xCBF43926);
+        assert_eq!(digest(Adler32::new(), b"Wikipedia"), 0x11E60398);
+        assert_eq!(digest(Fletcher16::new(), b"abcde"), 0xC8F0);
+        assert_eq!(digest(Fletcher32::new(), b"abcde"), 0xF04FC729);
+        assert_eq!(digest(Fletcher32::new(), b"abcdef"), 0x56502D2A);
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Packet {
+        kind: u8,
+        #[ender(checksum: crc8)]
+        header_crc: u8,
+        payload: Vec<u8>,
+        /// Covers the header, its checksum and the payload
+        #[ender(checksum: crc32, over: kind..=payload)]
+        crc: u32,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    enum Message {
+        Text {
+            text: String,
+            #[ender(checksum: adler32, over: text..sum)]
+            sum: u64,
+        },
+    }
+
+    #[test]
+    fn checksum_round_trip() {
+        let packet = Packet {
+            kind: 7,
+            header_crc: 0,
+            payload: vec![1, 2, 3, 4],
+            crc: 0,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        packet.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        let len = data.len();
+        assert_eq!(data[1], digest(Crc8::new(), &data[..1]));
+        assert_eq!(
+            u32::from_le_bytes(data[len - 4..].try_into().unwrap()),
+            digest(Crc32::new(), &data[..len - 4])
+        );
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Packet::decode(&mut decoder).unwrap(),
+            Packet {
+                header_crc: data[1],
+                crc: digest(Crc32::new(), &data[..len - 4]),
+                ..packet
+            }
+        );
+
+        let message = Message::Text {
+            text: "Ferris".to_string(),
+            sum: 0,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        message.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        let Message::Text { text, sum } = Message::decode(&mut decoder).unwrap();
+        assert_eq!(text, "Ferris");
+        // The variant index isn't covered
+        assert_eq!(sum, digest(Adler32::new(), &data[4..data.len() - 8]) as u64);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let packet = Packet {
+            kind: 7,
+            header_crc: 0,
+            payload: vec![1, 2, 3, 4],
+            crc: 0,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        packet.encode(&mut encoder).unwrap();
+        let mut data = encoder.finish().0.into_inner();
+
+        let last = data.len() - 5;
+        data[last] ^= 0xFF;
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        match Packet::decode(&mut decoder) {
+            Err(EncodingError::ChecksumMismatch { computed, stored }) => {
+                assert_ne!(computed, stored);
+                assert_eq!(
+                    stored,
+                    Opaque::from(u32::from_le_bytes(data[last + 1..].try_into().unwrap()))
+                );
+            }
+            other => panic!("Expected a checksum mismatch, got {:?}", other),
+        }
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Aligned {
+        kind: u8,
+        #[ender(align: 4)]
+        value: u32,
+        #[ender(checksum: crc32)]
+        crc: u32,
+    }
+
+    #[test]
+    fn checksum_seek() {
+        let aligned = Aligned {
+            kind: 7,
+            value: 0xDEADBEEF,
+            crc: 0,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        aligned.encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        // The padding is written, so it's covered too
+        assert_eq!(data.len(), 12);
+        let crc = digest(Crc32::new(), &data[..8]);
+        assert_eq!(u32::from_le_bytes(data[8..].try_into().unwrap()), crc);
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Aligned::decode(&mut decoder).unwrap(),
+            Aligned { crc, ..aligned }
+        );
+    }
+}
+
+mod padding {
+    use crate::io::{Slice, VecStream};
+    use crate::{Context, Decode, Encode, Encoder, EncodingError};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Header {
+        kind: u8,
+        #[ender(align: 4)]
+        size: u32,
+        #[ender(pad: 3; fill: 0xAA; check_padding)]
+        flags: u8,
+        #[ender(align: 8)]
+        offset: u64,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(pad: 2)]
+    struct Padded(u8);
+
+    fn header() -> Header {
+        Header {
+            kind: 1,
+            size: 2,
+            flags: 3,
+            offset: 4,
+        }
+    }
+
+    #[test]
+    fn padding_round_trip() {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        header().encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        assert_eq!(
+            data,
+            [
+                1, 0, 0, 0, // kind, aligned to 4
+                2, 0, 0, 0, // size
+                0xAA, 0xAA, 0xAA, 3, // 3 bytes of padding, flags
+                0, 0, 0, 0, // aligned to 8
+                4, 0, 0, 0, 0, 0, 0, 0, // offset
+            ]
+        );
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Header::decode(&mut decoder).unwrap(), header());
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        Padded(7).encode(&mut encoder).unwrap();
+        assert_eq!(encoder.finish().0.into_inner(), [0, 0, 7]);
+    }
+
+    #[test]
+    fn padding_check() {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        header().encode(&mut encoder).unwrap();
+        let mut data = encoder.finish().0.into_inner();
+
+        // Unchecked padding is simply skipped
+        data[1] = 0xFF;
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Header::decode(&mut decoder).unwrap(), header());
+
+        // Checked padding must match the fill byte
+        data[9] = 0xFF;
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert!(matches!(
+            Header::decode(&mut decoder),
+            Err(EncodingError::InvalidPadding {
+                expected: 0xAA,
+                actual: 0xFF
+            })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn padding_tracked() {
+        use crate::io::{PosTrack, Std};
+
+        // Streams that can't seek are aligned using the position tracked by `PosTrack`
+        let mut encoder = Encoder::new(PosTrack::new(Std::new(Vec::new()), 0), Context::new());
+        header().encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner().into_inner();
+
+        let mut expected = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        header().encode(&mut expected).unwrap();
+        assert_eq!(data, expected.finish().0.into_inner());
+
+        let mut decoder = Encoder::new(PosTrack::new(Std::new(data.as_slice()), 0), Context::new());
+        assert_eq!(Header::decode(&mut decoder).unwrap(), header());
+        assert_eq!(decoder.stream_position().unwrap(), data.len());
+
+        // The position starts from the given offset
+        let mut encoder = Encoder::new(PosTrack::new(Std::new(Vec::new()), 3), Context::new());
+        Padded(7).encode(&mut encoder).unwrap();
+        encoder.write_align(4, 0xFF).unwrap();
+        assert_eq!(encoder.finish().0.into_inner().into_inner(), [0, 0, 7, 0xFF, 0xFF]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn padding_async() {
+        use crate::io::Tokio;
+        use crate::{AsyncDecode, AsyncEncode};
+        use std::io::Cursor;
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        #[ender(async)]
+        struct Aligned {
+            kind: u8,
+            #[ender(align: 4; fill: 0xEE; check_padding)]
+            size: u32,
+            #[ender(pad: 1)]
+            flags: u8,
+        }
+
+        let aligned = Aligned {
+            kind: 1,
+            size: 2,
+            flags: 3,
+        };
+
+        let mut encoder = Encoder::new(Tokio::new(Cursor::new(Vec::new())), Context::new());
+        aligned.encode_async(&mut encoder).await.unwrap();
+        let data = encoder.finish().0.into_inner().into_inner();
+
+        assert_eq!(data, [1, 0xEE, 0xEE, 0xEE, 2, 0, 0, 0, 0, 3]);
+
+        let mut decoder = Encoder::new(Tokio::new(Cursor::new(data)), Context::new());
+        assert_eq!(Aligned::decode_async(&mut decoder).await.unwrap(), aligned);
+    }
+}
+
+mod tag {
+    use super::encode;
+    use crate::io::{Slice, VecStream};
+    use crate::{Context, Decode, Encode, Encoder, EncodingError, Opaque, TagValue};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(tag_type: [u8; 4])]
+    enum Chunk {
+        #[ender(tag: *b"IHDR")]
+        Header { width: u32, height: u32 },
+        #[ender(tag: *b"IDAT")]
+        Data(u8),
+        #[ender(tag: *b"IEND")]
+        End,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[repr(u8)]
+    enum Opcode {
+        Nop,
+        #[ender(tag: 0x10, 0x11)]
+        Load(u8),
+        #[ender(tag: 0x20..=0x2F)]
+        Jump,
+        Halt,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(tag_type: String)]
+    enum Command {
+        #[ender(tag: "get")]
+        Get(u8),
+        #[ender(tag: "set", "put")]
+        Set(u8, u8),
+    }
+
+    #[test]
+    fn tag_type_round_trip() {
+        let chunks = [
+            Chunk::Header {
+                width: 1,
+                height: 2,
+            },
+            Chunk::Data(3),
+            Chunk::End,
+        ];
+
+        for chunk in chunks {
+            let data = encode(&chunk, Context::new());
+            let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+            assert_eq!(Chunk::decode(&mut decoder).unwrap(), chunk);
+        }
+
+        assert_eq!(encode(&Chunk::End, Context::new()), *b"IEND");
+        assert_eq!(encode(&Chunk::Data(3), Context::new()), *b"IDAT\x03");
+
+        let mut decoder = Encoder::new(Slice::new(b"IXYZ"), Context::new());
+        assert!(matches!(
+            Chunk::decode(&mut decoder),
+            Err(EncodingError::InvalidTag(tag)) if tag.as_str() == "[73, 88, 89, 90]"
+        ));
+    }
+
+    #[test]
+    fn tag_aliases_and_ranges() {
+        // The bytes of an enum variant followed by a single byte payload
+        let variant = |index: u8, payload: Option<u8>| {
+            let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+            encoder.write_uvariant(index).unwrap();
+            if let Some(payload) = payload {
+                payload.encode(&mut encoder).unwrap();
+            }
+            encoder.finish().0.into_inner()
+        };
+
+        // Untagged variants keep their index, tagged ones write their first tag
+        assert_eq!(encode(&Opcode::Nop, Context::new()), variant(0, None));
+        assert_eq!(encode(&Opcode::Load(5), Context::new()), variant(0x10, Some(5)));
+        assert_eq!(encode(&Opcode::Jump, Context::new()), variant(0x20, None));
+        assert_eq!(encode(&Opcode::Halt, Context::new()), variant(3, None));
+
+        let decode = |data: Vec<u8>| {
+            let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+            Opcode::decode(&mut decoder)
+        };
+
+        assert_eq!(decode(variant(0x11, Some(5))).unwrap(), Opcode::Load(5));
+        assert_eq!(decode(variant(0x2A, None)).unwrap(), Opcode::Jump);
+        match decode(variant(0x30, None)) {
+            Err(EncodingError::InvalidVariant(variant)) => {
+                assert_eq!(variant, Opaque::from(0x30u8))
+            }
+            other => panic!("Expected an invalid variant, got {:?}", other),
+        }
+
+        let data = encode(&(String::from("put"), 1u8, 2u8), Context::new());
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Command::decode(&mut decoder).unwrap(), Command::Set(1, 2));
+        assert_eq!(
+            encode(&Command::Get(3), Context::new()),
+            encode(&(String::from("get"), 3u8), Context::new())
+        );
+
+        // Long tags are truncated to the capacity of the error, on a char boundary
+        let data = encode("ééééééééééééééééééé", Context::new());
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        match Command::decode(&mut decoder) {
+            Err(EncodingError::InvalidTag(tag)) => {
+                assert_eq!(tag.as_str(), "\"ééééééééééééééé");
+                assert_eq!(tag.to_string().len(), TagValue::CAPACITY - 1);
+            }
+            other => panic!("Expected an invalid tag, got {:?}", other),
+        }
+    }
+}
+
+mod unknown {
+    use super::encode;
+    use crate::io::{Slice, VecStream};
+    use crate::{Context, Decode, Encode, Encoder, Opaque};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(sized)]
+    #[repr(u8)]
+    enum MessageV2 {
+        Ping,
+        Pong(u32),
+        Chat { from: String, text: String },
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(sized)]
+    #[repr(u8)]
+    enum MessageV1 {
+        Ping,
+        Pong(u32),
+        #[ender(unknown)]
+        Unknown { tag: Opaque, data: Vec<u8> },
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(sized; tag_type: [u8; 4])]
+    enum Chunk {
+        #[ender(tag: *b"IEND")]
+        End,
+        #[ender(unknown)]
+        Other([u8; 4], Vec<u8>),
+    }
+
+    #[test]
+    fn unknown_preserves_bytes() {
+        let chat = MessageV2::Chat {
+            from: String::from("Ender"),
+            text: String::from("Hello!"),
+        };
+        let data = encode(&chat, Context::new());
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        let old = MessageV1::decode(&mut decoder).unwrap();
+        match &old {
+            MessageV1::Unknown { tag, data } => {
+                assert_eq!(*tag, Opaque::from(2u8));
+                let fields = (String::from("Ender"), String::from("Hello!"));
+                assert_eq!(*data, encode(&fields, Context::new()));
+            }
+            other => panic!("Expected an unknown variant, got {:?}", other),
+        }
+
+        // Re-encoding the unknown variant produces the exact same bytes
+        assert_eq!(encode(&old, Context::new()), data);
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(MessageV2::decode(&mut decoder).unwrap(), chat);
+
+        // Known variants are unaffected
+        let data = encode(&MessageV1::Pong(7), Context::new());
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(MessageV2::decode(&mut decoder).unwrap(), MessageV2::Pong(7));
+    }
+
+    #[test]
+    fn unknown_tag_type() {
+        let chunk = Chunk::Other(*b"tEXt", b"comment".to_vec());
+        let data = encode(&chunk, Context::new());
+
+        assert!(data.ends_with(b"tEXtcomment"));
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Chunk::decode(&mut decoder).unwrap(), chunk);
+
+        let data = encode(&Chunk::End, Context::new());
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(Chunk::decode(&mut decoder).unwrap(), Chunk::End);
+    }
+
+    #[test]
+    fn unknown_unbounded() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        #[repr(u8)]
+        enum Unbounded {
+            Known,
+            #[ender(unknown)]
+            Unknown(Opaque, Vec<u8>),
+        }
+
+        // Without a byte-counted region, there is no way to tell where the payload ends
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        encoder.write_uvariant(5u8).unwrap();
+        encoder.write_bytes(&[1, 2]).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert_eq!(
+            Unbounded::decode(&mut decoder).unwrap(),
+            Unbounded::Unknown(Opaque::from(5u8), Vec::new())
+        );
+    }
+}
+
+mod version {
+    use crate::io::{Slice, VecStream};
+    use crate::{Context, Decode, Encode, Encoder};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Document {
+        title: String,
+        meta: Metadata,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Metadata {
+        created: u64,
+        #[ender(since: 7)]
+        modified: u64,
+        #[ender(until: 9; default: 1)]
+        legacy_flags: u8,
+        #[ender(since: 3; until: 10; if: *created != 0)]
+        author: Option<String>,
+    }
+
+    fn document() -> Document {
+        Document {
+            title: String::from("Notes"),
+            meta: Metadata {
+                created: 100,
+                modified: 200,
+                legacy_flags: 3,
+                author: Some(String::from("Ender")),
+            },
+        }
+    }
+
+    fn round_trip(version: u32) -> (Vec<u8>, Document) {
+        let ctxt = Context::new().version(version);
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        document().encode(&mut encoder).unwrap();
+        let data = encoder.finish().0.into_inner();
+
+        let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+        let document = Document::decode(&mut decoder).unwrap();
+        (data, document)
+    }
+
+    #[test]
+    fn version_gated_fields() {
+        let (_, old) = round_trip(2);
+        assert_eq!(old.meta.modified, 0);
+        assert_eq!(old.meta.legacy_flags, 3);
+        assert_eq!(old.meta.author, None);
+
+        let (_, middle) = round_trip(8);
+        assert_eq!(middle, document());
+
+        let (_, new) = round_trip(11);
+        assert_eq!(new.meta.modified, 200);
+        assert_eq!(new.meta.legacy_flags, 1);
+        assert_eq!(new.meta.author, None);
+
+        // The fields that aren't present in a version aren't written at all
+        let (data, _) = round_trip(11);
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        (String::from("Notes"), 100u64, 200u64).encode(&mut encoder).unwrap();
+        assert_eq!(data, encoder.finish().0.into_inner());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod self_describing {
+    use crate::io::{Slice, VecStream};
+    use crate::{BinSettings, Context, Encoder};
+    use serde::{Deserialize, Serialize};
+
+    fn ctxt() -> Context<'static> {
+        Context::with_settings(BinSettings::new().self_describing(true))
+    }
+
+    fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt());
+        value.serialize(&mut encoder).unwrap();
+        encoder.finish().0.into_inner()
+    }
+
+    fn deserialize<'de, T: Deserialize<'de>>(data: &[u8]) -> T {
+        let mut decoder = Encoder::new(Slice::new(data), ctxt());
+        T::deserialize(&mut decoder).unwrap()
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Untagged {
+        Number(u32),
+        Text(String),
+        Point { x: i16, y: i16 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Rect(u8, u8),
+        Named { name: String, inner: Option<Box<Shape>> },
+    }
+
+    #[test]
+    fn untagged_round_trip() {
+        for value in [
+            Untagged::Number(42),
+            Untagged::Text(String::from("hello")),
+            Untagged::Point { x: -3, y: 7 },
+        ] {
+            assert_eq!(deserialize::<Untagged>(&serialize(&value)), value);
+        }
+    }
+
+    #[test]
+    fn options_and_enums_round_trip() {
+        let value = vec![
+            Some(Shape::Empty),
+            None,
+            Some(Shape::Circle(1.5)),
+            Some(Shape::Rect(2, 3)),
+            Some(Shape::Named {
+                name: String::from("outer"),
+                inner: Some(Box::new(Shape::Named {
+                    name: String::from("inner"),
+                    inner: None,
+                })),
+            }),
+        ];
+        assert_eq!(deserialize::<Vec<Option<Shape>>>(&serialize(&value)), value);
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ConfigV1 {
+        name: String,
+        retries: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ConfigV2 {
+        name: String,
+        timeout: Option<u64>,
+        retries: u8,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped() {
+        let data = serialize(&ConfigV2 {
+            name: String::from("server"),
+            timeout: Some(30),
+            retries: 5,
+            tags: vec![String::from("a"), String::from("b")],
+        });
+        assert_eq!(
+            deserialize::<ConfigV1>(&data),
+            ConfigV1 {
+                name: String::from("server"),
+                retries: 5,
+            }
+        );
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Flat {
+        id: u32,
+        width: u16,
+        height: u16,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Flattened {
+        id: u32,
+        #[serde(flatten)]
+        size: Size,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Size {
+        width: u16,
+        height: u16,
+    }
+
+    #[test]
+    fn flatten() {
+        let flattened = Flattened {
+            id: 1,
+            size: Size {
+                width: 640,
+                height: 480,
+            },
+        };
+        let data = serialize(&Flat {
+            id: 1,
+            width: 640,
+            height: 480,
+        });
+        assert_eq!(deserialize::<Flattened>(&data), flattened);
+
+        // Flattened structs are serialized as maps of unknown length
+        assert_eq!(serialize(&flattened), data);
+    }
+
+    #[test]
+    fn any_requires_self_describing() {
+        let data = serialize(&Untagged::Number(42));
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        assert!(Untagged::deserialize(&mut decoder).is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod borrow_serde {
+    use crate::io::{Slice, VecStream};
+    use crate::serde::BorrowDeserializer;
+    use crate::{BinSettings, Context, Encoder, StrEncoding, StringRepr};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Packet<'a> {
+        id: u16,
+        #[serde(borrow)]
+        name: &'a str,
+        #[serde(borrow)]
+        payload: &'a [u8],
+        kind: Kind,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Request,
+        Response(u8),
+    }
+
+    fn packet() -> Packet<'static> {
+        Packet {
+            id: 3,
+            name: "borrowed",
+            payload: &[1, 2, 3, 4],
+            kind: Kind::Request,
+        }
+    }
+
+    fn serialize<T: Serialize>(value: &T, ctxt: Context) -> Vec<u8> {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        value.serialize(&mut encoder).unwrap();
+        encoder.finish().0.into_inner()
+    }
+
+    fn contains(data: &[u8], slice: &[u8]) -> bool {
+        data.as_ptr_range().contains(&slice.as_ptr())
+    }
+
+    #[test]
+    fn zero_copy() {
+        let data = serialize(&packet(), Context::new());
+        let mut deserializer =
+            BorrowDeserializer::new(Encoder::new(Slice::new(&data), Context::new()));
+        let decoded = Packet::deserialize(&mut deserializer).unwrap();
+        assert_eq!(decoded, packet());
+        assert!(contains(&data, decoded.name.as_bytes()));
+        assert!(contains(&data, decoded.payload));
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Names<'a> {
+        #[serde(borrow)]
+        first: &'a str,
+        kinds: Vec<Kind>,
+    }
+
+    #[test]
+    fn zero_copy_self_describing() {
+        let ctxt = Context::with_settings(BinSettings::new().self_describing(true));
+        let names = Names {
+            first: "first",
+            kinds: vec![Kind::Request, Kind::Response(9)],
+        };
+        let data = serialize(&names, ctxt);
+        let mut deserializer = BorrowDeserializer::new(Encoder::new(Slice::new(&data), ctxt));
+        let decoded = Names::deserialize(&mut deserializer).unwrap();
+        assert_eq!(decoded, names);
+        assert!(contains(&data, decoded.first.as_bytes()));
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Owned {
+        name: String,
+    }
+
+    #[test]
+    fn copies_non_borrowable_strings() {
+        let ctxt = Context::with_settings(
+            BinSettings::new().string_repr(StringRepr::new().str_encoding(StrEncoding::Utf16)),
+        );
+        let owned = Owned {
+            name: String::from("wide"),
+        };
+        let data = serialize(&owned, ctxt);
+        let mut deserializer = BorrowDeserializer::new(Encoder::new(Slice::new(&data), ctxt));
+        assert_eq!(Owned::deserialize(&mut deserializer).unwrap(), owned);
+    }
+}
+
+#[cfg(feature = "serde")]
+mod unknown_len {
+    use crate::io::{SizeTrack, VecStream, Zero};
+    use crate::serde::SeekSerializer;
+    use crate::{BinSettings, Context, Encoder, NumEncoding, SizeRepr};
+    use serde::{Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    /// Serializes the even numbers with an unknown length
+    struct Evens<'a>(&'a [u16]);
+
+    impl Serialize for Evens<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|x| *x % 2 == 0))
+        }
+    }
+
+    /// Serializes the entries with an even key, and nested [`Evens`] as values
+    struct EvenEntries<'a>(&'a BTreeMap<u16, Vec<u16>>);
+
+    impl Serialize for EvenEntries<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(
+                self.0
+                    .iter()
+                    .filter(|(k, _)| *k % 2 == 0)
+                    .map(|(k, v)| (k, Evens(v))),
+            )
+        }
+    }
+
+    fn known(ctxt: Context) -> Vec<u8> {
+        let map: BTreeMap<u16, Vec<u16>> = [(0, vec![2, 4]), (2, vec![]), (4, vec![6, 8, 10])]
+            .into_iter()
+            .collect();
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        (vec![2u16, 4], map).serialize(&mut encoder).unwrap();
+        encoder.finish().0.into_inner()
+    }
+
+    fn unknown() -> (Vec<u16>, BTreeMap<u16, Vec<u16>>) {
+        let map = [
+            (0, vec![1, 2, 3, 4]),
+            (1, vec![5]),
+            (2, vec![7]),
+            (4, vec![6, 8, 9, 10]),
+        ]
+        .into_iter()
+        .collect();
+        (vec![1, 2, 3, 4, 5], map)
+    }
+
+    fn contexts() -> [Context<'static>; 3] {
+        [
+            Context::new(),
+            Context::with_settings(BinSettings::new().self_describing(true)),
+            Context::with_settings(
+                BinSettings::new().size_repr(SizeRepr::new().num_encoding(NumEncoding::Leb128)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn buffered() {
+        for ctxt in contexts() {
+            let (seq, map) = unknown();
+            let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+            (Evens(&seq), EvenEntries(&map))
+                .serialize(&mut encoder)
+                .unwrap();
+            assert_eq!(encoder.finish().0.into_inner(), known(ctxt));
+        }
+    }
+
+    #[test]
+    fn back_patched() {
+        for ctxt in contexts() {
+            let (seq, map) = unknown();
+            let encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+            let mut serializer = SeekSerializer::new(encoder);
+            (Evens(&seq), EvenEntries(&map))
+                .serialize(&mut serializer)
+                .unwrap();
+            assert_eq!(serializer.into_inner().finish().0.into_inner(), known(ctxt));
+        }
+    }
+
+    #[test]
+    fn buffered_context() {
+        /// Serializes the `true` values with an unknown length
+        struct Trues<'a>(&'a [bool]);
+
+        impl Serialize for Trues<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.0.iter().filter(|x| **x))
+            }
+        }
+
+        // The first element consumes the flatten state, so it isn't applied to the last bool
+        let mut ctxt = Context::new();
+        ctxt.bool_flatten = Some(true);
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        (Trues(&[true, false, true]), false)
+            .serialize(&mut encoder)
+            .unwrap();
+        assert_eq!(encoder.ctxt.bool_flatten, None);
+        assert_eq!(encoder.finish().0.into_inner(), [2, 0, 0, 0, 0, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn flatten_len() {
+        let mut ctxt = Context::new();
+        ctxt.size_flatten = Some(2);
+        let mut encoder = Encoder::new(SizeTrack::new(Zero), ctxt);
+        Evens(&[1, 2, 3, 4]).serialize(&mut encoder).unwrap();
+        assert_eq!(encoder.finish().0.size_written(), 4);
+
+        let mut ctxt = Context::new();
+        ctxt.size_flatten = Some(3);
+        let mut encoder = Encoder::new(SizeTrack::new(Zero), ctxt);
+        assert!(Evens(&[1, 2, 3, 4]).serialize(&mut encoder).is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_api {
+    use crate::io::{Slice, VecStream};
+    use crate::serde::{EncoderDeserializer, EncoderSerializer};
+    use crate::{serde, BinSettings, Context, Encoder};
+    use ::serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message<'a> {
+        id: u32,
+        body: &'a str,
+        tags: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct OwnedMessage {
+        id: u32,
+        body: String,
+        tags: Vec<String>,
+    }
+
+    fn message() -> Message<'static> {
+        Message {
+            id: 12,
+            body: "hello",
+            tags: vec![String::from("a"), String::from("b")],
+        }
+    }
+
+    #[test]
+    fn to_and_from() {
+        let data = serde::to_vec(&message()).unwrap();
+
+        let mut buf = [0u8; 128];
+        let written = serde::to_slice(&message(), &mut buf).unwrap();
+        assert_eq!(written, data.as_slice());
+
+        let mut writer = VecStream::new(Vec::new(), 0);
+        serde::to_writer(&mut writer, &message()).unwrap();
+        assert_eq!(writer.into_inner(), data);
+
+        let decoded: Message = serde::from_slice(&data).unwrap();
+        assert_eq!(decoded, message());
+
+        let owned: OwnedMessage = serde::from_reader(data.as_slice()).unwrap();
+        assert_eq!(owned.body, "hello");
+        assert_eq!(owned.tags, message().tags);
+    }
+
+    #[test]
+    fn with_context() {
+        let ctxt = Context::with_settings(BinSettings::new().self_describing(true));
+        let data = serde::to_vec_with(&message(), ctxt).unwrap();
+        assert_ne!(data, serde::to_vec(&message()).unwrap());
+
+        let mut buf = [0u8; 128];
+        assert_eq!(serde::to_slice_with(&message(), &mut buf, ctxt).unwrap(), data.as_slice());
+
+        let decoded: Message = serde::from_slice_with(&data, ctxt).unwrap();
+        assert_eq!(decoded, message());
+        assert!(serde::from_slice::<Message>(&data).is_err());
+    }
+
+    #[test]
+    fn named_types() {
+        fn copy<T: crate::io::Read, U: crate::io::Write>(
+            from: EncoderDeserializer<T>,
+            to: EncoderSerializer<U>,
+        ) {
+            let value = <(u8, u64)>::deserialize(from).unwrap();
+            value.serialize(to).unwrap();
+        }
+
+        let data = serde::to_vec(&(1u8, 2u64)).unwrap();
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        copy(&mut decoder, &mut encoder);
+        assert_eq!(encoder.finish().0.into_inner(), data);
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod presets {
+    use super::fixture;
+    use crate::io::{Slice, VecStream};
+    use crate::{BinSettings, Context, Decode, Encode, Encoder};
+    use core::fmt::Debug;
+    use serde::Serialize;
+
+    #[derive(Encode, Decode, Serialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(u8),
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Varints {
+        #[ender(num: bincode_varint)]
+        bincode: u32,
+        #[ender(num: postcard_varint)]
+        postcard: i32,
+    }
+
+    // Checks a value against the bytes produced by the reference implementation
+    macro_rules! check {
+        ($settings:expr, $reference:expr; $($value:expr),* $(,)?) => {
+            $(
+                let value = $value;
+                let bytes = $reference(&value);
+                fixture(value, &bytes, Context::with_settings($settings));
+            )*
+        };
+    }
+
+    fn bincode_v1_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+        bincode::serialize(value).unwrap()
+    }
+
+    fn bincode_v2_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+        bincode2::serde::encode_to_vec(value, bincode2::config::standard()).unwrap()
+    }
+
+    fn postcard_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+        postcard::to_allocvec(value).unwrap()
+    }
+
+    #[test]
+    fn bincode_v1() {
+        check!(BinSettings::bincode_v1(), bincode_v1_bytes;
+            255u8,
+            300u32,
+            -2i64,
+            true,
+            1.0f32,
+            'é',
+            Some(1u16),
+            None::<u16>,
+            String::from("hi"),
+            vec![1u16, 2],
+            (1u8, 2u32),
+            Shape::Empty,
+            Shape::Circle(7),
+        );
+    }
+
+    #[test]
+    fn bincode_v2_standard() {
+        check!(BinSettings::bincode_v2_standard(), bincode_v2_bytes;
+            255u8,
+            -1i8,
+            250u32,
+            251u32,
+            65535u16,
+            65536u32,
+            1u64 << 32,
+            -1i32,
+            -126i32,
+            200i64,
+            true,
+            1.0f32,
+            Some(5u32),
+            String::from("hi"),
+            vec![1u16, 300],
+            (1u8, 300u32),
+            Shape::Circle(7),
+        );
+    }
+
+    #[test]
+    fn postcard() {
+        check!(BinSettings::postcard(), postcard_bytes;
+            255u8,
+            -1i8,
+            127u16,
+            128u16,
+            65535u16,
+            300u32,
+            u64::MAX,
+            -1i16,
+            -65i32,
+            64i64,
+            true,
+            1.0f32,
+            Some(300u32),
+            None::<u32>,
+            String::from("hi"),
+            vec![1u16, 300],
+            (1u8, 128u32),
+            Shape::Empty,
+            Shape::Circle(7),
+        );
+    }
+
+    #[test]
+    fn protobuf_like() {
+        let ctxt = Context::with_settings(BinSettings::protobuf_like());
+        fixture(150u32, &[0x96, 0x01], ctxt);
+        fixture(200u8, &[0xC8, 0x01], ctxt);
+        fixture(-1i32, &[0x01], ctxt);
+        fixture(-2i64, &[0x03], ctxt);
+        fixture(true, &[0x01], ctxt);
+        fixture(1.0f32, &[0x00, 0x00, 0x80, 0x3F], ctxt);
+        fixture(1.0f64, &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF0, 0x3F], ctxt);
+        fixture(String::from("testing"), b"\x07testing", ctxt);
+        fixture(vec![3u32, 270, 86942], &[0x03, 0x03, 0x8E, 0x02, 0x9E, 0xA7, 0x05], ctxt);
+    }
+
+    #[test]
+    fn modifiers() {
+        let value = Varints {
+            bincode: 1000,
+            postcard: -1000,
+        };
+        let bytes = [bincode_v2_bytes(&1000u32), postcard_bytes(&-1000i32)].concat();
+        fixture(value, &bytes, Context::new());
+    }
+
+    #[test]
+    fn bincode_varint_markers() {
+        // 255 is not a valid marker, and values must fit the target type
+        let ctxt = Context::with_settings(BinSettings::bincode_v2_standard());
+        let mut decoder = Encoder::new(Slice::new(&[0xFF]), ctxt);
+        assert!(u32::decode(&mut decoder).is_err());
+        let mut decoder = Encoder::new(Slice::new(&[0xFC, 0x00, 0x00, 0x01, 0x00]), ctxt);
+        assert!(u16::decode(&mut decoder).is_err());
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod protobuf {
+    use super::{decode, fixture};
+    use crate::io::VecStream;
+    use crate::protobuf::WireType;
+    use crate::{
+        BinSettings, Context, Decode, Encode, Encoder, EncodingError, ProtobufError, SizeRepr,
+    };
+    use core::fmt::Debug;
+
+    #[derive(Encode, Decode, Debug, PartialEq, Default)]
+    #[ender(format: protobuf)]
+    struct Test1 {
+        #[ender(field: 1)]
+        a: i32,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq, Default)]
+    #[ender(format: protobuf)]
+    struct Test2 {
+        #[ender(field: 2)]
+        b: String,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq, Default)]
+    #[ender(format: protobuf)]
+    struct Test3 {
+        #[ender(field: 3)]
+        c: Test1,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq, Default)]
+    #[ender(format: protobuf)]
+    struct Scalars {
+        #[ender(field: 1; num: protobuf_zz)]
+        sint: i32,
+        #[ender(field: 2; num: fixed)]
+        fixed: u32,
+        #[ender(field: 3)]
+        flag: bool,
+        #[ender(field: 4)]
+        double: f64,
+        #[ender(field: 5)]
+        optional: Option<u64>,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq, Default)]
+    #[ender(format: protobuf)]
+    struct Repeated {
+        #[ender(field: 4)]
+        bytes: Vec<u8>,
+        #[ender(field: 6)]
+        packed: Vec<i32>,
+        #[ender(field: 7)]
+        strings: Vec<String>,
+        #[ender(field: 8)]
+        messages: Vec<Test1>,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(format: protobuf)]
+    struct Mixed {
+        #[ender(field: 1)]
+        id: u64,
+        #[ender(field: 2)]
+        name: String,
+        #[ender(skip; default: 7)]
+        local: u8,
+        #[ender(field: 3; default: 42)]
+        missing: u16,
+        #[ender(field: 4; since: 1)]
+        added: u32,
+    }
+
+    #[test]
+    fn spec_examples() {
+        fixture(Test1 { a: 150 }, &[0x08, 0x96, 0x01], Context::new());
+        fixture(
+            Test2 { b: "testing".into() },
+            &[0x12, 0x07, 0x74, 0x65, 0x73, 0x74, 0x69, 0x6e, 0x67],
+            Context::new(),
+        );
+        fixture(Test3 { c: Test1 { a: 150 } }, &[0x1a, 0x03, 0x08, 0x96, 0x01], Context::new());
+        fixture(
+            Repeated {
+                packed: vec![3, 270, 86942],
+                ..Default::default()
+            },
+            &[0x32, 0x06, 0x03, 0x8E, 0x02, 0x9E, 0xA7, 0x05],
+            Context::new(),
+        );
+    }
+
+    #[test]
+    fn scalars() {
+        // Default values are omitted
+        fixture(Test1 { a: 0 }, &[], Context::new());
+        fixture(Scalars::default(), &[], Context::new());
+        // Negative int32 values are sign-extended to 10 bytes
+        fixture(
+            Test1 { a: -2 },
+            &[0x08, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+            Context::new(),
+        );
+        fixture(
+            Scalars {
+                sint: -2,
+                fixed: 1,
+                flag: true,
+                double: 1.0,
+                optional: Some(0),
+            },
+            &[
+                0x08, 0x03, // sint32
+                0x15, 0x01, 0x00, 0x00, 0x00, // fixed32
+                0x18, 0x01, // bool
+                0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF0, 0x3F, // double
+                0x28, 0x00, // optional, present even though it's the default
+            ],
+            Context::new(),
+        );
+    }
+
+    #[test]
+    fn repeated() {
+        fixture(
+            Repeated {
+                bytes: vec![1, 2],
+                packed: vec![],
+                strings: vec!["a".into(), "".into()],
+                messages: vec![Test1 { a: 1 }, Test1 { a: 0 }],
+            },
+            &[
+                0x22, 0x02, 0x01, 0x02, // bytes
+                0x3A, 0x01, b'a', 0x3A, 0x00, // strings
+                0x42, 0x02, 0x08, 0x01, 0x42, 0x00, // messages
+            ],
+            Context::new(),
+        );
+
+        // Unpacked scalars are accepted too, and records of the same field are concatenated
+        let repeated: Repeated =
+            decode(&[0x30, 0x03, 0x32, 0x01, 0x04, 0x30, 0x05], Context::new()).unwrap();
+        assert_eq!(repeated.packed, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn any_order_and_unknown_fields() {
+        let bytes = [
+            0x48, 0x96, 0x01, // unknown varint (9)
+            0x12, 0x02, b'h', b'i', // name
+            0x51, 0, 0, 0, 0, 0, 0, 0, 0, // unknown i64 (10)
+            0x5A, 0x03, 1, 2, 3, // unknown len (11)
+            0x65, 0, 0, 0, 0, // unknown i32 (12)
+            0x08, 0x05, // id
+            0x08, 0x06, // id again, the last one wins
+        ];
+        let mixed: Mixed = decode(&bytes, Context::new()).unwrap();
+        assert_eq!(
+            mixed,
+            Mixed {
+                id: 6,
+                name: "hi".into(),
+                local: 7,
+                missing: 42,
+                added: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn versioned() {
+        let mixed = Mixed {
+            id: 1,
+            name: String::new(),
+            local: 7,
+            missing: 0,
+            added: 3,
+        };
+
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        mixed.encode(&mut encoder).unwrap();
+        assert_eq!(encoder.finish().0.into_inner(), [0x08, 0x01]);
+
+        let mut ctxt = Context::new();
+        ctxt.version = 1;
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        mixed.encode(&mut encoder).unwrap();
+        assert_eq!(encoder.finish().0.into_inner(), [0x08, 0x01, 0x20, 0x03]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            decode::<Test1>(&[0x0A, 0x00], Context::new()),
+            Err(EncodingError::ProtobufError(ProtobufError::WireTypeMismatch {
+                expected: WireType::Varint,
+                found: WireType::Len,
+            }))
+        ));
+        assert!(matches!(
+            decode::<Test1>(&[0x0B], Context::new()),
+            Err(EncodingError::ProtobufError(ProtobufError::InvalidWireType(3)))
+        ));
+        assert!(matches!(
+            decode::<Test1>(&[0x00], Context::new()),
+            Err(EncodingError::ProtobufError(ProtobufError::InvalidFieldNumber(0)))
+        ));
+        // The nested message is longer than its length
+        assert!(matches!(
+            decode::<Test3>(&[0x1a, 0x02, 0x08, 0x96, 0x01], Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode::<Test1>(&[0x08, 0x96], Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn huge_lengths() {
+        // Lengths close to `i64::MAX` can't be allocated before reading the data
+        let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+        let string = [&[0x12][..], &huge].concat();
+        let bytes = [&[0x22][..], &huge].concat();
+        assert!(matches!(
+            decode::<Test2>(&string, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode::<Repeated>(&bytes, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+
+        // Even if the stream is unbounded
+        let ctxt = Context::new().alloc_budget(1024);
+        let mut decoder = Encoder::new(VecStream::new(string, 0), ctxt);
+        assert!(matches!(
+            Test2::decode(&mut decoder),
+            Err(EncodingError::AllocBudgetExceeded { remaining: 1024, .. })
+        ));
+
+        let settings = BinSettings::new().size_repr(SizeRepr::new().max_size(16));
+        let mut decoder = Encoder::new(VecStream::new(bytes, 0), Context::with_settings(settings));
+        assert!(matches!(
+            Repeated::decode(&mut decoder),
+            Err(EncodingError::MaxSizeExceeded { max: 16, .. })
+        ));
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod tlv {
+    use super::{decode, encode};
+    use crate::{Context, Decode, Encode, EncodingError};
+
+    #[derive(Encode, Decode, Debug, PartialEq, Default)]
+    #[ender(tlv; variant: bit8; size: bit8)]
+    struct V1 {
+        #[ender(id: 1)]
+        a: u16,
+        #[ender(id: 2)]
+        name: String,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(tlv; variant: bit8; size: bit8)]
+    struct V2 {
+        #[ender(id: 1)]
+        a: u16,
+        #[ender(id: 2)]
+        name: String,
+        #[ender(id: 3; default: 9)]
+        extra: u32,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[ender(tlv; variant: leb128; size: leb128)]
+    struct Nested {
+        #[ender(id: 300)]
+        inner: V1,
+        #[ender(id: 1; num: leb128)]
+        value: u64,
+        #[ender(skip; default: 5)]
+        local: u8,
+    }
+
+    #[test]
+    fn records() {
+        let v1 = V1 {
+            a: 0x0102,
+            name: "hi".into(),
+        };
+        let bytes = [1, 2, 0x02, 0x01, 2, 3, 2, b'h', b'i'];
+        assert_eq!(encode(&v1, Context::new()), bytes);
+        assert_eq!(decode::<V1>(&bytes, Context::new()).unwrap(), v1);
+    }
+
+    #[test]
+    fn schema_evolution() {
+        let v2 = V2 {
+            a: 7,
+            name: "".into(),
+            extra: 0x0A0B0C0D,
+        };
+        let bytes = encode(&v2, Context::new());
+        assert_eq!(bytes, [1, 2, 7, 0, 2, 1, 0, 3, 4, 0x0D, 0x0C, 0x0B, 0x0A]);
+
+        // Old readers skip the new field
+        assert_eq!(
+            decode::<V1>(&bytes, Context::new()).unwrap(),
+            V1 {
+                a: 7,
+                name: "".into()
+            }
+        );
+
+        // New readers fall back to the default for the missing field,
+        // and accept the fields in any order
+        assert_eq!(
+            decode::<V2>(&[2, 1, 0, 1, 2, 7, 0], Context::new()).unwrap(),
+            V2 {
+                a: 7,
+                name: "".into(),
+                extra: 9,
+            }
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let nested = Nested {
+            inner: V1 {
+                a: 1,
+                name: "".into(),
+            },
+            value: 300,
+            local: 5,
+        };
+        let bytes = encode(&nested, Context::new());
+        assert_eq!(
+            bytes,
+            [0xAC, 0x02, 7, 1, 2, 1, 0, 2, 1, 0, 1, 2, 0xAC, 0x02]
+        );
+        assert_eq!(decode::<Nested>(&bytes, Context::new()).unwrap(), nested);
+    }
+
+    #[test]
+    fn errors() {
+        // The value is shorter than its record
+        assert!(matches!(
+            decode::<V1>(&[1, 3, 7, 0, 0], Context::new()),
+            Err(EncodingError::TrailingBytes { remaining: 1 })
+        ));
+        // The record is truncated
+        assert!(matches!(
+            decode::<V1>(&[1, 2, 7], Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod asn1 {
+    use super::{decode, encode};
+    use crate::asn1::{
+        Asn1Rules, BigInteger, Boolean, Integer, Null, ObjectIdentifier, OctetString, Sequence, SequenceOf,
+        SetOf, Tag,
+    };
+    use crate::io::{Slice, VecStream};
+    use crate::{Asn1Error, BinSettings, Context, Decode, Encode, Encoder, EncodingError};
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct AlgorithmIdentifier {
+        algorithm: ObjectIdentifier,
+        parameters: Null,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Record {
+        #[ender(asn1: context(0), explicit)]
+        version: Integer<u8>,
+        serial: Integer<u64>,
+        #[ender(asn1: context(1))]
+        data: OctetString,
+        #[ender(asn1: context(2), explicit, optional)]
+        extra: Option<Integer<i32>>,
+        #[ender(asn1: context(3), optional)]
+        flag: Option<Boolean>,
+        algorithm: Sequence<AlgorithmIdentifier>,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Length {
+        #[ender(num: asn1)]
+        value: u32,
+    }
+
+    fn decode_ber<T: for<'a> Decode<Slice<'a>>>(bytes: &[u8]) -> Result<T, EncodingError> {
+        let settings = BinSettings::new().asn1_rules(Asn1Rules::Ber);
+        decode(bytes, Context::with_settings(settings))
+    }
+
+    fn record(extra: Option<i32>, flag: Option<bool>) -> Sequence<Record> {
+        Sequence(Record {
+            version: Integer(2),
+            serial: Integer(5),
+            data: OctetString(vec![1, 2]),
+            extra: extra.map(Integer),
+            flag: flag.map(Boolean),
+            algorithm: Sequence(AlgorithmIdentifier {
+                algorithm: ObjectIdentifier(vec![1, 2, 840, 113549, 1, 1, 11]),
+                parameters: Null,
+            }),
+        })
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(encode(&Integer(0u8), Context::new()), [0x02, 0x01, 0x00]);
+        assert_eq!(encode(&Integer(127u8), Context::new()), [0x02, 0x01, 0x7F]);
+        assert_eq!(encode(&Integer(128u8), Context::new()), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(encode(&Integer(256u16), Context::new()), [0x02, 0x02, 0x01, 0x00]);
+        assert_eq!(encode(&Integer(-128i8), Context::new()), [0x02, 0x01, 0x80]);
+        assert_eq!(encode(&Integer(-129i16), Context::new()), [0x02, 0x02, 0xFF, 0x7F]);
+
+        let mut max = vec![0x02, 0x11, 0x00];
+        max.extend([0xFF; 16]);
+        assert_eq!(encode(&Integer(u128::MAX), Context::new()), max);
+        assert_eq!(decode::<Integer<u128>>(&max, Context::new()).unwrap(), Integer(u128::MAX));
+
+        assert_eq!(
+            decode::<Integer<u8>>(&[0x02, 0x02, 0x00, 0x80], Context::new()).unwrap(),
+            Integer(128)
+        );
+        assert_eq!(
+            decode::<Integer<i64>>(&[0x02, 0x02, 0xFF, 0x7F], Context::new()).unwrap(),
+            Integer(-129)
+        );
+
+        // Out of range for the type
+        assert!(matches!(
+            decode::<Integer<u8>>(&[0x02, 0x02, 0x01, 0x00], Context::new()),
+            Err(EncodingError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            decode::<Integer<u32>>(&[0x02, 0x01, 0xFF], Context::new()),
+            Err(EncodingError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn primitives() {
+        let oid = ObjectIdentifier(vec![1, 2, 840, 113549]);
+        let bytes = [0x06, 0x06, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D];
+        assert_eq!(encode(&oid, Context::new()), bytes);
+        assert_eq!(decode::<ObjectIdentifier>(&bytes, Context::new()).unwrap(), oid);
+        assert_eq!(oid.to_string(), "1.2.840.113549");
+
+        let oid = ObjectIdentifier(vec![2, 999, 3]);
+        let bytes = [0x06, 0x03, 0x88, 0x37, 0x03];
+        assert_eq!(encode(&oid, Context::new()), bytes);
+        assert_eq!(decode::<ObjectIdentifier>(&bytes, Context::new()).unwrap(), oid);
+
+        assert_eq!(encode(&Boolean(true), Context::new()), [0x01, 0x01, 0xFF]);
+        assert_eq!(encode(&Null, Context::new()), [0x05, 0x00]);
+
+        // Long form length
+        let octets = OctetString(vec![7; 200]);
+        let bytes = encode(&octets, Context::new());
+        assert_eq!(bytes[..3], [0x04, 0x81, 0xC8]);
+        assert_eq!(decode::<OctetString>(&bytes, Context::new()).unwrap(), octets);
+    }
+
+    #[test]
+    fn tags() {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        encoder.write_asn1_tag(Tag::context(30)).unwrap();
+        encoder.write_asn1_tag(Tag::context(31)).unwrap();
+        encoder.write_asn1_tag(Tag::private(200).constructed(true)).unwrap();
+        let bytes = encoder.finish().0.into_inner();
+        assert_eq!(bytes, [0x9E, 0x9F, 0x1F, 0xFF, 0x81, 0x48]);
+
+        let mut decoder = Encoder::new(Slice::new(&bytes), Context::new());
+        assert_eq!(decoder.read_asn1_tag().unwrap(), Tag::context(30));
+        assert_eq!(decoder.peek_asn1_tag().unwrap(), Some(Tag::context(31)));
+        assert_eq!(decoder.read_asn1_tag().unwrap(), Tag::context(31));
+        assert_eq!(
+            decoder.read_asn1_tag().unwrap(),
+            Tag::private(200).constructed(true)
+        );
+        assert_eq!(decoder.peek_asn1_tag().unwrap(), None);
+    }
+
+    #[test]
+    fn sequences() {
+        let value = record(None, Some(true));
+        let bytes = encode(&value, Context::new());
+        assert_eq!(
+            bytes,
+            [
+                0x30, 0x1E, // SEQUENCE
+                0xA0, 0x03, 0x02, 0x01, 0x02, // [0] EXPLICIT INTEGER
+                0x02, 0x01, 0x05, // INTEGER
+                0x81, 0x02, 0x01, 0x02, // [1] IMPLICIT OCTET STRING
+                0x83, 0x01, 0xFF, // [3] IMPLICIT BOOLEAN
+                0x30, 0x0D, 0x06, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B,
+                0x05, 0x00, // AlgorithmIdentifier
+            ]
+        );
+        assert_eq!(decode::<Sequence<Record>>(&bytes, Context::new()).unwrap(), value);
+
+        let value = record(Some(-1), None);
+        let bytes = encode(&value, Context::new());
+        assert_eq!(bytes[1], 0x20);
+        assert_eq!(bytes[14..19], [0xA2, 0x03, 0x02, 0x01, 0xFF]);
+        assert_eq!(decode::<Sequence<Record>>(&bytes, Context::new()).unwrap(), value);
+
+        let list = SequenceOf(vec![Integer(1u8), Integer(2)]);
+        let bytes = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02];
+        assert_eq!(encode(&list, Context::new()), bytes);
+        assert_eq!(decode::<SequenceOf<Integer<u8>>>(&bytes, Context::new()).unwrap(), list);
+    }
+
+    #[test]
+    fn set_of() {
+        let set = SetOf(vec![Integer(3u8), Integer(1), Integer(2)]);
+        let sorted = [0x31, 0x09, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0x02, 0x01, 0x03];
+        assert_eq!(encode(&set, Context::new()), sorted);
+        assert_eq!(
+            decode::<SetOf<Integer<u8>>>(&sorted, Context::new()).unwrap(),
+            SetOf(vec![Integer(1), Integer(2), Integer(3)])
+        );
+
+        let unsorted = [0x31, 0x06, 0x02, 0x01, 0x02, 0x02, 0x01, 0x01];
+        assert!(matches!(
+            decode::<SetOf<Integer<u8>>>(&unsorted, Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::NonCanonical))
+        ));
+        assert_eq!(
+            decode_ber::<SetOf<Integer<u8>>>(&unsorted).unwrap(),
+            SetOf(vec![Integer(2), Integer(1)])
+        );
+    }
+
+    #[test]
+    fn num_encoding() {
+        let value = Length { value: 300 };
+        let bytes = [0x82, 0x01, 0x2C];
+        assert_eq!(encode(&value, Context::new()), bytes);
+        assert_eq!(decode::<Length>(&bytes, Context::new()).unwrap(), value);
+        assert_eq!(encode(&Length { value: 5 }, Context::new()), [0x05]);
+    }
+
+    #[test]
+    fn canonical_form() {
+        let non_canonical: [&[u8]; 3] = [
+            &[0x02, 0x02, 0x00, 0x7F],       // Redundant leading byte
+            &[0x02, 0x81, 0x01, 0x7F],       // Long form of a short length
+            &[0x02, 0x82, 0x00, 0x01, 0x7F], // Leading zero in the length
+        ];
+        for bytes in non_canonical {
+            assert!(matches!(
+                decode::<Integer<u8>>(bytes, Context::new()),
+                Err(EncodingError::Asn1Error(Asn1Error::NonCanonical))
+            ));
+            assert_eq!(decode_ber::<Integer<u8>>(bytes).unwrap(), Integer(127));
+        }
+
+        assert!(matches!(
+            decode::<Boolean>(&[0x01, 0x01, 0x01], Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::NonCanonical))
+        ));
+        assert_eq!(decode_ber::<Boolean>(&[0x01, 0x01, 0x01]).unwrap(), Boolean(true));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            decode::<OctetString>(&[0x04, 0x80, 0x00, 0x00], Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::IndefiniteLength))
+        ));
+        assert!(matches!(
+            decode::<Integer<u8>>(&[0x04, 0x01, 0x00], Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::UnexpectedTag { .. }))
+        ));
+        assert!(matches!(
+            decode::<Integer<u8>>(&[0x02, 0x00], Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::InvalidContent(_)))
+        ));
+        assert!(matches!(
+            decode::<ObjectIdentifier>(&[0x06, 0x02, 0x80, 0x01], Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::InvalidContent(_)))
+        ));
+
+        // The explicit tag of the version is wrong
+        let mut bytes = encode(&record(None, None), Context::new());
+        bytes[2] = 0xA1;
+        assert!(matches!(
+            decode::<Sequence<Record>>(&bytes, Context::new()),
+            Err(EncodingError::Asn1Error(Asn1Error::UnexpectedTag { .. }))
+        ));
+
+        // An OID with a single arc can't be encoded
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        assert!(ObjectIdentifier(vec![1]).encode(&mut encoder).is_err());
+    }
+
+    #[test]
+    fn huge_lengths() {
+        // The contents are never allocated if the length can't be trusted
+        let huge = [0x04, 0x88, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        assert!(matches!(
+            decode::<OctetString>(&huge, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+
+        let ctxt = Context::new().alloc_budget(1024);
+        let mut decoder = Encoder::new(VecStream::new(huge.to_vec(), 0), ctxt);
+        assert!(matches!(
+            OctetString::decode(&mut decoder),
+            Err(EncodingError::AllocBudgetExceeded { remaining: 1024, .. })
+        ));
+
+        let mut integer = huge;
+        integer[0] = 0x02;
+        let ctxt = Context::new().alloc_budget(1024);
+        let mut decoder = Encoder::new(VecStream::new(integer.to_vec(), 0), ctxt);
+        assert!(matches!(
+            BigInteger::decode(&mut decoder),
+            Err(EncodingError::AllocBudgetExceeded { remaining: 1024, .. })
+        ));
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod formats {
+    use super::{decode, encode};
+    use crate::io::{Slice, VecStream};
+    use crate::{
+        decode_bytes_with, BinSettings, BorrowError, Context, Decode, Encode, Encoder,
+        EncodingError, Format, FormatError,
+    };
+    use std::collections::BTreeMap;
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle { radius: f32 },
+        Polygon(Vec<(i16, i16)>),
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Drawing {
+        name: String,
+        id: u64,
+        visible: bool,
+        layer: Option<i8>,
+        shapes: Vec<Shape>,
+        tags: BTreeMap<String, u32>,
+    }
+
+    fn context(format: Format) -> Context<'static> {
+        Context::new().settings(BinSettings::new().format(format))
+    }
+
+    fn assert_vector<T>(value: T, expected: &[u8], format: Format)
+    where
+        T: Encode<VecStream> + for<'a> Decode<Slice<'a>> + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(encode(&value, context(format)), expected, "encoding {value:?}");
+        assert_eq!(decode::<T>(expected, context(format)).unwrap(), value);
+    }
+
+    // From RFC 8949, Appendix A
+    #[test]
+    fn cbor_vectors() {
+        let cbor = Format::Cbor;
+        assert_vector(0u8, &[0x00], cbor);
+        assert_vector(23u32, &[0x17], cbor);
+        assert_vector(24u64, &[0x18, 0x18], cbor);
+        assert_vector(100i32, &[0x18, 0x64], cbor);
+        assert_vector(1000u16, &[0x19, 0x03, 0xe8], cbor);
+        assert_vector(1000000usize, &[0x1a, 0x00, 0x0f, 0x42, 0x40], cbor);
+        assert_vector(u64::MAX, &[0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], cbor);
+        assert_vector(
+            u64::MAX as u128 + 1,
+            &[0xc2, 0x49, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            cbor,
+        );
+        assert_vector(
+            -(u64::MAX as i128) - 2,
+            &[0xc3, 0x49, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            cbor,
+        );
+        assert_vector(-1i8, &[0x20], cbor);
+        assert_vector(-10i64, &[0x29], cbor);
+        assert_vector(-100isize, &[0x38, 0x63], cbor);
+        assert_vector(-1000i16, &[0x39, 0x03, 0xe7], cbor);
+        assert_vector(100000.0f32, &[0xfa, 0x47, 0xc3, 0x50, 0x00], cbor);
+        assert_vector(1.1f64, &[0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a], cbor);
+        assert_vector(false, &[0xf4], cbor);
+        assert_vector(true, &[0xf5], cbor);
+        assert_vector(String::new(), &[0x60], cbor);
+        assert_vector(String::from("IETF"), &[0x64, 0x49, 0x45, 0x54, 0x46], cbor);
+        assert_vector(String::from("\u{00fc}"), &[0x62, 0xc3, 0xbc], cbor);
+        assert_vector(String::from("\u{6c34}"), &[0x63, 0xe6, 0xb0, 0xb4], cbor);
+        assert_vector('a', &[0x61, 0x61], cbor);
+        assert_vector(Vec::<u8>::new(), &[0x80], cbor);
+        assert_vector(vec![1u8, 2, 3], &[0x83, 0x01, 0x02, 0x03], cbor);
+        assert_vector(
+            BTreeMap::from([(1u8, 2u8), (3, 4)]),
+            &[0xa2, 0x01, 0x02, 0x03, 0x04],
+            cbor,
+        );
+        assert_vector(vec![25u32; 25], &[&[0x98, 0x19][..], &[0x18, 0x19].repeat(25)].concat(), cbor);
+
+        // Half precision floats are accepted while decoding
+        assert_eq!(decode::<f32>(&[0xf9, 0x3c, 0x00], context(cbor)).unwrap(), 1.0);
+        assert_eq!(decode::<f64>(&[0xf9, 0xc4, 0x00], context(cbor)).unwrap(), -4.0);
+        assert_eq!(decode::<f32>(&[0xf9, 0x00, 0x01], context(cbor)).unwrap(), 2f32.powi(-24));
+        assert_eq!(decode::<f32>(&[0xf9, 0x7c, 0x00], context(cbor)).unwrap(), f32::INFINITY);
+        assert_eq!(
+            decode::<f64>(&[0xfa, 0x47, 0xc3, 0x50, 0x00], context(cbor)).unwrap(),
+            100000.0
+        );
+    }
+
+    // From the MessagePack specification
+    #[test]
+    fn msgpack_vectors() {
+        let msgpack = Format::MessagePack;
+        assert_vector(0u8, &[0x00], msgpack);
+        assert_vector(127u32, &[0x7f], msgpack);
+        assert_vector(128u32, &[0xcc, 0x80], msgpack);
+        assert_vector(256i32, &[0xcd, 0x01, 0x00], msgpack);
+        assert_vector(65536u64, &[0xce, 0x00, 0x01, 0x00, 0x00], msgpack);
+        assert_vector(1u64 << 32, &[0xcf, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00], msgpack);
+        assert_vector(-1i8, &[0xff], msgpack);
+        assert_vector(-32i32, &[0xe0], msgpack);
+        assert_vector(-33i32, &[0xd0, 0xdf], msgpack);
+        assert_vector(-129i64, &[0xd1, 0xff, 0x7f], msgpack);
+        assert_vector(-32769i64, &[0xd2, 0xff, 0xff, 0x7f, 0xff], msgpack);
+        assert_vector(i64::MIN, &[0xd3, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], msgpack);
+        assert_vector(1.5f32, &[0xca, 0x3f, 0xc0, 0x00, 0x00], msgpack);
+        assert_vector(1.5f64, &[0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], msgpack);
+        assert_vector(false, &[0xc2], msgpack);
+        assert_vector(true, &[0xc3], msgpack);
+        assert_vector(String::new(), &[0xa0], msgpack);
+        assert_vector(String::from("a"), &[0xa1, 0x61], msgpack);
+        assert_vector("x".repeat(32), &[&[0xd9, 0x20][..], &[b'x'; 32]].concat(), msgpack);
+        assert_vector("x".repeat(256), &[&[0xda, 0x01, 0x00][..], &[b'x'; 256]].concat(), msgpack);
+        assert_vector(vec![1u8, 2, 3], &[0x93, 0x01, 0x02, 0x03], msgpack);
+        assert_vector(vec![0u8; 16], &[&[0xdc, 0x00, 0x10][..], &[0; 16]].concat(), msgpack);
+        assert_vector(BTreeMap::from([(1u8, 2u8)]), &[0x81, 0x01, 0x02], msgpack);
+
+        // Wider encodings are accepted while decoding
+        assert_eq!(decode::<u8>(&[0xcf, 0, 0, 0, 0, 0, 0, 0, 5], context(msgpack)).unwrap(), 5);
+        assert_eq!(decode::<i32>(&[0xcc, 0xff], context(msgpack)).unwrap(), 255);
+        assert_eq!(decode::<f64>(&[0xca, 0x3f, 0xc0, 0x00, 0x00], context(msgpack)).unwrap(), 1.5);
+    }
+
+    #[test]
+    fn derived() {
+        let drawing = Drawing {
+            name: String::from("sketch"),
+            id: 300,
+            visible: true,
+            layer: Some(-2),
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle { radius: 0.5 },
+                Shape::Polygon(vec![(0, 0), (-200, 1000)]),
+            ],
+            tags: BTreeMap::from([(String::from("a"), 1)]),
+        };
+
+        let cbor = encode(&drawing, context(Format::Cbor));
+        #[rustfmt::skip]
+        assert_eq!(cbor, [
+            0x86,
+            0x66, b's', b'k', b'e', b't', b'c', b'h',
+            0x19, 0x01, 0x2c,
+            0xf5,
+            0x81, 0x21,
+            0x83,
+            0x82, 0x00, 0x80,
+            0x82, 0x01, 0x81, 0xfa, 0x3f, 0x00, 0x00, 0x00,
+            0x82, 0x02, 0x81, 0x82, 0x82, 0x00, 0x00, 0x82, 0x38, 0xc7, 0x19, 0x03, 0xe8,
+            0xa1, 0x61, b'a', 0x01,
+        ]);
+        assert_eq!(decode::<Drawing>(&cbor, context(Format::Cbor)).unwrap(), drawing);
+
+        let msgpack = encode(&drawing, context(Format::MessagePack));
+        #[rustfmt::skip]
+        assert_eq!(msgpack, [
+            0x96,
+            0xa6, b's', b'k', b'e', b't', b'c', b'h',
+            0xcd, 0x01, 0x2c,
+            0xc3,
+            0x91, 0xfe,
+            0x93,
+            0x92, 0x00, 0x90,
+            0x92, 0x01, 0x91, 0xca, 0x3f, 0x00, 0x00, 0x00,
+            0x92, 0x02, 0x91, 0x92, 0x92, 0x00, 0x00, 0x92, 0xd1, 0xff, 0x38, 0xcd, 0x03, 0xe8,
+            0x81, 0xa1, b'a', 0x01,
+        ]);
+        assert_eq!(decode::<Drawing>(&msgpack, context(Format::MessagePack)).unwrap(), drawing);
+
+        // Options are null or an array of one item, so nested options stay apart
+        assert_vector(Option::<u8>::None, &[0xf6], Format::Cbor);
+        assert_vector(Some(Option::<u8>::None), &[0x81, 0xf6], Format::Cbor);
+        assert_vector(Option::<u8>::None, &[0xc0], Format::MessagePack);
+        assert_vector(Some(Some(5u8)), &[0x91, 0x91, 0x05], Format::MessagePack);
+    }
+
+    #[test]
+    fn borrow() {
+        for format in [Format::Cbor, Format::MessagePack] {
+            let bytes = encode(&"borrowed", context(format));
+            let string: &str = decode_bytes_with(&bytes, context(format)).unwrap();
+            assert_eq!(string, "borrowed");
+
+            let bytes = encode(&[1u8, 2, 3][..], context(format));
+            assert!(matches!(
+                decode_bytes_with::<_, &[u8]>(&bytes, context(format)),
+                Err(EncodingError::BorrowError(BorrowError::NonBorrowableFormat { .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        // A string where an integer is expected
+        assert!(matches!(
+            decode::<u32>(&[0x61, 0x61], context(Format::Cbor)),
+            Err(EncodingError::FormatError(FormatError::UnexpectedItem { found: 0x61, .. }))
+        ));
+        assert!(matches!(
+            decode::<bool>(&[0x01], context(Format::MessagePack)),
+            Err(EncodingError::FormatError(FormatError::UnexpectedItem { found: 0x01, .. }))
+        ));
+        // Indefinite-length array
+        assert!(matches!(
+            decode::<Vec<u8>>(&[0x9f, 0x01, 0xff], context(Format::Cbor)),
+            Err(EncodingError::FormatError(FormatError::IndefiniteLength))
+        ));
+        // Out of range for the requested type
+        assert!(matches!(
+            decode::<u8>(&[0x19, 0x01, 0x00], context(Format::Cbor)),
+            Err(EncodingError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            decode::<u16>(&[0xff], context(Format::MessagePack)),
+            Err(EncodingError::TooLarge { .. })
+        ));
+        // A string of two chars
+        assert!(decode::<char>(&[0xa2, b'a', b'b'], context(Format::MessagePack)).is_err());
+        // A tuple of the wrong arity
+        assert!(matches!(
+            decode::<(u8, u8)>(&[0x83, 0x01, 0x02, 0x03], context(Format::Cbor)),
+            Err(EncodingError::FormatError(FormatError::LengthMismatch { expected: 2, found: 3 }))
+        ));
+
+        // 128-bit values can't be represented in MessagePack
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), context(Format::MessagePack));
+        assert!(matches!(
+            encoder.write_u128(u64::MAX as u128 + 1),
+            Err(EncodingError::TooLarge { .. })
+        ));
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod encoded_size {
+    use super::decimal;
+    use crate::io::VecStream;
+    use crate::{
+        encode_bytes_with, BinSettings, BitWidth, Context, Encode, EncodedSize, EncodingError,
+        Format, NumEncoding, NumRepr, SizeRepr, StrEncoding, StrLen, StringError, StringRepr, VariantRepr,
+    };
+    use std::collections::BTreeMap;
+
+    /// A redirection which prefixes the data with a marker byte
+    mod marked {
+        use crate::io::Write;
+        use crate::{Encoder, EncodingResult};
+
+        pub fn encode<T, F>(encoder: &mut Encoder<T>, fun: F) -> EncodingResult<()>
+        where
+            T: Write,
+            F: FnOnce(&mut Encoder<T>) -> EncodingResult<()>,
+        {
+            encoder.write_u8(0xFF)?;
+            fun(encoder)
+        }
+    }
+
+    #[derive(Encode, EncodedSize, Debug)]
+    enum Shape {
+        Point,
+        Circle { radius: f32 },
+        Polygon(Vec<(i16, i16)>),
+    }
+
+    #[derive(Encode, EncodedSize, Debug)]
+    #[ender(magic: b"DRAW")]
+    struct Drawing {
+        name: String,
+        id: u64,
+        #[ender(len_of: shapes)]
+        count: u16,
+        shapes: Vec<Shape>,
+        layer: Option<i8>,
+        tags: BTreeMap<String, u32>,
+    }
+
+    #[derive(Encode, EncodedSize, Debug)]
+    #[ender(size: bit32)]
+    struct Packet {
+        #[ender(len_of bytes: body; num: fixed)]
+        length: u32,
+        #[ender(pad: 3)]
+        kind: u8,
+        has_extra: bool,
+        #[ender(if: *has_extra; num: leb128)]
+        extra: u64,
+        #[ender(sized)]
+        body: Drawing,
+        #[ender(with: decimal)]
+        code: u32,
+        #[ender(redir: marked())]
+        trailer: String,
+        #[ender(skip)]
+        ignored: u64,
+    }
+
+    #[derive(Encode, EncodedSize, Debug)]
+    #[ender(tag_type: String)]
+    enum Command {
+        #[ender(tag: "get")]
+        Get(String),
+        #[ender(tag: "set")]
+        Set(String, u32),
+        #[ender(unknown)]
+        Other(String, Vec<u8>),
+    }
+
+    #[derive(Encode, EncodedSize, Debug)]
+    #[ender(variant: bit8, leb128)]
+    enum Message {
+        Ping,
+        #[ender(unknown)]
+        Unknown(u8, Vec<u8>),
+    }
+
+    #[derive(Encode, EncodedSize, Debug)]
+    #[ender(redir: marked())]
+    struct Wrapped {
+        value: u64,
+    }
+
+    fn binary_settings() -> Vec<BinSettings> {
+        let mut settings = Vec::new();
+        for num_encoding in [
+            NumEncoding::Fixed,
+            NumEncoding::Leb128,
+            NumEncoding::ProtobufWasteful,
+            NumEncoding::ProtobufZigzag,
+            NumEncoding::BincodeVarint,
+            NumEncoding::PostcardVarint,
+            NumEncoding::Asn1,
+        ] {
+            for width in [BitWidth::Bit16, BitWidth::Bit32, BitWidth::Bit64] {
+                for (encoding, len) in [
+                    (StrEncoding::Utf8, StrLen::LengthPrefixed),
+                    (StrEncoding::Utf16, StrLen::NullTerminated),
+                    (StrEncoding::Utf32, StrLen::LengthPrefixed),
+                    (StrEncoding::Windows1252, StrLen::NullTerminatedFixed(64)),
+                ] {
+                    settings.push(
+                        BinSettings::new()
+                            .num_repr(NumRepr::new().num_encoding(num_encoding))
+                            .size_repr(SizeRepr::new().num_encoding(num_encoding).bit_width(width))
+                            .variant_repr(VariantRepr::new().num_encoding(num_encoding).bit_width(width))
+                            .string_repr(StringRepr::new().str_encoding(encoding).len_encoding(len)),
+                    );
+                }
+            }
+        }
+        settings
+    }
+
+    fn settings() -> Vec<BinSettings> {
+        let mut settings = binary_settings();
+        settings.push(BinSettings::new().format(Format::Cbor));
+        settings.push(BinSettings::new().format(Format::MessagePack));
+        settings
+    }
+
+    fn assert_size<T: Encode<VecStream> + EncodedSize + std::fmt::Debug>(value: T) {
+        assert_size_with(value, settings());
+    }
+
+    fn assert_size_with<T>(value: T, settings: Vec<BinSettings>)
+    where
+        T: Encode<VecStream> + EncodedSize + std::fmt::Debug,
+    {
+        for settings in settings {
+            let ctxt = Context::with_settings(settings);
+            let encoded = encode_bytes_with(&value, ctxt).unwrap();
+            assert_eq!(
+                value.encoded_size(&ctxt).unwrap(),
+                encoded.len(),
+                "size of {value:?} with {settings:?}"
+            );
+        }
+    }
+
+    fn drawing() -> Drawing {
+        Drawing {
+            name: String::from("sketch"),
+            id: 300,
+            count: 3,
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle { radius: 0.5 },
+                Shape::Polygon(vec![(0, 0), (-200, 1000)]),
+            ],
+            layer: Some(-2),
+            tags: BTreeMap::from([(String::from("a"), 1), (String::from("long"), 70000)]),
+        }
+    }
+
+    #[test]
+    fn primitives() {
+        for value in [0u64, 1, 127, 128, 250, 251, 300, 70000, u32::MAX as u64 + 1, u64::MAX] {
+            assert_size(value);
+            assert_size(value as u16);
+            assert_size(value as u128);
+        }
+        for value in [0i64, -1, 63, -64, 64, -65, 1000, -70000, i64::MIN, i64::MAX] {
+            assert_size(value);
+            assert_size(value as i8);
+            assert_size(value as i32);
+        }
+        assert_size(true);
+        assert_size(1.5f32);
+        assert_size(-2.25f64);
+        assert_size('a');
+        assert_size('é');
+        assert_size(());
+        assert_size(1000usize);
+    }
+
+    #[test]
+    fn collections() {
+        assert_size("Hello, world!");
+        assert_size(String::from("Grüße"));
+        assert_size(vec![1u32, 2, 3]);
+        assert_size([7i16; 4]);
+        assert_size(Some(String::from("some")));
+        assert_size(Option::<u8>::None);
+        assert_size(Result::<u8, String>::Err(String::from("error")));
+        assert_size((1u8, -5i32, String::from("tuple")));
+        assert_size(BTreeMap::from([(1u16, 'x'), (500, 'y')]));
+        assert_size(0..100u32);
+        assert_size(std::time::Duration::from_millis(1500));
+        assert_size(std::net::SocketAddr::from(([127, 0, 0, 1], 8080)));
+    }
+
+    #[test]
+    fn derive() {
+        assert_size(drawing());
+        // A back-patched length can't change size, so the self-describing formats are excluded
+        assert_size_with(
+            Packet {
+                length: 0,
+                kind: 4,
+                has_extra: true,
+                extra: 1 << 40,
+                body: drawing(),
+                code: 12345,
+                trailer: String::from("end"),
+                ignored: 1,
+            },
+            binary_settings(),
+        );
+        assert_size_with(
+            Packet {
+                length: 0,
+                kind: 4,
+                has_extra: false,
+                extra: 1 << 40,
+                body: drawing(),
+                code: 7,
+                trailer: String::new(),
+                ignored: 1,
+            },
+            binary_settings(),
+        );
+        assert_size(Wrapped { value: 99 });
+    }
+
+    #[test]
+    fn tags() {
+        assert_size(Command::Get(String::from("key")));
+        assert_size(Command::Set(String::from("key"), 1000));
+        assert_size(Command::Other(String::from("delete"), vec![1, 2, 3]));
+        assert_size(Message::Ping);
+        assert_size(Message::Unknown(200, vec![4, 5]));
+    }
+
+    #[test]
+    fn errors() {
+        // The same checks as while encoding are performed
+        let ctxt = Context::with_settings(
+            BinSettings::new().size_repr(SizeRepr::new().max_size(2)),
+        );
+        assert!(matches!(
+            vec![1u8, 2, 3].encoded_size(&ctxt),
+            Err(EncodingError::MaxSizeExceeded { max: 2, requested: 3 })
+        ));
+
+        let ctxt = Context::with_settings(
+            BinSettings::new().string_repr(StringRepr::new().str_encoding(StrEncoding::Ascii)),
+        );
+        assert!(matches!(
+            "Grüße".encoded_size(&ctxt),
+            Err(EncodingError::StringError(StringError::InvalidChar))
+        ));
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod bulk {
+    use crate::io::{Slice, VecStream};
+    use crate::{
+        decode_bytes_with, encode_bytes_with, BinSettings, Context, Decode, Encode, Encoder,
+        Endianness, Format, NumEncoding, NumRepr,
+    };
+
+    fn context(num_encoding: NumEncoding, endianness: Endianness) -> Context<'static> {
+        Context::with_settings(
+            BinSettings::new()
+                .num_repr(NumRepr::new().num_encoding(num_encoding).endianness(endianness)),
+        )
+    }
+
+    /// Encodes each element separately, like the slice implementations used to
+    fn encode_each<T: Encode<VecStream>>(values: &[T], ctxt: Context) -> Vec<u8> {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+        encoder.write_seq_len(values.len()).unwrap();
+        for value in values {
+            value.encode(&mut encoder).unwrap();
+        }
+        encoder.finish().0.into_inner()
+    }
+
+    #[test]
+    fn matches_elementwise() {
+        // Long enough to span multiple chunks when byte-swapping
+        let ints: Vec<u32> = (0..1000).map(|x| x * 0x00010203).collect();
+        let signed: Vec<i16> = (-500..500).collect();
+        let floats: Vec<f64> = (0..300).map(|x| x as f64 * -1.5).collect();
+        let bytes: Vec<i8> = (-128..=127).collect();
+
+        for num_encoding in [NumEncoding::Fixed, NumEncoding::Leb128, NumEncoding::BincodeVarint] {
+            for endianness in [Endianness::LittleEndian, Endianness::BigEndian] {
+                let ctxt = context(num_encoding, endianness);
+
+                let encoded = encode_bytes_with(&ints, ctxt).unwrap();
+                assert_eq!(encoded, encode_each(&ints, ctxt));
+                assert_eq!(decode_bytes_with::<_, Vec<u32>>(&encoded, ctxt).unwrap(), ints);
+
+                let encoded = encode_bytes_with(&signed[..], ctxt).unwrap();
+                assert_eq!(encoded, encode_each(&signed, ctxt));
+                assert_eq!(decode_bytes_with::<_, Box<[i16]>>(&encoded, ctxt).unwrap(), signed[..].into());
+
+                let encoded = encode_bytes_with(&floats, ctxt).unwrap();
+                assert_eq!(encoded, encode_each(&floats, ctxt));
+                assert_eq!(decode_bytes_with::<_, Vec<f64>>(&encoded, ctxt).unwrap(), floats);
+
+                let encoded = encode_bytes_with(&bytes, ctxt).unwrap();
+                assert_eq!(encoded, encode_each(&bytes, ctxt));
+                assert_eq!(decode_bytes_with::<_, Vec<i8>>(&encoded, ctxt).unwrap(), bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn arrays() {
+        let array: [u64; 5] = [1, 2, u64::MAX, 0x0102030405060708, 0];
+        for endianness in [Endianness::LittleEndian, Endianness::BigEndian] {
+            let ctxt = context(NumEncoding::Fixed, endianness);
+            let encoded = encode_bytes_with(array, ctxt).unwrap();
+
+            let mut expected = Vec::new();
+            for value in array {
+                match endianness {
+                    Endianness::LittleEndian => expected.extend(value.to_le_bytes()),
+                    Endianness::BigEndian => expected.extend(value.to_be_bytes()),
+                }
+            }
+            assert_eq!(encoded, expected);
+            assert_eq!(decode_bytes_with::<_, [u64; 5]>(&encoded, ctxt).unwrap(), array);
+        }
+    }
+
+    #[test]
+    fn formats() {
+        // The self-describing formats encode every element separately
+        let ctxt = Context::with_settings(BinSettings::new().format(Format::Cbor));
+        let values = vec![1u16, 500, 70];
+        let encoded = encode_bytes_with(&values, ctxt).unwrap();
+        assert_eq!(encoded, [0x83, 0x01, 0x19, 0x01, 0xf4, 0x18, 0x46]);
+
+        let mut decoder = Encoder::new(Slice::new(&encoded), ctxt);
+        assert_eq!(Vec::<u16>::decode(&mut decoder).unwrap(), values);
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod borrow_pod {
+    use super::encode;
+    use crate::io::{Slice, VecStream};
+    use crate::{
+        BinSettings, BorrowError, Context, Decode, Encode, Encoder, EncodingError, Endianness,
+        NumEncoding, NumRepr,
+    };
+
+    #[derive(Encode, Decode, Copy, Clone, Debug, PartialEq)]
+    #[repr(C)]
+    struct Vertex {
+        x: f32,
+        y: f32,
+        color: u32,
+    }
+
+    // SAFETY: `Vertex` is `#[repr(C)]`, has no padding and all of its fields are `Pod`
+    unsafe impl bytemuck::Zeroable for Vertex {}
+    unsafe impl bytemuck::Pod for Vertex {}
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Mesh<'a> {
+        name: String,
+        #[ender(borrow)]
+        vertices: &'a [Vertex],
+        #[ender(borrow)]
+        indices: &'a [u16],
+    }
+
+    fn context(endianness: Endianness) -> Context<'static> {
+        Context::with_settings(BinSettings::new().num_repr(NumRepr::new().endianness(endianness)))
+    }
+
+    /// Encodes the value into a buffer aligned for any of the types above
+    fn encode_aligned<T: Encode<VecStream>>(value: &T, ctxt: Context) -> Vec<u64> {
+        let bytes = encode(value, ctxt);
+
+        let mut aligned = vec![0u64; bytes.len().div_ceil(8)];
+        bytemuck::cast_slice_mut::<u64, u8>(&mut aligned)[..bytes.len()].copy_from_slice(&bytes);
+        aligned
+    }
+
+    #[test]
+    fn records() {
+        let vertices = [
+            Vertex { x: 0.0, y: 1.0, color: 0xFF0000FF },
+            Vertex { x: -1.5, y: 2.25, color: 0x00FF00FF },
+        ];
+        // The name keeps the following slices aligned
+        let mesh = Mesh {
+            name: String::from("triangle"),
+            vertices: &vertices,
+            indices: &[0, 1, 0],
+        };
+
+        let ctxt = context(Endianness::native());
+        let buf = encode_aligned(&mesh, ctxt);
+        let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+        assert_eq!(Mesh::decode(&mut decoder).unwrap(), mesh);
+
+        let values: &[f32] = &[1.0, 2.0];
+        let buf = encode_aligned(&values, ctxt);
+        let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+        assert_eq!(<&[f32]>::decode(&mut decoder).unwrap(), values);
+    }
+
+    #[test]
+    fn errors() {
+        let values: &[u32] = &[1, 2, 3];
+
+        let foreign = match Endianness::native() {
+            Endianness::LittleEndian => Endianness::BigEndian,
+            Endianness::BigEndian => Endianness::LittleEndian,
+        };
+        let ctxt = context(foreign);
+        let buf = encode_aligned(&values, ctxt);
+        let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+        assert!(matches!(
+            <&[u32]>::decode(&mut decoder),
+            Err(EncodingError::BorrowError(BorrowError::EndiannessMismatch { .. }))
+        ));
+
+        let ctxt = Context::with_settings(
+            BinSettings::new().num_repr(NumRepr::new().num_encoding(NumEncoding::Leb128)),
+        );
+        let buf = encode_aligned(&values, ctxt);
+        let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+        assert!(matches!(
+            <&[u32]>::decode(&mut decoder),
+            Err(EncodingError::BorrowError(BorrowError::NonBorrowableNumEncoding { .. }))
+        ));
+    }
+
+    #[test]
+    fn varint_bytes() {
+        // Bincode and postcard write single bytes as-is, so they can still be borrowed
+        for settings in [BinSettings::postcard(), BinSettings::bincode_v2_standard()] {
+            let ctxt = Context::with_settings(settings);
+
+            let bytes: &[u8] = &[1, 2, 200];
+            let buf = encode_aligned(&bytes, ctxt);
+            let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+            assert_eq!(<&[u8]>::decode(&mut decoder).unwrap(), bytes);
+
+            let bytes: &[i8] = &[-1, 5, -128];
+            let buf = encode_aligned(&bytes, ctxt);
+            let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+            assert_eq!(<&[i8]>::decode(&mut decoder).unwrap(), bytes);
+
+            let values: &[u16] = &[1, 2];
+            let buf = encode_aligned(&values, ctxt);
+            let mut decoder = Encoder::new(Slice::new(bytemuck::cast_slice(&buf)), ctxt);
+            assert!(matches!(
+                <&[u16]>::decode(&mut decoder),
+                Err(EncodingError::BorrowError(BorrowError::NonBorrowableNumEncoding { .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn owned_fallback() {
+        let buf = [0u32; 4];
+        let data = &bytemuck::cast_slice::<u32, u8>(&buf)[1..];
+
+        let mut decoder = Encoder::new(Slice::new(data), Context::new());
+        let err = decoder.borrow_pod_slice::<u32>(3, NumEncoding::Fixed, Endianness::native());
+        assert!(matches!(
+            err,
+            Err(EncodingError::BorrowError(BorrowError::AlignmentMismatch {
+                required: 4,
+                offset: 1,
+                ..
+            }))
+        ));
+
+        // The stream wasn't advanced, so the data can still be decoded
+        let owned = <[u32; 3]>::decode(&mut decoder).unwrap();
+        assert_eq!(owned, [0; 3]);
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod seq {
+    use crate::io::Slice;
+    use crate::{
+        encode_bytes_with, BinSettings, Context, Decode, Encode, EncodedSize, Encoder,
+        EncodingError, Format, LazySeq, NumEncoding, SeqEncoder, SizeRepr, Skip, StringError,
+    };
+
+    #[derive(Encode, Decode, EncodedSize, Skip, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+        name: String,
+    }
+
+    #[derive(Encode, Decode, Debug)]
+    struct Log<'data> {
+        version: u16,
+        #[ender(borrow)]
+        records: LazySeq<'data, Record>,
+        #[ender(borrow)]
+        tags: LazySeq<'data, &'data str>,
+        end: u8,
+    }
+
+    fn records() -> Vec<Record> {
+        (0..5)
+            .map(|id| Record {
+                id,
+                name: format!("record {id}"),
+            })
+            .collect()
+    }
+
+    /// An iterator which reports the wrong length
+    #[derive(Clone)]
+    struct Liar(core::ops::Range<u8>, usize);
+
+    impl Iterator for Liar {
+        type Item = u8;
+        fn next(&mut self) -> Option<u8> {
+            self.0.next()
+        }
+    }
+
+    impl ExactSizeIterator for Liar {
+        fn len(&self) -> usize {
+            self.1
+        }
+    }
+
+    #[test]
+    fn seq_decoder() {
+        let data = encode_bytes_with((records(), 0xAAu8), Context::new()).unwrap();
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+
+        let mut seq = decoder.seq_decoder::<Record>().unwrap();
+        assert_eq!(seq.len(), 5);
+        assert_eq!(seq.next().unwrap().unwrap(), records()[0]);
+        assert_eq!(seq.len(), 4);
+        let rest: Vec<Record> = seq.map(Result::unwrap).collect();
+        assert_eq!(rest, records()[1..]);
+
+        // The stream continues after the sequence
+        assert_eq!(decoder.read_u8().unwrap(), 0xAA);
+
+        // The iteration ends after an error
+        let mut decoder = Encoder::new(Slice::new(&data[..40]), Context::new());
+        let mut seq = decoder.seq_decoder::<Record>().unwrap();
+        assert!(seq.next().unwrap().is_ok());
+        assert!(matches!(seq.next(), Some(Err(EncodingError::UnexpectedEnd))));
+        assert!(seq.next().is_none());
+        assert_eq!(seq.len(), 0);
+    }
+
+    #[test]
+    fn seq_encoder() {
+        for ctxt in [
+            Context::new(),
+            Context::with_settings(BinSettings::new().format(Format::MessagePack)),
+        ] {
+            let records = records();
+            let seq = SeqEncoder::new(records.iter());
+            let data = encode_bytes_with(&seq, ctxt).unwrap();
+            assert_eq!(data, encode_bytes_with(&records, ctxt).unwrap());
+            assert_eq!(seq.encoded_size(&ctxt).unwrap(), data.len());
+        }
+
+        assert!(matches!(
+            encode_bytes_with(SeqEncoder::new(Liar(0..3, 2)), Context::new()),
+            Err(EncodingError::SeqLenMismatch { expected: 2, got: 3 })
+        ));
+        assert!(matches!(
+            encode_bytes_with(SeqEncoder::new(Liar(0..3, 4)), Context::new()),
+            Err(EncodingError::SeqLenMismatch { expected: 4, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn lazy_seq() {
+        for ctxt in [
+            Context::new(),
+            Context::with_settings(
+                BinSettings::new().size_repr(SizeRepr::new().num_encoding(NumEncoding::Leb128)),
+            ),
+            Context::with_settings(BinSettings::new().format(Format::Cbor)),
+        ] {
+            let data =
+                encode_bytes_with((3u16, records(), vec!["a", "bc"], 0x55u8), ctxt).unwrap();
+            let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+            let log = Log::decode(&mut decoder).unwrap();
+
+            assert_eq!(log.version, 3);
+            assert_eq!(log.end, 0x55);
+            assert_eq!(log.records.len(), 5);
+            let decoded: Vec<Record> = log.records.iter().map(Result::unwrap).collect();
+            assert_eq!(decoded, records());
+            let tags: Vec<&str> = log.tags.iter().map(Result::unwrap).collect();
+            assert_eq!(tags, ["a", "bc"]);
+
+            // The borrowed data is written back as-is
+            assert_eq!(encode_bytes_with(&log, ctxt).unwrap(), data);
+        }
+
+        // The items are only validated while iterating
+        let invalid: &[u8] = &[0xFF, 0xFE];
+        let data = encode_bytes_with(
+            (3u16, vec![(1u32, invalid)], vec!["a"], 0x55u8),
+            Context::new(),
+        )
+        .unwrap();
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        let log = Log::decode(&mut decoder).unwrap();
+        assert_eq!(log.end, 0x55);
+        assert!(matches!(
+            log.records.iter().next(),
+            Some(Err(EncodingError::StringError(StringError::InvalidChar)))
+        ));
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod skip {
+    use super::decimal;
+    use crate::io::{BorrowRead, Read, Slice, VecStream};
+    use crate::{
+        encode_bytes_with, BinSettings, BitWidth, Context, Decode, Encode, Encoder, EncodingError,
+        EncodingResult, Format, NumEncoding, NumRepr, SizeRepr, Skip, StrEncoding, StrLen,
+        StringRepr, VariantRepr,
+    };
+    use std::collections::BTreeMap;
+
+    /// A stream which can only be read, so that it's skipped by reading the bytes
+    struct Plain<'a>(&'a [u8]);
+
+    impl Read for Plain<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> EncodingResult<()> {
+            if buf.len() > self.0.len() {
+                return Err(EncodingError::UnexpectedEnd);
+            }
+            let (data, rest) = self.0.split_at(buf.len());
+            buf.copy_from_slice(data);
+            self.0 = rest;
+            Ok(())
+        }
+    }
+
+    #[derive(Encode, Decode, Skip, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle { radius: f32 },
+        Polygon(Vec<(i16, i16)>),
+    }
+
+    #[derive(Encode, Decode, Skip, Debug, PartialEq)]
+    #[ender(magic: b"DRAW")]
+    struct Drawing {
+        name: String,
+        id: u64,
+        #[ender(len_of: shapes)]
+        count: u16,
+        shapes: Vec<Shape>,
+        has_layer: bool,
+        #[ender(if: *has_layer)]
+        layer: i8,
+        tags: BTreeMap<String, u32>,
+        #[ender(pad: 2; with: decimal)]
+        code: u32,
+        #[ender(skip)]
+        ignored: u64,
+    }
+
+    #[derive(Encode, Decode, Skip, Debug, PartialEq)]
+    #[ender(tag_type: String)]
+    enum Command {
+        #[ender(tag: "get")]
+        Get(String),
+        #[ender(tag: "set")]
+        Set(String, u32),
+    }
+
+    #[derive(Encode, Debug)]
+    struct Header {
+        version: u8,
+        reserved: [u16; 3],
+        checksum: u32,
+        name: String,
+    }
+
+    #[derive(Decode, Skip, Debug, PartialEq)]
+    struct Summary {
+        version: u8,
+        #[ender(de; skip_value: ([u16; 3], u32))]
+        skipped: (),
+        name: String,
+    }
+
+    fn settings() -> Vec<BinSettings> {
+        let mut settings = Vec::new();
+        for num_encoding in [NumEncoding::Fixed, NumEncoding::Leb128, NumEncoding::ProtobufZigzag] {
+            for width in [BitWidth::Bit16, BitWidth::Bit64] {
+                for (encoding, len) in [
+                    (StrEncoding::Utf8, StrLen::LengthPrefixed),
+                    (StrEncoding::Utf16, StrLen::NullTerminated),
+                    (StrEncoding::Windows1252, StrLen::NullTerminatedFixed(64)),
+                ] {
+                    settings.push(
+                        BinSettings::new()
+                            .num_repr(NumRepr::new().num_encoding(num_encoding))
+                            .size_repr(SizeRepr::new().num_encoding(num_encoding).bit_width(width))
+                            .variant_repr(VariantRepr::new().num_encoding(num_encoding).bit_width(width))
+                            .string_repr(StringRepr::new().str_encoding(encoding).len_encoding(len)),
+                    );
+                }
+            }
+        }
+        settings.push(BinSettings::new().format(Format::Cbor));
+        settings.push(BinSettings::new().format(Format::MessagePack));
+        settings
+    }
+
+    /// Checks that skipping the value leaves the stream right before the data that follows,
+    /// both when the stream can lend the data and when it can't
+    fn assert_skip<T>(value: T)
+    where
+        T: Encode<VecStream> + for<'a> Skip<Slice<'a>> + for<'a> Skip<Plain<'a>> + std::fmt::Debug,
+    {
+        for settings in settings() {
+            let ctxt = Context::with_settings(settings);
+            let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), ctxt);
+            value.encode(&mut encoder).unwrap();
+            encoder.write_u8(0xAA).unwrap();
+            let data = encoder.stream.into_inner();
+
+            let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+            T::skip(&mut decoder).unwrap();
+            assert_eq!(
+                decoder.read_u8().unwrap(),
+                0xAA,
+                "skipping {value:?} with {settings:?}"
+            );
+            assert!(decoder.stream.borrow_read(1).is_err());
+
+            let mut decoder = Encoder::new(Plain(&data), ctxt);
+            T::skip(&mut decoder).unwrap();
+            assert_eq!(decoder.read_u8().unwrap(), 0xAA);
+            assert!(decoder.stream.0.is_empty());
+        }
+    }
+
+    fn drawing() -> Drawing {
+        Drawing {
+            name: String::from("sketch"),
+            id: 300,
+            count: 3,
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle { radius: 0.5 },
+                Shape::Polygon(vec![(0, 0), (-200, 1000)]),
+            ],
+            has_layer: true,
+            layer: -2,
+            tags: BTreeMap::from([(String::from("a"), 1), (String::from("long"), 70000)]),
+            code: 12345,
+            ignored: 0,
+        }
+    }
+
+    #[test]
+    fn primitives() {
+        for value in [0u64, 1, 127, 128, 300, 70000, u64::MAX] {
+            assert_skip(value);
+            assert_skip(value as u16);
+            assert_skip(value as i128);
+        }
+        assert_skip(true);
+        assert_skip(1.5f32);
+        assert_skip(-2.25f64);
+        assert_skip('é');
+        assert_skip(());
+        assert_skip(1000usize);
+        assert_skip(core::num::NonZeroU32::new(7).unwrap());
+    }
+
+    #[test]
+    fn collections() {
+        assert_skip(String::from("Grüße"));
+        assert_skip(vec![1u32, 2, 3]);
+        assert_skip(vec![-1.5f64; 10]);
+        assert_skip([7i16; 4]);
+        assert_skip(Some(String::from("some")));
+        assert_skip(Option::<u8>::None);
+        assert_skip(Result::<u8, String>::Err(String::from("error")));
+        assert_skip((1u8, -5i32, String::from("tuple")));
+        assert_skip(BTreeMap::from([(1u16, 'x'), (500, 'y')]));
+        assert_skip(0..100u32);
+        assert_skip(core::ops::Bound::Excluded(5u8));
+        assert_skip(std::time::Duration::from_millis(1500));
+        assert_skip(std::net::SocketAddr::from(([127, 0, 0, 1], 8080)));
+        assert_skip(std::path::PathBuf::from("a/b"));
+    }
+
+    #[test]
+    fn derive() {
+        assert_skip(drawing());
+        assert_skip(Drawing {
+            has_layer: false,
+            layer: 0,
+            ..drawing()
+        });
+        assert_skip(Command::Get(String::from("key")));
+        assert_skip(Command::Set(String::from("key"), 1000));
+    }
+
+    #[test]
+    fn skip_value() {
+        let header = Header {
+            version: 2,
+            reserved: [1, 2, 3],
+            checksum: 0xDEADBEEF,
+            name: String::from("header"),
+        };
+
+        // The self-describing formats frame every struct as an array of its fields, so a
+        // skipped value can only stand in for other fields in the binary format
+        for settings in settings()
+            .into_iter()
+            .filter(|settings| settings.format == Format::Binary)
+        {
+            let ctxt = Context::with_settings(settings);
+            let data = encode_bytes_with(&header, ctxt).unwrap();
+
+            let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+            let summary = Summary::decode(&mut decoder).unwrap();
+            assert_eq!(
+                summary,
+                Summary {
+                    version: 2,
+                    skipped: (),
+                    name: String::from("header"),
+                }
+            );
+
+            let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+            Summary::skip(&mut decoder).unwrap();
+            assert!(decoder.stream.borrow_read(1).is_err());
+        }
+    }
+
+    #[test]
+    fn bulk() {
+        // Numbers laid out as-is are skipped without being read
+        let data = encode_bytes_with(vec![1u32; 1000], Context::new()).unwrap();
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new());
+        Vec::<u32>::skip(&mut decoder).unwrap();
+        assert!(decoder.stream.borrow_read(1).is_err());
+
+        // Truncated data is still detected
+        let mut decoder = Encoder::new(Slice::new(&data[..100]), Context::new());
+        assert!(matches!(
+            Vec::<u32>::skip(&mut decoder),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        let mut decoder = Encoder::new(Plain(&data[..100]), Context::new());
+        assert!(matches!(
+            Vec::<u32>::skip(&mut decoder),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        let mut decoder = Encoder::new(VecStream::new(data[..100].to_vec(), 0), Context::new());
+        assert!(matches!(
+            Vec::<u32>::skip(&mut decoder),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+
+        // Skipping doesn't grow a vector, even by an amount that would overflow
+        let mut stream = VecStream::new(vec![0; 10], 4);
+        assert!(matches!(stream.skip(7), Err(EncodingError::UnexpectedEnd)));
+        assert!(matches!(stream.skip(usize::MAX), Err(EncodingError::UnexpectedEnd)));
+        stream.skip(6).unwrap();
+        assert_eq!(stream.into_inner().len(), 10);
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod limits {
+    use super::decode;
+    use crate::io::{Slice, VecStream};
+    use crate::{encode_bytes_with, Context, Decode, Encode, Encoder, EncodingError, Skip};
+
+    #[derive(Encode, Decode, Skip, Debug, PartialEq)]
+    enum List {
+        Cons(u8, Box<List>),
+        Nil,
+    }
+
+    impl List {
+        fn with_len(len: u8) -> Self {
+            (0..len).fold(List::Nil, |list, i| List::Cons(i, Box::new(list)))
+        }
+    }
+
+    /// Encodes a length prefix claiming `len` items, followed by a few bytes
+    fn huge_len(len: usize) -> Vec<u8> {
+        let mut encoder = Encoder::new(VecStream::new(Vec::new(), 0), Context::new());
+        encoder.write_usize(len).unwrap();
+        encoder.write_bytes(&[1, 2, 3, 4]).unwrap();
+        encoder.finish().0.into_inner()
+    }
+
+    #[test]
+    pub fn alloc_budget() {
+        // Every length is small on its own, but the total isn't
+        let value = vec![vec![0u8; 100]; 4];
+        let data = encode_bytes_with(&value, Context::new()).unwrap();
+        let outer = 4 * size_of::<Vec<u8>>();
+
+        let ctxt = Context::new().alloc_budget(outer + 400);
+        let mut decoder = Encoder::new(Slice::new(&data), ctxt);
+        assert_eq!(Vec::<Vec<u8>>::decode(&mut decoder).unwrap(), value);
+        assert_eq!(decoder.ctxt.alloc_budget, Some(0));
+
+        // Items decoded on demand are charged to the decoder the sequence is read from
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new().alloc_budget(400));
+        for item in decoder.seq_decoder::<Vec<u8>>().unwrap() {
+            item.unwrap();
+        }
+        assert_eq!(decoder.ctxt.alloc_budget, Some(0));
+
+        let ctxt = Context::new().alloc_budget(outer + 399);
+        assert!(matches!(
+            decode::<Vec<Vec<u8>>>(&data, ctxt),
+            Err(EncodingError::AllocBudgetExceeded {
+                remaining: 99,
+                requested: 100
+            })
+        ));
+
+        // Strings and maps are charged too
+        let value = vec![String::from("hello"); 10];
+        let data = encode_bytes_with(&value, Context::new()).unwrap();
+        let ctxt = Context::new().alloc_budget(10 * size_of::<String>() + 49);
+        assert!(matches!(
+            decode::<Vec<String>>(&data, ctxt),
+            Err(EncodingError::AllocBudgetExceeded { .. })
+        ));
+
+        // The length of a string is charged before its contents are read
+        let ctxt = Context::new().alloc_budget(100);
+        assert!(matches!(
+            decode::<String>(&huge_len(1000), ctxt),
+            Err(EncodingError::AllocBudgetExceeded {
+                remaining: 100,
+                requested: 1000
+            })
+        ));
+
+        let value = std::collections::HashMap::from([(1u32, 2u64), (3, 4)]);
+        let data = encode_bytes_with(&value, Context::new()).unwrap();
+        let ctxt = Context::new().alloc_budget(size_of::<(u32, u64)>());
+        assert!(matches!(
+            decode::<std::collections::HashMap<u32, u64>>(&data, ctxt),
+            Err(EncodingError::AllocBudgetExceeded { .. })
+        ));
+    }
+
+    #[test]
+    pub fn max_depth() {
+        let list = List::with_len(10);
+        let data = encode_bytes_with(&list, Context::new()).unwrap();
+
+        // Each element is one level for the enum and one for the box
+        assert_eq!(decode::<List>(&data, Context::new()).unwrap(), list);
+        assert_eq!(decode::<List>(&data, Context::new().max_depth(21)).unwrap(), list);
+        assert!(matches!(
+            decode::<List>(&data, Context::new().max_depth(20)),
+            Err(EncodingError::MaxDepthExceeded { max: 20 })
+        ));
+
+        // Only the derived items count while skipping, since nothing is boxed
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new().max_depth(11));
+        List::skip(&mut decoder).unwrap();
+        assert_eq!(decoder.ctxt.depth, 0);
+
+        let mut decoder = Encoder::new(Slice::new(&data), Context::new().max_depth(10));
+        assert!(matches!(
+            List::skip(&mut decoder),
+            Err(EncodingError::MaxDepthExceeded { max: 10 })
+        ));
+
+        // The depth is restored when decoding fails midway
+        let mut decoder = Encoder::new(Slice::new(&data[..data.len() - 1]), Context::new());
+        assert!(List::decode(&mut decoder).is_err());
+        assert_eq!(decoder.ctxt.depth, 0);
+
+        let value = vec![vec![vec![1u8]]];
+        let data = encode_bytes_with(&value, Context::new()).unwrap();
+        let ctxt = Context::new().max_depth(3);
+        assert_eq!(decode::<Vec<Vec<Vec<u8>>>>(&data, ctxt).unwrap(), value);
+        assert!(matches!(
+            decode::<Vec<Vec<Vec<u8>>>>(&data, Context::new().max_depth(2)),
+            Err(EncodingError::MaxDepthExceeded { max: 2 })
+        ));
+
+        let mut decoder = Encoder::new(Slice::new(&data[..data.len() - 1]), Context::new());
+        assert!(Vec::<Vec<Vec<u8>>>::decode(&mut decoder).is_err());
+        assert_eq!(decoder.ctxt.depth, 0);
+    }
+
+    #[test]
+    pub fn capacity() {
+        // A corrupted length doesn't reserve more memory than the data left could fill
+        let data = huge_len(usize::MAX / 64);
+        assert!(matches!(
+            decode::<Vec<u32>>(&data, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode::<Vec<(u8, u16)>>(&data, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode::<std::collections::VecDeque<String>>(&data, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode::<std::collections::HashMap<u8, u8>>(&data, Context::new()),
+            Err(EncodingError::UnexpectedEnd)
+        ));
+    }
+}